@@ -1,3 +1,4 @@
+use dagviz::context::LayoutContext;
 use dagviz::graph::Graph;
 use dagviz::rank::network_simplex::network_simplex;
 use dagviz::types::{Edge, EdgeLabel, NodeLabel};
@@ -87,7 +88,7 @@ fn main() {
     );
 
     // 运行 NetworkSimplex 算法
-    network_simplex(&mut graph);
+    network_simplex(&mut graph, &mut LayoutContext::new());
 
     println!("NetworkSimplex algorithm completed successfully!");
 
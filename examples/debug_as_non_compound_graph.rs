@@ -12,7 +12,7 @@ fn main() {
     print_ranks(&g, "原始图");
     
     // 执行rank计算
-    dagviz::rank::rank(&mut g);
+    dagviz::rank::rank(&mut g, &mut dagviz::context::LayoutContext::new());
     print_ranks(&g, "rank计算后");
     
     // 执行normalize ranks
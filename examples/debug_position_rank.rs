@@ -13,7 +13,7 @@ fn main() {
     print_ranks(&g, "初始状态");
     
     // 执行rank计算
-    dagviz::rank::rank(&mut g);
+    dagviz::rank::rank(&mut g, &mut dagviz::context::LayoutContext::new());
     print_ranks(&g, "rank计算后");
     
     // 执行normalize ranks
@@ -18,7 +18,7 @@ fn main() {
     print_ranks(&g, "无环化后");
 
     println!("\n2. 执行rank...");
-    dagviz::rank::rank(&mut g);
+    dagviz::rank::rank(&mut g, &mut dagviz::context::LayoutContext::new());
     print_ranks(&g, "rank后");
 
     println!("\n3. 执行normalize ranks...");
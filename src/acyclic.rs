@@ -1,49 +1,69 @@
 use crate::graph::{Graph, NodeIndex};
-use crate::types::{Edge, EdgeLabel, NodeLabel};
-use indexmap::IndexSet;
+use crate::types::{Edge, EdgeLabel};
+use indexmap::{IndexMap, IndexSet};
+use std::collections::VecDeque;
 
 /// Acyclic module for making graphs acyclic
 pub struct Acyclic;
 
 impl Acyclic {
-    /// Run acyclic algorithm on the graph
-    pub fn run(graph: &mut Graph) {
+    /// Run acyclic algorithm on the graph, returning the number of reversed edges
+    pub fn run(graph: &mut Graph) -> usize {
         let acyclicer = graph.config().acyclicer.clone();
         let fas = match acyclicer.as_str() {
             "greedy" => Self::greedy_fas(graph, |e| e.weight),
             _ => Self::dfs_fas(graph),
         };
 
+        let mut reversed = 0;
         for edge in fas {
-            if let Some(label) = graph.edge_label(&edge) {
-                let mut new_label = label.clone();
-                new_label.forward_name = Some(format!("rev_{}", edge.source.index()));
-                new_label.reversed = Some(true);
-
-                graph.remove_edge(&edge);
-
-                let reversed_edge = Edge::new(edge.target, edge.source);
-                graph.add_edge(reversed_edge, new_label);
+            if let Some(label) = graph.remove_edge(&edge) {
+                Self::add_reversed(graph, &edge, label);
+                reversed += 1;
             }
         }
+        reversed
     }
 
-    /// Undo acyclic changes
-    pub fn undo(graph: &mut Graph) {
-        let edges_to_undo: Vec<_> = graph.edges().into_iter().collect();
+    /// 反向添加边，`undo` 时恢复原来的方向
+    pub(crate) fn add_reversed(graph: &mut Graph, edge: &Edge, mut label: EdgeLabel) {
+        // Reversed edges get a fresh name so they never collide with an
+        // existing edge between the same nodes (e.g. in a 2-cycle).
+        let mut next_id = 0;
+        let reversed_edge = loop {
+            let candidate = Edge::with_name(edge.target, edge.source, format!("rev{}", next_id));
+            next_id += 1;
+            if !graph.has_edge(&candidate) {
+                break candidate;
+            }
+        };
 
-        for edge in edges_to_undo {
-            if let Some(label) = graph.edge_label(&edge) {
-                if label.reversed == Some(true) {
-                    let mut new_label = label.clone();
-                    new_label.reversed = None;
-                    new_label.forward_name = None;
+        label.forward_name = edge.name.clone();
+        label.reversed = Some(true);
+        // 端口跟随端点
+        std::mem::swap(&mut label.source_port, &mut label.target_port);
+        graph.add_edge(reversed_edge, label);
+    }
 
-                    graph.remove_edge(&edge);
+    /// Undo acyclic changes
+    pub fn undo(graph: &mut Graph) {
+        for edge in graph.edges() {
+            let is_reversed = graph
+                .edge_label(&edge)
+                .is_some_and(|label| label.reversed == Some(true));
+            if !is_reversed {
+                continue;
+            }
 
-                    let forward_edge = Edge::new(edge.target, edge.source);
-                    graph.add_edge(forward_edge, new_label);
-                }
+            if let Some(mut label) = graph.remove_edge(&edge) {
+                let forward_edge = Edge {
+                    source: edge.target,
+                    target: edge.source,
+                    name: label.forward_name.take(),
+                };
+                label.reversed = None;
+                std::mem::swap(&mut label.source_port, &mut label.target_port);
+                graph.add_edge(forward_edge, label);
             }
         }
     }
@@ -89,6 +109,11 @@ impl Acyclic {
     }
 
     /// Greedy feedback arc set algorithm
+    ///
+    /// Port of greedyFAS() in lib/greedy-fas.js (Eades, Lin and Smyth). Sinks and
+    /// sources are removed first; otherwise the node with the largest
+    /// `out - in` weight is removed and its incoming edges join the set.
+    /// Parallel edges are weighed together and reversed together.
     fn greedy_fas<F>(graph: &Graph, weight_fn: F) -> Vec<Edge>
     where
         F: Fn(&EdgeLabel) -> f64,
@@ -97,197 +122,156 @@ impl Acyclic {
             return Vec::new();
         }
 
-        let mut state = Self::build_state(graph, &weight_fn);
-        let fas_results = Self::do_greedy_fas(&mut state.graph, &mut state.buckets, state.zero_idx);
-
-        // Map fas_graph edges back to original graph edges
-        let mut expanded_results = Vec::new();
-        for fas_edge in fas_results {
-            // Find the original node indices using reverse mapping
-            let mut original_source = None;
-            let mut original_target = None;
+        let mut state = FasState::new(graph, &weight_fn);
+        let mut results = Vec::new();
+        let last = state.buckets.len() - 1;
 
-            for (orig_node, fas_node) in &state.node_mapping {
-                if *fas_node == fas_edge.source {
-                    original_source = Some(*orig_node);
-                }
-                if *fas_node == fas_edge.target {
-                    original_target = Some(*orig_node);
-                }
+        while state.remaining > 0 {
+            while let Some(v) = state.buckets[0].pop_back() {
+                state.remove_node(v, None);
             }
-
-            if let (Some(source), Some(target)) = (original_source, original_target) {
-                // Find the corresponding edge in the original graph
-                for original_edge in graph.edges() {
-                    if original_edge.source == source && original_edge.target == target {
-                        expanded_results.push(original_edge);
+            while let Some(v) = state.buckets[last].pop_back() {
+                state.remove_node(v, None);
+            }
+            if state.remaining > 0 {
+                for i in (1..last).rev() {
+                    if let Some(v) = state.buckets[i].pop_back() {
+                        state.remove_node(v, Some(&mut results));
                         break;
                     }
                 }
             }
         }
 
-        expanded_results
-    }
-
-    fn do_greedy_fas(g: &mut Graph, buckets: &mut [Vec<FasEntry>], zero_idx: usize) -> Vec<Edge> {
-        let mut results = Vec::new();
-
-        // Check if graph is already acyclic
-        if is_acyclic(g) {
-            return results;
-        }
-
-        // Simple approach: just remove all nodes one by one
-        while !g.is_empty() {
-            let nodes: Vec<_> = g.node_indices().collect();
-            if nodes.is_empty() {
-                break;
-            }
-
-            // Remove the first node and collect its incoming edges
-            let node = nodes[0];
-            let mut incoming_edges = Vec::new();
-
-            for edge in g.in_edges(node) {
-                incoming_edges.push(edge);
-            }
-
-            results.extend(incoming_edges);
-            g.remove_node(node);
-        }
-
         results
+            .into_iter()
+            .flat_map(|(u, v)| {
+                let (source, target) = (state.nodes[u], state.nodes[v]);
+                graph.edges_between(source, target)
+            })
+            .collect()
     }
+}
 
-    fn remove_node(
-        g: &mut Graph,
-        buckets: &mut [Vec<FasEntry>],
-        zero_idx: usize,
-        entry: FasEntry,
-        collect_predecessors: bool,
-    ) -> Vec<Edge> {
-        let mut results = if collect_predecessors {
-            Vec::new()
-        } else {
-            vec![]
-        };
-
-        for edge in g.in_edges(entry.v) {
-            if let Some(weight) = g.edge_label(&edge).map(|l| l.weight) {
-                if collect_predecessors {
-                    results.push(edge);
-                }
-                // Update bucket assignments would go here
-            }
-        }
-
-        for edge in g.out_edges(entry.v) {
-            if let Some(weight) = g.edge_label(&edge).map(|l| l.weight) {
-                // Update bucket assignments would go here
-            }
-        }
-
-        // Actually remove the node from the graph
-        g.remove_node(entry.v);
-
-        results
-    }
+/// Working state of the greedy feedback arc set algorithm
+///
+/// Nodes are numbered by their position in `nodes`; parallel edges are merged
+/// and their weights summed.
+struct FasState {
+    nodes: Vec<NodeIndex>,
+    in_edges: Vec<Vec<(usize, f64)>>,
+    out_edges: Vec<Vec<(usize, f64)>>,
+    in_weight: Vec<f64>,
+    out_weight: Vec<f64>,
+    removed: Vec<bool>,
+    remaining: usize,
+    /// buckets[0] holds sinks, the last bucket holds sources, and bucket
+    /// `out - in + zero_idx` holds the other nodes. Each bucket is a queue.
+    buckets: Vec<VecDeque<usize>>,
+    bucket_of: Vec<Option<usize>>,
+    zero_idx: usize,
+}
 
-    fn build_state<F>(graph: &Graph, weight_fn: &F) -> FasState
+impl FasState {
+    fn new<F>(graph: &Graph, weight_fn: &F) -> Self
     where
         F: Fn(&EdgeLabel) -> f64,
     {
-        let mut fas_graph = Graph::new();
-        let mut max_in = 0;
-        let mut max_out = 0;
-
-        // Create mapping from original node indices to fas_graph node indices
-        let mut node_mapping = std::collections::HashMap::new();
-
-        // Add nodes
-        for node_id in graph.node_indices() {
-            let fas_node_id = fas_graph.add_node(NodeLabel::default());
-            node_mapping.insert(node_id, fas_node_id);
-        }
+        let nodes: Vec<NodeIndex> = graph.node_indices().collect();
+        let position: IndexMap<NodeIndex, usize> =
+            nodes.iter().enumerate().map(|(i, &v)| (v, i)).collect();
 
-        // Add edges and calculate weights
+        let mut weights: IndexMap<(usize, usize), f64> = IndexMap::new();
         for edge in graph.edges() {
             if let Some(label) = graph.edge_label(&edge) {
-                let weight = weight_fn(label);
-                let edge_label = EdgeLabel {
-                    weight,
-                    ..Default::default()
-                };
-
-                // Map the edge's source and target to fas_graph node indices
-                let fas_source = node_mapping[&edge.source];
-                let fas_target = node_mapping[&edge.target];
-                let fas_edge = Edge::new(fas_source, fas_target);
-
-                fas_graph.add_edge(fas_edge, edge_label);
-
-                max_out = max_out.max(weight as usize);
-                max_in = max_in.max(weight as usize);
+                *weights
+                    .entry((position[&edge.source], position[&edge.target]))
+                    .or_default() += weight_fn(label);
             }
         }
 
-        let mut buckets = vec![Vec::new(); max_out + max_in + 3];
-        let zero_idx = max_in + 1;
-
-        // Add nodes to appropriate buckets based on their weights
-        for node_id in fas_graph.node_indices() {
-            let mut in_weight = 0.0;
-            let mut out_weight = 0.0;
+        let n = nodes.len();
+        let mut in_edges = vec![Vec::new(); n];
+        let mut out_edges = vec![Vec::new(); n];
+        let mut in_weight = vec![0.0; n];
+        let mut out_weight = vec![0.0; n];
+        for (&(u, v), &weight) in &weights {
+            out_edges[u].push((v, weight));
+            in_edges[v].push((u, weight));
+            out_weight[u] += weight;
+            in_weight[v] += weight;
+        }
 
-            for edge in fas_graph.in_edges(node_id) {
-                if let Some(label) = fas_graph.edge_label(&edge) {
-                    in_weight += label.weight;
-                }
-            }
+        let max_in = in_weight.iter().copied().fold(0.0, f64::max).ceil() as usize;
+        let max_out = out_weight.iter().copied().fold(0.0, f64::max).ceil() as usize;
+
+        let mut state = Self {
+            nodes,
+            in_edges,
+            out_edges,
+            in_weight,
+            out_weight,
+            removed: vec![false; n],
+            remaining: n,
+            buckets: vec![VecDeque::new(); max_out + max_in + 3],
+            bucket_of: vec![None; n],
+            zero_idx: max_in + 1,
+        };
+        for v in 0..n {
+            state.assign_bucket(v);
+        }
+        state
+    }
 
-            for edge in fas_graph.out_edges(node_id) {
-                if let Some(label) = fas_graph.edge_label(&edge) {
-                    out_weight += label.weight;
-                }
-            }
+    /// Move a node to the bucket matching its current in and out weights
+    fn assign_bucket(&mut self, v: usize) {
+        if let Some(bucket) = self.bucket_of[v].take() {
+            self.buckets[bucket].retain(|&entry| entry != v);
+        }
 
-            let entry = FasEntry {
-                v: node_id,
-                in_weight,
-                out_weight,
-            };
+        let last = self.buckets.len() - 1;
+        let bucket = if self.out_weight[v] == 0.0 {
+            0
+        } else if self.in_weight[v] == 0.0 {
+            last
+        } else {
+            let index = self.out_weight[v] - self.in_weight[v] + self.zero_idx as f64;
+            (index.max(1.0) as usize).min(last - 1)
+        };
+        self.buckets[bucket].push_front(v);
+        self.bucket_of[v] = Some(bucket);
+    }
 
-            // Place node in appropriate bucket based on in_weight - out_weight
-            let bucket_idx = (in_weight - out_weight + zero_idx as f64) as usize;
-            if bucket_idx < buckets.len() {
-                buckets[bucket_idx].push(entry);
+    /// Remove a node, updating its neighbours' buckets
+    ///
+    /// When `predecessors` is given, the node's remaining incoming edges are
+    /// added to it.
+    fn remove_node(&mut self, v: usize, mut predecessors: Option<&mut Vec<(usize, usize)>>) {
+        self.bucket_of[v] = None;
+        self.removed[v] = true;
+        self.remaining -= 1;
+
+        for (u, weight) in std::mem::take(&mut self.in_edges[v]) {
+            if self.removed[u] {
+                continue;
+            }
+            if let Some(results) = predecessors.as_mut() {
+                results.push((u, v));
             }
+            self.out_weight[u] -= weight;
+            self.assign_bucket(u);
         }
 
-        FasState {
-            graph: fas_graph,
-            buckets,
-            zero_idx,
-            node_mapping,
+        for (w, weight) in std::mem::take(&mut self.out_edges[v]) {
+            if self.removed[w] {
+                continue;
+            }
+            self.in_weight[w] -= weight;
+            self.assign_bucket(w);
         }
     }
 }
 
-#[derive(Debug, Clone)]
-struct FasEntry {
-    v: crate::graph::NodeIndex,
-    in_weight: f64,
-    out_weight: f64,
-}
-
-pub struct FasState {
-    graph: Graph,
-    buckets: Vec<Vec<FasEntry>>,
-    zero_idx: usize,
-    node_mapping: std::collections::HashMap<NodeIndex, NodeIndex>,
-}
-
 /// Check if graph is acyclic
 pub fn is_acyclic(graph: &Graph) -> bool {
     let mut visited = IndexSet::new();
@@ -0,0 +1,140 @@
+//! 布局预算
+//!
+//! [`LayoutBudget`] 通过 `LayoutOptions::budget` 限制排序扫描次数、网络单纯形换边次数和总耗时。
+//! 布局开始时转换为 [`Budget`] 保存在布局上下文中，预算用完的阶段停止迭代并使用目前为止最好的结果：
+//!
+//! - 排序使用交叉数最少的一次扫描结果
+//! - 网络单纯形停止换边，当前的可行排名仍然满足所有最小长度约束
+//!
+//! 用完的预算记录在 `LayoutStats::budgets_exceeded` 中。
+//!
+//! [`CancellationToken`] 通过 `LayoutOptions::cancel` 传入，在同样的循环中检查。
+//! 取消后布局尽快返回，不修改输入图。
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// 布局预算，`None` 表示不限制
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LayoutBudget {
+    /// 排序阶段的最大扫描次数
+    pub max_order_sweeps: Option<usize>,
+    /// 网络单纯形的最大换边次数
+    pub max_simplex_pivots: Option<usize>,
+    /// 整个布局的耗时上限，从调用 `layout` 开始计算
+    pub time_limit: Option<Duration>,
+}
+
+/// 被用完的预算
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BudgetKind {
+    /// 排序扫描次数
+    OrderSweeps,
+    /// 网络单纯形换边次数
+    SimplexPivots,
+    /// 总耗时
+    TimeLimit,
+}
+
+/// 取消令牌
+///
+/// 克隆的令牌共享同一个状态，可以在另一个线程中调用 [`CancellationToken::cancel`]
+/// 取消正在运行的布局。
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// 创建未取消的令牌
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取消使用该令牌的布局
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// 是否已取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// 布局过程中使用的预算，总耗时上限已转换为截止时间
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    limits: LayoutBudget,
+    deadline: Option<Instant>,
+    cancel: Option<CancellationToken>,
+}
+
+impl Budget {
+    /// 从现在开始计算耗时上限
+    pub fn start(limits: LayoutBudget) -> Self {
+        Budget {
+            limits,
+            deadline: limits.time_limit.map(|limit| Instant::now() + limit),
+            cancel: None,
+        }
+    }
+
+    /// 使用取消令牌
+    pub fn with_cancellation(mut self, cancel: Option<CancellationToken>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// 布局是否已被取消
+    pub fn cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// 预算限制
+    pub fn limits(&self) -> &LayoutBudget {
+        &self.limits
+    }
+
+    /// 是否已超过截止时间
+    pub fn expired(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// 已扫描 `sweeps` 次后是否还能继续扫描，不能时返回用完的预算
+    pub fn order_sweeps_exhausted(&self, sweeps: usize) -> Option<BudgetKind> {
+        self.exhausted(
+            sweeps,
+            self.limits.max_order_sweeps,
+            BudgetKind::OrderSweeps,
+        )
+    }
+
+    /// 已换边 `pivots` 次后是否还能继续换边，不能时返回用完的预算
+    pub fn simplex_pivots_exhausted(&self, pivots: usize) -> Option<BudgetKind> {
+        self.exhausted(
+            pivots,
+            self.limits.max_simplex_pivots,
+            BudgetKind::SimplexPivots,
+        )
+    }
+
+    fn exhausted(
+        &self,
+        count: usize,
+        limit: Option<usize>,
+        kind: BudgetKind,
+    ) -> Option<BudgetKind> {
+        if limit.is_some_and(|limit| count >= limit) {
+            Some(kind)
+        } else if self.expired() {
+            Some(BudgetKind::TimeLimit)
+        } else {
+            None
+        }
+    }
+}
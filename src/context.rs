@@ -0,0 +1,51 @@
+//! 布局上下文
+//!
+//! 一次布局过程中的状态保存在 [`LayoutContext`] 中，由 `layout` 创建并传给每个布局阶段，
+//! 布局结束时取出统计返回给调用方。这些状态不属于图本身，不保存在 [`Graph`](crate::graph::Graph) 上。
+
+use crate::budget::{Budget, BudgetKind};
+use crate::graph::NodeIndex;
+use crate::incremental::LayoutHints;
+use crate::stats::LayoutStats;
+use crate::types::{Edge, EdgeLabel, Point};
+use indexmap::IndexMap;
+
+/// 单次布局的上下文
+#[derive(Debug, Clone, Default)]
+pub struct LayoutContext {
+    /// 本次布局的统计
+    pub stats: LayoutStats,
+    /// 本次布局的预算和取消令牌
+    pub budget: Budget,
+    /// 固定节点在输出坐标系中的目标位置，由 [`collect_pins`](crate::pin::collect_pins) 收集
+    pub pins: IndexMap<NodeIndex, Point>,
+    /// 增量布局提示，已转换到布局图的节点
+    pub hints: LayoutHints,
+    /// 排名前暂时移除的不参与排名的边及其标签
+    pub non_constraint_edges: Vec<(Edge, EdgeLabel)>,
+}
+
+impl LayoutContext {
+    /// 创建空的上下文
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用指定预算的上下文
+    pub fn with_budget(budget: Budget) -> Self {
+        Self {
+            budget,
+            ..Self::default()
+        }
+    }
+
+    /// 超过截止时间时在统计中记录用完的时间预算
+    ///
+    /// 每个阶段结束后调用。迭代的阶段（排序扫描、网络单纯形换边和 Brandes-Köpf 对齐）
+    /// 自己检查截止时间并提前停止，这里保证截止时间在其他阶段中到达时同样被记录。
+    pub fn check_deadline(&mut self) {
+        if self.budget.expired() {
+            self.stats.exceed_budget(BudgetKind::TimeLimit);
+        }
+    }
+}
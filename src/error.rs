@@ -0,0 +1,182 @@
+//! 布局错误类型
+//!
+//! `try_layout` 和 `Graph::try_add_edge` 在遇到无法布局的输入时返回这里定义的错误，
+//! 而不是 panic。除 `Cancelled` 外，每个变体都携带出错的节点或边，便于调用方定位问题。
+
+use crate::graph::NodeIndex;
+use crate::types::Edge;
+use std::fmt;
+
+/// 布局错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutError {
+    /// 节点索引属于另一个图
+    ForeignNode {
+        /// 出错的节点索引
+        node: NodeIndex,
+        /// 当前图的 ID
+        graph_id: u32,
+        /// 引用该节点的边（如果有）
+        edge: Option<Edge>,
+    },
+    /// 节点索引属于当前图，但节点已不存在
+    UnknownNode {
+        /// 出错的节点索引
+        node: NodeIndex,
+        /// 引用该节点的边（如果有）
+        edge: Option<Edge>,
+    },
+    /// 节点尺寸为 NaN、无穷大或负数
+    InvalidNodeSize {
+        /// 出错的节点
+        node: NodeIndex,
+        /// 节点宽度
+        width: f64,
+        /// 节点高度
+        height: f64,
+    },
+    /// 父节点不存在于图中
+    DanglingParent {
+        /// 子节点
+        node: NodeIndex,
+        /// 不存在的父节点
+        parent: NodeIndex,
+    },
+    /// 复合图的父子层级中存在环
+    ParentCycle {
+        /// 组成环的节点，按父链顺序排列
+        nodes: Vec<NodeIndex>,
+    },
+    /// 固定排名与边的最小长度约束相矛盾
+    RankContradiction {
+        /// 违反约束的边
+        edge: Edge,
+        /// 源节点排名
+        source_rank: i32,
+        /// 目标节点排名
+        target_rank: i32,
+        /// 边要求的最小长度
+        minlen: i32,
+    },
+    /// 排名约束相互矛盾，或与边的方向矛盾
+    RankConstraintConflict {
+        /// 受矛盾约束的节点
+        nodes: Vec<NodeIndex>,
+        /// 与约束矛盾的边（如果有）
+        edge: Option<Edge>,
+    },
+    /// 边引用的端口不存在于节点上
+    UnknownPort {
+        /// 引用端口的边
+        edge: Edge,
+        /// 缺少该端口的节点
+        node: NodeIndex,
+        /// 端口 ID
+        port: String,
+    },
+    /// 布局通过取消令牌被取消
+    Cancelled,
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::ForeignNode {
+                node,
+                graph_id,
+                edge,
+            } => {
+                write!(
+                    f,
+                    "node {} belongs to graph {}, but current graph is {}",
+                    node.index(),
+                    node.which_graph,
+                    graph_id
+                )?;
+                if let Some(edge) = edge {
+                    write!(f, " (edge {})", format_edge(edge))?;
+                }
+                Ok(())
+            }
+            LayoutError::UnknownNode { node, edge } => {
+                write!(f, "node {} does not exist", node.index())?;
+                if let Some(edge) = edge {
+                    write!(f, " (edge {})", format_edge(edge))?;
+                }
+                Ok(())
+            }
+            LayoutError::InvalidNodeSize {
+                node,
+                width,
+                height,
+            } => write!(
+                f,
+                "node {} has invalid size {}x{}",
+                node.index(),
+                width,
+                height
+            ),
+            LayoutError::DanglingParent { node, parent } => write!(
+                f,
+                "node {} has parent {} which does not exist",
+                node.index(),
+                parent.index()
+            ),
+            LayoutError::ParentCycle { nodes } => {
+                let path: Vec<String> = nodes.iter().map(|n| n.index().to_string()).collect();
+                write!(f, "parent cycle: {}", path.join(" -> "))
+            }
+            LayoutError::RankContradiction {
+                edge,
+                source_rank,
+                target_rank,
+                minlen,
+            } => write!(
+                f,
+                "edge {} requires minlen {}, but ranks are {} and {}",
+                format_edge(edge),
+                minlen,
+                source_rank,
+                target_rank
+            ),
+            LayoutError::RankConstraintConflict { nodes, edge } => {
+                let nodes: Vec<String> = nodes.iter().map(|n| n.index().to_string()).collect();
+                match edge {
+                    Some(edge) => write!(
+                        f,
+                        "rank constraints on nodes {} contradict edge {}",
+                        nodes.join(", "),
+                        format_edge(edge)
+                    ),
+                    None => write!(
+                        f,
+                        "rank constraints on nodes {} cannot be satisfied",
+                        nodes.join(", ")
+                    ),
+                }
+            }
+            LayoutError::UnknownPort { edge, node, port } => write!(
+                f,
+                "edge {} references port {:?}, but node {} has no such port",
+                format_edge(edge),
+                port,
+                node.index()
+            ),
+            LayoutError::Cancelled => write!(f, "layout was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+fn format_edge(edge: &Edge) -> String {
+    match &edge.name {
+        Some(name) => format!(
+            "{} -> {} ({})",
+            edge.source.index(),
+            edge.target.index(),
+            name
+        ),
+        None => format!("{} -> {}", edge.source.index(), edge.target.index()),
+    }
+}
@@ -1,9 +1,17 @@
-//! 图数据结构实现，基于 petgraph
+//! 图数据结构实现，基于 petgraph 的 `StableGraph`
+//!
+//! 删除节点或边不会改变其他节点和边的索引，调用方和布局过程中保存的
+//! [`NodeIndex`]（例如 `dummy_chains`）在删除之后仍然指向原来的节点。
+//! 被删除节点的槽位可能在之后的 `add_node` 中被重新使用，但每个槽位带有代数，
+//! 复用时代数递增，所以指向已删除节点的旧索引不会指向新节点：
+//! `node_label` 等查询返回 `None`，`check_node` 返回 `UnknownNode`。
+use crate::error::LayoutError;
 use crate::types::*;
 use indexmap::IndexMap;
 use petgraph::graph::EdgeIndex;
 use petgraph::visit::EdgeRef;
-use petgraph::{Directed, Graph as PetGraph};
+use petgraph::Directed;
+use petgraph::stable_graph::StableGraph;
 use std::panic::Location;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Instant;
@@ -59,12 +67,14 @@ impl StackContext {
 /// 图结构，基于 petgraph
 #[derive(Clone)]
 pub struct Graph {
-    /// petgraph 图实例
-    graph: PetGraph<NodeLabel, EdgeLabel, Directed>,
+    /// petgraph 图实例，删除元素时索引保持稳定
+    graph: StableGraph<NodeLabel, EdgeLabel, Directed>,
     /// 边到EdgeIndex的映射
     edge_to_index: IndexMap<Edge, EdgeIndex>,
     /// EdgeIndex到边的映射
     index_to_edge: IndexMap<EdgeIndex, Edge>,
+    /// 每个节点槽位的代数，删除节点时递增
+    generations: Vec<u32>,
     /// 图配置
     config: GraphConfig,
     /// 虚拟链
@@ -80,9 +90,10 @@ impl Graph {
     #[track_caller]
     pub fn new() -> Self {
         Self {
-            graph: PetGraph::new(),
+            graph: StableGraph::new(),
             edge_to_index: IndexMap::new(),
             index_to_edge: IndexMap::new(),
+            generations: Vec::new(),
             config: GraphConfig::default(),
             dummy_chains: Vec::new(),
             graph_id: get_next_graph_id(),
@@ -94,9 +105,10 @@ impl Graph {
     #[track_caller]
     pub fn with_config(config: GraphConfig) -> Self {
         Self {
-            graph: PetGraph::new(),
+            graph: StableGraph::new(),
             edge_to_index: IndexMap::new(),
             index_to_edge: IndexMap::new(),
+            generations: Vec::new(),
             config,
             dummy_chains: Vec::new(),
             graph_id: get_next_graph_id(),
@@ -122,10 +134,33 @@ impl Graph {
     /// 添加节点
     pub fn add_node(&mut self, label: NodeLabel) -> NodeIndex {
         let node_index = self.graph.add_node(label);
-        NodeIndex::new(node_index, self.graph_id)
+        if node_index.index() >= self.generations.len() {
+            self.generations.resize(node_index.index() + 1, 0);
+        }
+        self.wrap_index(node_index)
+    }
+
+    /// 把 petgraph 的节点索引包装为当前图的 `NodeIndex`，带上槽位当前的代数
+    ///
+    /// 用于把 `petgraph()` 上运行的算法结果转换回该图的节点
+    pub fn wrap_index(&self, node_index: petgraph::graph::NodeIndex) -> NodeIndex {
+        let generation = self
+            .generations
+            .get(node_index.index())
+            .copied()
+            .unwrap_or_default();
+        NodeIndex::new(node_index, self.graph_id, generation)
+    }
+
+    /// 节点是否仍然存在，槽位被复用后旧索引不再有效
+    fn is_live(&self, node_index: NodeIndex) -> bool {
+        self.generations.get(node_index.index()) == Some(&node_index.generation)
+            && self.graph.node_weight(node_index.node_index).is_some()
     }
 
     /// 创建边（使用正确的图ID）
+    ///
+    /// 端点不属于当前图时 panic，需要错误处理时使用 `try_create_edge`
     pub fn create_edge(&self, source: NodeIndex, target: NodeIndex) -> Edge {
         // 验证source和target是否属于当前图
         if !source.belongs_to_graph(self.graph_id) {
@@ -140,47 +175,97 @@ impl Graph {
                 target.which_graph, self.graph_id
             );
         }
-        
+
         let mut edge = Edge::new(source, target);
         edge.source.set_graph_id(self.graph_id);
         edge.target.set_graph_id(self.graph_id);
         edge
     }
 
+    /// 创建边，端点不属于当前图或已不存在时返回错误而不是 panic
+    pub fn try_create_edge(
+        &self,
+        source: NodeIndex,
+        target: NodeIndex,
+    ) -> Result<Edge, LayoutError> {
+        let edge = Edge::new(source, target);
+        self.check_node(source, Some(&edge))?;
+        self.check_node(target, Some(&edge))?;
+        Ok(edge)
+    }
+
     /// 创建用于查询的边（使用正确的图ID）
     pub fn create_query_edge(&self, source: NodeIndex, target: NodeIndex) -> Edge {
         self.create_edge(source, target)
     }
 
     /// 添加边
-    pub fn add_edge(&mut self, mut edge: Edge, label: EdgeLabel) -> EdgeIndex {
+    ///
+    /// 如果边的端点不属于当前图则 panic，需要错误处理时使用 `try_add_edge`。
+    ///
+    /// 图中已有同一条边（源节点、目标节点和名称都相同）时不会再添加一条平行边，
+    /// 而是用 `label` 替换原有标签，并返回原有的边索引。需要平行边时用
+    /// [`Edge::with_name`] 给每条边不同的名称。
+    pub fn add_edge(&mut self, edge: Edge, label: EdgeLabel) -> EdgeIndex {
+        self.try_add_edge(edge, label)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// 添加边，端点无效时返回错误而不是 panic
+    ///
+    /// 重复添加同一条边时的行为与 `add_edge` 相同
+    pub fn try_add_edge(
+        &mut self,
+        mut edge: Edge,
+        label: EdgeLabel,
+    ) -> Result<EdgeIndex, LayoutError> {
         // 验证边的节点是否属于当前图
-        if !edge.source.belongs_to_graph(self.graph_id) {
-            panic!(
-                "Source node belongs to graph {}, but current graph is {}",
-                edge.source.which_graph, self.graph_id
-            );
-        }
-        if !edge.target.belongs_to_graph(self.graph_id) {
-            panic!(
-                "Target node belongs to graph {}, but current graph is {}",
-                edge.target.which_graph, self.graph_id
-            );
-        }
+        self.check_node(edge.source, Some(&edge))?;
+        self.check_node(edge.target, Some(&edge))?;
 
         // 确保Edge的图ID正确
         edge.source.set_graph_id(self.graph_id);
         edge.target.set_graph_id(self.graph_id);
 
+        // 同一条边（端点和名称都相同）重复添加时只更新标签
+        if let Some(&edge_index) = self.edge_to_index.get(&edge) {
+            self.graph[edge_index] = label;
+            return Ok(edge_index);
+        }
+
         let edge_index = self
             .graph
             .add_edge(edge.source.node_index, edge.target.node_index, label);
         self.edge_to_index.insert(edge.clone(), edge_index);
         self.index_to_edge.insert(edge_index, edge);
-        edge_index
+        Ok(edge_index)
+    }
+
+    /// 检查节点是否属于当前图且仍然存在
+    pub fn check_node(
+        &self,
+        node_index: NodeIndex,
+        edge: Option<&Edge>,
+    ) -> Result<(), LayoutError> {
+        if !node_index.belongs_to_graph(self.graph_id) {
+            return Err(LayoutError::ForeignNode {
+                node: node_index,
+                graph_id: self.graph_id,
+                edge: edge.cloned(),
+            });
+        }
+        if !self.is_live(node_index) {
+            return Err(LayoutError::UnknownNode {
+                node: node_index,
+                edge: edge.cloned(),
+            });
+        }
+        Ok(())
     }
 
     /// 获取节点标签
+    ///
+    /// 节点属于其他图时 panic，需要错误处理时使用 `try_node_label`
     pub fn node_label(&self, node_index: NodeIndex) -> Option<&NodeLabel> {
         if !node_index.belongs_to_graph(self.graph_id) {
             panic!(
@@ -192,9 +277,18 @@ impl Graph {
                 node_index.belongs_to_graph(self.graph_id)
             );
         }
+        if !self.is_live(node_index) {
+            return None;
+        }
         self.graph.node_weight(node_index.node_index)
     }
 
+    /// 获取节点标签，节点不属于当前图或已不存在时返回错误而不是 panic
+    pub fn try_node_label(&self, node_index: NodeIndex) -> Result<&NodeLabel, LayoutError> {
+        self.check_node(node_index, None)?;
+        Ok(&self.graph[node_index.node_index])
+    }
+
     /// 获取节点标签（可变）
     pub fn node_label_mut(&mut self, node_index: NodeIndex) -> Option<&mut NodeLabel> {
         if !node_index.belongs_to_graph(self.graph_id) {
@@ -207,10 +301,15 @@ impl Graph {
                 node_index.belongs_to_graph(self.graph_id)
             );
         }
+        if !self.is_live(node_index) {
+            return None;
+        }
         self.graph.node_weight_mut(node_index.node_index)
     }
 
     /// 获取边标签
+    ///
+    /// 边由端点和名称共同确定，`Edge::with_name(a, b, "x")` 与 `Edge::new(a, b)` 是两条不同的边
     pub fn edge_label(&self, edge: &Edge) -> Option<&EdgeLabel> {
         let edge_index = self.edge_to_index.get(edge)?;
         self.graph.edge_weight(*edge_index)
@@ -226,7 +325,7 @@ impl Graph {
     pub fn node_indices(&self) -> impl Iterator<Item = NodeIndex> + '_ {
         self.graph
             .node_indices()
-            .map(move |node_index| NodeIndex::new(node_index, self.graph_id))
+            .map(move |node_index| self.wrap_index(node_index))
     }
 
     /// 获取所有边
@@ -246,9 +345,11 @@ impl Graph {
                 node_index.belongs_to_graph(self.graph_id)
             );
         }
+        let live = self.is_live(node_index);
         self.graph
             .neighbors_directed(node_index.node_index, petgraph::Direction::Incoming)
-            .map(move |idx| NodeIndex::new(idx, self.graph_id))
+            .filter(move |_| live)
+            .map(move |idx| self.wrap_index(idx))
     }
 
     /// 获取节点的后继节点
@@ -263,9 +364,11 @@ impl Graph {
                 node_index.belongs_to_graph(self.graph_id)
             );
         }
+        let live = self.is_live(node_index);
         self.graph
             .neighbors_directed(node_index.node_index, petgraph::Direction::Outgoing)
-            .map(move |idx| NodeIndex::new(idx, self.graph_id))
+            .filter(move |_| live)
+            .map(move |idx| self.wrap_index(idx))
     }
 
     /// 获取节点的入边
@@ -280,6 +383,9 @@ impl Graph {
                 node_index.belongs_to_graph(self.graph_id)
             );
         }
+        if !self.is_live(node_index) {
+            return Vec::new();
+        }
         self.graph
             .edges_directed(node_index.node_index, petgraph::Direction::Incoming)
             .filter_map(|edge_ref| self.index_to_edge.get(&edge_ref.id()).cloned())
@@ -298,6 +404,9 @@ impl Graph {
                 node_index.belongs_to_graph(self.graph_id)
             );
         }
+        if !self.is_live(node_index) {
+            return Vec::new();
+        }
         self.graph
             .edges_directed(node_index.node_index, petgraph::Direction::Outgoing)
             .filter_map(|edge_ref| self.index_to_edge.get(&edge_ref.id()).cloned())
@@ -314,7 +423,17 @@ impl Graph {
         &mut self.config
     }
 
-    /// 查找边
+    /// 获取从 `source` 到 `target` 的所有边，包括命名的平行边
+    ///
+    /// 对应 graphlib 的 outEdges(v, w)
+    pub fn edges_between(&self, source: NodeIndex, target: NodeIndex) -> Vec<Edge> {
+        self.out_edges(source)
+            .into_iter()
+            .filter(|edge| edge.target == target)
+            .collect()
+    }
+
+    /// 查找未命名的边，命名边使用 `edge_label` 或 `edges_between`
     pub fn find_edge(&self, source: NodeIndex, target: NodeIndex) -> Option<EdgeIndex> {
         if !source.belongs_to_graph(self.graph_id) {
             panic!(
@@ -364,7 +483,7 @@ impl Graph {
                 node_index.belongs_to_graph(self.graph_id)
             );
         }
-        self.graph.node_weight(node_index.node_index).is_some()
+        self.is_live(node_index)
     }
 
     /// 检查边是否存在
@@ -373,6 +492,9 @@ impl Graph {
     }
 
     /// 移除节点
+    ///
+    /// 同时移除节点的所有入边和出边，其他节点和边的索引保持不变。
+    /// 子节点成为顶层节点，约束中对该节点的引用一并删除。
     pub fn remove_node(&mut self, node_index: NodeIndex) -> Option<NodeLabel> {
         if !node_index.belongs_to_graph(self.graph_id) {
             panic!(
@@ -384,17 +506,43 @@ impl Graph {
                 node_index.belongs_to_graph(self.graph_id)
             );
         }
+        if !self.is_live(node_index) {
+            return None;
+        }
+        // 先通过边映射移除关联边，保持映射与 petgraph 一致
+        let mut incident = self.in_edges(node_index);
+        incident.extend(self.out_edges(node_index));
+        for edge in incident {
+            self.remove_edge(&edge);
+        }
+        // 节点索引可能被复用，不能留在子节点的父节点和约束中
+        for child in self.children(node_index) {
+            if let Some(label) = self.node_label_mut(child) {
+                label.parent = None;
+            }
+        }
+        for constraint in &mut self.config.rank_constraints {
+            constraint.nodes.retain(|&node| node != node_index);
+        }
+        self.config
+            .rank_constraints
+            .retain(|constraint| !constraint.nodes.is_empty());
+        for constraint in &mut self.config.order_constraints {
+            constraint.nodes.retain(|&node| node != node_index);
+        }
+        self.config
+            .order_constraints
+            .retain(|constraint| !constraint.nodes.is_empty());
+        // 槽位复用时旧索引随代数一起失效
+        self.generations[node_index.index()] += 1;
         self.graph.remove_node(node_index.node_index)
     }
 
-    /// 移除边
+    /// 移除边，平行边中只移除名称相同的那一条
     pub fn remove_edge(&mut self, edge: &Edge) -> Option<EdgeLabel> {
-        if let Some(edge_index) = self.edge_to_index.swap_remove(edge) {
-            self.index_to_edge.swap_remove(&edge_index);
-            self.graph.remove_edge(edge_index)
-        } else {
-            None
-        }
+        let edge_index = self.edge_to_index.swap_remove(edge)?;
+        self.index_to_edge.swap_remove(&edge_index);
+        self.graph.remove_edge(edge_index)
     }
 
     /// 获取子图（用于复合图支持）
@@ -424,6 +572,8 @@ impl Graph {
     }
 
     /// 设置父节点（用于复合图支持）
+    ///
+    /// 节点属于其他图时 panic，需要错误处理时使用 `try_set_parent`
     pub fn set_parent(&mut self, child_index: NodeIndex, parent_index: NodeIndex) {
         if !child_index.belongs_to_graph(self.graph_id) {
             panic!(
@@ -443,7 +593,21 @@ impl Graph {
         }
     }
 
+    /// 设置父节点，子节点或父节点不属于当前图或已不存在时返回错误而不是 panic
+    pub fn try_set_parent(
+        &mut self,
+        child_index: NodeIndex,
+        parent_index: NodeIndex,
+    ) -> Result<(), LayoutError> {
+        self.check_node(child_index, None)?;
+        self.check_node(parent_index, None)?;
+        self.graph[child_index.node_index].parent = Some(parent_index);
+        Ok(())
+    }
+
     /// 获取父节点
+    ///
+    /// 节点属于其他图时 panic，需要错误处理时使用 `try_parent`
     pub fn parent(&self, child_index: NodeIndex) -> Option<NodeIndex> {
         if !child_index.belongs_to_graph(self.graph_id) {
             panic!(
@@ -454,13 +618,92 @@ impl Graph {
         self.node_label(child_index)?.parent
     }
 
+    /// 获取父节点，节点不属于当前图或已不存在时返回错误而不是 panic
+    pub fn try_parent(&self, child_index: NodeIndex) -> Result<Option<NodeIndex>, LayoutError> {
+        Ok(self.try_node_label(child_index)?.parent)
+    }
+
+    /// 添加排名约束，约束中的节点必须属于该图
+    ///
+    /// 节点属于其他图时 panic，需要错误处理时使用 `try_add_rank_constraint`
+    pub fn add_rank_constraint(&mut self, constraint: RankConstraint) {
+        for &node in &constraint.nodes {
+            if !node.belongs_to_graph(self.graph_id) {
+                panic!(
+                    "Node belongs to graph {}, but current graph is {}",
+                    node.which_graph, self.graph_id
+                );
+            }
+        }
+        self.config.rank_constraints.push(constraint);
+    }
+
+    /// 添加排名约束，约束中的节点不属于当前图或已不存在时返回错误而不是 panic
+    pub fn try_add_rank_constraint(
+        &mut self,
+        constraint: RankConstraint,
+    ) -> Result<(), LayoutError> {
+        for &node in &constraint.nodes {
+            self.check_node(node, None)?;
+        }
+        self.config.rank_constraints.push(constraint);
+        Ok(())
+    }
+
+    /// 所有排名约束，按添加顺序排列
+    pub fn rank_constraints(&self) -> &[RankConstraint] {
+        &self.config.rank_constraints
+    }
+
+    /// 移除所有排名约束
+    pub fn clear_rank_constraints(&mut self) {
+        self.config.rank_constraints.clear();
+    }
+
+    /// 添加顺序约束，约束中的节点必须属于该图
+    ///
+    /// 节点属于其他图时 panic，需要错误处理时使用 `try_add_order_constraint`
+    pub fn add_order_constraint(&mut self, constraint: OrderConstraint) {
+        for &node in &constraint.nodes {
+            if !node.belongs_to_graph(self.graph_id) {
+                panic!(
+                    "Node belongs to graph {}, but current graph is {}",
+                    node.which_graph, self.graph_id
+                );
+            }
+        }
+        self.config.order_constraints.push(constraint);
+    }
+
+    /// 添加顺序约束，约束中的节点不属于当前图或已不存在时返回错误而不是 panic
+    pub fn try_add_order_constraint(
+        &mut self,
+        constraint: OrderConstraint,
+    ) -> Result<(), LayoutError> {
+        for &node in &constraint.nodes {
+            self.check_node(node, None)?;
+        }
+        self.config.order_constraints.push(constraint);
+        Ok(())
+    }
+
+    /// 所有顺序约束，按添加顺序排列
+    pub fn order_constraints(&self) -> &[OrderConstraint] {
+        &self.config.order_constraints
+    }
+
+    /// 移除所有顺序约束
+    pub fn clear_order_constraints(&mut self) {
+        self.config.order_constraints.clear();
+    }
+
     /// 获取 petgraph 实例（用于高级操作）
-    pub fn petgraph(&self) -> &PetGraph<NodeLabel, EdgeLabel, Directed> {
+    pub fn petgraph(&self) -> &StableGraph<NodeLabel, EdgeLabel, Directed> {
         &self.graph
     }
 
     /// 获取 petgraph 实例（可变，用于高级操作）
-    pub fn petgraph_mut(&mut self) -> &mut PetGraph<NodeLabel, EdgeLabel, Directed> {
+    pub fn petgraph_mut(&mut self) -> &mut StableGraph<NodeLabel, EdgeLabel, Directed> {
         &mut self.graph
     }
 }
@@ -507,6 +750,29 @@ mod tests {
         assert_eq!(graph.edge_count(), 1);
         assert!(graph.has_edge(&edge));
     }
+
+    #[test]
+    fn test_try_add_edge_rejects_foreign_node() {
+        let mut graph = Graph::new();
+        let mut other = Graph::new();
+        let a = graph.add_node(NodeLabel::default());
+        let foreign = other.add_node(NodeLabel::default());
+
+        let edge = Edge::new(a, foreign);
+        let err = graph
+            .try_add_edge(edge.clone(), EdgeLabel::default())
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            LayoutError::ForeignNode {
+                node: foreign,
+                graph_id: graph.graph_id(),
+                edge: Some(edge),
+            }
+        );
+        assert_eq!(graph.edge_count(), 0);
+    }
 }
 
 /// 全局图 ID 计数器
@@ -519,21 +785,25 @@ pub struct NodeIndex {
     pub node_index: petgraph::graph::NodeIndex,
     /// 所属图的 ID
     pub which_graph: u32,
+    /// 所在槽位的代数，槽位被复用后旧索引失效
+    pub generation: u32,
 }
 
 impl NodeIndex {
     /// 创建新的 WrappedNodeIndex
-    fn new(node_index: petgraph::prelude::NodeIndex, which_graph: u32) -> Self {
+    fn new(node_index: petgraph::prelude::NodeIndex, which_graph: u32, generation: u32) -> Self {
         Self {
             node_index,
             which_graph,
+            generation,
         }
     }
-    /// 创建新的 WrappedNodeIndex
+    /// 创建新的 WrappedNodeIndex，代数为 0，只对从未删除过节点的槽位有效
     pub fn new_raw(usize_index: usize, which_graph: u32) -> Self {
         Self {
             node_index: petgraph::prelude::NodeIndex::new(usize_index),
             which_graph,
+            generation: 0,
         }
     }
 
@@ -548,6 +818,7 @@ impl NodeIndex {
         Self {
             node_index,
             which_graph: 0, // 无效的图 ID，需要后续设置
+            generation: 0,
         }
     }
 
@@ -572,6 +843,7 @@ impl Default for NodeIndex {
         Self {
             node_index: petgraph::prelude::NodeIndex::new(0),
             which_graph: 0,
+            generation: 0,
         }
     }
 }
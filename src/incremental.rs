@@ -0,0 +1,266 @@
+//! 增量布局
+//!
+//! 图只发生局部变化时，把上一次布局的排名、顺序和坐标作为提示传给 `LayoutOptions::hints`，
+//! 新布局尽量保持原来的样子，只在变化附近调整：
+//!
+//! - [`anchor_ranks`] 网络单纯形为有提示的节点增加偏离原排名的代价
+//! - [`seed_order`] 初始排序按原顺序排列，之后的扫描只接受交叉更少的排序
+//! - `BrandesKoepf::hint_coordinates` 有提示的节点回到原来的横坐标，同层其他节点向两侧推开
+//! - [`translate_to_hints`] 整体平移，使没有移动的节点坐标保持不变
+//! - [`measure_displacement`] 统计节点相对上一次布局的位移
+//!
+//! 提示以 `NodeIndex` 为键。删除节点后索引可能被新节点复用，这时应先从提示中移除被删除的节点。
+
+use crate::context::LayoutContext;
+use crate::graph::{Graph, NodeIndex};
+use crate::stats::Displacement;
+use crate::types::{Edge, EdgeLabel, NodeLabel};
+use indexmap::IndexMap;
+
+/// 默认的排名变化代价
+pub const DEFAULT_RANK_WEIGHT: f64 = 2.0;
+
+/// 位移小于该值时视为没有移动
+const MOVE_EPSILON: f64 = 1e-6;
+
+/// 单个节点在上一次布局中的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeHint {
+    /// 排名，单位与 `NodeLabel::rank` 的布局结果相同
+    pub rank: Option<i32>,
+    /// 在排名中的顺序
+    pub order: Option<usize>,
+    /// X 坐标，与布局结果使用同一坐标系
+    pub x: f64,
+    /// Y 坐标，与布局结果使用同一坐标系
+    pub y: f64,
+}
+
+/// 增量布局提示
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutHints {
+    /// 节点 -> 上一次布局的结果，只包含叶子节点
+    pub nodes: IndexMap<NodeIndex, NodeHint>,
+    /// 节点排名偏离原排名一个单位的代价，与同样权重的边伸长一个单位相同
+    ///
+    /// 网络单纯形按整数计算代价，小数部分会被舍去。
+    pub rank_weight: f64,
+}
+
+impl Default for LayoutHints {
+    fn default() -> Self {
+        Self {
+            nodes: IndexMap::new(),
+            rank_weight: DEFAULT_RANK_WEIGHT,
+        }
+    }
+}
+
+impl LayoutHints {
+    /// 从布局过的图中读取提示，没有坐标的节点被跳过
+    pub fn from_graph(graph: &Graph) -> Self {
+        let nodes = graph
+            .node_indices()
+            .filter(|&node| graph.children(node).is_empty())
+            .filter_map(|node| {
+                let label = graph.node_label(node)?;
+                let hint = NodeHint {
+                    rank: label.rank,
+                    order: label.order,
+                    x: label.x?,
+                    y: label.y?,
+                };
+                Some((node, hint))
+            })
+            .collect();
+        Self {
+            nodes,
+            ..Default::default()
+        }
+    }
+
+    /// 设置排名变化的代价
+    pub fn with_rank_weight(mut self, rank_weight: f64) -> Self {
+        self.rank_weight = rank_weight;
+        self
+    }
+
+    /// 是否没有任何提示
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// 按节点映射转换到另一个图，映射中不存在的节点被丢弃
+    pub fn remap(&self, old_to_new: &IndexMap<NodeIndex, NodeIndex>) -> Self {
+        let nodes = self
+            .nodes
+            .iter()
+            .filter_map(|(node, &hint)| Some((*old_to_new.get(node)?, hint)))
+            .collect();
+        Self {
+            nodes,
+            rank_weight: self.rank_weight,
+        }
+    }
+}
+
+/// 为有提示的节点增加偏离原排名的代价
+///
+/// 在网络单纯形使用的简单图上添加一个锚点，并为每个原排名添加一个目标节点，锚点到目标节点的
+/// 边长度等于原排名与最小原排名之差，权重足够大，因此长度不会改变。每个有提示的节点通过一个
+/// 辅助节点连到它原排名的目标节点，两条辅助边的最小总长度就是排名变化的绝对值。
+/// 最小化带权边长的同时也就最小化了排名变化。
+pub fn anchor_ranks(graph: &mut Graph, hints: &LayoutHints) {
+    let ranked: Vec<(NodeIndex, i32)> = hints
+        .nodes
+        .iter()
+        .filter(|(node, _)| graph.node_label(**node).is_some())
+        .filter_map(|(&node, hint)| Some((node, hint.rank?)))
+        .collect();
+    let Some(min_rank) = ranked.iter().map(|&(_, rank)| rank).min() else {
+        return;
+    };
+    let weight = hints.rank_weight.max(0.0);
+    let anchor_weight = weight * ranked.len() as f64 + 1.0;
+
+    let anchor = graph.add_node(NodeLabel::default());
+    let mut targets: IndexMap<i32, NodeIndex> = IndexMap::new();
+    for (node, rank) in ranked {
+        let target = *targets.entry(rank).or_insert_with(|| {
+            let target = graph.add_node(NodeLabel::default());
+            let label = EdgeLabel {
+                minlen: rank - min_rank,
+                weight: anchor_weight,
+                ..Default::default()
+            };
+            graph.add_edge(Edge::new(anchor, target), label);
+            target
+        });
+
+        let aux = graph.add_node(NodeLabel::default());
+        for w in [node, target] {
+            let label = EdgeLabel {
+                minlen: 0,
+                weight,
+                ..Default::default()
+            };
+            graph.add_edge(Edge::new(aux, w), label);
+        }
+    }
+}
+
+/// 按提示的顺序调整初始排序
+///
+/// 有提示的节点以原顺序为键；其他节点（新节点和虚拟节点）先自上而下取已有键的前驱的平均值，
+/// 再自下而上取后继的平均值，仍然没有键的节点使用它在初始排序中的位置。每层按键稳定排序。
+pub fn seed_order(
+    graph: &Graph,
+    hints: &LayoutHints,
+    mut layering: Vec<Vec<NodeIndex>>,
+) -> Vec<Vec<NodeIndex>> {
+    if hints.is_empty() {
+        return layering;
+    }
+
+    let mut keys: IndexMap<NodeIndex, f64> = layering
+        .iter()
+        .flatten()
+        .filter_map(|node| Some((*node, hints.nodes.get(node)?.order? as f64)))
+        .collect();
+
+    for downward in [true, false] {
+        let layers: Vec<&Vec<NodeIndex>> = if downward {
+            layering.iter().collect()
+        } else {
+            layering.iter().rev().collect()
+        };
+        for layer in layers {
+            for &v in layer {
+                if keys.contains_key(&v) {
+                    continue;
+                }
+                let neighbors: Vec<f64> = if downward {
+                    graph.predecessors(v).collect::<Vec<_>>()
+                } else {
+                    graph.successors(v).collect::<Vec<_>>()
+                }
+                .into_iter()
+                .filter_map(|u| keys.get(&u).copied())
+                .collect();
+                if !neighbors.is_empty() {
+                    let key = neighbors.iter().sum::<f64>() / neighbors.len() as f64;
+                    keys.insert(v, key);
+                }
+            }
+        }
+    }
+
+    for layer in &mut layering {
+        let mut keyed: Vec<(f64, NodeIndex)> = layer
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (keys.get(v).copied().unwrap_or(i as f64), *v))
+            .collect();
+        keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+        *layer = keyed.into_iter().map(|(_, v)| v).collect();
+    }
+    layering
+}
+
+/// 整体平移图，使没有移动的节点回到上一次布局的坐标
+///
+/// 在 `translate_graph` 之后调用。平移量取有提示节点坐标差的中位数，只有少数节点移动时
+/// 其余节点的坐标保持不变。有固定节点时由固定节点决定平移，这里不做处理。
+pub fn translate_to_hints(graph: &mut Graph, ctx: &mut LayoutContext) {
+    if !ctx.pins.is_empty() {
+        return;
+    }
+    let (mut dxs, mut dys): (Vec<f64>, Vec<f64>) = ctx
+        .hints
+        .nodes
+        .iter()
+        .filter_map(|(&node, hint)| {
+            let label = graph.node_label(node)?;
+            Some((hint.x - label.x?, hint.y - label.y?))
+        })
+        .unzip();
+    if dxs.is_empty() {
+        return;
+    }
+    let dx = median(&mut dxs);
+    let dy = median(&mut dys);
+    crate::position::shift_graph(graph, dx, dy);
+}
+
+/// 统计有提示的节点相对上一次布局的位移，结果保存在布局统计中
+pub fn measure_displacement(graph: &mut Graph, ctx: &mut LayoutContext) {
+    if ctx.hints.is_empty() {
+        return;
+    }
+    let distances: Vec<f64> = ctx
+        .hints
+        .nodes
+        .iter()
+        .filter_map(|(&node, hint)| {
+            let label = graph.node_label(node)?;
+            Some((label.x? - hint.x).hypot(label.y? - hint.y))
+        })
+        .collect();
+
+    ctx.stats.displacement = Some(Displacement {
+        nodes: distances.len(),
+        moved: distances.iter().filter(|&&d| d > MOVE_EPSILON).count(),
+        total: distances.iter().sum(),
+        max: distances.iter().copied().fold(0.0, f64::max),
+    });
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
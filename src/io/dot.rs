@@ -0,0 +1,877 @@
+//! Graphviz DOT 解析
+//!
+//! 支持布局需要的 DOT 子集：
+//!
+//! - `digraph`（可带 `strict` 和图名），不支持无向图 `graph`
+//! - 节点语句、边语句（`a -> b -> c`，操作数也可以是子图 `{ b c }`）
+//! - `graph`/`node`/`edge` 默认属性语句和 `key = value` 图属性，默认属性按子图作用域生效
+//! - `subgraph cluster_*` 生成一个复合节点，其中的节点通过 `set_parent` 挂到该节点下
+//! - 子图的 `rank` 属性（`same`、`min`、`max`、`source`、`sink`）转换为图的排名约束，
+//!   `rank = same` 的分组另外记录在 [`DotGraph::same_rank`] 中
+//!
+//! 属性按 Graphviz 的语义转换：`width`、`height`、`nodesep`、`ranksep` 的单位是英寸，
+//! 转换为 72 点每英寸；未指定时使用 Graphviz 的默认值（节点 0.75 x 0.5 英寸、
+//! `nodesep` 0.25 英寸、`ranksep` 0.5 英寸）。节点标签默认为节点 ID。
+//! `label`、`minlen`、`weight`、`rankdir` 映射到对应字段，其余属性以字符串保存在 `custom` 中。
+//! 边的 `constraint` 属性映射为 [`EdgeLabel::constraint`]，接受 `true`/`false`、`yes`/`no` 和整数。
+//! 端口的罗盘方向（`a:n`、`a:p:se`，以及 `tailport`、`headport` 属性）在节点上创建同名端口，
+//! 边连接到这些端口；命名端口依赖 record 和 HTML 标签，会被忽略。
+//! 节点的 `shape` 属性同时映射为 [`NodeShape`]：`box`、`ellipse`、`circle`、`diamond`、`hexagon`
+//! 等转换为对应的形状，其他形状按矩形布局；未指定时使用矩形，而不是 Graphviz 默认的椭圆。
+
+use super::{FormatError, NodeIds};
+use crate::graph::{Graph, NodeIndex};
+use crate::types::{
+    Edge, EdgeLabel, GraphConfig, NodeLabel, NodeShape, Port, PortSide, RankConstraint,
+    RankDirection, RankKind,
+};
+use serde_json::Value;
+
+/// 每英寸的点数
+const POINTS_PER_INCH: f64 = 72.0;
+
+/// 解析结果
+#[derive(Clone)]
+pub struct DotGraph {
+    /// 构建出的图
+    pub graph: Graph,
+    /// DOT 节点 ID 到图节点的映射
+    pub ids: NodeIds,
+    /// 集群子图名到复合节点的映射
+    pub clusters: NodeIds,
+    /// `rank = same` 子图中的节点分组，与图中 `RankKind::Same` 约束的节点相同
+    pub same_rank: Vec<Vec<NodeIndex>>,
+}
+
+/// 解析 DOT 源码
+pub fn parse(source: &str) -> Result<DotGraph, FormatError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser::new(tokens);
+    parser.parse_graph()?;
+    Ok(DotGraph {
+        graph: parser.graph,
+        ids: parser.ids,
+        clusters: parser.clusters,
+        same_rank: parser.same_rank,
+    })
+}
+
+/// 词法单元
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// 标识符、数字、字符串或 HTML 字符串
+    Id(String),
+    /// 不区分大小写的关键字
+    Keyword(Keyword),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Semi,
+    Comma,
+    Equal,
+    Colon,
+    /// `->`
+    Arrow,
+    /// `--`
+    Line,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Keyword {
+    Strict,
+    Graph,
+    Digraph,
+    Node,
+    Edge,
+    Subgraph,
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> FormatError {
+    FormatError::Syntax {
+        line,
+        message: message.into(),
+    }
+}
+
+/// 词法分析，返回 (词法单元, 行号) 列表
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, FormatError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line = 1;
+    let mut line_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\n' {
+            line += 1;
+            line_start = true;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        // 预处理器输出的行，例如 `# 1 "file.dot"`
+        if c == '#' && line_start {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        line_start = false;
+
+        // 注释
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            let start_line = line;
+            i += 2;
+            loop {
+                match chars.get(i) {
+                    None => return Err(syntax(start_line, "unterminated comment")),
+                    Some('*') if chars.get(i + 1) == Some(&'/') => {
+                        i += 2;
+                        break;
+                    }
+                    Some('\n') => line += 1,
+                    Some(_) => {}
+                }
+                i += 1;
+            }
+            continue;
+        }
+
+        let token_line = line;
+        let token = match c {
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ';' => Token::Semi,
+            ',' => Token::Comma,
+            '=' => Token::Equal,
+            ':' => Token::Colon,
+            '-' if chars.get(i + 1) == Some(&'>') => {
+                i += 1;
+                Token::Arrow
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                i += 1;
+                Token::Line
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    let (part, next) = read_quoted(&chars, i, &mut line)?;
+                    text.push_str(&part);
+                    i = next;
+                    // `"a" + "b"` 拼接
+                    let mut j = i;
+                    let mut lines = 0;
+                    while j < chars.len() && chars[j].is_whitespace() {
+                        if chars[j] == '\n' {
+                            lines += 1;
+                        }
+                        j += 1;
+                    }
+                    if chars.get(j) != Some(&'+') {
+                        break;
+                    }
+                    let mut k = j + 1;
+                    while k < chars.len() && chars[k].is_whitespace() {
+                        if chars[k] == '\n' {
+                            lines += 1;
+                        }
+                        k += 1;
+                    }
+                    if chars.get(k) != Some(&'"') {
+                        break;
+                    }
+                    line += lines;
+                    i = k;
+                }
+                tokens.push((Token::Id(text), token_line));
+                continue;
+            }
+            '<' => {
+                let mut depth = 0;
+                let mut text = String::new();
+                loop {
+                    match chars.get(i) {
+                        None => return Err(syntax(token_line, "unterminated HTML string")),
+                        Some('<') => {
+                            if depth > 0 {
+                                text.push('<');
+                            }
+                            depth += 1;
+                        }
+                        Some('>') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                            text.push('>');
+                        }
+                        Some(&other) => {
+                            if other == '\n' {
+                                line += 1;
+                            }
+                            text.push(other);
+                        }
+                    }
+                    i += 1;
+                }
+                Token::Id(text)
+            }
+            c if c == '-' || c == '.' || c.is_ascii_digit() => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                if text.parse::<f64>().is_err() {
+                    return Err(syntax(token_line, format!("invalid number {}", text)));
+                }
+                tokens.push((Token::Id(text), token_line));
+                continue;
+            }
+            c if c == '_' || c.is_alphabetic() => {
+                let start = i;
+                while i < chars.len() && (chars[i] == '_' || chars[i].is_alphanumeric()) {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let token = match text.to_ascii_lowercase().as_str() {
+                    "strict" => Token::Keyword(Keyword::Strict),
+                    "graph" => Token::Keyword(Keyword::Graph),
+                    "digraph" => Token::Keyword(Keyword::Digraph),
+                    "node" => Token::Keyword(Keyword::Node),
+                    "edge" => Token::Keyword(Keyword::Edge),
+                    "subgraph" => Token::Keyword(Keyword::Subgraph),
+                    _ => Token::Id(text),
+                };
+                tokens.push((token, token_line));
+                continue;
+            }
+            other => {
+                return Err(syntax(
+                    token_line,
+                    format!("unexpected character {:?}", other),
+                ));
+            }
+        };
+        tokens.push((token, token_line));
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+/// 读取以 `chars[start]` 开头的双引号字符串，返回内容和结束后的位置
+fn read_quoted(
+    chars: &[char],
+    start: usize,
+    line: &mut usize,
+) -> Result<(String, usize), FormatError> {
+    let start_line = *line;
+    let mut text = String::new();
+    let mut i = start + 1;
+    loop {
+        match chars.get(i) {
+            None => return Err(syntax(start_line, "unterminated string")),
+            Some('"') => return Ok((text, i + 1)),
+            Some('\\') if chars.get(i + 1) == Some(&'"') => {
+                text.push('"');
+                i += 1;
+            }
+            // 行尾的反斜杠表示续行
+            Some('\\') if chars.get(i + 1) == Some(&'\n') => {
+                *line += 1;
+                i += 1;
+            }
+            Some(&other) => {
+                if other == '\n' {
+                    *line += 1;
+                }
+                text.push(other);
+            }
+        }
+        i += 1;
+    }
+}
+
+/// 子图作用域中的默认属性
+#[derive(Clone, Default)]
+struct Scope {
+    node_defaults: Vec<(String, String)>,
+    edge_defaults: Vec<(String, String)>,
+    /// 最近的集群复合节点
+    cluster: Option<NodeIndex>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    strict: bool,
+    graph: Graph,
+    ids: NodeIds,
+    clusters: NodeIds,
+    same_rank: Vec<Vec<NodeIndex>>,
+}
+
+impl Parser {
+    fn new(tokens: Vec<(Token, usize)>) -> Self {
+        let config = GraphConfig {
+            node_sep: 0.25 * POINTS_PER_INCH,
+            rank_sep: 0.5 * POINTS_PER_INCH,
+            ..Default::default()
+        };
+        Self {
+            tokens,
+            pos: 0,
+            strict: false,
+            graph: Graph::with_config(config),
+            ids: NodeIds::new(),
+            clusters: NodeIds::new(),
+            same_rank: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: &Token, what: &str) -> Result<(), FormatError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(syntax(self.line(), format!("expected {}", what)))
+        }
+    }
+
+    fn expect_id(&mut self, what: &str) -> Result<String, FormatError> {
+        match self.peek() {
+            Some(Token::Id(text)) => {
+                let text = text.clone();
+                self.pos += 1;
+                Ok(text)
+            }
+            _ => Err(syntax(self.line(), format!("expected {}", what))),
+        }
+    }
+
+    /// graph : [strict] digraph [ID] '{' stmt_list '}'
+    fn parse_graph(&mut self) -> Result<(), FormatError> {
+        self.strict = self.eat(&Token::Keyword(Keyword::Strict));
+        match self.next() {
+            Some(Token::Keyword(Keyword::Digraph)) => {}
+            Some(Token::Keyword(Keyword::Graph)) => {
+                return Err(FormatError::Unsupported("undirected graph".to_string()));
+            }
+            _ => return Err(syntax(self.line(), "expected digraph")),
+        }
+        if let Some(Token::Id(_)) = self.peek() {
+            self.pos += 1;
+        }
+        self.expect(&Token::LBrace, "'{'")?;
+        let mut scope = Scope::default();
+        self.parse_stmt_list(&mut scope, &mut Vec::new(), &mut None)?;
+        self.expect(&Token::RBrace, "'}'")?;
+        if self.peek().is_some() {
+            return Err(syntax(self.line(), "unexpected input after graph"));
+        }
+        Ok(())
+    }
+
+    /// 解析语句列表直到 '}'，`members` 收集出现过的节点，`rank` 收集 `rank` 属性
+    fn parse_stmt_list(
+        &mut self,
+        scope: &mut Scope,
+        members: &mut Vec<NodeIndex>,
+        rank: &mut Option<String>,
+    ) -> Result<(), FormatError> {
+        while !matches!(self.peek(), Some(Token::RBrace) | None) {
+            self.parse_stmt(scope, members, rank)?;
+            self.eat(&Token::Semi);
+        }
+        Ok(())
+    }
+
+    fn parse_stmt(
+        &mut self,
+        scope: &mut Scope,
+        members: &mut Vec<NodeIndex>,
+        rank: &mut Option<String>,
+    ) -> Result<(), FormatError> {
+        match self.peek() {
+            Some(Token::Keyword(Keyword::Graph)) => {
+                self.pos += 1;
+                for (key, value) in self.parse_attr_lists()? {
+                    self.apply_graph_attr(scope, rank, key, value)?;
+                }
+            }
+            Some(Token::Keyword(Keyword::Node)) => {
+                self.pos += 1;
+                let attrs = self.parse_attr_lists()?;
+                scope.node_defaults.extend(attrs);
+            }
+            Some(Token::Keyword(Keyword::Edge)) => {
+                self.pos += 1;
+                let attrs = self.parse_attr_lists()?;
+                scope.edge_defaults.extend(attrs);
+            }
+            Some(Token::Id(_)) if self.peek_at(1) == Some(&Token::Equal) => {
+                let key = self.expect_id("attribute name")?;
+                self.pos += 1;
+                let value = self.expect_id("attribute value")?;
+                self.apply_graph_attr(scope, rank, key, value)?;
+            }
+            Some(Token::Id(_) | Token::LBrace | Token::Keyword(Keyword::Subgraph)) => {
+                let first = self.parse_operand(scope, members)?;
+                if matches!(self.peek(), Some(Token::Arrow | Token::Line)) {
+                    self.parse_edge_rhs(scope, members, first)?;
+                } else if let Operand::Node(node, _) = first {
+                    let attrs = self.parse_attr_lists()?;
+                    self.apply_node_attrs(node, attrs)?;
+                }
+            }
+            _ => return Err(syntax(self.line(), "expected statement")),
+        }
+        Ok(())
+    }
+
+    /// 解析边语句的剩余部分：('->' operand)+ [attr_list]
+    fn parse_edge_rhs(
+        &mut self,
+        scope: &mut Scope,
+        members: &mut Vec<NodeIndex>,
+        first: Operand,
+    ) -> Result<(), FormatError> {
+        let mut operands = vec![first];
+        loop {
+            match self.peek() {
+                Some(Token::Arrow) => self.pos += 1,
+                Some(Token::Line) => {
+                    return Err(syntax(self.line(), "'--' is not allowed in a digraph"));
+                }
+                _ => break,
+            }
+            operands.push(self.parse_operand(scope, members)?);
+        }
+
+        let mut attrs = scope.edge_defaults.clone();
+        attrs.extend(self.parse_attr_lists()?);
+
+        for pair in operands.windows(2) {
+            for (source, source_port) in pair[0].endpoints() {
+                for (target, target_port) in pair[1].endpoints() {
+                    self.add_edge((source, source_port), (target, target_port), &attrs)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 解析节点 ID（可带端口）或子图
+    fn parse_operand(
+        &mut self,
+        scope: &mut Scope,
+        members: &mut Vec<NodeIndex>,
+    ) -> Result<Operand, FormatError> {
+        match self.peek() {
+            Some(Token::LBrace | Token::Keyword(Keyword::Subgraph)) => {
+                let nodes = self.parse_subgraph(scope)?;
+                members.extend(nodes.iter().copied());
+                Ok(Operand::Subgraph(nodes))
+            }
+            _ => {
+                let id = self.expect_id("node id")?;
+                // 端口 `a:port` 或 `a:port:compass`，只保留罗盘方向，命名端口被忽略
+                let mut compass = None;
+                for _ in 0..2 {
+                    if self.eat(&Token::Colon) {
+                        let port = self.expect_id("port")?;
+                        compass = compass_port(&port).map(|_| port).or(compass);
+                    }
+                }
+                let node = self.node(&id, scope)?;
+                members.push(node);
+                Ok(Operand::Node(node, compass))
+            }
+        }
+    }
+
+    /// subgraph : [subgraph [ID]] '{' stmt_list '}'
+    fn parse_subgraph(&mut self, parent_scope: &Scope) -> Result<Vec<NodeIndex>, FormatError> {
+        let mut name = None;
+        if self.eat(&Token::Keyword(Keyword::Subgraph))
+            && let Some(Token::Id(text)) = self.peek()
+        {
+            name = Some(text.clone());
+            self.pos += 1;
+        }
+
+        let mut scope = parent_scope.clone();
+        if let Some(name) = name.as_ref().filter(|name| name.starts_with("cluster")) {
+            let cluster = match self.clusters.get(name) {
+                Some(&cluster) => cluster,
+                None => {
+                    let cluster = self.graph.add_node(NodeLabel::default());
+                    if let Some(outer) = parent_scope.cluster {
+                        self.graph.set_parent(cluster, outer);
+                    }
+                    self.clusters.insert(name.clone(), cluster);
+                    cluster
+                }
+            };
+            scope.cluster = Some(cluster);
+        }
+
+        self.expect(&Token::LBrace, "'{'")?;
+        let mut members = Vec::new();
+        let mut rank = None;
+        self.parse_stmt_list(&mut scope, &mut members, &mut rank)?;
+        self.expect(&Token::RBrace, "'}'")?;
+
+        match rank.as_deref().map(str::parse::<RankKind>) {
+            Some(Ok(kind)) if !members.is_empty() => {
+                let mut group: Vec<NodeIndex> = Vec::new();
+                for &node in &members {
+                    if !group.contains(&node) {
+                        group.push(node);
+                    }
+                }
+                if kind == RankKind::Same {
+                    self.same_rank.push(group.clone());
+                }
+                self.graph
+                    .add_rank_constraint(RankConstraint::new(kind, group));
+            }
+            Some(Ok(_)) | None => {}
+            Some(Err(_)) => log::warn!(
+                "rank={} is not supported and was ignored",
+                rank.unwrap_or_default()
+            ),
+        }
+
+        Ok(members)
+    }
+
+    /// 解析一个或多个 '[' a_list ']'
+    fn parse_attr_lists(&mut self) -> Result<Vec<(String, String)>, FormatError> {
+        let mut attrs = Vec::new();
+        while self.eat(&Token::LBracket) {
+            while !self.eat(&Token::RBracket) {
+                let key = self.expect_id("attribute name")?;
+                let value = if self.eat(&Token::Equal) {
+                    self.expect_id("attribute value")?
+                } else {
+                    "true".to_string()
+                };
+                attrs.push((key, value));
+                if !self.eat(&Token::Comma) {
+                    self.eat(&Token::Semi);
+                }
+            }
+        }
+        Ok(attrs)
+    }
+
+    /// 查找节点，首次出现时按作用域的默认属性创建
+    ///
+    /// 已存在的节点出现在更深的集群中时，移到该集群下。
+    fn node(&mut self, id: &str, scope: &Scope) -> Result<NodeIndex, FormatError> {
+        if let Some(&node) = self.ids.get(id) {
+            if let Some(cluster) = scope.cluster {
+                self.assign_cluster(node, cluster);
+            }
+            return Ok(node);
+        }
+
+        let node = self.graph.add_node(NodeLabel {
+            label: Some(id.to_string()),
+            width: 0.75 * POINTS_PER_INCH,
+            height: 0.5 * POINTS_PER_INCH,
+            ..Default::default()
+        });
+        self.ids.insert(id.to_string(), node);
+        if let Some(cluster) = scope.cluster {
+            self.graph.set_parent(node, cluster);
+        }
+        self.apply_node_attrs(node, scope.node_defaults.clone())?;
+        Ok(node)
+    }
+
+    /// 节点没有集群或所在集群是 `cluster` 的祖先时，把它移到 `cluster` 下
+    fn assign_cluster(&mut self, node: NodeIndex, cluster: NodeIndex) {
+        if node == cluster {
+            return;
+        }
+        match self.graph.parent(node) {
+            None => self.graph.set_parent(node, cluster),
+            Some(current) if current != cluster && self.is_ancestor(current, cluster) => {
+                self.graph.set_parent(node, cluster);
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn is_ancestor(&self, ancestor: NodeIndex, mut node: NodeIndex) -> bool {
+        while let Some(parent) = self.graph.parent(node) {
+            if parent == ancestor {
+                return true;
+            }
+            node = parent;
+        }
+        false
+    }
+
+    fn add_edge(
+        &mut self,
+        (source, source_port): (NodeIndex, Option<&str>),
+        (target, target_port): (NodeIndex, Option<&str>),
+        attrs: &[(String, String)],
+    ) -> Result<(), FormatError> {
+        let mut label = EdgeLabel {
+            source_port: source_port.and_then(|port| self.compass_port(source, port)),
+            target_port: target_port.and_then(|port| self.compass_port(target, port)),
+            ..Default::default()
+        };
+        for (key, value) in attrs {
+            match key.as_str() {
+                "label" => label.label = Some(value.clone()),
+                "minlen" => label.minlen = parse_integer(key, value)?,
+                "weight" => label.weight = parse_number(key, value)?,
+                "constraint" => label.constraint = parse_boolean(key, value)?,
+                "tailport" if compass_port(value).is_some() => {
+                    label.source_port = self.compass_port(source, value);
+                }
+                "headport" if compass_port(value).is_some() => {
+                    label.target_port = self.compass_port(target, value);
+                }
+                _ => {
+                    label
+                        .custom
+                        .insert(key.clone(), Value::String(value.clone()));
+                }
+            }
+        }
+
+        // 非 strict 图允许重复的边，后出现的边按序号命名
+        let mut edge = Edge::new(source, target);
+        if !self.strict {
+            let mut n = 1;
+            while self.graph.has_edge(&edge) {
+                edge = Edge::with_name(source, target, n.to_string());
+                n += 1;
+            }
+        }
+        self.graph.add_edge(edge, label);
+        Ok(())
+    }
+
+    /// 在节点上添加罗盘方向对应的端口，返回端口 ID；中心方向（`c`、`_`）返回 `None`
+    fn compass_port(&mut self, node: NodeIndex, compass: &str) -> Option<String> {
+        let (side, offset) = compass_port(compass)?;
+        let label = self.graph.node_label_mut(node)?;
+        if crate::port::find_port(label, compass).is_none() {
+            label
+                .ports
+                .push(Port::new(compass, side).with_offset(offset));
+        }
+        Some(compass.to_string())
+    }
+
+    fn apply_node_attrs(
+        &mut self,
+        node: NodeIndex,
+        attrs: Vec<(String, String)>,
+    ) -> Result<(), FormatError> {
+        let id = self
+            .ids
+            .iter()
+            .find(|&(_, &index)| index == node)
+            .map(|(id, _)| id.clone())
+            .unwrap_or_default();
+        let Some(label) = self.graph.node_label_mut(node) else {
+            return Ok(());
+        };
+        for (key, value) in attrs {
+            match key.as_str() {
+                "label" => label.label = Some(value.replace("\\N", &id)),
+                "width" => label.width = parse_number(&key, &value)? * POINTS_PER_INCH,
+                "height" => label.height = parse_number(&key, &value)? * POINTS_PER_INCH,
+                // 原值仍保存在 `custom` 中，不支持的形状按矩形布局
+                "shape" => {
+                    label.shape = node_shape(&value).unwrap_or_default();
+                    label.custom.insert(key, Value::String(value));
+                }
+                _ => {
+                    label.custom.insert(key, Value::String(value));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_graph_attr(
+        &mut self,
+        scope: &Scope,
+        rank: &mut Option<String>,
+        key: String,
+        value: String,
+    ) -> Result<(), FormatError> {
+        if key == "rank" {
+            *rank = Some(value);
+            return Ok(());
+        }
+
+        // 集群中的图属性属于集群节点
+        if let Some(cluster) = scope.cluster
+            && let Some(label) = self.graph.node_label_mut(cluster)
+        {
+            match key.as_str() {
+                "label" => label.label = Some(value),
+                _ => {
+                    label.custom.insert(key, Value::String(value));
+                }
+            }
+            return Ok(());
+        }
+
+        let config = self.graph.config_mut();
+        match key.as_str() {
+            "rankdir" => {
+                config.rankdir = match value.to_ascii_uppercase().as_str() {
+                    "TB" => RankDirection::TopBottom,
+                    "BT" => RankDirection::BottomTop,
+                    "LR" => RankDirection::LeftRight,
+                    "RL" => RankDirection::RightLeft,
+                    _ => return Err(invalid(&key, &value)),
+                };
+            }
+            "nodesep" => config.node_sep = parse_number(&key, &value)? * POINTS_PER_INCH,
+            "ranksep" => {
+                // `ranksep="1.2 equally"` 只取数值部分
+                let number = value.split_whitespace().next().unwrap_or_default();
+                config.rank_sep = parse_number(&key, number)? * POINTS_PER_INCH;
+            }
+            _ => {
+                config.custom.insert(key, Value::String(value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 边语句的操作数
+enum Operand {
+    /// 节点和它的罗盘端口
+    Node(NodeIndex, Option<String>),
+    Subgraph(Vec<NodeIndex>),
+}
+
+impl Operand {
+    /// 操作数中的节点及其罗盘端口
+    fn endpoints(&self) -> Vec<(NodeIndex, Option<&str>)> {
+        match self {
+            Operand::Node(node, port) => vec![(*node, port.as_deref())],
+            Operand::Subgraph(nodes) => nodes.iter().map(|&node| (node, None)).collect(),
+        }
+    }
+}
+
+/// 罗盘方向对应的端口位置，中心方向和命名端口返回 `None`
+fn compass_port(compass: &str) -> Option<(PortSide, f64)> {
+    match compass {
+        "n" => Some((PortSide::Top, 0.5)),
+        "ne" => Some((PortSide::Top, 1.0)),
+        "e" => Some((PortSide::Right, 0.5)),
+        "se" => Some((PortSide::Bottom, 1.0)),
+        "s" => Some((PortSide::Bottom, 0.5)),
+        "sw" => Some((PortSide::Bottom, 0.0)),
+        "w" => Some((PortSide::Left, 0.5)),
+        "nw" => Some((PortSide::Top, 0.0)),
+        _ => None,
+    }
+}
+
+/// Graphviz 节点形状对应的 [`NodeShape`]，不支持的形状返回 `None`
+fn node_shape(name: &str) -> Option<NodeShape> {
+    match name {
+        "box" | "rect" | "rectangle" | "square" => Some(NodeShape::Rect),
+        "ellipse" | "oval" => Some(NodeShape::Ellipse),
+        "circle" | "doublecircle" => Some(NodeShape::Circle),
+        "diamond" => Some(NodeShape::Diamond),
+        "hexagon" => Some(NodeShape::Hexagon),
+        _ => None,
+    }
+}
+
+fn invalid(key: &str, value: &str) -> FormatError {
+    FormatError::InvalidAttribute {
+        key: key.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn parse_number(key: &str, value: &str) -> Result<f64, FormatError> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| invalid(key, value))
+}
+
+fn parse_integer(key: &str, value: &str) -> Result<i32, FormatError> {
+    value.trim().parse::<i32>().map_err(|_| invalid(key, value))
+}
+
+/// Graphviz 的布尔值：`true`/`false`、`yes`/`no`（不区分大小写），或非零即真的整数
+fn parse_boolean(key: &str, value: &str) -> Result<bool, FormatError> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        number => number
+            .parse::<i64>()
+            .map(|number| number != 0)
+            .map_err(|_| invalid(key, value)),
+    }
+}
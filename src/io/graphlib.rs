@@ -0,0 +1,549 @@
+//! graphlib JSON 格式
+//!
+//! 读写 graphlib `json.write()` / `json.read()` 使用的格式：
+//!
+//! ```json
+//! {
+//!   "options": { "directed": true, "multigraph": true, "compound": true },
+//!   "nodes": [
+//!     { "v": "a", "value": { "width": 50, "height": 20 }, "parent": "cluster" },
+//!     { "v": "cluster" }
+//!   ],
+//!   "edges": [{ "v": "a", "w": "b", "name": "x", "value": { "minlen": 2 } }],
+//!   "value": { "rankdir": "LR", "nodesep": 30 }
+//! }
+//! ```
+//!
+//! 节点、边和图的 `value` 按 dagre 的属性名映射到 [`NodeLabel`]、[`EdgeLabel`] 和
+//! [`GraphConfig`]，无法识别的属性保存在 `custom` 中，写出时原样输出。
+//! 读取时缺省的属性取 dagre 的默认值（例如 `edgesep` 为 20、`labelpos` 为 `"r"`、
+//! 未指定 `acyclicer` 时使用 DFS），这样同一份输入在 dagviz 和 dagre.js 中的布局可以直接比较。
+//! 与 graphlib 一致，边或 `parent` 引用的节点不存在时会自动创建。
+//!
+//! 对应 JS 函数: write() / read() in graphlib lib/json.js
+
+use super::FormatError;
+pub use super::NodeIds;
+use crate::graph::{Graph, NodeIndex};
+use crate::types::{
+    Edge, EdgeLabel, GraphConfig, LabelPosition, NodeLabel, Point, RankDirection, Ranker,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+/// graphlib 文档
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphlibDocument {
+    /// 图选项
+    #[serde(default)]
+    pub options: GraphlibOptions,
+    /// 节点列表
+    #[serde(default)]
+    pub nodes: Vec<GraphlibNode>,
+    /// 边列表
+    #[serde(default)]
+    pub edges: Vec<GraphlibEdge>,
+    /// 图标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// graphlib 图选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphlibOptions {
+    /// 是否为有向图，dagviz 只支持有向图
+    #[serde(default = "default_directed")]
+    pub directed: bool,
+    /// 是否允许同一对节点之间存在多条边
+    #[serde(default)]
+    pub multigraph: bool,
+    /// 是否为复合图
+    #[serde(default)]
+    pub compound: bool,
+}
+
+impl Default for GraphlibOptions {
+    fn default() -> Self {
+        Self {
+            directed: true,
+            multigraph: false,
+            compound: false,
+        }
+    }
+}
+
+fn default_directed() -> bool {
+    true
+}
+
+/// graphlib 节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphlibNode {
+    /// 节点 ID
+    pub v: String,
+    /// 节点标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    /// 父节点 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+/// graphlib 边
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphlibEdge {
+    /// 源节点 ID
+    pub v: String,
+    /// 目标节点 ID
+    pub w: String,
+    /// 边的名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 边标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// 从 graphlib JSON 字符串读取图
+pub fn from_str(json: &str) -> Result<(Graph, NodeIds), FormatError> {
+    let document: GraphlibDocument = serde_json::from_str(json)?;
+    read(document)
+}
+
+/// 从 graphlib JSON 值读取图
+pub fn from_value(value: Value) -> Result<(Graph, NodeIds), FormatError> {
+    let document: GraphlibDocument = serde_json::from_value(value)?;
+    read(document)
+}
+
+/// 写出为 graphlib JSON 字符串
+///
+/// `ids` 给出节点的 graphlib ID，未列出的节点使用节点索引作为 ID。
+pub fn to_string(graph: &Graph, ids: Option<&NodeIds>) -> Result<String, FormatError> {
+    Ok(serde_json::to_string(&write(graph, ids))?)
+}
+
+/// 写出为 graphlib JSON 值
+pub fn to_value(graph: &Graph, ids: Option<&NodeIds>) -> Value {
+    serde_json::to_value(write(graph, ids)).expect("graphlib document is always valid JSON")
+}
+
+/// 从 graphlib 文档构建图
+///
+/// 对应 JS 函数: read() in graphlib lib/json.js
+pub fn read(document: GraphlibDocument) -> Result<(Graph, NodeIds), FormatError> {
+    if !document.options.directed {
+        return Err(FormatError::Unsupported("undirected graph".to_string()));
+    }
+
+    let config = match document.value {
+        Some(value) => read_graph_value(value)?,
+        None => dagre_config(),
+    };
+    let mut graph = Graph::with_config(config);
+    let mut ids = NodeIds::new();
+
+    for node in &document.nodes {
+        let label = read_node_value(node.value.clone())?;
+        match ids.get(&node.v) {
+            // 与 graphlib 的 setNode 一致，重复的节点覆盖之前的标签
+            Some(&existing) => {
+                if let Some(existing_label) = graph.node_label_mut(existing) {
+                    *existing_label = label;
+                }
+            }
+            None => {
+                let index = graph.add_node(label);
+                ids.insert(node.v.clone(), index);
+            }
+        }
+    }
+
+    for node in &document.nodes {
+        if let Some(parent) = &node.parent {
+            let parent = node_for_id(&mut graph, &mut ids, parent);
+            graph.set_parent(ids[&node.v], parent);
+        }
+    }
+
+    for edge in document.edges {
+        let source = node_for_id(&mut graph, &mut ids, &edge.v);
+        let target = node_for_id(&mut graph, &mut ids, &edge.w);
+        let label = read_edge_value(edge.value)?;
+        // 非多重图中忽略边名称，与 graphlib 一致
+        let name = edge.name.filter(|_| document.options.multigraph);
+        graph.add_edge(
+            Edge {
+                source,
+                target,
+                name,
+            },
+            label,
+        );
+    }
+
+    Ok((graph, ids))
+}
+
+/// 把图写出为 graphlib 文档
+///
+/// 对应 JS 函数: write() in graphlib lib/json.js
+pub fn write(graph: &Graph, ids: Option<&NodeIds>) -> GraphlibDocument {
+    let mut names: IndexMap<NodeIndex, String> = IndexMap::new();
+    if let Some(ids) = ids {
+        for (id, &node) in ids {
+            names.insert(node, id.clone());
+        }
+    }
+    let name_of = |node: NodeIndex| {
+        names
+            .get(&node)
+            .cloned()
+            .unwrap_or_else(|| node.index().to_string())
+    };
+
+    let nodes: Vec<GraphlibNode> = graph
+        .node_indices()
+        .filter_map(|node| {
+            let label = graph.node_label(node)?;
+            Some(GraphlibNode {
+                v: name_of(node),
+                value: Some(write_node_value(label)),
+                parent: label.parent.map(name_of),
+            })
+        })
+        .collect();
+
+    let edges: Vec<GraphlibEdge> = graph
+        .edges()
+        .into_iter()
+        .filter_map(|edge| {
+            let label = graph.edge_label(&edge)?;
+            Some(GraphlibEdge {
+                v: name_of(edge.source),
+                w: name_of(edge.target),
+                name: edge.name.clone(),
+                value: Some(write_edge_value(label)),
+            })
+        })
+        .collect();
+
+    GraphlibDocument {
+        options: GraphlibOptions {
+            directed: true,
+            multigraph: edges.iter().any(|edge| edge.name.is_some()),
+            compound: nodes.iter().any(|node| node.parent.is_some()),
+        },
+        nodes,
+        edges,
+        value: Some(write_graph_value(graph.config())),
+    }
+}
+
+/// 查找节点，不存在时创建一个默认节点
+fn node_for_id(graph: &mut Graph, ids: &mut NodeIds, id: &str) -> NodeIndex {
+    if let Some(&node) = ids.get(id) {
+        return node;
+    }
+    let node = graph.add_node(NodeLabel::default());
+    ids.insert(id.to_string(), node);
+    node
+}
+
+/// dagre 的图默认值
+///
+/// 对应 JS 变量: graphDefaults in lib/layout.js
+fn dagre_config() -> GraphConfig {
+    GraphConfig {
+        node_sep: 50.0,
+        edge_sep: 20.0,
+        rank_sep: 50.0,
+        rankdir: RankDirection::TopBottom,
+        acyclicer: "dfs".to_string(),
+        ..Default::default()
+    }
+}
+
+/// 把标签值转换为对象，`null` 和缺省视为空对象
+fn into_object(value: Option<Value>, key: &str) -> Result<Map<String, Value>, FormatError> {
+    match value {
+        None | Some(Value::Null) => Ok(Map::new()),
+        Some(Value::Object(map)) => Ok(map),
+        Some(other) => Err(invalid(key, &other)),
+    }
+}
+
+fn invalid(key: &str, value: &Value) -> FormatError {
+    FormatError::InvalidAttribute {
+        key: key.to_string(),
+        value: value.to_string(),
+    }
+}
+
+/// 取出数值属性，与 dagre 一样接受数字字符串
+fn take_number(map: &mut Map<String, Value>, key: &str) -> Result<Option<f64>, FormatError> {
+    let Some(value) = map.remove(key) else {
+        return Ok(None);
+    };
+    let number = match &value {
+        Value::Null => return Ok(None),
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    number
+        .filter(|number| number.is_finite())
+        .map(Some)
+        .ok_or_else(|| invalid(key, &value))
+}
+
+fn take_integer(map: &mut Map<String, Value>, key: &str) -> Result<Option<i32>, FormatError> {
+    let original = map.get(key).cloned();
+    match take_number(map, key)? {
+        Some(number) if number.fract() == 0.0 && number.abs() <= i32::MAX as f64 => {
+            Ok(Some(number as i32))
+        }
+        Some(_) => Err(invalid(key, &original.unwrap_or(Value::Null))),
+        None => Ok(None),
+    }
+}
+
+fn take_string(map: &mut Map<String, Value>, key: &str) -> Result<Option<String>, FormatError> {
+    match map.remove(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(text)) => Ok(Some(text)),
+        // dagre-d3 的标签也可以是数字
+        Some(Value::Number(number)) => Ok(Some(number.to_string())),
+        Some(other) => Err(invalid(key, &other)),
+    }
+}
+
+fn take_bool(map: &mut Map<String, Value>, key: &str) -> Result<Option<bool>, FormatError> {
+    match map.remove(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Bool(flag)) => Ok(Some(flag)),
+        Some(other) => Err(invalid(key, &other)),
+    }
+}
+
+fn take_points(map: &mut Map<String, Value>) -> Result<Vec<Point>, FormatError> {
+    match map.remove("points") {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(value) => serde_json::from_value(value.clone()).map_err(|_| invalid("points", &value)),
+    }
+}
+
+/// 剩余的属性保存为自定义属性
+fn into_custom(map: Map<String, Value>) -> IndexMap<String, Value> {
+    map.into_iter().collect()
+}
+
+fn read_graph_value(value: Value) -> Result<GraphConfig, FormatError> {
+    let mut map = into_object(Some(value), "value")?;
+    let mut config = dagre_config();
+
+    if let Some(node_sep) = take_number(&mut map, "nodesep")? {
+        config.node_sep = node_sep;
+    }
+    if let Some(edge_sep) = take_number(&mut map, "edgesep")? {
+        config.edge_sep = edge_sep;
+    }
+    if let Some(rank_sep) = take_number(&mut map, "ranksep")? {
+        config.rank_sep = rank_sep;
+    }
+    if let Some(marginx) = take_number(&mut map, "marginx")? {
+        config.marginx = marginx;
+    }
+    if let Some(marginy) = take_number(&mut map, "marginy")? {
+        config.marginy = marginy;
+    }
+    config.width = take_number(&mut map, "width")?;
+    config.height = take_number(&mut map, "height")?;
+
+    if let Some(rankdir) = map.remove("rankdir") {
+        config.rankdir = match rankdir.as_str().map(str::to_ascii_uppercase).as_deref() {
+            Some("TB") => RankDirection::TopBottom,
+            Some("BT") => RankDirection::BottomTop,
+            Some("LR") => RankDirection::LeftRight,
+            Some("RL") => RankDirection::RightLeft,
+            _ => return Err(invalid("rankdir", &rankdir)),
+        };
+    }
+    if let Some(ranker) = map.remove("ranker") {
+        config.ranker = match ranker.as_str() {
+            Some("network-simplex") => Ranker::NetworkSimplex,
+            Some("tight-tree") => Ranker::TightTree,
+            Some("longest-path") => Ranker::LongestPath,
+            _ => return Err(invalid("ranker", &ranker)),
+        };
+    }
+    if let Some(acyclicer) = take_string(&mut map, "acyclicer")? {
+        config.acyclicer = acyclicer;
+    }
+
+    config.custom = into_custom(map);
+    Ok(config)
+}
+
+fn write_graph_value(config: &GraphConfig) -> Value {
+    let mut map = Map::new();
+    let rankdir = match config.rankdir {
+        RankDirection::TopBottom => "TB",
+        RankDirection::BottomTop => "BT",
+        RankDirection::LeftRight => "LR",
+        RankDirection::RightLeft => "RL",
+    };
+    map.insert("rankdir".to_string(), json!(rankdir));
+    map.insert("nodesep".to_string(), json!(config.node_sep));
+    map.insert("edgesep".to_string(), json!(config.edge_sep));
+    map.insert("ranksep".to_string(), json!(config.rank_sep));
+    map.insert("marginx".to_string(), json!(config.marginx));
+    map.insert("marginy".to_string(), json!(config.marginy));
+    let ranker = match config.ranker {
+        Ranker::NetworkSimplex => Some("network-simplex"),
+        Ranker::TightTree => Some("tight-tree"),
+        Ranker::LongestPath => Some("longest-path"),
+        // dagre 没有对应的排名器
+        Ranker::FeasibleTree | Ranker::None => None,
+    };
+    if let Some(ranker) = ranker {
+        map.insert("ranker".to_string(), json!(ranker));
+    }
+    if config.acyclicer == "greedy" {
+        map.insert("acyclicer".to_string(), json!("greedy"));
+    }
+    if let Some(width) = config.width {
+        map.insert("width".to_string(), json!(width));
+    }
+    if let Some(height) = config.height {
+        map.insert("height".to_string(), json!(height));
+    }
+    for (key, value) in &config.custom {
+        map.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+    Value::Object(map)
+}
+
+fn read_node_value(value: Option<Value>) -> Result<NodeLabel, FormatError> {
+    let mut map = into_object(value, "value")?;
+    let mut label = NodeLabel {
+        label: take_string(&mut map, "label")?,
+        width: take_number(&mut map, "width")?.unwrap_or(0.0),
+        height: take_number(&mut map, "height")?.unwrap_or(0.0),
+        x: take_number(&mut map, "x")?,
+        y: take_number(&mut map, "y")?,
+        rank: take_integer(&mut map, "rank")?,
+        ..Default::default()
+    };
+    label.hidden = take_bool(&mut map, "hidden")?.unwrap_or(false);
+    label.custom = into_custom(map);
+    Ok(label)
+}
+
+fn write_node_value(label: &NodeLabel) -> Value {
+    let mut map = Map::new();
+    if let Some(text) = &label.label {
+        map.insert("label".to_string(), json!(text));
+    }
+    map.insert("width".to_string(), json!(label.width));
+    map.insert("height".to_string(), json!(label.height));
+    if let Some(x) = label.x {
+        map.insert("x".to_string(), json!(x));
+    }
+    if let Some(y) = label.y {
+        map.insert("y".to_string(), json!(y));
+    }
+    if let Some(rank) = label.rank {
+        map.insert("rank".to_string(), json!(rank));
+    }
+    if label.hidden {
+        map.insert("hidden".to_string(), json!(true));
+    }
+    for (key, value) in &label.custom {
+        map.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+    Value::Object(map)
+}
+
+fn read_edge_value(value: Option<Value>) -> Result<EdgeLabel, FormatError> {
+    let mut map = into_object(value, "value")?;
+    let mut label = EdgeLabel {
+        // dagre 的边默认标签在右侧
+        labelpos: LabelPosition::Right,
+        ..Default::default()
+    };
+
+    label.label = take_string(&mut map, "label")?;
+    if let Some(minlen) = take_integer(&mut map, "minlen")? {
+        label.minlen = minlen;
+    }
+    if let Some(weight) = take_number(&mut map, "weight")? {
+        label.weight = weight;
+    }
+    if let Some(width) = take_number(&mut map, "width")? {
+        label.width = width;
+    }
+    if let Some(height) = take_number(&mut map, "height")? {
+        label.height = height;
+    }
+    if let Some(labeloffset) = take_number(&mut map, "labeloffset")? {
+        label.labeloffset = labeloffset;
+    }
+    if let Some(labelpos) = map.remove("labelpos") {
+        label.labelpos = match labelpos.as_str().map(str::to_ascii_lowercase).as_deref() {
+            Some("l") => LabelPosition::Left,
+            Some("c") => LabelPosition::Center,
+            Some("r") => LabelPosition::Right,
+            Some("t") => LabelPosition::Top,
+            Some("b") => LabelPosition::Bottom,
+            _ => return Err(invalid("labelpos", &labelpos)),
+        };
+    }
+    label.x = take_number(&mut map, "x")?;
+    label.y = take_number(&mut map, "y")?;
+    label.points = take_points(&mut map)?;
+    label.hidden = take_bool(&mut map, "hidden")?.unwrap_or(false);
+    label.constraint = take_bool(&mut map, "constraint")?.unwrap_or(true);
+    label.custom = into_custom(map);
+    Ok(label)
+}
+
+fn write_edge_value(label: &EdgeLabel) -> Value {
+    let mut map = Map::new();
+    if let Some(text) = &label.label {
+        map.insert("label".to_string(), json!(text));
+    }
+    map.insert("minlen".to_string(), json!(label.minlen));
+    map.insert("weight".to_string(), json!(label.weight));
+    map.insert("width".to_string(), json!(label.width));
+    map.insert("height".to_string(), json!(label.height));
+    let labelpos = match label.labelpos {
+        LabelPosition::Left => "l",
+        LabelPosition::Center => "c",
+        LabelPosition::Right => "r",
+        LabelPosition::Top => "t",
+        LabelPosition::Bottom => "b",
+    };
+    map.insert("labelpos".to_string(), json!(labelpos));
+    map.insert("labeloffset".to_string(), json!(label.labeloffset));
+    if let Some(x) = label.x {
+        map.insert("x".to_string(), json!(x));
+    }
+    if let Some(y) = label.y {
+        map.insert("y".to_string(), json!(y));
+    }
+    if !label.points.is_empty() {
+        map.insert("points".to_string(), json!(label.points));
+    }
+    if label.hidden {
+        map.insert("hidden".to_string(), json!(true));
+    }
+    if !label.constraint {
+        map.insert("constraint".to_string(), json!(false));
+    }
+    for (key, value) in &label.custom {
+        map.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+    Value::Object(map)
+}
@@ -0,0 +1,290 @@
+//! dagviz JSON 格式
+//!
+//! 整个图序列化为一个带版本号的信封：
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "config": { "rankdir": "LR", "node_sep": 50.0, "custom": { "theme": "dark" } },
+//!   "nodes": [
+//!     { "id": 0, "width": 50.0, "height": 20.0, "x": 25.0, "y": 10.0, "rank": 0 },
+//!     { "id": 1, "parent": 2, "width": 30.0, "height": 40.0 },
+//!     { "id": 2, "label": "cluster" },
+//!     { "id": 3, "width": 30.0, "height": 40.0 }
+//!   ],
+//!   "edges": [
+//!     { "source": 0, "target": 1, "name": "x", "weight": 2.0,
+//!       "points": [{ "x": 25.0, "y": 20.0 }, { "x": 25.0, "y": 45.0 }] }
+//!   ],
+//!   "rank_constraints": [{ "kind": "same", "nodes": [1, 3] }],
+//!   "order_constraints": [{ "kind": "sequence", "nodes": [3, 1] }]
+//! }
+//! ```
+//!
+//! - `nodes[].id` 是文档内的节点 ID，只要求唯一；`parent` 和边的 `source`/`target` 引用它。
+//! - 节点和边的其余字段就是 [`NodeLabel`] / [`EdgeLabel`] 的字段，`config` 是 [`GraphConfig`]。
+//!   缺省的字段取默认值，空值和默认的可选字段在输出时省略。
+//! - 布局结果（`x`、`y`、`rank`、`order`、`points` 以及图的 `width`/`height`）一并保存，
+//!   `custom` 原样保存。布局过程中的内部字段不保存。
+//! - `rank_constraints`、`order_constraints` 是 [`Graph::rank_constraints`]、
+//!   [`Graph::order_constraints`]，`nodes` 引用节点 ID，没有约束时省略。
+//!   版本 2 加入了这两个字段，版本 1 的文档读取后没有约束。
+//! - 读取时拒绝版本号高于 [`SCHEMA_VERSION`] 的文档。
+
+use super::FormatError;
+use crate::graph::{Graph, NodeIndex};
+use crate::types::{
+    Edge, EdgeLabel, GraphConfig, NodeLabel, OrderConstraint, OrderKind, RankConstraint, RankKind,
+};
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// 当前的格式版本
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// 带版本号的图文档
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphDocument {
+    /// 格式版本
+    pub version: u32,
+    /// 图配置
+    #[serde(default)]
+    pub config: GraphConfig,
+    /// 节点列表
+    #[serde(default)]
+    pub nodes: Vec<NodeRecord>,
+    /// 边列表
+    #[serde(default)]
+    pub edges: Vec<EdgeRecord>,
+    /// 排名约束
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rank_constraints: Vec<ConstraintRecord<RankKind>>,
+    /// 顺序约束
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub order_constraints: Vec<ConstraintRecord<OrderKind>>,
+}
+
+/// 文档中的节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeRecord {
+    /// 文档内的节点 ID
+    pub id: usize,
+    /// 父节点 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+    /// 节点标签
+    #[serde(flatten)]
+    pub label: NodeLabel,
+}
+
+/// 文档中的边
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeRecord {
+    /// 源节点 ID
+    pub source: usize,
+    /// 目标节点 ID
+    pub target: usize,
+    /// 边的名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 边标签
+    #[serde(flatten)]
+    pub label: EdgeLabel,
+}
+
+/// 文档中的约束
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstraintRecord<K> {
+    /// 约束类型
+    pub kind: K,
+    /// 受约束的节点 ID
+    pub nodes: Vec<usize>,
+}
+
+impl<K: Copy> ConstraintRecord<K> {
+    fn new(kind: K, nodes: &[NodeIndex]) -> Self {
+        ConstraintRecord {
+            kind,
+            nodes: nodes.iter().map(|node| node.index()).collect(),
+        }
+    }
+
+    /// 把节点 ID 换成新图中的节点
+    fn resolve(&self, ids: &IndexMap<usize, NodeIndex>) -> Result<Vec<NodeIndex>, FormatError> {
+        self.nodes
+            .iter()
+            .map(|id| {
+                ids.get(id)
+                    .copied()
+                    .ok_or(FormatError::UnknownNode { id: *id })
+            })
+            .collect()
+    }
+}
+
+impl GraphDocument {
+    /// 从图生成文档，节点 ID 取节点索引
+    pub fn from_graph(graph: &Graph) -> Self {
+        let nodes = graph
+            .node_indices()
+            .filter_map(|node| {
+                let label = graph.node_label(node)?;
+                Some(NodeRecord {
+                    id: node.index(),
+                    parent: label.parent.map(|parent| parent.index()),
+                    label: label.clone(),
+                })
+            })
+            .collect();
+
+        let edges = graph
+            .edges()
+            .into_iter()
+            .filter_map(|edge| {
+                let label = graph.edge_label(&edge)?;
+                Some(EdgeRecord {
+                    source: edge.source.index(),
+                    target: edge.target.index(),
+                    name: edge.name.clone(),
+                    label: label.clone(),
+                })
+            })
+            .collect();
+
+        let rank_constraints = graph
+            .rank_constraints()
+            .iter()
+            .map(|constraint| ConstraintRecord::new(constraint.kind, &constraint.nodes))
+            .collect();
+        let order_constraints = graph
+            .order_constraints()
+            .iter()
+            .map(|constraint| ConstraintRecord::new(constraint.kind, &constraint.nodes))
+            .collect();
+
+        GraphDocument {
+            version: SCHEMA_VERSION,
+            config: graph.config().clone(),
+            nodes,
+            edges,
+            rank_constraints,
+            order_constraints,
+        }
+    }
+
+    /// 从文档构建图
+    pub fn into_graph(self) -> Result<Graph, FormatError> {
+        self.into_graph_with_ids().map(|(graph, _)| graph)
+    }
+
+    /// 从文档构建图，同时返回文档节点 ID 到新节点索引的映射
+    pub fn into_graph_with_ids(self) -> Result<(Graph, IndexMap<usize, NodeIndex>), FormatError> {
+        if self.version > SCHEMA_VERSION {
+            return Err(FormatError::UnsupportedVersion {
+                found: self.version.into(),
+                supported: SCHEMA_VERSION,
+            });
+        }
+
+        let mut graph = Graph::with_config(self.config);
+        let mut ids = IndexMap::new();
+        let mut parents = Vec::new();
+
+        for record in self.nodes {
+            if ids.contains_key(&record.id) {
+                return Err(FormatError::DuplicateNode { id: record.id });
+            }
+            let node = graph.add_node(record.label);
+            ids.insert(record.id, node);
+            if let Some(parent) = record.parent {
+                parents.push((node, parent));
+            }
+        }
+
+        // 父节点可能出现在子节点之后，所有节点创建完再设置
+        for (node, parent) in parents {
+            let parent = *ids
+                .get(&parent)
+                .ok_or(FormatError::UnknownNode { id: parent })?;
+            graph.set_parent(node, parent);
+        }
+
+        for record in self.edges {
+            let source = *ids
+                .get(&record.source)
+                .ok_or(FormatError::UnknownNode { id: record.source })?;
+            let target = *ids
+                .get(&record.target)
+                .ok_or(FormatError::UnknownNode { id: record.target })?;
+            let edge = Edge {
+                source,
+                target,
+                name: record.name,
+            };
+            if graph.has_edge(&edge) {
+                return Err(FormatError::DuplicateEdge {
+                    source: record.source,
+                    target: record.target,
+                    name: edge.name,
+                });
+            }
+            graph.add_edge(edge, record.label);
+        }
+
+        for record in &self.rank_constraints {
+            graph.add_rank_constraint(RankConstraint::new(record.kind, record.resolve(&ids)?));
+        }
+        for record in &self.order_constraints {
+            graph.add_order_constraint(OrderConstraint::new(record.kind, record.resolve(&ids)?));
+        }
+
+        Ok((graph, ids))
+    }
+}
+
+/// 序列化为 JSON 字符串
+pub fn to_string(graph: &Graph) -> Result<String, FormatError> {
+    Ok(serde_json::to_string(&GraphDocument::from_graph(graph))?)
+}
+
+/// 序列化为带缩进的 JSON 字符串
+pub fn to_string_pretty(graph: &Graph) -> Result<String, FormatError> {
+    Ok(serde_json::to_string_pretty(&GraphDocument::from_graph(
+        graph,
+    ))?)
+}
+
+/// 从 JSON 字符串读取图
+///
+/// 先检查版本号再解析其余字段，新版本的文档即使结构不同也会报告版本错误。
+pub fn from_str(json: &str) -> Result<Graph, FormatError> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+    let version = value
+        .get("version")
+        .ok_or(FormatError::MissingVersion)?
+        .as_u64()
+        .ok_or(FormatError::MissingVersion)?;
+    if version > u64::from(SCHEMA_VERSION) {
+        return Err(FormatError::UnsupportedVersion {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    let document: GraphDocument = serde_json::from_value(value)?;
+    document.into_graph()
+}
+
+impl Serialize for Graph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GraphDocument::from_graph(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Graph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        GraphDocument::deserialize(deserializer)?
+            .into_graph()
+            .map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,121 @@
+//! 图的导入导出
+//!
+//! [`json`] 定义了 dagviz 自己的 JSON 格式：带版本号的信封，包含图配置、节点（含父节点）
+//! 和边（含标签）。`Graph` 的 `Serialize`/`Deserialize` 实现也使用这个格式。
+//!
+//! [`graphlib`] 读写 graphlib `json.write()` 的格式，用于和 dagre.js 交换数据。
+//!
+//! [`dot`] 解析 Graphviz DOT 的常用子集。
+
+pub mod dot;
+pub mod graphlib;
+pub mod json;
+
+use crate::graph::NodeIndex;
+use indexmap::IndexMap;
+use std::fmt;
+
+pub use json::{GraphDocument, SCHEMA_VERSION};
+
+/// 外部格式中的节点 ID 到图节点的映射
+pub type NodeIds = IndexMap<String, NodeIndex>;
+
+/// 导入导出错误
+#[derive(Debug)]
+pub enum FormatError {
+    /// 输入不是合法的 JSON，或字段类型不符
+    Json(serde_json::Error),
+    /// 文档缺少 `version` 字段
+    MissingVersion,
+    /// 文档版本高于当前支持的版本
+    UnsupportedVersion {
+        /// 文档中的版本号
+        found: u64,
+        /// 当前支持的最高版本号
+        supported: u32,
+    },
+    /// 两个节点使用了相同的 ID
+    DuplicateNode {
+        /// 重复的节点 ID
+        id: usize,
+    },
+    /// 父节点或边引用了不存在的节点 ID
+    UnknownNode {
+        /// 不存在的节点 ID
+        id: usize,
+    },
+    /// 同一条边（端点和名称都相同）出现了多次
+    DuplicateEdge {
+        /// 源节点 ID
+        source: usize,
+        /// 目标节点 ID
+        target: usize,
+        /// 边的名称
+        name: Option<String>,
+    },
+    /// 属性值的类型或取值无效
+    InvalidAttribute {
+        /// 属性名
+        key: String,
+        /// 原始值
+        value: String,
+    },
+    /// 输入使用了不支持的特性，例如无向图
+    Unsupported(String),
+    /// 文本格式的语法错误
+    Syntax {
+        /// 出错的行号，从 1 开始
+        line: usize,
+        /// 错误描述
+        message: String,
+    },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Json(err) => write!(f, "invalid JSON: {}", err),
+            FormatError::MissingVersion => write!(f, "document has no version"),
+            FormatError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "document version {} is newer than supported version {}",
+                found, supported
+            ),
+            FormatError::DuplicateNode { id } => write!(f, "node {} is defined twice", id),
+            FormatError::UnknownNode { id } => write!(f, "node {} does not exist", id),
+            FormatError::DuplicateEdge {
+                source,
+                target,
+                name,
+            } => {
+                write!(f, "edge {} -> {}", source, target)?;
+                if let Some(name) = name {
+                    write!(f, " ({})", name)?;
+                }
+                write!(f, " is defined twice")
+            }
+            FormatError::InvalidAttribute { key, value } => {
+                write!(f, "attribute {} has invalid value {}", key, value)
+            }
+            FormatError::Unsupported(feature) => write!(f, "unsupported: {}", feature),
+            FormatError::Syntax { line, message } => {
+                write!(f, "syntax error on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FormatError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for FormatError {
+    fn from(err: serde_json::Error) -> Self {
+        FormatError::Json(err)
+    }
+}
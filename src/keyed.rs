@@ -0,0 +1,394 @@
+//! 以字符串 ID 寻址的图
+//!
+//! [`Graph`] 使用带图 ID 的 [`NodeIndex`] 标识节点。[`KeyedGraph`] 在其上维护 ID 到索引的映射，
+//! 接口与 graphlib 一致：`set_edge` 和 `set_parent` 引用的节点不存在时会自动创建，
+//! 布局结果也按同样的 ID 返回。
+//!
+//! ```
+//! use dagviz::{KeyedGraph, NodeLabel, EdgeLabel};
+//!
+//! let mut graph = KeyedGraph::new();
+//! graph.set_node("a", NodeLabel { width: 50.0, height: 20.0, ..Default::default() });
+//! graph.set_node("b", NodeLabel { width: 50.0, height: 20.0, ..Default::default() });
+//! graph.set_edge("a", "b", EdgeLabel::default());
+//!
+//! let result = graph.layout(None);
+//! let a = &result.nodes["a"];
+//! assert!(a.x.is_some() && a.y.is_some());
+//! ```
+
+use crate::error::LayoutError;
+use crate::graph::{Graph, NodeIndex};
+use crate::io::NodeIds;
+use crate::io::dot::DotGraph;
+use crate::layout::{layout, try_layout};
+use crate::stats::LayoutStats;
+use crate::types::{
+    Edge, EdgeLabel, GraphConfig, LayoutOptions, NodeLabel, OrderConstraint, OrderKind,
+    RankConstraint, RankKind,
+};
+use indexmap::IndexMap;
+
+/// 以 ID 表示的边
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyedEdge {
+    /// 源节点 ID
+    pub source: String,
+    /// 目标节点 ID
+    pub target: String,
+    /// 边的名称，未命名的边为 `None`
+    pub name: Option<String>,
+}
+
+/// 按 ID 返回的布局结果
+#[derive(Debug, Clone, Default)]
+pub struct KeyedLayout {
+    /// 布局后图的宽度
+    pub width: f64,
+    /// 布局后图的高度
+    pub height: f64,
+    /// 节点 ID 到布局后节点标签的映射，按节点添加顺序排列
+    pub nodes: IndexMap<String, NodeLabel>,
+    /// 边到布局后边标签的映射
+    pub edges: IndexMap<KeyedEdge, EdgeLabel>,
+    /// 本次布局的统计信息
+    pub stats: LayoutStats,
+}
+
+/// 以字符串 ID 寻址节点、边和父节点的图
+#[derive(Clone, Default)]
+pub struct KeyedGraph {
+    graph: Graph,
+    ids: NodeIds,
+    keys: IndexMap<NodeIndex, String>,
+}
+
+impl KeyedGraph {
+    /// 创建空图
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用指定配置创建空图
+    pub fn with_config(config: GraphConfig) -> Self {
+        Self::from_parts(Graph::with_config(config), NodeIds::new())
+    }
+
+    /// 从图和 ID 映射创建，例如 `io::graphlib::from_str` 的结果
+    ///
+    /// 映射中不存在的节点不能通过 ID 访问，但仍然参与布局。
+    pub fn from_parts(graph: Graph, ids: NodeIds) -> Self {
+        let keys = ids.iter().map(|(id, &node)| (node, id.clone())).collect();
+        KeyedGraph { graph, ids, keys }
+    }
+
+    /// 拆分为图和 ID 映射
+    pub fn into_parts(self) -> (Graph, NodeIds) {
+        (self.graph, self.ids)
+    }
+
+    /// 底层的图
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// 底层的图（可变）
+    ///
+    /// 通过这里添加的节点没有 ID，删除节点请使用 [`KeyedGraph::remove_node`]，
+    /// 否则 ID 映射会指向已删除的节点。
+    pub fn graph_mut(&mut self) -> &mut Graph {
+        &mut self.graph
+    }
+
+    /// 图配置
+    pub fn config(&self) -> &GraphConfig {
+        self.graph.config()
+    }
+
+    /// 图配置（可变）
+    pub fn config_mut(&mut self) -> &mut GraphConfig {
+        self.graph.config_mut()
+    }
+
+    /// ID 对应的节点索引
+    pub fn index(&self, id: &str) -> Option<NodeIndex> {
+        self.ids.get(id).copied()
+    }
+
+    /// 节点索引对应的 ID
+    pub fn id(&self, node: NodeIndex) -> Option<&str> {
+        self.keys.get(&node).map(String::as_str)
+    }
+
+    /// 按添加顺序返回所有节点 ID
+    pub fn node_ids(&self) -> impl Iterator<Item = &str> + '_ {
+        self.ids.keys().map(String::as_str)
+    }
+
+    /// 节点数量
+    pub fn node_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// 是否存在该节点
+    pub fn has_node(&self, id: &str) -> bool {
+        self.ids.contains_key(id)
+    }
+
+    /// 设置节点标签，节点不存在时创建，返回节点索引
+    ///
+    /// 替换已有节点的标签时保留其父节点。
+    ///
+    /// 对应 JS 函数: setNode() in graphlib lib/graph.js
+    pub fn set_node(&mut self, id: impl Into<String>, mut label: NodeLabel) -> NodeIndex {
+        let id = id.into();
+        if let Some(&node) = self.ids.get(&id) {
+            if let Some(existing) = self.graph.node_label_mut(node) {
+                label.parent = existing.parent;
+                *existing = label;
+            }
+            return node;
+        }
+        let node = self.graph.add_node(label);
+        self.keys.insert(node, id.clone());
+        self.ids.insert(id, node);
+        node
+    }
+
+    /// 节点存在时返回其索引，否则以默认标签创建
+    pub fn ensure_node(&mut self, id: &str) -> NodeIndex {
+        match self.index(id) {
+            Some(node) => node,
+            None => self.set_node(id, NodeLabel::default()),
+        }
+    }
+
+    /// 节点标签
+    pub fn node(&self, id: &str) -> Option<&NodeLabel> {
+        self.graph.node_label(self.index(id)?)
+    }
+
+    /// 节点标签（可变）
+    pub fn node_mut(&mut self, id: &str) -> Option<&mut NodeLabel> {
+        let node = self.index(id)?;
+        self.graph.node_label_mut(node)
+    }
+
+    /// 删除节点及其所有边，子节点移到根层级
+    ///
+    /// 对应 JS 函数: removeNode() in graphlib lib/graph.js
+    pub fn remove_node(&mut self, id: &str) -> Option<NodeLabel> {
+        let node = self.ids.shift_remove(id)?;
+        self.keys.shift_remove(&node);
+        self.graph.remove_node(node)
+    }
+
+    /// 设置未命名边的标签，边不存在时创建，端点不存在时以默认标签创建
+    ///
+    /// 对应 JS 函数: setEdge() in graphlib lib/graph.js
+    pub fn set_edge(&mut self, source: &str, target: &str, label: EdgeLabel) -> Edge {
+        let edge = Edge::new(self.ensure_node(source), self.ensure_node(target));
+        self.insert_edge(edge, label)
+    }
+
+    /// 设置命名边的标签，同一对节点之间可以有多条名称不同的边
+    pub fn set_named_edge(
+        &mut self,
+        source: &str,
+        target: &str,
+        name: impl Into<String>,
+        label: EdgeLabel,
+    ) -> Edge {
+        let edge = Edge::with_name(self.ensure_node(source), self.ensure_node(target), name);
+        self.insert_edge(edge, label)
+    }
+
+    fn insert_edge(&mut self, edge: Edge, label: EdgeLabel) -> Edge {
+        match self.graph.edge_label_mut(&edge) {
+            Some(existing) => *existing = label,
+            None => {
+                self.graph.add_edge(edge.clone(), label);
+            }
+        }
+        edge
+    }
+
+    /// 未命名边的标签
+    pub fn edge(&self, source: &str, target: &str) -> Option<&EdgeLabel> {
+        self.graph.edge_label(&self.edge_key(source, target, None)?)
+    }
+
+    /// 未命名边的标签（可变）
+    pub fn edge_mut(&mut self, source: &str, target: &str) -> Option<&mut EdgeLabel> {
+        let edge = self.edge_key(source, target, None)?;
+        self.graph.edge_label_mut(&edge)
+    }
+
+    /// 命名边的标签
+    pub fn named_edge(&self, source: &str, target: &str, name: &str) -> Option<&EdgeLabel> {
+        self.graph
+            .edge_label(&self.edge_key(source, target, Some(name))?)
+    }
+
+    /// 命名边的标签（可变）
+    pub fn named_edge_mut(
+        &mut self,
+        source: &str,
+        target: &str,
+        name: &str,
+    ) -> Option<&mut EdgeLabel> {
+        let edge = self.edge_key(source, target, Some(name))?;
+        self.graph.edge_label_mut(&edge)
+    }
+
+    /// 删除未命名边
+    pub fn remove_edge(&mut self, source: &str, target: &str) -> Option<EdgeLabel> {
+        let edge = self.edge_key(source, target, None)?;
+        self.graph.remove_edge(&edge)
+    }
+
+    /// 删除命名边
+    pub fn remove_named_edge(
+        &mut self,
+        source: &str,
+        target: &str,
+        name: &str,
+    ) -> Option<EdgeLabel> {
+        let edge = self.edge_key(source, target, Some(name))?;
+        self.graph.remove_edge(&edge)
+    }
+
+    fn edge_key(&self, source: &str, target: &str, name: Option<&str>) -> Option<Edge> {
+        Some(Edge {
+            source: self.index(source)?,
+            target: self.index(target)?,
+            name: name.map(str::to_string),
+        })
+    }
+
+    /// 所有端点都有 ID 的边
+    pub fn edges(&self) -> Vec<KeyedEdge> {
+        self.graph
+            .edges()
+            .iter()
+            .filter_map(|edge| self.keyed_edge(edge))
+            .collect()
+    }
+
+    /// 把 [`Edge`] 转换为以 ID 表示的边，端点没有 ID 时返回 `None`
+    pub fn keyed_edge(&self, edge: &Edge) -> Option<KeyedEdge> {
+        Some(KeyedEdge {
+            source: self.id(edge.source)?.to_string(),
+            target: self.id(edge.target)?.to_string(),
+            name: edge.name.clone(),
+        })
+    }
+
+    /// 设置父节点，两个节点不存在时以默认标签创建
+    ///
+    /// 对应 JS 函数: setParent() in graphlib lib/graph.js
+    pub fn set_parent(&mut self, child: &str, parent: &str) {
+        let child = self.ensure_node(child);
+        let parent = self.ensure_node(parent);
+        self.graph.set_parent(child, parent);
+    }
+
+    /// 把节点移到根层级
+    pub fn clear_parent(&mut self, child: &str) {
+        if let Some(label) = self.node_mut(child) {
+            label.parent = None;
+        }
+    }
+
+    /// 父节点 ID
+    pub fn parent(&self, child: &str) -> Option<&str> {
+        self.id(self.node(child)?.parent?)
+    }
+
+    /// 子节点 ID
+    pub fn children(&self, parent: &str) -> Vec<&str> {
+        let Some(parent) = self.index(parent) else {
+            return Vec::new();
+        };
+        self.graph
+            .children(parent)
+            .into_iter()
+            .filter_map(|child| self.id(child))
+            .collect()
+    }
+
+    /// 添加排名约束，不存在的节点以默认标签创建
+    pub fn add_rank_constraint<'a>(
+        &mut self,
+        kind: RankKind,
+        ids: impl IntoIterator<Item = &'a str>,
+    ) {
+        let nodes: Vec<NodeIndex> = ids.into_iter().map(|id| self.ensure_node(id)).collect();
+        self.graph
+            .add_rank_constraint(RankConstraint::new(kind, nodes));
+    }
+
+    /// 添加顺序约束，不存在的节点以默认标签创建
+    pub fn add_order_constraint<'a>(
+        &mut self,
+        kind: OrderKind,
+        ids: impl IntoIterator<Item = &'a str>,
+    ) {
+        let nodes: Vec<NodeIndex> = ids.into_iter().map(|id| self.ensure_node(id)).collect();
+        self.graph
+            .add_order_constraint(OrderConstraint::new(kind, nodes));
+    }
+
+    /// 执行布局，返回按 ID 排列的结果
+    ///
+    /// 与 [`layout`] 一样在无效输入时 panic。
+    pub fn layout(&mut self, opts: Option<&LayoutOptions>) -> KeyedLayout {
+        let stats = layout(&mut self.graph, opts);
+        let config = self.graph.config();
+        self.result(
+            config.width.unwrap_or(0.0),
+            config.height.unwrap_or(0.0),
+            stats,
+        )
+    }
+
+    /// 执行布局，遇到无效输入时返回错误
+    ///
+    /// 错误中的节点索引可以用 [`KeyedGraph::id`] 转换为 ID。
+    pub fn try_layout(&mut self, opts: Option<&LayoutOptions>) -> Result<KeyedLayout, LayoutError> {
+        let result = try_layout(&mut self.graph, opts)?;
+        Ok(self.result(result.width, result.height, result.stats))
+    }
+
+    fn result(&self, width: f64, height: f64, stats: LayoutStats) -> KeyedLayout {
+        let nodes = self
+            .ids
+            .iter()
+            .filter_map(|(id, &node)| Some((id.clone(), self.graph.node_label(node)?.clone())))
+            .collect();
+        let edges = self
+            .graph
+            .edges()
+            .iter()
+            .filter_map(|edge| Some((self.keyed_edge(edge)?, self.graph.edge_label(edge)?.clone())))
+            .collect();
+        KeyedLayout {
+            width,
+            height,
+            nodes,
+            edges,
+            stats,
+        }
+    }
+}
+
+impl From<DotGraph> for KeyedGraph {
+    /// 集群子图以其名称作为 ID，与节点 ID 重名时节点优先
+    fn from(parsed: DotGraph) -> Self {
+        let mut ids = parsed.ids;
+        for (name, node) in parsed.clusters {
+            ids.entry(name).or_insert(node);
+        }
+        KeyedGraph::from_parts(parsed.graph, ids)
+    }
+}
@@ -1,112 +1,303 @@
 //! 主布局算法
 
-use crate::counters::*;
+use crate::budget::Budget;
+use crate::context::LayoutContext;
+use crate::error::LayoutError;
 use crate::graph::Graph;
 use crate::graph::NodeIndex;
 use crate::order::order;
 use crate::position::position;
 use crate::rank::rank;
+use crate::stats::LayoutStats;
 use crate::types::*;
-use crate::util::time;
+use crate::util::{StageSpan, Timer, stage, stage_with_context};
 use indexmap::IndexMap;
 
-fn log_graph_state(graph: &Graph, stage: &str) {
-    println!("\n--- {} 图状态 ---", stage);
-    println!("节点:");
-    for node_idx in graph.node_indices() {
-        let node = graph.node_label(node_idx).unwrap();
-        println!(
-            "  {}: rank={:?}, x={:?}, y={:?}, width={}, height={}",
-            node_idx.index(),
-            node.rank,
-            node.x,
-            node.y,
-            node.width,
-            node.height
-        );
+/// 执行图布局，返回本次布局的统计信息
+///
+/// 通过 `LayoutOptions::cancel` 取消时不修改输入图，返回的统计中 `cancelled` 为 `true`。
+///
+/// 对应 JS 函数: layout() in lib/layout.js
+pub fn layout(graph: &mut Graph, opts: Option<&LayoutOptions>) -> LayoutStats {
+    layout_checked(graph, opts).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// `layout` 和 `try_layout` 共用的布局流程，复制输入图时出错返回错误而不是 panic
+fn layout_checked(
+    graph: &mut Graph,
+    opts: Option<&LayoutOptions>,
+) -> Result<LayoutStats, LayoutError> {
+    let default_opts = LayoutOptions::default();
+    let opts = opts.unwrap_or(&default_opts);
+    let timer = Timer::new();
+
+    // 构建布局图
+    let span = StageSpan::enter("build_layout_graph", graph);
+    let (mut layout_graph, mapping) = build_layout_graph(graph)?;
+    let budget = Budget::start(opts.budget).with_cancellation(opts.cancel.clone());
+    let mut ctx = LayoutContext::with_budget(budget);
+    if let Some(hints) = &opts.hints {
+        ctx.hints = hints.remap(&mapping.nodes);
+    }
+    let elapsed = span.exit(&layout_graph);
+    ctx.stats.record_stage("build_layout_graph", elapsed);
+
+    // 运行布局算法
+    stage_with_context("run_layout", &mut layout_graph, &mut ctx, |g, ctx| {
+        run_layout(g, ctx, opts)
+    });
+
+    let LayoutContext {
+        mut stats, budget, ..
+    } = ctx;
+
+    // 取消时丢弃布局图，输入图保持不变
+    if budget.cancelled() {
+        stats.cancelled = true;
+        return Ok(stats);
     }
 
-    println!("边:");
-    for edge in graph.edges() {
-        if let Some(edge_obj) = graph.edge_label(&edge) {
-            println!(
-                "  {:?} -> {:?}: points={}个控制点",
-                edge.source,
-                edge.target,
-                edge_obj.points.len()
-            );
-        }
+    // 更新输入图
+    stats.pin_conflicts = stats
+        .pin_conflicts
+        .into_iter()
+        .filter_map(|node| mapping.input_node(node))
+        .collect();
+    stats.rank_constraint_conflict = stats
+        .rank_constraint_conflict
+        .map(|err| mapping.to_input_error(err));
+    let span = StageSpan::enter("update_input_graph", &layout_graph);
+    update_input_graph(graph, &layout_graph, &mapping);
+    let elapsed = span.exit(graph);
+    stats.record_stage("update_input_graph", elapsed);
+
+    if opts.debug_timing {
+        log::info!("layout took {:?}", timer.elapsed());
     }
+    Ok(stats)
 }
 
-/// 执行图布局
+/// 执行图布局，输入无效时返回错误
 ///
-/// 对应 JS 函数: layout() in lib/layout.js
-pub fn layout(graph: &mut Graph, opts: Option<&LayoutOptions>) {
-    // 重置计数器
-    reset_counters();
+/// 与 `layout` 相同，但在修改输入图之前先校验节点尺寸、父节点引用、
+/// 复合层级、固定排名、排名约束以及边引用的端口，出错时输入图保持不变。布局被取消时返回
+/// [`LayoutError::Cancelled`]，输入图同样保持不变。
+///
+/// 校验和复制输入图只使用 `Graph` 的 `try_` 方法，无效的输入不会引起 panic。
+pub fn try_layout(
+    graph: &mut Graph,
+    opts: Option<&LayoutOptions>,
+) -> Result<LayoutResult, LayoutError> {
+    validate_input_graph(graph)?;
 
-    let default_opts = LayoutOptions::default();
-    let opts = opts.unwrap_or(&default_opts);
+    let stats = layout_checked(graph, opts)?;
+    if stats.cancelled {
+        return Err(LayoutError::Cancelled);
+    }
 
-    if opts.debug_timing {
-        time("layout", || {
-            // 构建布局图
-            let mut layout_graph = time("build_layout_graph", || build_layout_graph(graph));
+    let config = graph.config();
+    Ok(LayoutResult {
+        width: config.width.unwrap_or(0.0),
+        height: config.height.unwrap_or(0.0),
+        stats,
+    })
+}
 
-            // 运行布局算法
-            time("run_layout", || run_layout(&mut layout_graph, opts));
+/// 校验输入图是否可以布局
+fn validate_input_graph(graph: &Graph) -> Result<(), LayoutError> {
+    for node_id in graph.node_indices() {
+        let label = graph.try_node_label(node_id)?;
 
-            // 更新输入图
-            time("update_input_graph", || {
-                update_input_graph(graph, &layout_graph)
+        if !is_valid_size(label.width) || !is_valid_size(label.height) {
+            return Err(LayoutError::InvalidNodeSize {
+                node: node_id,
+                width: label.width,
+                height: label.height,
             });
-        });
-    } else {
-        // 构建布局图
-        let mut layout_graph = build_layout_graph(graph);
+        }
+
+        if let Some(parent) = label.parent {
+            match graph.check_node(parent, None) {
+                Ok(()) => {}
+                Err(LayoutError::UnknownNode { .. }) => {
+                    return Err(LayoutError::DanglingParent {
+                        node: node_id,
+                        parent,
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    // 沿父链检测环，已确认无环的节点不再重复遍历
+    let mut acyclic_nodes = indexmap::IndexSet::new();
+    for node_id in graph.node_indices() {
+        let mut chain: indexmap::IndexSet<NodeIndex> = indexmap::IndexSet::new();
+        let mut current = Some(node_id);
+        while let Some(v) = current {
+            if acyclic_nodes.contains(&v) {
+                break;
+            }
+            if let Some(start) = chain.get_index_of(&v) {
+                return Err(LayoutError::ParentCycle {
+                    nodes: chain.iter().skip(start).copied().collect(),
+                });
+            }
+            chain.insert(v);
+            current = graph.try_parent(v)?;
+        }
+        acyclic_nodes.extend(chain);
+    }
+
+    for constraint in graph.order_constraints() {
+        for &node in &constraint.nodes {
+            graph.check_node(node, None)?;
+        }
+    }
+
+    for edge in graph.edges() {
+        let Some(label) = graph.edge_label(&edge) else {
+            continue;
+        };
+        for (node, port) in [
+            (edge.source, &label.source_port),
+            (edge.target, &label.target_port),
+        ] {
+            if let Some(port) = port
+                && graph
+                    .node_label(node)
+                    .is_none_or(|l| crate::port::find_port(l, port).is_none())
+            {
+                return Err(LayoutError::UnknownPort {
+                    edge: edge.clone(),
+                    node,
+                    port: port.clone(),
+                });
+            }
+        }
+    }
+
+    // 不运行排序器时使用输入的排名，需要满足边的最小长度
+    if graph.config().ranker == Ranker::None {
+        for edge in graph.edges() {
+            let source_rank = graph.node_label(edge.source).and_then(|l| l.rank);
+            let target_rank = graph.node_label(edge.target).and_then(|l| l.rank);
+            let minlen = graph.edge_label(&edge).map_or(1, |l| l.minlen);
+            let constraint = graph.edge_label(&edge).is_none_or(|l| l.constraint);
+            if let (Some(source_rank), Some(target_rank)) = (source_rank, target_rank)
+                && edge.source != edge.target
+                && constraint
+                && target_rank - source_rank < minlen
+            {
+                return Err(LayoutError::RankContradiction {
+                    edge,
+                    source_rank,
+                    target_rank,
+                    minlen,
+                });
+            }
+        }
+    }
+
+    validate_rank_constraints(graph)
+}
+
+/// 校验排名约束
+///
+/// 约束是否矛盾取决于去环后边的方向，因此在布局图的副本上运行去环后再检查，
+/// 错误中的节点和边转换回输入图。
+fn validate_rank_constraints(graph: &Graph) -> Result<(), LayoutError> {
+    if graph.rank_constraints().is_empty() {
+        return Ok(());
+    }
+    for constraint in graph.rank_constraints() {
+        for &node in &constraint.nodes {
+            graph.check_node(node, None)?;
+        }
+    }
+
+    let (mut layout_graph, mapping) = build_layout_graph(graph)?;
+    remove_non_constraint_edges(&mut layout_graph, &mut LayoutContext::new());
+    if graph.config().ranker != Ranker::None {
+        remove_self_edges(&mut layout_graph);
+        crate::acyclic::Acyclic::run(&mut layout_graph);
+    }
+    crate::rank::constraints::check(&layout_graph).map_err(|err| mapping.to_input_error(err))
+}
+
+fn is_valid_size(value: f64) -> bool {
+    value.is_finite() && value >= 0.0
+}
 
-        // 运行布局算法
-        run_layout(&mut layout_graph, opts);
+/// 输入图与布局图之间的节点和边映射
+///
+/// 布局图中的节点和边会在布局过程中增删，结果写回输入图时只能通过该映射查找，
+/// 不能依赖两个图的遍历顺序。
+#[derive(Debug, Default)]
+struct LayoutMapping {
+    /// 输入图节点 -> 布局图节点
+    nodes: IndexMap<NodeIndex, NodeIndex>,
+    /// 输入图边 -> 布局图边（保留边名称，多重边各自对应）
+    edges: IndexMap<Edge, Edge>,
+}
 
-        // 更新输入图
-        update_input_graph(graph, &layout_graph);
+impl LayoutMapping {
+    /// 布局图节点对应的输入图节点
+    fn input_node(&self, node: NodeIndex) -> Option<NodeIndex> {
+        self.nodes
+            .iter()
+            .find_map(|(&input, &layout)| (layout == node).then_some(input))
+    }
+
+    /// 把布局图中的排名约束错误转换为输入图的节点和边
+    ///
+    /// 去环时被反转的边按端点查找对应的输入边。
+    fn to_input_error(&self, err: LayoutError) -> LayoutError {
+        let LayoutError::RankConstraintConflict { nodes, edge } = err else {
+            return err;
+        };
+        let nodes = nodes
+            .into_iter()
+            .filter_map(|node| self.input_node(node))
+            .collect();
+        let edge = edge.and_then(|edge| {
+            self.edges.iter().find_map(|(input, layout)| {
+                let same = layout.source == edge.source && layout.target == edge.target;
+                let reversed = layout.source == edge.target && layout.target == edge.source;
+                (same || reversed).then(|| input.clone())
+            })
+        });
+        LayoutError::RankConstraintConflict { nodes, edge }
     }
 }
 
 /// 构建布局图
 ///
+/// 指向不存在的节点的父节点被忽略，由 `try_layout` 的校验报告。
+///
 /// 对应 JS 函数: buildLayoutGraph() in lib/layout.js
-fn build_layout_graph(input_graph: &Graph) -> Graph {
-    println!("=== 开始构建布局图 ===");
+fn build_layout_graph(input_graph: &Graph) -> Result<(Graph, LayoutMapping), LayoutError> {
     let mut layout_graph = Graph::with_config(input_graph.config().clone());
+    let mut mapping = LayoutMapping::default();
 
     // 复制节点
-    let mut node_mapping = indexmap::IndexMap::new();
     for node_index in input_graph.node_indices() {
-        if let Some(label) = input_graph.node_label(node_index) {
-            let mut new_label = label.clone();
-
-            // 设置默认值
-            if new_label.width == 0.0 {
-                new_label.width = 0.0;
-            }
-            if new_label.height == 0.0 {
-                new_label.height = 0.0;
-            }
-
-            // 调试：检查 rank 是否被正确复制
-            println!("复制节点 {:?}: rank = {:?}", node_index, new_label.rank);
-
-            let new_node_index = layout_graph.add_node(new_label);
-            node_mapping.insert(node_index, new_node_index);
+        let label = input_graph.try_node_label(node_index)?;
+        // 父节点索引属于输入图，下面按映射重新设置
+        let new_node_index = layout_graph.add_node(NodeLabel {
+            parent: None,
+            ..label.clone()
+        });
+        mapping.nodes.insert(node_index, new_node_index);
+    }
 
-            // 设置父节点
-            if let Some(parent) = input_graph.parent(node_index) {
-                if let Some(&new_parent) = node_mapping.get(&parent) {
-                    layout_graph.set_parent(new_node_index, new_parent);
-                }
-            }
+    // 设置父节点，所有节点都已复制，父节点声明在子节点之后也能找到
+    for (&node_index, &new_node_index) in &mapping.nodes {
+        if let Some(parent) = input_graph.try_parent(node_index)?
+            && let Some(&new_parent) = mapping.nodes.get(&parent)
+        {
+            layout_graph.try_set_parent(new_node_index, new_parent)?;
         }
     }
 
@@ -115,94 +306,187 @@ fn build_layout_graph(input_graph: &Graph) -> Graph {
         if let Some(edge_label) = input_graph.edge_label(&edge) {
             let mut new_edge_label = edge_label.clone();
 
-            // 设置默认值
-            if new_edge_label.minlen == 0 {
-                new_edge_label.minlen = 1;
-            }
+            // 设置默认值，`minlen` 为 0 的边允许两端位于同一排名
             if new_edge_label.weight == 0.0 {
                 new_edge_label.weight = 1.0;
             }
-            if new_edge_label.width == 0.0 {
-                new_edge_label.width = 0.0;
-            }
-            if new_edge_label.height == 0.0 {
-                new_edge_label.height = 0.0;
-            }
             if new_edge_label.labeloffset == 0.0 {
                 new_edge_label.labeloffset = 10.0;
             }
 
             if let (Some(&new_source), Some(&new_target)) = (
-                node_mapping.get(&edge.source),
-                node_mapping.get(&edge.target),
+                mapping.nodes.get(&edge.source),
+                mapping.nodes.get(&edge.target),
             ) {
-                let new_edge = Edge::new(new_source, new_target);
-                let _ = layout_graph.add_edge(new_edge, new_edge_label);
+                let new_edge = Edge {
+                    source: new_source,
+                    target: new_target,
+                    name: edge.name.clone(),
+                };
+                layout_graph.try_add_edge(new_edge.clone(), new_edge_label)?;
+                mapping.edges.insert(edge, new_edge);
             }
         }
     }
 
-    println!("=== 布局图构建完成 ===");
-    layout_graph
+    // 排名约束和顺序约束中的节点索引属于输入图
+    let constraints = input_graph
+        .rank_constraints()
+        .iter()
+        .map(|constraint| RankConstraint {
+            kind: constraint.kind,
+            nodes: constraint
+                .nodes
+                .iter()
+                .filter_map(|node| mapping.nodes.get(node).copied())
+                .collect(),
+        })
+        .collect();
+    layout_graph.config_mut().rank_constraints = constraints;
+    let constraints = input_graph
+        .order_constraints()
+        .iter()
+        .map(|constraint| OrderConstraint {
+            kind: constraint.kind,
+            nodes: constraint
+                .nodes
+                .iter()
+                .filter_map(|node| mapping.nodes.get(node).copied())
+                .collect(),
+        })
+        .collect();
+    layout_graph.config_mut().order_constraints = constraints;
+
+    Ok((layout_graph, mapping))
 }
 
 /// 运行布局算法
 ///
 /// 对应 JS 函数: runLayout() in lib/layout.js
-fn run_layout(graph: &mut Graph, opts: &LayoutOptions) {
-    println!("=== Rust 布局过程日志 ===");
-
-    make_space_for_edge_labels(graph);
-    remove_self_edges(graph);
-    acyclic(graph);
-    nesting_graph_run(graph);
-
-    println!("--- 执行 rank 前 ---");
-    log_graph_state(graph, "rank前");
-    increment_rank();
-    rank(graph);
-    log_graph_state(graph, "rank后");
-
-    inject_edge_label_proxies(graph);
-    log_graph_state(graph, "inject_edge_label_proxies后");
-    crate::util::remove_empty_ranks(graph);
-    log_graph_state(graph, "remove_empty_ranks后");
-    nesting_graph_cleanup(graph);
-    log_graph_state(graph, "nesting_graph_cleanup后");
-    crate::util::normalize_ranks(graph);
-    log_graph_state(graph, "normalize_ranks后");
-    normalize_edges(graph); // 添加虚拟节点
-    assign_rank_min_max(graph);
-    remove_edge_label_proxies(graph);
-    parent_dummy_chains(graph);
-    add_border_segments(graph);
-
-    println!("--- 执行 order 前 ---");
-    log_graph_state(graph, "order前");
-    increment_order();
-    order(graph, Some(opts));
-    println!("--- 执行 order 后 ---");
-    log_graph_state(graph, "order后");
-
-    insert_self_edges(graph);
-    log_graph_state(graph, "insert_self_edges后");
-    crate::position::adjust_coordinate_system(graph);
-
-    log_graph_state(graph, "increment_position后");
-    increment_position();
-    position(graph);
-    log_graph_state(graph, "position后");
-
-    position_self_edges(graph);
-    log_graph_state(graph, "position_self_edges后");
-    remove_border_nodes(graph);
-    log_graph_state(graph, "remove_border_nodes后");
-    fixup_edge_label_coords(graph);
-    undo_coordinate_system(graph);
-    crate::position::translate_graph(graph);
-    assign_node_intersects(graph);
-    reverse_points_for_reversed_edges(graph);
-    acyclic_undo(graph);
+fn run_layout(graph: &mut Graph, ctx: &mut LayoutContext, opts: &LayoutOptions) {
+    stage_with_context("collect_pins", graph, ctx, crate::pin::collect_pins);
+    stage(
+        "make_space_for_edge_labels",
+        graph,
+        ctx,
+        make_space_for_edge_labels,
+    );
+    stage("remove_self_edges", graph, ctx, remove_self_edges);
+    stage_with_context(
+        "remove_non_constraint_edges",
+        graph,
+        ctx,
+        remove_non_constraint_edges,
+    );
+    stage_with_context("acyclic", graph, ctx, |g, ctx| {
+        ctx.stats.reversed_edges += crate::acyclic::Acyclic::run(g)
+    });
+    stage_with_context("nesting_graph_run", graph, ctx, nesting_graph_run);
+    stage_with_context("rank", graph, ctx, rank);
+    stage_with_context("pin_ranks", graph, ctx, crate::pin::pin_ranks);
+    if ctx.budget.cancelled() {
+        return;
+    }
+    stage_with_context(
+        "restore_non_constraint_edges",
+        graph,
+        ctx,
+        restore_non_constraint_edges,
+    );
+    stage_with_context(
+        "inject_edge_label_proxies",
+        graph,
+        ctx,
+        inject_edge_label_proxies,
+    );
+    stage(
+        "remove_empty_ranks",
+        graph,
+        ctx,
+        crate::util::remove_empty_ranks,
+    );
+    stage("nesting_graph_cleanup", graph, ctx, nesting_graph_cleanup);
+    stage("normalize_ranks", graph, ctx, crate::util::normalize_ranks);
+    stage("assign_rank_min_max", graph, ctx, assign_rank_min_max);
+    stage(
+        "remove_edge_label_proxies",
+        graph,
+        ctx,
+        remove_edge_label_proxies,
+    );
+    stage_with_context("normalize", graph, ctx, normalize_edges); // 添加虚拟节点
+    stage("parent_dummy_chains", graph, ctx, parent_dummy_chains);
+    stage_with_context("add_border_segments", graph, ctx, add_border_segments);
+    stage_with_context("order", graph, ctx, |g, ctx| order(g, ctx, Some(opts)));
+    if ctx.budget.cancelled() {
+        return;
+    }
+    stage_with_context("insert_self_edges", graph, ctx, insert_self_edges);
+    stage(
+        "adjust_coordinate_system",
+        graph,
+        ctx,
+        crate::position::adjust_coordinate_system,
+    );
+    stage_with_context(
+        "insert_flat_edge_labels",
+        graph,
+        ctx,
+        insert_flat_edge_labels,
+    );
+    stage_with_context("position", graph, ctx, position);
+    if ctx.budget.cancelled() {
+        return;
+    }
+    stage("position_flat_edges", graph, ctx, position_flat_edges);
+    stage("position_self_edges", graph, ctx, position_self_edges);
+    stage("remove_border_nodes", graph, ctx, remove_border_nodes);
+    stage("normalize_undo", graph, ctx, undo_normalize_edges);
+    stage(
+        "fixup_edge_label_coords",
+        graph,
+        ctx,
+        fixup_edge_label_coords,
+    );
+    stage(
+        "undo_coordinate_system",
+        graph,
+        ctx,
+        crate::position::undo_coordinate_system,
+    );
+    stage(
+        "translate_graph",
+        graph,
+        ctx,
+        crate::position::translate_graph,
+    );
+    stage_with_context(
+        "translate_to_pins",
+        graph,
+        ctx,
+        crate::pin::translate_to_pins,
+    );
+    stage_with_context(
+        "translate_to_hints",
+        graph,
+        ctx,
+        crate::incremental::translate_to_hints,
+    );
+    stage("assign_node_intersects", graph, ctx, assign_node_intersects);
+    stage("route_edges", graph, ctx, crate::route::route_edges);
+    stage(
+        "reverse_points_for_reversed_edges",
+        graph,
+        ctx,
+        reverse_points_for_reversed_edges,
+    );
+    stage("acyclic_undo", graph, ctx, crate::acyclic::Acyclic::undo);
+    stage_with_context(
+        "measure_displacement",
+        graph,
+        ctx,
+        crate::incremental::measure_displacement,
+    );
 }
 
 /// 为边标签留出空间
@@ -233,197 +517,221 @@ fn make_space_for_edge_labels(graph: &mut Graph) {
 
 /// 移除自环边
 ///
+/// 自环边及其标签暂存在节点的 `self_edges` 中，排序后由 [`insert_self_edges`] 插入虚拟节点。
+///
 /// 对应 JS 函数: removeSelfEdges() in lib/layout.js
 fn remove_self_edges(graph: &mut Graph) {
-    let mut self_edges = Vec::new();
-
     for edge in graph.edges() {
-        if edge.source == edge.target {
-            self_edges.push(edge);
+        if edge.source != edge.target {
+            continue;
         }
-    }
-
-    for edge in self_edges {
-        if let Some(_edge_label) = graph.edge_label(&edge) {
-            // 存储自环边信息
-            if let Some(_node_label) = graph.node_label_mut(edge.source) {
-                // 简化实现，不再使用custom字段
-            }
+        if let Some(edge_label) = graph.edge_label(&edge).cloned()
+            && let Some(node_label) = graph.node_label_mut(edge.source)
+        {
+            node_label
+                .self_edges
+                .get_or_insert_with(Vec::new)
+                .push((edge.clone(), edge_label));
         }
         graph.remove_edge(&edge);
     }
 }
 
-/// 无环化处理
+/// 暂时移除不参与排名的边
 ///
-/// 对应 JS 函数: acyclic.run() in lib/acyclic.js
-fn acyclic(graph: &mut Graph) {
-    // 使用深度优先搜索检测环并反转边
-    let mut visited = indexmap::IndexSet::new();
-    let mut rec_stack = indexmap::IndexSet::new();
-    let mut edges_to_reverse = Vec::new();
-
-    let node_indices: Vec<NodeIndex> = graph.node_indices().collect();
-    for node_id in node_indices {
-        if !visited.contains(&node_id) {
-            dfs_acyclic(
-                graph,
-                node_id,
-                &mut visited,
-                &mut rec_stack,
-                &mut edges_to_reverse,
-            );
-        }
-    }
-
-    // 反转检测到的环边
-    for edge in edges_to_reverse {
-        if let Some(edge_label) = graph.edge_label_mut(&edge) {
-            edge_label.reversed = Some(true);
+/// 这些边不参与去环和排名，排名完成后由 `restore_non_constraint_edges` 加回。
+fn remove_non_constraint_edges(graph: &mut Graph, ctx: &mut LayoutContext) {
+    for edge in graph.edges() {
+        if graph
+            .edge_label(&edge)
+            .is_some_and(|label| !label.constraint)
+            && let Some(label) = graph.remove_edge(&edge)
+        {
+            ctx.non_constraint_edges.push((edge, label));
         }
     }
 }
 
-/// 深度优先搜索检测环
+/// 加回不参与排名的边
 ///
-/// 对应 JS 函数: dfsAcyclic() in lib/acyclic.js
-fn dfs_acyclic(
-    graph: &mut Graph,
-    node_id: NodeIndex,
-    visited: &mut indexmap::IndexSet<NodeIndex>,
-    rec_stack: &mut indexmap::IndexSet<NodeIndex>,
-    edges_to_reverse: &mut Vec<Edge>,
-) {
-    visited.insert(node_id);
-    rec_stack.insert(node_id);
-
-    let successors: Vec<NodeIndex> = graph.successors(node_id).collect();
-    for next_node in successors {
-        if !visited.contains(&next_node) {
-            dfs_acyclic(graph, next_node, visited, rec_stack, edges_to_reverse);
-        } else if rec_stack.contains(&next_node) {
-            // 发现环，标记边需要反转
-            let edge = Edge::new(node_id, next_node);
-            edges_to_reverse.push(edge);
+/// 目标排名小于源排名的边像去环时一样反向加入，之后按普通的边拆分和布线，
+/// `acyclic_undo` 恢复原来的方向。两端排名相同的边成为同层边。
+fn restore_non_constraint_edges(graph: &mut Graph, ctx: &mut LayoutContext) {
+    let rank = |graph: &Graph, node: NodeIndex| graph.node_label(node).and_then(|l| l.rank);
+    for (edge, label) in std::mem::take(&mut ctx.non_constraint_edges) {
+        if rank(graph, edge.target) < rank(graph, edge.source) {
+            crate::acyclic::Acyclic::add_reversed(graph, &edge, label);
+        } else {
+            graph.add_edge(edge, label);
         }
     }
-
-    rec_stack.swap_remove(&node_id);
 }
 
 /// 注入边标签代理
 ///
+/// 在有标签的边的中间层级放置一个代理节点，避免该层级在 `remove_empty_ranks` 中被删除。
+///
 /// 对应 JS 函数: injectEdgeLabelProxies() in lib/layout.js
-fn inject_edge_label_proxies(graph: &mut Graph) {
-    let mut proxies: Vec<(Edge, NodeIndex)> = Vec::new();
-
+fn inject_edge_label_proxies(graph: &mut Graph, ctx: &mut LayoutContext) {
     for edge in graph.edges() {
-        if let Some(edge_label) = graph.edge_label(&edge) {
-            if edge_label.width > 0.0 && edge_label.height > 0.0 {
-                if let (Some(source_label), Some(target_label)) =
-                    (graph.node_label(edge.source), graph.node_label(edge.target))
-                {
-                    if let (Some(source_rank), Some(target_rank)) =
-                        (source_label.rank, target_label.rank)
-                    {
-                        let proxy_rank = (target_rank - source_rank) / 2 + source_rank;
-                        let _proxy_id = format!("_ep_{}", graph.node_count());
-
-                        let mut proxy_label = crate::types::NodeLabel::default();
-                        proxy_label.rank = Some(proxy_rank);
-                        proxy_label.dummy = Some(Dummy::EdgeProxy);
-
-                        let proxy_node = graph.add_node(proxy_label);
-                        proxies.push((edge, proxy_node));
-                    }
-                }
-            }
+        if let Some(edge_label) = graph.edge_label(&edge)
+            && edge_label.width > 0.0
+            && edge_label.height > 0.0
+            && let (Some(source_rank), Some(target_rank)) = (
+                graph.node_label(edge.source).and_then(|l| l.rank),
+                graph.node_label(edge.target).and_then(|l| l.rank),
+            )
+        {
+            graph.add_node(NodeLabel {
+                rank: Some((target_rank - source_rank) / 2 + source_rank),
+                dummy: Some(Dummy::EdgeProxy),
+                edge_obj: Some(edge),
+                ..Default::default()
+            });
+            ctx.stats.dummy_nodes += 1;
         }
     }
 }
 
 /// 规范化边，添加虚拟节点
-fn normalize_edges(graph: &mut Graph) {
+///
+/// 跨越多个层级的边被替换为一串相邻层级之间的短边，中间的虚拟节点记录原始边，
+/// 链的第一个节点保存原始边标签，并记录在 `dummy_chains` 中。
+/// 位于 `label_rank` 层级的虚拟节点承载边标签的尺寸。
+///
+/// 对应 JS 函数: normalize.run() in lib/normalize.js
+fn normalize_edges(graph: &mut Graph, ctx: &mut LayoutContext) {
     let mut dummy_chains = Vec::new();
-    let mut edges_to_normalize = Vec::new();
 
-    // 收集需要规范化的边
     for edge in graph.edges() {
-        if let (Some(source_label), Some(target_label)) =
-            (graph.node_label(edge.source), graph.node_label(edge.target))
-        {
-            if let (Some(source_rank), Some(target_rank)) = (source_label.rank, target_label.rank) {
-                if target_rank > source_rank + 1 {
-                    edges_to_normalize.push(edge);
-                }
-            }
+        let (Some(source_rank), Some(target_rank)) = (
+            graph.node_label(edge.source).and_then(|l| l.rank),
+            graph.node_label(edge.target).and_then(|l| l.rank),
+        ) else {
+            continue;
+        };
+        if target_rank <= source_rank + 1 {
+            continue;
         }
-    }
-
-    // 规范化每条边
-    for edge in edges_to_normalize {
-        // 先获取所有需要的信息，避免借用冲突
-        let (source_rank, target_rank, edge_weight) =
-            if let Some(edge_label) = graph.edge_label(&edge) {
-                let source_rank = graph
-                    .node_label(edge.source)
-                    .and_then(|l| l.rank)
-                    .unwrap_or(0);
-                let target_rank = graph
-                    .node_label(edge.target)
-                    .and_then(|l| l.rank)
-                    .unwrap_or(0);
-                (source_rank, target_rank, edge_label.weight)
-            } else {
-                continue;
+        let Some(mut edge_label) = graph.remove_edge(&edge) else {
+            continue;
+        };
+        edge_label.points.clear();
+        let weight = edge_label.weight;
+        // 第一段从源端口出发，最后一段到达目标端口，排序时按端口位置计算重心
+        let source_port = edge_label.source_port.clone();
+        let target_port = edge_label.target_port.clone();
+        // 边标签随第一个虚拟节点保存，标签尺寸需要提前取出
+        let label_size = edge_label
+            .label_rank
+            .map(|rank| (rank, edge_label.width, edge_label.height));
+        let mut edge_label = Some(Box::new(edge_label));
+
+        let mut current_node = edge.source;
+        for rank in source_rank + 1..target_rank {
+            let mut dummy_label = NodeLabel {
+                rank: Some(rank),
+                dummy: Some(Dummy::Edge),
+                edge_obj: Some(edge.clone()),
+                ..Default::default()
             };
+            if let Some((label_rank, width, height)) = label_size
+                && label_rank == rank
+            {
+                dummy_label.width = width;
+                dummy_label.height = height;
+                dummy_label.dummy = Some(Dummy::EdgeLabel);
+            }
+            let is_first = current_node == edge.source;
+            if is_first {
+                dummy_label.edge_label = edge_label.take();
+            }
 
-        if target_rank > source_rank + 1 {
-            let mut current_node = edge.source;
-            let mut current_rank = source_rank;
-            let mut is_first_dummy = true;
-
-            // 移除原边
-            graph.remove_edge(&edge);
-
-            // 添加虚拟节点
-            while current_rank < target_rank - 1 {
-                current_rank += 1;
+            let dummy_node = graph.add_node(dummy_label);
+            ctx.stats.dummy_nodes += 1;
+            graph.add_edge(
+                Edge {
+                    source: current_node,
+                    target: dummy_node,
+                    name: edge.name.clone(),
+                },
+                EdgeLabel {
+                    weight,
+                    source_port: if is_first { source_port.clone() } else { None },
+                    ..Default::default()
+                },
+            );
+            if is_first {
+                dummy_chains.push(dummy_node);
+            }
+            current_node = dummy_node;
+        }
 
-                let mut dummy_label = NodeLabel::default();
-                dummy_label.width = 0.0;
-                dummy_label.height = 0.0;
-                dummy_label.rank = Some(current_rank);
-                dummy_label.dummy = Some(Dummy::Edge);
-                dummy_label.edge_obj = Some(edge.clone());
+        graph.add_edge(
+            Edge {
+                source: current_node,
+                target: edge.target,
+                name: edge.name.clone(),
+            },
+            EdgeLabel {
+                weight,
+                target_port,
+                ..Default::default()
+            },
+        );
+    }
 
-                let dummy_node = graph.add_node(dummy_label);
+    // 存储虚拟节点链
+    let config = graph.config_mut();
+    config.dummy_chains = Some(dummy_chains);
+}
 
-                // 添加边
-                let dummy_edge = Edge::new(current_node, dummy_node);
-                let mut dummy_edge_label = EdgeLabel::default();
-                dummy_edge_label.weight = edge_weight;
-                graph.add_edge(dummy_edge, dummy_edge_label);
+/// 撤销边的规范化
+///
+/// 沿每条虚拟节点链收集坐标作为原始边的折点，并把标签虚拟节点的位置作为边标签的位置，
+/// 然后恢复原始边并删除虚拟节点。
+///
+/// 对应 JS 函数: normalize.undo() in lib/normalize.js
+fn undo_normalize_edges(graph: &mut Graph) {
+    let dummy_chains = graph.config_mut().dummy_chains.take().unwrap_or_default();
+    let mut dummies = Vec::new();
+
+    for chain_start in dummy_chains {
+        let Some(node_label) = graph.node_label_mut(chain_start) else {
+            continue;
+        };
+        let (Some(edge), Some(mut edge_label)) =
+            (node_label.edge_obj.clone(), node_label.edge_label.take())
+        else {
+            continue;
+        };
 
-                if is_first_dummy {
-                    dummy_chains.push(dummy_node);
-                    is_first_dummy = false;
+        let mut current = chain_start;
+        while let Some(node_label) = graph.node_label(current)
+            && matches!(node_label.dummy, Some(Dummy::Edge | Dummy::EdgeLabel))
+        {
+            if let (Some(x), Some(y)) = (node_label.x, node_label.y) {
+                edge_label.points.push(Point::new(x, y));
+                if node_label.dummy == Some(Dummy::EdgeLabel) {
+                    edge_label.x = Some(x);
+                    edge_label.y = Some(y);
+                    edge_label.width = node_label.width;
+                    edge_label.height = node_label.height;
                 }
-
-                current_node = dummy_node;
             }
-
-            // 添加最后一条边到目标节点
-            let final_edge = Edge::new(current_node, edge.target);
-            let mut final_edge_label = EdgeLabel::default();
-            final_edge_label.weight = edge_weight;
-            graph.add_edge(final_edge, final_edge_label);
+            dummies.push(current);
+            match graph.successors(current).next() {
+                Some(next) => current = next,
+                None => break,
+            }
         }
+
+        graph.add_edge(edge, *edge_label);
     }
 
-    // 存储虚拟节点链
-    let config = graph.config_mut();
-    config.dummy_chains = Some(dummy_chains);
+    for dummy in dummies {
+        graph.remove_node(dummy);
+    }
 }
 
 /// 分配排名最小最大值
@@ -446,21 +754,29 @@ fn assign_rank_min_max(graph: &mut Graph) {
 
 /// 移除边标签代理
 ///
+/// 代理节点的层级记录为对应边的 `label_rank`。
+///
 /// 对应 JS 函数: removeEdgeLabelProxies() in lib/layout.js
 fn remove_edge_label_proxies(graph: &mut Graph) {
-    let mut to_remove = Vec::new();
+    let mut proxies = Vec::new();
 
     for node_index in graph.node_indices() {
-        if let Some(label) = graph.node_label(node_index) {
-            if let Some(dummy_type) = &label.dummy {
-                if matches!(dummy_type, Dummy::EdgeProxy) {
-                    to_remove.push(node_index);
-                }
-            }
+        if let Some(label) = graph.node_label(node_index)
+            && label.dummy == Some(Dummy::EdgeProxy)
+        {
+            proxies.push((node_index, label.edge_obj.clone(), label.rank));
+        }
+    }
+
+    for (_, edge, rank) in &proxies {
+        if let Some(edge) = edge
+            && let Some(edge_label) = graph.edge_label_mut(edge)
+        {
+            edge_label.label_rank = *rank;
         }
     }
 
-    for node_index in to_remove {
+    for (node_index, _, _) in proxies {
         graph.remove_node(node_index);
     }
 }
@@ -588,7 +904,7 @@ struct PathData {
 /// 添加边界段
 ///
 /// 对应 JS 函数: addBorderSegments() in lib/add-border-segments.js
-fn add_border_segments(graph: &mut Graph) {
+fn add_border_segments(graph: &mut Graph, ctx: &mut LayoutContext) {
     // 遍历所有节点，为有子图的节点添加边界段
     let nodes_to_process: Vec<NodeIndex> = graph.node_indices().collect();
 
@@ -617,13 +933,13 @@ fn add_border_segments(graph: &mut Graph) {
             // 为每个层级添加边界节点
             for rank in min_rank..=max_rank {
                 // 添加左边界节点
-                let left_border = add_border_node(graph, "borderLeft", "_bl", node_id, rank);
+                let left_border = add_border_node(graph, ctx, "borderLeft", "_bl", node_id, rank);
                 if let Some(node_label) = graph.node_label_mut(node_id) {
                     node_label.border_left.push(left_border);
                 }
 
                 // 添加右边界节点
-                let right_border = add_border_node(graph, "borderRight", "_br", node_id, rank);
+                let right_border = add_border_node(graph, ctx, "borderRight", "_br", node_id, rank);
                 if let Some(node_label) = graph.node_label_mut(node_id) {
                     node_label.border_right.push(right_border);
                 }
@@ -664,6 +980,7 @@ fn add_border_segments(graph: &mut Graph) {
 /// 对应 JS 函数: addBorderNode() in lib/add-border-segments.js
 fn add_border_node(
     graph: &mut Graph,
+    ctx: &mut LayoutContext,
     border_type: &str,
     _prefix: &str,
     parent: NodeIndex,
@@ -677,6 +994,7 @@ fn add_border_node(
     border_label.dummy = Some(Dummy::Border);
 
     let border_node = graph.add_node(border_label);
+    ctx.stats.dummy_nodes += 1;
     graph.set_parent(border_node, parent);
 
     border_node
@@ -684,91 +1002,287 @@ fn add_border_node(
 
 /// 插入自环边
 ///
+/// 每条自环边在节点右侧插入一个与边标签同样大小的虚拟节点，同一节点的多个自环依次向右排列，
+/// 定位后虚拟节点的位置决定环的大小和标签位置。
+///
 /// 对应 JS 函数: insertSelfEdges() in lib/layout.js
-fn insert_self_edges(graph: &mut Graph) {
-    // 构建层级矩阵
+fn insert_self_edges(graph: &mut Graph, ctx: &mut LayoutContext) {
     let layers = crate::util::build_layer_matrix(graph);
 
     for layer in layers {
         let mut order_shift = 0;
 
-        for (i, node_id) in layer.iter().enumerate() {
-            // 先获取自环边信息
-            let self_edges = if let Some(node_label) = graph.node_label(*node_id) {
-                node_label.self_edges.clone()
-            } else {
-                None
+        for (i, &node) in layer.iter().enumerate() {
+            let Some(node_label) = graph.node_label_mut(node) else {
+                continue;
             };
-
-            // 更新节点顺序
-            if let Some(node_label) = graph.node_label_mut(*node_id) {
-                node_label.order = Some(i + order_shift as usize);
+            node_label.order = Some(i + order_shift);
+            let rank = node_label.rank;
+            let self_edges = node_label.self_edges.take().unwrap_or_default();
+
+            for (edge, edge_label) in self_edges {
+                order_shift += 1;
+                graph.add_node(NodeLabel {
+                    width: edge_label.width,
+                    height: edge_label.height,
+                    rank,
+                    order: Some(i + order_shift),
+                    dummy: Some(Dummy::SelfEdge),
+                    edge_obj: Some(edge),
+                    edge_label: Some(Box::new(edge_label)),
+                    ..Default::default()
+                });
+                ctx.stats.dummy_nodes += 1;
             }
+        }
+    }
+}
 
-            // 处理自环边
-            if let Some(self_edges) = self_edges {
-                for self_edge in self_edges {
-                    // 创建自环边虚拟节点
-                    let mut dummy_label = NodeLabel::default();
-                    dummy_label.width = self_edge.width;
-                    dummy_label.height = self_edge.height;
-                    dummy_label.dummy = Some(Dummy::SelfEdge);
-                    // 创建边对象
-                    let edge = Edge::new(*node_id, *node_id);
-                    dummy_label.edge_obj = Some(edge);
-                    if let Some(node_label) = graph.node_label(*node_id) {
-                        dummy_label.rank = node_label.rank;
-                    }
-                    dummy_label.order = Some(i + order_shift as usize);
+/// 生成自环边的几何并移除虚拟节点
+///
+/// 环从节点右侧的边出发，经过虚拟节点所在的位置再回到右侧的边，共五个折点，
+/// 边标签放在虚拟节点的位置上。同一节点的多个自环按虚拟节点的位置由内向外排列，
+/// 内层的环在节点上的起止点更靠近中心，因此各个环互相嵌套而不重叠。
+/// 此时仍处于从上到下的坐标系，“右侧”随 `rankdir` 变换为排名内方向的一侧。
+///
+/// 对应 JS 函数: positionSelfEdges() in lib/layout.js
+fn position_self_edges(graph: &mut Graph) {
+    // 节点 -> 该节点的自环虚拟节点
+    let mut loops: IndexMap<NodeIndex, Vec<NodeIndex>> = IndexMap::new();
+    for node in graph.node_indices() {
+        if let Some(label) = graph.node_label(node)
+            && label.dummy == Some(Dummy::SelfEdge)
+            && let Some(edge) = &label.edge_obj
+        {
+            loops.entry(edge.source).or_default().push(node);
+        }
+    }
+
+    for (node, mut dummies) in loops {
+        let Some(node_label) = graph.node_label(node) else {
+            continue;
+        };
+        let x = node_label.x.unwrap_or(0.0) + node_label.width / 2.0;
+        let y = node_label.y.unwrap_or(0.0);
+        let half_height = node_label.height / 2.0;
+
+        let dummy_x = |dummy: &NodeIndex| graph.node_label(*dummy).and_then(|l| l.x);
+        dummies.sort_by(|a, b| {
+            dummy_x(a)
+                .unwrap_or(0.0)
+                .total_cmp(&dummy_x(b).unwrap_or(0.0))
+        });
 
-                    let dummy_node = graph.add_node(dummy_label);
-                    graph.set_parent(dummy_node, *node_id);
+        let count = dummies.len();
+        for (k, dummy) in dummies.into_iter().enumerate() {
+            let Some(dummy_label) = graph.remove_node(dummy) else {
+                continue;
+            };
+            let (Some(edge), Some(edge_label)) = (dummy_label.edge_obj, dummy_label.edge_label)
+            else {
+                continue;
+            };
+            let (dummy_x, dummy_y) = (dummy_label.x.unwrap_or(x), dummy_label.y.unwrap_or(y));
+            let dx = dummy_x - x;
+            let dy = half_height * (k + 1) as f64 / count as f64;
+
+            // 虚拟节点的宽高已按坐标系交换过，之后随其他边一起换回
+            let edge_label = EdgeLabel {
+                width: dummy_label.width,
+                height: dummy_label.height,
+                x: Some(dummy_x),
+                y: Some(dummy_y),
+                points: vec![
+                    Point::new(x + 2.0 * dx / 3.0, y - dy),
+                    Point::new(x + 5.0 * dx / 6.0, y - dy),
+                    Point::new(x + dx, y),
+                    Point::new(x + 5.0 * dx / 6.0, y + dy),
+                    Point::new(x + 2.0 * dx / 3.0, y + dy),
+                ],
+                ..*edge_label
+            };
+            graph.add_edge(edge, edge_label);
+        }
+    }
+}
 
-                    // 添加自环边
-                    let edge_label = self_edge;
-                    graph.add_edge(Edge::new(*node_id, dummy_node), edge_label);
+/// 同层边：两端位于同一排名的不同叶子节点
+fn is_flat_edge(graph: &Graph, edge: &Edge) -> bool {
+    let rank = |node: NodeIndex| graph.node_label(node).and_then(|l| l.rank);
+    edge.source != edge.target
+        && rank(edge.source).is_some()
+        && rank(edge.source) == rank(edge.target)
+        && graph.children(edge.source).is_empty()
+        && graph.children(edge.target).is_empty()
+}
 
-                    order_shift += 1;
-                }
+/// 为有标签的同层边留出空间
+///
+/// 两端相邻（中间只有其他同层边的标签节点）时，在两端之间插入一个虚拟节点，
+/// 宽度为标签宽度，高度为标签高度的两倍，标签放在边的上方时不会与两端的节点重叠。
+/// 在 `adjust_coordinate_system` 之后运行，标签的宽高已按坐标系交换。
+fn insert_flat_edge_labels(graph: &mut Graph, ctx: &mut LayoutContext) {
+    let labeled: Vec<Edge> = graph
+        .edges()
+        .into_iter()
+        .filter(|edge| is_flat_edge(graph, edge))
+        .filter(|edge| {
+            graph
+                .edge_label(edge)
+                .is_some_and(|l| l.width > 0.0 && l.height > 0.0)
+        })
+        .collect();
+    if labeled.is_empty() {
+        return;
+    }
+
+    let mut layers = crate::util::build_layer_matrix(graph);
+    let min_rank = crate::util::min_rank(graph);
+    for edge in labeled {
+        let Some(rank) = graph.node_label(edge.source).and_then(|l| l.rank) else {
+            continue;
+        };
+        let layer = &mut layers[(rank - min_rank) as usize];
+        let position = |node: NodeIndex| layer.iter().position(|&v| v == node);
+        let (Some(a), Some(b)) = (position(edge.source), position(edge.target)) else {
+            continue;
+        };
+        let (lo, hi) = (a.min(b), a.max(b));
+        let adjacent = layer[lo + 1..hi].iter().all(|&v| {
+            crate::util::is_placeholder(v)
+                || graph
+                    .node_label(v)
+                    .is_some_and(|l| l.dummy == Some(Dummy::FlatEdgeLabel))
+        });
+        if !adjacent {
+            continue;
+        }
+
+        let Some(edge_label) = graph.edge_label(&edge) else {
+            continue;
+        };
+        let dummy = graph.add_node(NodeLabel {
+            width: edge_label.width,
+            height: edge_label.height * 2.0,
+            rank: Some(rank),
+            dummy: Some(Dummy::FlatEdgeLabel),
+            edge_obj: Some(edge),
+            ..Default::default()
+        });
+        ctx.stats.dummy_nodes += 1;
+        layer.insert(hi, dummy);
+    }
+
+    for layer in &layers {
+        let nodes = layer
+            .iter()
+            .filter(|&&node| !crate::util::is_placeholder(node));
+        for (i, &node) in nodes.enumerate() {
+            if let Some(label) = graph.node_label_mut(node) {
+                label.order = Some(i);
             }
         }
     }
 }
 
-/// 位置自环边
+/// 生成同层边的几何并移除标签虚拟节点
 ///
-/// 对应 JS 函数: positionSelfEdges() in lib/layout.js
-fn position_self_edges(graph: &mut Graph) {
-    let nodes_to_process: Vec<NodeIndex> = graph.node_indices().collect();
+/// 两端之间没有其他节点时边是一条水平线段，折点留空，由 `assign_node_intersects` 补上两端；
+/// 否则边从两端的上方绕过中间的节点，在排名与上一个排名之间的空隙里水平穿过。
+/// 标签放在边的上方，有虚拟节点时横坐标取虚拟节点的位置，否则取两端的中点。
+/// 绕行的边即使没有标签也记录标签位置，使图的尺寸包含绕行的部分。
+///
+/// 此时仍处于从上到下的坐标系。“上方”是朝向上一个排名的一侧，`BottomTop` 时取反，
+/// 使标签和绕行的边在最终的图中仍位于上方；`LeftRight`、`RightLeft` 时位于朝向上一个排名的一侧。
+fn position_flat_edges(graph: &mut Graph) {
+    let rank_sep = graph.config().rank_sep;
+    let up = if graph.config().rankdir == RankDirection::BottomTop {
+        1.0
+    } else {
+        -1.0
+    };
 
-    for node_id in nodes_to_process {
-        if let Some(node_label) = graph.node_label(node_id) {
-            if node_label.dummy == Some(Dummy::SelfEdge) {
-                if let Some(edge_obj) = &node_label.edge_obj {
-                    // 获取源节点信息
-                    if let Some(source_node_label) = graph.node_label(edge_obj.source) {
-                        let x = source_node_label.x.unwrap_or(0.0) + source_node_label.width / 2.0;
-                        let y = source_node_label.y.unwrap_or(0.0);
-
-                        // 计算自环边的位置
-                        let dx = node_label.x.unwrap_or(0.0) - x;
-                        let dy = source_node_label.height / 2.0;
-
-                        // 更新自环边标签位置
-                        if let Some(edge_label) =
-                            graph.edge_label_mut(&Edge::new(edge_obj.source, edge_obj.target))
-                        {
-                            edge_label.x = Some(x + dx);
-                            edge_label.y = Some(y + dy);
-                        }
+    // 排名 -> 该排名的节点（顺序、节点）和最大半高
+    let mut ranks: IndexMap<i32, (Vec<(usize, NodeIndex)>, f64)> = IndexMap::new();
+    let mut label_dummies: IndexMap<Edge, NodeIndex> = IndexMap::new();
+    for node in graph.node_indices() {
+        let Some(label) = graph.node_label(node) else {
+            continue;
+        };
+        if !graph.children(node).is_empty() {
+            continue;
+        }
+        if let Some(rank) = label.rank {
+            let entry = ranks.entry(rank).or_default();
+            entry.0.extend(label.order.map(|order| (order, node)));
+            entry.1 = entry.1.max(label.height / 2.0);
+        }
+        if label.dummy == Some(Dummy::FlatEdgeLabel)
+            && let Some(edge) = &label.edge_obj
+        {
+            label_dummies.insert(edge.clone(), node);
+        }
+    }
 
-                        // 移除虚拟节点
-                        graph.remove_node(node_id);
-                    }
-                }
+    for edge in graph.edges() {
+        if !is_flat_edge(graph, &edge) {
+            continue;
+        }
+        let (Some(source), Some(target)) =
+            (graph.node_label(edge.source), graph.node_label(edge.target))
+        else {
+            continue;
+        };
+        let (Some(rank), Some(source_order), Some(target_order)) =
+            (source.rank, source.order, target.order)
+        else {
+            continue;
+        };
+        let (source_x, target_x) = (source.x.unwrap_or(0.0), target.x.unwrap_or(0.0));
+        let y = source.y.unwrap_or(0.0);
+        let (lo, hi) = (
+            source_order.min(target_order),
+            source_order.max(target_order),
+        );
+        let (members, half_height) = &ranks[&rank];
+
+        // 中间的边虚拟节点只是边经过的位置，标签虚拟节点的标签在边的上方
+        let blocked = members.iter().any(|&(order, node)| {
+            order > lo
+                && order < hi
+                && graph.node_label(node).is_some_and(|l| {
+                    !matches!(
+                        l.dummy,
+                        Some(Dummy::Edge | Dummy::EdgeLabel | Dummy::FlatEdgeLabel)
+                    )
+                })
+        });
+        let dummy_x = label_dummies
+            .get(&edge)
+            .and_then(|&dummy| graph.node_label(dummy))
+            .and_then(|l| l.x);
+
+        let Some(edge_label) = graph.edge_label_mut(&edge) else {
+            continue;
+        };
+        let labeled = edge_label.width > 0.0 && edge_label.height > 0.0;
+        if blocked {
+            let level = y + up * (half_height + rank_sep / 2.0);
+            edge_label.points = vec![Point::new(source_x, level), Point::new(target_x, level)];
+            edge_label.x = Some((source_x + target_x) / 2.0);
+            edge_label.y = Some(level + up * edge_label.height / 2.0);
+        } else {
+            edge_label.points.clear();
+            if labeled {
+                edge_label.x = Some(dummy_x.unwrap_or((source_x + target_x) / 2.0));
+                edge_label.y = Some(y + up * edge_label.height / 2.0);
             }
         }
     }
+
+    for dummy in label_dummies.into_values() {
+        graph.remove_node(dummy);
+    }
 }
 
 /// 构建层级矩阵
@@ -874,71 +1388,52 @@ fn fixup_edge_label_coords(graph: &mut Graph) {
     }
 }
 
-/// 撤销坐标系统调整
-///
-/// 对应 JS 函数: coordinateSystem.undo() in lib/coordinate-system.js
-fn undo_coordinate_system(graph: &mut Graph) {
-    // 获取图的配置
-    let rank_dir = "TB"; // 简化实现，使用默认值
-
-    // 根据层级方向调整坐标
-    let node_indices: Vec<NodeIndex> = graph.node_indices().collect();
-    for node_id in node_indices {
-        if let Some(node_label) = graph.node_label_mut(node_id) {
-            if let (Some(x), Some(y)) = (node_label.x, node_label.y) {
-                match rank_dir {
-                    "BT" => {
-                        // 从下到上，需要翻转Y坐标
-                        node_label.y = Some(-y);
-                    }
-                    "RL" => {
-                        // 从右到左，交换X和Y坐标
-                        node_label.x = Some(y);
-                        node_label.y = Some(-x);
-                    }
-                    "LR" => {
-                        // 从左到右，交换X和Y坐标
-                        node_label.x = Some(y);
-                        node_label.y = Some(x);
-                    }
-                    _ => {
-                        // "TB" 默认情况，不需要调整
-                    }
-                }
-            }
-        }
-    }
-}
-
 /// 分配节点交点
 ///
+/// 保留 `points` 中已有的折点，在首尾加上节点边界与第一个（最后一个）折点方向的交点；
+/// 没有折点时取两个节点中心的连线。连接端口的一端使用端口的位置。
+/// 自环的两端从节点朝向环的一侧离开。
+///
 /// 对应 JS 函数: assignNodeIntersects() in lib/layout.js
 pub fn assign_node_intersects(graph: &mut Graph) {
+    let rankdir = graph.config().rankdir;
     let mut edge_updates = Vec::new();
 
     for edge in graph.edges() {
-        if let (Some(source_label), Some(target_label)) =
-            (graph.node_label(edge.source), graph.node_label(edge.target))
+        if let (Some(source_label), Some(target_label), Some(edge_label)) = (
+            graph.node_label(edge.source),
+            graph.node_label(edge.target),
+            graph.edge_label(&edge),
+        ) && let (Some(source_x), Some(source_y), Some(target_x), Some(target_y)) =
+            (source_label.x, source_label.y, target_label.x, target_label.y)
         {
-            let mut points = Vec::new();
-
-            // 添加起点相交点
-            if let (Some(source_x), Some(source_y)) = (source_label.x, source_label.y) {
-                let intersect = crate::util::intersect_rect(
-                    source_label,
-                    &crate::types::Point::new(source_x, source_y),
-                );
-                points.push(intersect);
-            }
-
-            // 添加终点相交点
-            if let (Some(target_x), Some(target_y)) = (target_label.x, target_label.y) {
-                let intersect = crate::util::intersect_rect(
-                    target_label,
-                    &crate::types::Point::new(target_x, target_y),
-                );
-                points.push(intersect);
-            }
+            // 连接端口的一端直接落在端口上
+            let source_port =
+                crate::port::port_point(source_label, edge_label.source_port.as_deref());
+            let target_port =
+                crate::port::port_point(target_label, edge_label.target_port.as_deref());
+
+            let mut points = edge_label.points.clone();
+            let (toward_source, toward_target) = match (points.first(), points.last()) {
+                (Some(&first), Some(&last)) if edge.source == edge.target => (
+                    self_loop_toward(source_label, first, rankdir),
+                    self_loop_toward(target_label, last, rankdir),
+                ),
+                (Some(&first), Some(&last)) => (first, last),
+                _ => (
+                    target_port.unwrap_or(Point::new(target_x, target_y)),
+                    source_port.unwrap_or(Point::new(source_x, source_y)),
+                ),
+            };
+            points.insert(
+                0,
+                source_port
+                    .unwrap_or_else(|| crate::shape::intersect_node(source_label, &toward_source)),
+            );
+            points.push(
+                target_port
+                    .unwrap_or_else(|| crate::shape::intersect_node(target_label, &toward_target)),
+            );
 
             edge_updates.push((edge, points));
         }
@@ -951,88 +1446,68 @@ pub fn assign_node_intersects(graph: &mut Graph) {
     }
 }
 
-/// 反转边的点
+/// 自环一端与节点边界求交时的朝向点
 ///
-/// 对应 JS 函数: reversePointsForReversedEdges() in lib/layout.js
-fn reverse_points_for_reversed_edges(graph: &mut Graph) {
-    for edge in graph.edges() {
-        if let Some(_edge_label) = graph.edge_label_mut(&edge) {
-            // 简化实现，不再使用custom字段
+/// 自环位于节点排名内方向的一侧。取节点外接矩形朝向自环的边上、与折点在排名方向上
+/// 对齐的点，矩形节点的环因此垂直离开节点，嵌套的多个环在节点上的起止点也不会交错。
+fn self_loop_toward(node: &NodeLabel, point: Point, rankdir: RankDirection) -> Point {
+    let (x, y) = (node.x.unwrap_or(0.0), node.y.unwrap_or(0.0));
+    match rankdir {
+        RankDirection::TopBottom | RankDirection::BottomTop => {
+            Point::new(x + (node.width / 2.0).copysign(point.x - x), point.y)
+        }
+        RankDirection::LeftRight | RankDirection::RightLeft => {
+            Point::new(point.x, y + (node.height / 2.0).copysign(point.y - y))
         }
     }
 }
 
-/// 撤销无环化
+/// 反转边的点
 ///
-/// 对应 JS 函数: acyclic.undo() in lib/acyclic.js
-fn acyclic_undo(graph: &mut Graph) {
-    // 恢复被反转的边
-    let edges_to_reverse: Vec<Edge> = graph
-        .edges()
-        .into_iter()
-        .filter(|edge| {
-            if let Some(edge_label) = graph.edge_label(edge) {
-                edge_label.reversed.unwrap_or(false)
-            } else {
-                false
-            }
-        })
-        .collect();
-
-    for edge in edges_to_reverse {
-        // 移除当前边
-        if let Some(edge_label) = graph.edge_label(&edge) {
-            let mut new_edge_label = edge_label.clone();
-            new_edge_label.reversed = Some(false);
-
-            // 添加反向边
-            graph.add_edge(Edge::new(edge.target, edge.source), new_edge_label);
-            graph.remove_edge(&edge);
+/// 对应 JS 函数: reversePointsForReversedEdges() in lib/layout.js
+fn reverse_points_for_reversed_edges(graph: &mut Graph) {
+    for edge in graph.edges() {
+        if let Some(edge_label) = graph.edge_label_mut(&edge)
+            && edge_label.reversed == Some(true)
+        {
+            edge_label.points.reverse();
+            edge_label.control_points.reverse();
         }
     }
 }
 
 /// 更新输入图
 ///
+/// 通过 [`LayoutMapping`] 把布局结果写回对应的输入节点和边。
+///
 /// 对应 JS 函数: updateInputGraph() in lib/layout.js
-fn update_input_graph(input_graph: &mut Graph, layout_graph: &Graph) {
-    // 由于现在使用 NodeIndex，我们需要通过索引来匹配节点
-    // 这里简化实现，假设两个图的节点顺序相同
-    let input_nodes: Vec<_> = input_graph.node_indices().collect();
-    let layout_nodes: Vec<_> = layout_graph.node_indices().collect();
-
-    for (i, &input_node) in input_nodes.iter().enumerate() {
-        if let Some(layout_node) = layout_nodes.get(i) {
-            if let (Some(input_label), Some(layout_label)) = (
-                input_graph.node_label_mut(input_node),
-                layout_graph.node_label(*layout_node),
-            ) {
-                input_label.x = layout_label.x;
-                input_label.y = layout_label.y;
-                input_label.rank = layout_label.rank;
-
-                if layout_graph.children(*layout_node).len() > 0 {
-                    input_label.width = layout_label.width;
-                    input_label.height = layout_label.height;
-                }
+fn update_input_graph(input_graph: &mut Graph, layout_graph: &Graph, mapping: &LayoutMapping) {
+    for (&input_node, &layout_node) in &mapping.nodes {
+        if let (Some(input_label), Some(layout_label)) = (
+            input_graph.node_label_mut(input_node),
+            layout_graph.node_label(layout_node),
+        ) {
+            input_label.x = layout_label.x;
+            input_label.y = layout_label.y;
+            input_label.rank = layout_label.rank;
+            input_label.order = layout_label.order;
+
+            if !layout_graph.children(layout_node).is_empty() {
+                input_label.width = layout_label.width;
+                input_label.height = layout_label.height;
             }
         }
     }
 
-    // 复制边信息 - 简化实现，假设边顺序相同
-    let input_edges: Vec<_> = input_graph.edges();
-    let layout_edges: Vec<_> = layout_graph.edges();
-
-    for (i, input_edge) in input_edges.iter().enumerate() {
-        if let Some(layout_edge) = layout_edges.get(i) {
-            if let (Some(input_edge_label), Some(layout_edge_label)) = (
-                input_graph.edge_label_mut(input_edge),
-                layout_graph.edge_label(layout_edge),
-            ) {
-                input_edge_label.points = layout_edge_label.points.clone();
-                input_edge_label.x = layout_edge_label.x;
-                input_edge_label.y = layout_edge_label.y;
-            }
+    for (input_edge, layout_edge) in &mapping.edges {
+        if let (Some(input_edge_label), Some(layout_edge_label)) = (
+            input_graph.edge_label_mut(input_edge),
+            layout_graph.edge_label(layout_edge),
+        ) {
+            input_edge_label.points = layout_edge_label.points.clone();
+            input_edge_label.control_points = layout_edge_label.control_points.clone();
+            input_edge_label.x = layout_edge_label.x;
+            input_edge_label.y = layout_edge_label.y;
         }
     }
 
@@ -1095,20 +1570,24 @@ mod tests {
 /// 运行嵌套图算法
 ///
 /// 对应 JS 函数: nestingGraph.run() in lib/nesting-graph.js
-fn nesting_graph_run(graph: &mut Graph) {
+fn nesting_graph_run(graph: &mut Graph, ctx: &mut LayoutContext) {
+    // 这里不为子图添加边界节点，也不放大边的 minlen，所以相邻排名的间隔为 1
+    graph.config_mut().node_rank_factor = Some(1);
+
     // 添加根节点
     let mut root_label = NodeLabel::default();
     root_label.dummy = Some(Dummy::Root);
     root_label.width = 0.0;
     root_label.height = 0.0;
     let root = graph.add_node(root_label);
+    ctx.stats.dummy_nodes += 1;
 
-    // 为所有现有节点添加到根节点的边
+    // 为所有现有节点添加到根节点的边，权重为0，只用于保证图连通
     let existing_nodes: Vec<NodeIndex> = graph.node_indices().filter(|&id| id != root).collect();
     for node_id in existing_nodes {
         let edge = Edge::new(root, node_id);
         let mut edge_label = EdgeLabel::default();
-        edge_label.weight = 1.0;
+        edge_label.weight = 0.0;
         graph.add_edge(edge, edge_label);
     }
 }
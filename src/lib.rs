@@ -5,17 +5,34 @@
 //! automatic node positioning and edge routing.
 
 pub mod acyclic;
-pub mod counters;
+pub mod budget;
+pub mod context;
+pub mod error;
 pub mod graph;
+pub mod incremental;
+pub mod io;
+pub mod keyed;
 pub mod layout;
 pub mod order;
+pub mod pin;
+pub mod port;
 pub mod position;
 pub mod rank;
+pub mod render;
+pub mod route;
+pub mod shape;
+pub mod stats;
 pub mod types;
 pub mod util;
 
+pub use budget::{BudgetKind, CancellationToken, LayoutBudget};
+pub use context::LayoutContext;
+pub use error::LayoutError;
 pub use graph::Graph;
-pub use layout::layout;
+pub use incremental::{LayoutHints, NodeHint};
+pub use keyed::{KeyedEdge, KeyedGraph, KeyedLayout};
+pub use layout::{layout, try_layout};
+pub use stats::{Displacement, LayoutStats};
 pub use types::*;
 
 /// 版本信息
@@ -1,177 +1,159 @@
 //! 构建层级图
 
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 
 use crate::graph::Graph;
 use crate::graph::NodeIndex;
-use crate::types::Edge;
+use crate::types::{Edge, NodeLabel};
 
 /// 层级图结构，包含层级图和节点映射
 pub struct LayerGraph {
     pub graph: Graph,
+    /// 根节点，本层可移动的节点都在它之下
+    pub root: NodeIndex,
     pub node_mapping: IndexMap<NodeIndex, NodeIndex>, // 原始节点ID -> 层级图节点ID
+    pub original: IndexMap<NodeIndex, NodeIndex>,     // 层级图节点ID -> 原始节点ID
+    /// 本层不参与排序的原始节点（带排名的复合节点），写回顺序时排在已排序节点之后
+    pub unsorted: Vec<NodeIndex>,
 }
 
-/// 构建指定排名的层级图
-pub fn build_layer_graph(graph: &Graph, rank: i32, relationship: &str) -> LayerGraph {
-    let mut layer_graph = Graph::new();
-    let mut node_mapping = IndexMap::default();
-
-    // 添加该层级的所有节点
-    for node_id in graph.node_indices() {
-        if let Some(label) = graph.node_label(node_id) {
-            if let Some(node_rank) = label.rank {
-                if node_rank == rank {
-                    let new_node_id = layer_graph.add_node(label.clone());
-                    node_mapping.insert(node_id, new_node_id);
-                }
-            }
+impl LayerGraph {
+    /// 把原始节点加入层级图，标签中指向原图的字段被清空
+    fn add(&mut self, graph: &Graph, node: NodeIndex) -> NodeIndex {
+        if let Some(&mapped) = self.node_mapping.get(&node) {
+            return mapped;
         }
+        let mut label = graph.node_label(node).cloned().unwrap_or_default();
+        label.parent = None;
+        label.border_left.clear();
+        label.border_right.clear();
+        label.border_top = None;
+        label.border_bottom = None;
+        let mapped = self.graph.add_node(label);
+        self.node_mapping.insert(node, mapped);
+        self.original.insert(mapped, node);
+        mapped
     }
 
-    // 根据关系类型添加边
-    match relationship {
-        "in_edges" => add_in_edges_to_layer_graph(graph, &mut layer_graph, &node_mapping, rank),
-        "out_edges" => add_out_edges_to_layer_graph(graph, &mut layer_graph, &node_mapping, rank),
-        _ => {
-            // 默认添加所有相关边
-            add_all_edges_to_layer_graph(graph, &mut layer_graph, &node_mapping, rank);
+    /// 原始父节点在层级图中的副本，祖先链一直连到根节点
+    fn container(&mut self, graph: &Graph, parent: Option<NodeIndex>) -> NodeIndex {
+        let Some(parent) = parent else {
+            return self.root;
+        };
+        if let Some(&mapped) = self.node_mapping.get(&parent) {
+            return mapped;
         }
-    }
-
-    LayerGraph {
-        graph: layer_graph,
-        node_mapping,
+        let mapped = self.add(graph, parent);
+        let up = self.container(graph, graph.parent(parent));
+        self.graph.set_parent(mapped, up);
+        mapped
     }
 }
 
-/// 添加入边到层级图
-fn add_in_edges_to_layer_graph(
-    graph: &Graph,
-    layer_graph: &mut Graph,
-    node_mapping: &IndexMap<NodeIndex, NodeIndex>,
-    rank: i32,
-) {
-    // 收集所有需要添加的边，避免借用冲突
-    let mut edges_to_add = Vec::new();
-
-    // 遍历层级图中的每个节点
-    for (original_node_id, layer_node_id) in node_mapping {
-        // 在原始图中查找该节点的入边
-        for edge in graph.in_edges(*original_node_id) {
-            if let Some(source_label) = graph.node_label(edge.source) {
-                if let Some(source_rank) = source_label.rank {
-                    if source_rank == rank - 1 {
-                        // 检查源节点是否也在层级图中
-                        if let Some(&source_layer_id) = node_mapping.get(&edge.source) {
-                            if let Some(edge_label) = graph.edge_label(&edge) {
-                                // 创建新的边，使用层级图中的节点ID
-                                let new_edge = Edge::new(source_layer_id, *layer_node_id);
-                                edges_to_add.push((new_edge, edge_label.clone()));
-                            }
-                        }
-                    }
-                }
-            }
+/// 构建指定排名的层级图
+///
+/// 本层的叶子节点挂在各自父节点的副本下，没有父节点的挂在根节点下。`relationship` 为
+/// `"in_edges"` 时取入边、`"out_edges"` 时取出边、其他取两者，边另一端相邻排名的节点作为
+/// 不可移动的节点加入。层级图中的边总是从邻居指向本层节点，保留原边的名称、权重和端口。
+///
+/// 对应 JS 函数: buildLayerGraph() in lib/order/build-layer-graph.js
+pub fn build_layer_graph(graph: &Graph, rank: i32, relationship: &str) -> LayerGraph {
+    let mut layer_graph = Graph::new();
+    // 端口的位置与排名方向有关
+    layer_graph.config_mut().rankdir = graph.config().rankdir;
+    let root = layer_graph.add_node(NodeLabel::default());
+    let mut result = LayerGraph {
+        graph: layer_graph,
+        root,
+        node_mapping: IndexMap::default(),
+        original: IndexMap::default(),
+        unsorted: Vec::new(),
+    };
+
+    let compounds: IndexSet<NodeIndex> = graph
+        .node_indices()
+        .filter_map(|node_id| graph.parent(node_id))
+        .collect();
+    let mut movable = Vec::new();
+    for node_id in graph.node_indices() {
+        if graph.node_label(node_id).and_then(|label| label.rank) != Some(rank) {
+            continue;
+        }
+        if compounds.contains(&node_id) {
+            result.unsorted.push(node_id);
+        } else {
+            movable.push(node_id);
         }
     }
 
-    // 添加收集到的边
-    for (edge, edge_label) in edges_to_add {
-        let _ = layer_graph.add_edge(edge, edge_label);
+    for &node_id in &movable {
+        let layer_node = result.add(graph, node_id);
+        let parent = result.container(graph, graph.parent(node_id));
+        result.graph.set_parent(layer_node, parent);
     }
-}
 
-/// 添加出边到层级图
-fn add_out_edges_to_layer_graph(
-    graph: &Graph,
-    layer_graph: &mut Graph,
-    node_mapping: &IndexMap<NodeIndex, NodeIndex>,
-    rank: i32,
-) {
-    // 收集所有需要添加的边，避免借用冲突
-    let mut edges_to_add = Vec::new();
-
-    // 遍历层级图中的每个节点
-    for (original_node_id, layer_node_id) in node_mapping {
-        // 在原始图中查找该节点的出边
-        for edge in graph.out_edges(*original_node_id) {
-            if let Some(target_label) = graph.node_label(edge.target) {
-                if let Some(target_rank) = target_label.rank {
-                    if target_rank == rank + 1 {
-                        // 检查目标节点是否也在层级图中
-                        if let Some(&target_layer_id) = node_mapping.get(&edge.target) {
-                            if let Some(edge_label) = graph.edge_label(&edge) {
-                                // 创建新的边，使用层级图中的节点ID
-                                let new_edge = Edge::new(*layer_node_id, target_layer_id);
-                                edges_to_add.push((new_edge, edge_label.clone()));
-                            }
-                        }
-                    }
-                }
-            }
+    // 复合节点的边界节点在本层的副本
+    for (&original, &layer_node) in result.node_mapping.clone().iter() {
+        let Some(label) = graph.node_label(original) else {
+            continue;
+        };
+        let Some(index) = label.min_rank.map(|min_rank| (rank - min_rank) as usize) else {
+            continue;
+        };
+        let border = |borders: &[NodeIndex]| {
+            borders
+                .get(index)
+                .and_then(|border| result.node_mapping.get(border).copied())
+        };
+        if let (Some(left), Some(right)) = (border(&label.border_left), border(&label.border_right))
+        {
+            let layer_label = result.graph.node_label_mut(layer_node).unwrap();
+            layer_label.border_left = vec![left];
+            layer_label.border_right = vec![right];
         }
     }
 
-    // 添加收集到的边
-    for (edge, edge_label) in edges_to_add {
-        let _ = layer_graph.add_edge(edge, edge_label);
-    }
-}
-
-/// 添加所有相关边到层级图
-fn add_all_edges_to_layer_graph(
-    graph: &Graph,
-    layer_graph: &mut Graph,
-    node_mapping: &IndexMap<NodeIndex, NodeIndex>,
-    rank: i32,
-) {
-    // 收集所有需要添加的边，避免借用冲突
-    let mut edges_to_add = Vec::new();
-
-    // 遍历层级图中的每个节点
-    for (original_node_id, layer_node_id) in node_mapping {
-        // 添加入边
-        for edge in graph.in_edges(*original_node_id) {
-            if let Some(source_label) = graph.node_label(edge.source) {
-                if let Some(source_rank) = source_label.rank {
-                    if source_rank == rank - 1 {
-                        // 检查源节点是否也在层级图中
-                        if let Some(&source_layer_id) = node_mapping.get(&edge.source) {
-                            if let Some(edge_label) = graph.edge_label(&edge) {
-                                // 创建新的边，使用层级图中的节点ID
-                                let new_edge = Edge::new(source_layer_id, *layer_node_id);
-                                edges_to_add.push((new_edge, edge_label.clone()));
-                            }
-                        }
-                    }
-                }
+    for &node_id in &movable {
+        let layer_node = result.node_mapping[&node_id];
+        let edges = match relationship {
+            "in_edges" => graph.in_edges(node_id),
+            "out_edges" => graph.out_edges(node_id),
+            _ => {
+                let mut edges = graph.in_edges(node_id);
+                edges.extend(graph.out_edges(node_id));
+                edges
             }
-        }
-
-        // 添加出边
-        for edge in graph.out_edges(*original_node_id) {
-            if let Some(target_label) = graph.node_label(edge.target) {
-                if let Some(target_rank) = target_label.rank {
-                    if target_rank == rank + 1 {
-                        // 检查目标节点是否也在层级图中
-                        if let Some(&target_layer_id) = node_mapping.get(&edge.target) {
-                            if let Some(edge_label) = graph.edge_label(&edge) {
-                                // 创建新的边，使用层级图中的节点ID
-                                let new_edge = Edge::new(*layer_node_id, target_layer_id);
-                                edges_to_add.push((new_edge, edge_label.clone()));
-                            }
-                        }
-                    }
-                }
+        };
+        for edge in edges {
+            let outgoing = edge.source == node_id;
+            let neighbor = if outgoing { edge.target } else { edge.source };
+            // 同层的边由 flat_edges 处理
+            let neighbor_rank = graph.node_label(neighbor).and_then(|label| label.rank);
+            if neighbor == node_id || neighbor_rank == Some(rank) {
+                continue;
+            }
+            let Some(mut edge_label) = graph.edge_label(&edge).cloned() else {
+                continue;
+            };
+            if outgoing {
+                std::mem::swap(&mut edge_label.source_port, &mut edge_label.target_port);
+            }
+            let layer_neighbor = result.add(graph, neighbor);
+            let layer_edge = Edge {
+                source: layer_neighbor,
+                target: layer_node,
+                name: edge.name.clone(),
+            };
+            // 两个方向上的平行边合并为一条，权重相加
+            if let Some(existing) = result.graph.edge_label_mut(&layer_edge) {
+                existing.weight += edge_label.weight;
+            } else {
+                result.graph.add_edge(layer_edge, edge_label);
             }
         }
     }
 
-    // 添加收集到的边
-    for (edge, edge_label) in edges_to_add {
-        let _ = layer_graph.add_edge(edge, edge_label);
-    }
+    result
 }
 
 /// 构建层级图的简化版本
@@ -344,10 +326,17 @@ mod tests {
 
         let layer_graph = build_layer_graph(&graph, 1, "in_edges");
 
-        assert_eq!(layer_graph.graph.node_count(), 2);
+        // 根节点、本层的 b 和 c、上层的邻居 a
+        assert_eq!(layer_graph.graph.node_count(), 4);
         // 检查节点映射中是否包含原始节点
         assert!(layer_graph.node_mapping.contains_key(&b));
         assert!(layer_graph.node_mapping.contains_key(&c));
+        let lb = layer_graph.node_mapping[&b];
+        let lc = layer_graph.node_mapping[&c];
+        assert_eq!(layer_graph.graph.children(layer_graph.root), vec![lb, lc]);
+        let la = layer_graph.node_mapping[&a];
+        assert!(layer_graph.graph.has_edge(&Edge::new(la, lb)));
+        assert!(layer_graph.graph.has_edge(&Edge::new(la, lc)));
     }
 
     #[test]
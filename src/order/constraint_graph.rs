@@ -106,6 +106,7 @@ mod tests {
         NodeIndex {
             node_index: petgraph::prelude::NodeIndex::new(node_index),
             which_graph: graph_id,
+            generation: 0,
         }
     }
 
@@ -0,0 +1,183 @@
+//! 用户指定的顺序约束
+//!
+//! 每个 [`OrderConstraint`](crate::types::OrderConstraint) 按层展开为“左侧节点 -> 右侧节点”的约束边：
+//! `Sequence` 中位于同一排名的节点按给定顺序依次相连，`Leftmost` 的节点连向同层的其他节点，
+//! 同层的其他节点连向 `Rightmost` 的节点。边界节点始终位于复合节点两侧，不参与展开。
+//!
+//! 约束边在两处生效：扫描时与子图约束一起传给 `sort_subgraph`，由冲突解决合并违反约束的条目；
+//! 初始排序和每次扫描的结果再经过 [`apply`]，在尽量保持原有顺序的前提下满足所有约束。
+//! 不同复合节点中的节点之间的约束与子图约束一样，提升到它们在共同祖先下的子节点上。
+
+use crate::graph::{Graph, NodeIndex};
+use crate::order::constraint_graph::ConstraintGraph;
+use crate::types::{Dummy, OrderKind};
+use crate::util::is_placeholder;
+use indexmap::{IndexMap, IndexSet};
+
+/// 把图中的顺序约束展开为同层节点之间的约束边
+pub fn layer_constraints(graph: &Graph, layering: &[Vec<NodeIndex>]) -> ConstraintGraph {
+    let mut constraints = ConstraintGraph::new();
+    if graph.order_constraints().is_empty() {
+        return constraints;
+    }
+
+    let layer_of: IndexMap<NodeIndex, usize> = layering
+        .iter()
+        .enumerate()
+        .flat_map(|(i, layer)| layer.iter().map(move |&node| (node, i)))
+        .filter(|&(node, _)| !is_placeholder(node))
+        .collect();
+    let mut add = |from: NodeIndex, to: NodeIndex| {
+        if from != to && !constraints.has_constraint(from, to) {
+            constraints.add_constraint(from, to);
+        }
+    };
+
+    let mut leftmost = IndexSet::new();
+    let mut rightmost = IndexSet::new();
+    for constraint in graph.order_constraints() {
+        match constraint.kind {
+            OrderKind::Sequence => {
+                let mut previous: IndexMap<usize, NodeIndex> = IndexMap::new();
+                for &node in &constraint.nodes {
+                    let Some(&layer) = layer_of.get(&node) else {
+                        continue;
+                    };
+                    if let Some(before) = previous.insert(layer, node) {
+                        add(before, node);
+                    }
+                }
+            }
+            OrderKind::Leftmost => leftmost.extend(constraint.nodes.iter().copied()),
+            OrderKind::Rightmost => rightmost.extend(constraint.nodes.iter().copied()),
+        }
+    }
+
+    let is_border = |node: NodeIndex| {
+        graph
+            .node_label(node)
+            .is_some_and(|l| l.dummy == Some(Dummy::Border))
+    };
+    for (extremes, first) in [(&leftmost, true), (&rightmost, false)] {
+        for &node in extremes {
+            let Some(&layer) = layer_of.get(&node) else {
+                continue;
+            };
+            for &other in &layering[layer] {
+                if is_placeholder(other) || is_border(other) || extremes.contains(&other) {
+                    continue;
+                }
+                if first {
+                    add(node, other);
+                } else {
+                    add(other, node);
+                }
+            }
+        }
+    }
+    constraints
+}
+
+/// 调整每层的顺序使其满足约束
+///
+/// 按复合节点的层级自上而下处理：同一父节点下的子节点（或包含本层节点的子图）按约束做拓扑排序，
+/// 没有约束关系的子节点保持原来的先后顺序，子图内的节点保持连续。约束成环时选择最靠前的
+/// 子节点打破环。不含受约束节点的层保持不变。
+pub fn apply(
+    graph: &Graph,
+    constraints: &ConstraintGraph,
+    mut layering: Vec<Vec<NodeIndex>>,
+) -> Vec<Vec<NodeIndex>> {
+    if constraints.is_empty() {
+        return layering;
+    }
+    for layer in &mut layering {
+        let constrained = layer
+            .iter()
+            .any(|node| constraints.get_constraints(*node).is_some());
+        if constrained {
+            let nodes: Vec<NodeIndex> = layer
+                .iter()
+                .copied()
+                .filter(|&node| !is_placeholder(node))
+                .collect();
+            *layer = sort_children(graph, constraints, None, nodes);
+        }
+    }
+    layering
+}
+
+/// 对 `parent` 下的本层节点排序，`nodes` 按当前顺序排列
+fn sort_children(
+    graph: &Graph,
+    constraints: &ConstraintGraph,
+    parent: Option<NodeIndex>,
+    nodes: Vec<NodeIndex>,
+) -> Vec<NodeIndex> {
+    // 子节点 -> 其中的本层节点，按第一次出现的位置排列
+    let mut items: IndexMap<NodeIndex, Vec<NodeIndex>> = IndexMap::new();
+    for &node in &nodes {
+        items
+            .entry(child_under(graph, node, parent))
+            .or_default()
+            .push(node);
+    }
+
+    let item_of: IndexMap<NodeIndex, usize> = items
+        .values()
+        .enumerate()
+        .flat_map(|(i, members)| members.iter().map(move |&node| (node, i)))
+        .collect();
+    let mut successors: Vec<IndexSet<usize>> = vec![IndexSet::new(); items.len()];
+    let mut indegree = vec![0usize; items.len()];
+    for &node in &nodes {
+        for target in constraints.get_constraints(node).into_iter().flatten() {
+            let (Some(&from), Some(&to)) = (item_of.get(&node), item_of.get(target)) else {
+                continue;
+            };
+            if from != to && successors[from].insert(to) {
+                indegree[to] += 1;
+            }
+        }
+    }
+
+    // 每次取入度为 0 且最靠前的子节点，没有时说明剩余的约束成环
+    let mut placed = vec![false; items.len()];
+    let mut sorted = Vec::with_capacity(items.len());
+    while sorted.len() < items.len() {
+        let ready = (0..items.len()).find(|&i| !placed[i] && indegree[i] == 0);
+        let next = ready.unwrap_or_else(|| {
+            log::warn!("order constraints form a cycle, breaking it at the current order");
+            (0..items.len()).find(|&i| !placed[i]).unwrap()
+        });
+        placed[next] = true;
+        sorted.push(next);
+        for &to in &successors[next] {
+            indegree[to] = indegree[to].saturating_sub(1);
+        }
+    }
+
+    let mut result = Vec::with_capacity(nodes.len());
+    for i in sorted {
+        let (&child, members) = items.get_index(i).unwrap();
+        let (own, nested): (Vec<NodeIndex>, Vec<NodeIndex>) =
+            members.iter().partition(|&&node| node == child);
+        result.extend(own);
+        if !nested.is_empty() {
+            result.extend(sort_children(graph, constraints, Some(child), nested));
+        }
+    }
+    result
+}
+
+/// `node` 在 `parent` 下的祖先（或它自己）
+fn child_under(graph: &Graph, node: NodeIndex, parent: Option<NodeIndex>) -> NodeIndex {
+    let mut current = node;
+    while let Some(up) = graph.parent(current) {
+        if Some(up) == parent {
+            break;
+        }
+        current = up;
+    }
+    current
+}
@@ -22,6 +22,11 @@ pub fn cross_count(graph: &Graph, layering: &Vec<Vec<NodeIndex>>) -> usize {
 }
 
 /// 计算两个层级之间的交叉数
+///
+/// 按上层节点的顺序依次插入每个节点的出边（同一节点的出边按下层位置排序），
+/// 用累加器树统计已插入的边中下端点位于当前边右侧的权重之和。
+///
+/// 对应 JS 函数: twoLayerCrossCount() in lib/order/cross-count.js
 fn count_crossings_between_layers(
     graph: &Graph,
     upper_layer: &[NodeIndex],
@@ -34,38 +39,35 @@ fn count_crossings_between_layers(
         .map(|(i, &node)| (node, i))
         .collect();
 
-    // 收集所有边及其权重
+    // 按上层顺序收集边，每个上层节点的出边按下层位置排序
     let mut south_entries = Vec::new();
-
     for &upper_node in upper_layer {
         // 跳过占位符节点
         if is_placeholder(upper_node) {
             continue;
         }
-        
-        for edge in graph.out_edges(upper_node) {
-            if let Some(&pos) = lower_positions.get(&edge.target) {
-                let weight = graph.edge_label(&edge)
+
+        let mut entries: Vec<(usize, usize)> = graph
+            .out_edges(upper_node)
+            .into_iter()
+            .filter_map(|edge| {
+                let pos = *lower_positions.get(&edge.target)?;
+                let weight = graph
+                    .edge_label(&edge)
                     .map(|label| label.weight as usize)
                     .unwrap_or(1);
-                south_entries.push((pos, weight));
-            }
-        }
+                Some((pos, weight))
+            })
+            .collect();
+        entries.sort_by_key(|&(pos, _)| pos);
+        south_entries.extend(entries);
     }
 
-    // 按位置排序
-    south_entries.sort_by_key(|&(pos, _)| pos);
-
     // 构建累加器树
-    let first_index = if lower_layer.is_empty() {
-        1
-    } else {
-        let mut fi = 1;
-        while fi < lower_layer.len() {
-            fi <<= 1;
-        }
-        fi
-    };
+    let mut first_index = 1;
+    while first_index < lower_layer.len() {
+        first_index <<= 1;
+    }
     let tree_size = 2 * first_index - 1;
     let first_index = first_index - 1;
     let mut tree = vec![0; tree_size];
@@ -76,7 +78,6 @@ fn count_crossings_between_layers(
         let mut index = pos + first_index;
         tree[index] += weight;
         let mut weight_sum = 0;
-        
         while index > 0 {
             if index % 2 == 1 {
                 weight_sum += tree[index + 1];
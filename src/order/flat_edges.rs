@@ -0,0 +1,115 @@
+//! 同层边
+//!
+//! `minlen` 为 0 的边两端可以位于同一排名。扫描只考虑相邻排名之间的边，不会让这样的两端靠近，
+//! 因此初始排序和每次扫描的结果都经过 [`group`]：同层边连通的节点移到一起，两端尽量相邻，
+//! 源节点排在目标节点左侧。之后再应用用户的顺序约束，约束与相邻冲突时以约束为准。
+
+use crate::graph::{Graph, NodeIndex};
+use crate::util::is_placeholder;
+use indexmap::{IndexMap, IndexSet};
+
+/// 图中同层边的两端，只包括两端属于同一个父节点的叶子节点之间的边
+pub fn flat_edges(graph: &Graph) -> Vec<(NodeIndex, NodeIndex)> {
+    let rank = |node: NodeIndex| graph.node_label(node).and_then(|l| l.rank);
+    graph
+        .edges()
+        .into_iter()
+        .filter(|edge| edge.source != edge.target)
+        .filter(|edge| rank(edge.source).is_some() && rank(edge.source) == rank(edge.target))
+        .filter(|edge| graph.parent(edge.source) == graph.parent(edge.target))
+        .filter(|edge| {
+            graph.children(edge.source).is_empty() && graph.children(edge.target).is_empty()
+        })
+        .map(|edge| (edge.source, edge.target))
+        .collect()
+}
+
+/// 把每层中由同层边连通的节点移到一起
+///
+/// 每组节点放在组内第一个节点原来的位置上，组内按同层边的方向拓扑排序，
+/// 没有先后关系的节点保持原来的顺序。组内节点属于同一个父节点，移动不会拆开任何子图。
+/// 没有同层边的层保持不变。
+pub fn group(graph: &Graph, mut layering: Vec<Vec<NodeIndex>>) -> Vec<Vec<NodeIndex>> {
+    let all_edges = flat_edges(graph);
+    if all_edges.is_empty() {
+        return layering;
+    }
+    for layer in &mut layering {
+        let members: IndexSet<NodeIndex> = layer.iter().copied().collect();
+        let edges: Vec<(NodeIndex, NodeIndex)> = all_edges
+            .iter()
+            .copied()
+            .filter(|(source, target)| members.contains(source) && members.contains(target))
+            .collect();
+        if !edges.is_empty() {
+            *layer = group_layer(layer, &edges);
+        }
+    }
+    layering
+}
+
+fn group_layer(layer: &[NodeIndex], edges: &[(NodeIndex, NodeIndex)]) -> Vec<NodeIndex> {
+    let nodes: Vec<NodeIndex> = layer
+        .iter()
+        .copied()
+        .filter(|&node| !is_placeholder(node))
+        .collect();
+    let position: IndexMap<NodeIndex, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, &node)| (node, i))
+        .collect();
+
+    // 并查集，代表元取组内最靠前的节点
+    let mut leader: Vec<usize> = (0..nodes.len()).collect();
+    fn find(leader: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while leader[root] != root {
+            root = leader[root];
+        }
+        leader[i] = root;
+        root
+    }
+    for &(source, target) in edges {
+        let (a, b) = (
+            find(&mut leader, position[&source]),
+            find(&mut leader, position[&target]),
+        );
+        leader[a.max(b)] = a.min(b);
+    }
+
+    let mut groups: IndexMap<usize, Vec<usize>> = IndexMap::new();
+    for i in 0..nodes.len() {
+        let root = find(&mut leader, i);
+        groups.entry(root).or_default().push(i);
+    }
+
+    let mut result = Vec::with_capacity(nodes.len());
+    for members in groups.values() {
+        if members.len() == 1 {
+            result.push(nodes[members[0]]);
+            continue;
+        }
+        // 组内拓扑排序，每次取入度为 0 且最靠前的节点，成环时取最靠前的节点
+        let mut indegree: IndexMap<usize, usize> = members.iter().map(|&i| (i, 0)).collect();
+        for &(_, target) in edges {
+            if let Some(count) = indegree.get_mut(&position[&target]) {
+                *count += 1;
+            }
+        }
+        let mut remaining: Vec<usize> = members.clone();
+        while !remaining.is_empty() {
+            let next = remaining.iter().position(|i| indegree[i] == 0).unwrap_or(0);
+            let i = remaining.remove(next);
+            result.push(nodes[i]);
+            for &(source, target) in edges {
+                if source == nodes[i]
+                    && let Some(count) = indegree.get_mut(&position[&target])
+                {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+    }
+    result
+}
@@ -50,7 +50,7 @@ pub fn init_order(graph: &Graph) -> Vec<Vec<NodeIndex>> {
         }
 
         // 遍历后继节点
-        for successor in graph.successors(node_id) {
+        for successor in ordered_successors(graph, node_id) {
             dfs(graph, successor, visited, layers);
         }
     }
@@ -77,6 +77,38 @@ pub fn init_order(graph: &Graph) -> Vec<Vec<NodeIndex>> {
     layers
 }
 
+/// 节点的后继，节点有端口时按出边的源端口在排名内方向上的位置排列
+///
+/// 先访问的后继在下一层中排在前面，连到左侧端口的后继因此排在左侧。
+fn ordered_successors(graph: &Graph, node_id: NodeIndex) -> Vec<NodeIndex> {
+    let successors: Vec<NodeIndex> = graph.successors(node_id).collect();
+    let Some(label) = graph
+        .node_label(node_id)
+        .filter(|label| !label.ports.is_empty())
+    else {
+        return successors;
+    };
+
+    let rankdir = graph.config().rankdir;
+    let out_edges = graph.out_edges(node_id);
+    let mut keyed: Vec<(f64, NodeIndex)> = successors
+        .into_iter()
+        .map(|successor| {
+            let key = out_edges
+                .iter()
+                .filter(|edge| edge.target == successor)
+                .filter_map(|edge| graph.edge_label(edge))
+                .map(|edge_label| {
+                    crate::port::across_offset(label, edge_label.source_port.as_deref(), rankdir)
+                })
+                .fold(f64::INFINITY, f64::min);
+            (key, successor)
+        })
+        .collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    keyed.into_iter().map(|(_, successor)| successor).collect()
+}
+
 /// 使用随机排序初始化
 pub fn init_order_random(graph: &Graph) -> Vec<Vec<NodeIndex>> {
     let mut layering = build_layer_matrix(graph);
@@ -3,15 +3,19 @@
 pub mod barycenter;
 pub mod build_layer_graph;
 pub mod constraint_graph;
+pub mod constraints;
 pub mod cross_count;
+pub mod flat_edges;
 pub mod init_order;
 pub mod sort_subgraph;
 
 use crate::LayoutOptions;
-use crate::counters::*;
+use crate::context::LayoutContext;
 use crate::graph::Graph;
 use crate::graph::NodeIndex;
-use crate::util::{build_layer_matrix, is_placeholder, max_rank, range, range_with_step, time};
+use crate::util::{
+    build_layer_matrix, is_placeholder, max_rank, range, range_with_step, stage_with_context,
+};
 
 use build_layer_graph::{LayerGraph, build_layer_graph};
 use constraint_graph::{ConstraintGraph, add_subgraph_constraints};
@@ -19,7 +23,7 @@ use constraint_graph::{ConstraintGraph, add_subgraph_constraints};
 /// 为图中的节点分配顺序以最小化边交叉
 ///
 /// 对应 JS 函数: order() in lib/order/index.js
-pub fn order(graph: &mut Graph, opts: Option<&LayoutOptions>) {
+pub fn order(graph: &mut Graph, ctx: &mut LayoutContext, opts: Option<&LayoutOptions>) {
     let default = LayoutOptions::default();
     let opts = opts.unwrap_or(&default);
 
@@ -34,11 +38,17 @@ pub fn order(graph: &mut Graph, opts: Option<&LayoutOptions>) {
         build_layer_graphs(graph, &range_with_step(max_rank - 1, -1, -1), "out_edges");
 
     // 初始化排序
-    let layering = time("init_order", || {
-        increment_init_order();
-        init_order::init_order(graph)
+    let layering = stage_with_context("init_order", graph, ctx, |g, ctx| {
+        crate::incremental::seed_order(g, &ctx.hints, init_order::init_order(g))
     });
 
+    // 同层边的两端尽量相邻；用户指定的顺序约束，初始排序和之后的每次扫描都要满足
+    let user_constraints = constraints::layer_constraints(graph, &layering);
+    let arrange = |graph: &Graph, layering| {
+        constraints::apply(graph, &user_constraints, flat_edges::group(graph, layering))
+    };
+    let layering = arrange(graph, layering);
+
     // 分配初始顺序
     assign_order(graph, &layering);
 
@@ -48,11 +58,27 @@ pub fn order(graph: &mut Graph, opts: Option<&LayoutOptions>) {
     }
 
     // 优化排序以减少交叉 - 与JS版本保持一致的动态停止机制
-    let mut best_cc = usize::MAX;
+    // 增量布局时初始排序来自上一次布局，只有交叉更少的排序才能替换它
+    let mut best_cc = if ctx.hints.is_empty() {
+        usize::MAX
+    } else {
+        cross_count::cross_count(graph, &layering)
+    };
     let mut best_layering = layering;
     let mut last_best = 0;
 
     for i in 0.. {
+        // 取消时结果会被丢弃，不再分配排序
+        if ctx.budget.cancelled() {
+            return;
+        }
+
+        // 预算用完时使用目前最好的排序
+        if let Some(kind) = ctx.budget.order_sweeps_exhausted(i) {
+            ctx.stats.exceed_budget(kind);
+            break;
+        }
+
         // 修复层级图选择逻辑，与JS版本一致
         let layer_graphs = if i % 2 == 1 {
             &mut down_layer_graphs
@@ -61,13 +87,14 @@ pub fn order(graph: &mut Graph, opts: Option<&LayoutOptions>) {
         };
         let bias_right = i % 4 >= 2;
 
-        sweep_layer_graphs(layer_graphs, bias_right);
+        sweep_layer_graphs(graph, layer_graphs, bias_right, &user_constraints);
 
-        let current_layering = build_layer_matrix(graph);
-        let cc = {
-            increment_cross_count();
-            cross_count::cross_count(graph, &current_layering)
-        };
+        // 下一次扫描从整理后的顺序开始
+        let current_layering = arrange(graph, build_layer_matrix(graph));
+        assign_order(graph, &current_layering);
+        let cc = cross_count::cross_count(graph, &current_layering);
+        ctx.stats.order_sweeps += 1;
+        ctx.stats.crossings.push(cc);
 
         if cc < best_cc {
             last_best = 0; // 重置计数器，与JS版本一致
@@ -96,46 +123,116 @@ fn build_layer_graphs(graph: &Graph, ranks: &[i32], relationship: &str) -> Vec<L
 }
 
 /// 扫描层级图
-fn sweep_layer_graphs(layer_graphs: &mut [LayerGraph], bias_right: bool) {
-    let mut constraint_graph = ConstraintGraph::new();
+///
+/// 每个层级图排序前从原图读取节点的当前顺序，排序结果写回原图，下一层就能看到。
+/// 子图约束和 `user_constraints` 都以原图的节点表示，由 [`lift_constraint`] 转换到层级图中参与冲突解决。
+///
+/// 对应 JS 函数: sweepLayerGraphs() in lib/order/index.js
+fn sweep_layer_graphs(
+    graph: &mut Graph,
+    layer_graphs: &mut [LayerGraph],
+    bias_right: bool,
+    user_constraints: &ConstraintGraph,
+) {
+    let mut subgraph_constraints = ConstraintGraph::new();
 
     for layer_graph in layer_graphs {
         // 跳过空的层级图
-        if layer_graph.graph.node_count() == 0 {
+        if layer_graph.node_mapping.is_empty() && layer_graph.unsorted.is_empty() {
             continue;
         }
 
-        // 在层级图中找到根节点
-        let root = find_root(&layer_graph.graph);
-
-        // 在层级图中进行排序，传递约束图
-        let sort_result =
-            sort_subgraph::sort_subgraph(&layer_graph.graph, root, &constraint_graph, bias_right);
+        for (&original, &layer_node) in &layer_graph.node_mapping {
+            let order = graph.node_label(original).and_then(|label| label.order);
+            if let Some(label) = layer_graph.graph.node_label_mut(layer_node) {
+                label.order = order;
+            }
+        }
 
-        // 分配顺序到层级图
-        for (i, layer_node_id) in sort_result.vs.iter().enumerate() {
-            // 确保节点ID属于层级图
-            if layer_node_id.belongs_to_graph(layer_graph.graph.graph_id()) {
-                if let Some(label) = layer_graph.graph.node_label_mut(*layer_node_id) {
-                    label.order = Some(i);
+        let mut constraint_graph = ConstraintGraph::new();
+        for constraints in [user_constraints, &subgraph_constraints] {
+            for (from, targets) in constraints.get_all_constraints() {
+                for target in targets {
+                    if let Some((from, to)) = lift_constraint(layer_graph, *from, *target)
+                        && !constraint_graph.has_constraint(from, to)
+                    {
+                        constraint_graph.add_constraint(from, to);
+                    }
                 }
-            } else {
-                panic!(
-                    "Layer node ID {:?} does not belong to layer graph {}\nLayer graph debug info: {}\nNode debug info: belongs_to_graph({}) = {}",
-                    layer_node_id,
-                    layer_graph.graph.graph_id(),
-                    layer_graph.graph.debug_info(),
-                    layer_graph.graph.graph_id(),
-                    layer_node_id.belongs_to_graph(layer_graph.graph.graph_id())
-                );
+            }
+        }
+
+        let sort_result = sort_subgraph::sort_subgraph(
+            &layer_graph.graph,
+            layer_graph.root,
+            &constraint_graph,
+            bias_right,
+        );
+
+        // 写回原图，未参与排序的节点保持原来的先后顺序排在后面
+        let mut unsorted = layer_graph.unsorted.clone();
+        unsorted.sort_by_key(|&node| graph.node_label(node).and_then(|label| label.order));
+        let sorted = sort_result
+            .vs
+            .iter()
+            .map(|layer_node| layer_graph.original[layer_node])
+            .chain(unsorted);
+        for (i, node_id) in sorted.enumerate() {
+            if let Some(label) = graph.node_label_mut(node_id) {
+                label.order = Some(i);
             }
         }
 
         // 添加子图约束
-        add_subgraph_constraints(&layer_graph.graph, &mut constraint_graph, &sort_result.vs);
+        let mut layer_constraints = ConstraintGraph::new();
+        add_subgraph_constraints(&layer_graph.graph, &mut layer_constraints, &sort_result.vs);
+        for (from, targets) in layer_constraints.get_all_constraints() {
+            let Some(&from) = layer_graph.original.get(from) else {
+                continue;
+            };
+            for target in targets {
+                if let Some(&to) = layer_graph.original.get(target)
+                    && !subgraph_constraints.has_constraint(from, to)
+                {
+                    subgraph_constraints.add_constraint(from, to);
+                }
+            }
+        }
     }
 }
 
+/// 把原图节点之间的约束转换到层级图中
+///
+/// 两个节点不在同一父节点下时，约束提升到它们在共同祖先下的子节点上；
+/// 节点不在本层或两者相同时返回 `None`。
+fn lift_constraint(
+    layer_graph: &LayerGraph,
+    from: NodeIndex,
+    to: NodeIndex,
+) -> Option<(NodeIndex, NodeIndex)> {
+    let ancestors = |original: NodeIndex| -> Option<Vec<NodeIndex>> {
+        let mut chain = vec![*layer_graph.node_mapping.get(&original)?];
+        while let Some(parent) = layer_graph.graph.parent(*chain.last().unwrap()) {
+            chain.push(parent);
+        }
+        // 只有可移动的节点和复合节点的副本在根节点之下
+        (chain.last() == Some(&layer_graph.root) && chain.len() > 1).then_some(chain)
+    };
+    let from_chain = ancestors(from)?;
+    let to_chain = ancestors(to)?;
+
+    // 从根节点向下找到第一个分叉的位置
+    let shared = from_chain
+        .iter()
+        .rev()
+        .zip(to_chain.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let lifted_from = *from_chain.iter().rev().nth(shared)?;
+    let lifted_to = *to_chain.iter().rev().nth(shared)?;
+    Some((lifted_from, lifted_to))
+}
+
 /// 分配顺序到图
 fn assign_order(graph: &mut Graph, layering: &Vec<Vec<NodeIndex>>) {
     for layer in layering {
@@ -154,24 +251,6 @@ fn assign_order(graph: &mut Graph, layering: &Vec<Vec<NodeIndex>>) {
     }
 }
 
-/// 查找根节点
-fn find_root(graph: &Graph) -> NodeIndex {
-    // 查找入度为0的节点作为根节点
-    for node_id in graph.node_indices() {
-        if graph.predecessors(node_id).into_iter().count() == 0 {
-            return node_id;
-        }
-    }
-
-    // 如果没有入度为0的节点，返回第一个节点
-    if let Some(first_node) = graph.node_indices().next() {
-        first_node
-    } else {
-        // 如果图是空的，这是一个错误情况
-        panic!("Cannot find root node in empty graph");
-    }
-}
-
 /// 构建层级信息
 fn build_layers(graph: &Graph) -> Vec<Vec<NodeIndex>> {
     let mut layers: Vec<Vec<NodeIndex>> = Vec::new();
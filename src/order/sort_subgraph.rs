@@ -3,6 +3,7 @@
 use crate::graph::Graph;
 use crate::graph::NodeIndex;
 use crate::order::constraint_graph::ConstraintGraph;
+use crate::port::across_offset;
 use std::collections::HashMap;
 
 /// 排序子图
@@ -167,11 +168,16 @@ impl SortResult {
 }
 
 /// 计算重心
+///
+/// 边连接端口时，源节点的位置按源端口的位置修正，再减去目标端口相对目标节点中心的偏移，
+/// 即目标节点中心应在的位置。
 fn barycenter(graph: &Graph, movable: &[NodeIndex]) -> Vec<BarycenterEntry> {
+    let rankdir = graph.config().rankdir;
     movable
         .iter()
         .map(|&v| {
             let in_edges = graph.in_edges(v);
+            let v_label = graph.node_label(v).unwrap();
 
             if in_edges.is_empty() {
                 BarycenterEntry {
@@ -187,7 +193,9 @@ fn barycenter(graph: &Graph, movable: &[NodeIndex]) -> Vec<BarycenterEntry> {
                     let edge_label = graph.edge_label(&edge).unwrap();
                     let source_label = graph.node_label(edge.source).unwrap();
                     let edge_weight = edge_label.weight;
-                    let source_order = source_label.order.unwrap_or(0) as f64;
+                    let source_order = source_label.order.unwrap_or(0) as f64
+                        + across_offset(source_label, edge_label.source_port.as_deref(), rankdir)
+                        - across_offset(v_label, edge_label.target_port.as_deref(), rankdir);
 
                     sum += edge_weight * source_order;
                     weight += edge_weight;
@@ -350,8 +358,10 @@ fn merge_entries(mapped_entries: &mut [MappedEntry], target_idx: usize, source_i
         weight += s_w;
     }
 
-    // 更新目标条目
-    target.vs.extend(source.vs);
+    // 更新目标条目，源条目必须排在目标条目之前
+    let mut vs = source.vs;
+    vs.append(&mut target.vs);
+    target.vs = vs;
     target.barycenter = if weight > 0.0 {
         Some(sum / weight)
     } else {
@@ -365,54 +375,48 @@ fn merge_entries(mapped_entries: &mut [MappedEntry], target_idx: usize, source_i
 }
 
 /// 展开子图
+///
+/// 对应 JS 函数: expandSubgraphs() in lib/order/sort-subgraph.js
 fn expand_subgraphs(
-    entries: Vec<ResolvedEntry>,
+    mut entries: Vec<ResolvedEntry>,
     subgraphs: &HashMap<NodeIndex, SortResult>,
-) -> Vec<BarycenterEntry> {
-    let mut result = Vec::new();
-
-    for entry in entries {
-        if let Some(subgraph_result) = subgraphs.get(&entry.vs[0]) {
-            // 如果第一个节点有子图，展开它
-            for &node in &subgraph_result.vs {
-                result.push(BarycenterEntry {
-                    v: node,
-                    barycenter: None, // 子图节点没有重心
-                    weight: None,
-                });
-            }
-        } else {
-            // 没有子图，直接添加
-            for &node in &entry.vs {
-                result.push(BarycenterEntry {
-                    v: node,
-                    barycenter: entry.barycenter,
-                    weight: entry.weight,
-                });
-            }
-        }
+) -> Vec<ResolvedEntry> {
+    for entry in &mut entries {
+        entry.vs = entry
+            .vs
+            .iter()
+            .flat_map(|v| match subgraphs.get(v) {
+                Some(subgraph_result) => subgraph_result.vs.clone(),
+                None => vec![*v],
+            })
+            .collect();
     }
-
-    result
+    entries
 }
 
 /// 排序条目
-fn sort_entries(entries: &[BarycenterEntry], bias_right: bool) -> SortResult {
-    let mut sortable: Vec<_> = entries.iter().filter(|e| e.barycenter.is_some()).collect();
-    let unsortable: Vec<_> = entries.iter().filter(|e| e.barycenter.is_none()).collect();
+///
+/// 有重心的条目按重心排序，重心相同时按原来的位置，右偏置时反过来；没有重心的条目
+/// 留在原来的位置上。
+///
+/// 对应 JS 函数: sort() in lib/order/sort.js
+fn sort_entries(entries: &[ResolvedEntry], bias_right: bool) -> SortResult {
+    let (mut sortable, mut unsortable): (Vec<_>, Vec<_>) =
+        entries.iter().partition(|e| e.barycenter.is_some());
+    unsortable.sort_by_key(|e| std::cmp::Reverse(e.i));
 
-    // 按重心排序，考虑bias参数
     sortable.sort_by(|a, b| {
         let a_bc = a.barycenter.unwrap();
         let b_bc = b.barycenter.unwrap();
-
-        if bias_right {
-            // 右偏置：重心相同时，保持原有顺序
-            a_bc.partial_cmp(&b_bc).unwrap()
-        } else {
-            // 左偏置：重心相同时，保持原有顺序
-            a_bc.partial_cmp(&b_bc).unwrap()
-        }
+        a_bc.partial_cmp(&b_bc)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| {
+                if bias_right {
+                    b.i.cmp(&a.i)
+                } else {
+                    a.i.cmp(&b.i)
+                }
+            })
     });
 
     // 合并结果
@@ -420,16 +424,14 @@ fn sort_entries(entries: &[BarycenterEntry], bias_right: bool) -> SortResult {
     let mut sum = 0.0;
     let mut weight = 0.0;
 
+    consume_unsortable(&mut vs, &mut unsortable);
     for entry in sortable {
-        vs.push(entry.v);
+        vs.extend(entry.vs.iter().copied());
         if let (Some(bc), Some(w)) = (entry.barycenter, entry.weight) {
             sum += bc * w;
             weight += w;
         }
-    }
-
-    for entry in unsortable {
-        vs.push(entry.v);
+        consume_unsortable(&mut vs, &mut unsortable);
     }
 
     SortResult {
@@ -442,3 +444,16 @@ fn sort_entries(entries: &[BarycenterEntry], bias_right: bool) -> SortResult {
         weight: if weight > 0.0 { Some(weight) } else { None },
     }
 }
+
+/// 把原位置不超过当前长度的无重心条目放回原位，`unsortable` 按位置降序排列
+///
+/// 对应 JS 函数: consumeUnsortable() in lib/order/sort.js
+fn consume_unsortable(vs: &mut Vec<NodeIndex>, unsortable: &mut Vec<&ResolvedEntry>) {
+    while let Some(last) = unsortable.last() {
+        if last.i > vs.len() {
+            break;
+        }
+        vs.extend(last.vs.iter().copied());
+        unsortable.pop();
+    }
+}
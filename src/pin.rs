@@ -0,0 +1,267 @@
+//! 固定节点
+//!
+//! `NodeLabel::pinned` 为 `true` 且设置了 `x`、`y` 的叶子节点在布局后仍位于给定坐标上，
+//! 坐标与布局结果使用同一坐标系（已按 `rankdir` 变换）。其余节点围绕它们布局：
+//!
+//! - [`collect_pins`] 布局开始时把目标坐标保存到布局上下文的 `pins` 中
+//! - [`pin_ranks`] 排名方向上坐标相同的固定节点放在同一排名，坐标较大的排名也较大
+//! - [`pin_rank_coordinates`] 含固定节点的排名移到固定节点的坐标上，其他排名按比例分布
+//! - `BrandesKoepf::run` 固定节点作为横向压缩的不动点，同层其他节点向两侧推开以保持间距
+//! - [`translate_to_pins`] 最后整体平移，使固定节点落在目标坐标上；固定坐标互相矛盾时
+//!   没有落在目标上的节点记录在 `LayoutStats::pin_conflicts` 中
+//!
+//! 有固定节点时图不再平移到边距处，`width`、`height` 仍是包围盒加边距的尺寸。
+
+use crate::context::LayoutContext;
+use crate::graph::{Graph, NodeIndex};
+use crate::types::{Point, RankDirection};
+use crate::util::build_layer_matrix;
+use indexmap::IndexMap;
+use petgraph::algo::toposort;
+
+/// 排名方向上的坐标差小于该值时视为同一排名
+const SAME_RANK_EPSILON: f64 = 1e-6;
+
+/// 平移后与目标坐标的差小于该值时视为浮点误差
+const PIN_EPSILON: f64 = 1e-6;
+
+/// 收集固定节点的目标坐标
+pub fn collect_pins(graph: &mut Graph, ctx: &mut LayoutContext) {
+    ctx.pins = graph
+        .node_indices()
+        .filter(|&node| graph.children(node).is_empty())
+        .filter_map(|node| {
+            let label = graph.node_label(node)?;
+            if !label.pinned {
+                return None;
+            }
+            Some((node, Point::new(label.x?, label.y?)))
+        })
+        .collect();
+}
+
+/// 把输出坐标转换为布局内部从上到下的坐标，`y` 为排名方向
+///
+/// 是 `undo_coordinate_system` 的逆变换，不包含平移。
+pub fn to_layout_coordinates(point: Point, rankdir: RankDirection) -> Point {
+    match rankdir {
+        RankDirection::TopBottom => point,
+        RankDirection::BottomTop => Point::new(point.x, -point.y),
+        RankDirection::LeftRight => Point::new(point.y, point.x),
+        RankDirection::RightLeft => Point::new(point.y, -point.x),
+    }
+}
+
+/// 固定节点在布局内部坐标系中的位置
+pub fn layout_pins(
+    pins: &IndexMap<NodeIndex, Point>,
+    rankdir: RankDirection,
+) -> IndexMap<NodeIndex, Point> {
+    pins.iter()
+        .map(|(&node, &point)| (node, to_layout_coordinates(point, rankdir)))
+        .collect()
+}
+
+/// 按排名方向上的坐标调整固定节点的排名
+///
+/// 固定节点按坐标分组，同组节点提升到组内最大的排名。后一组与前一组的排名差至少是
+/// 两组坐标差折算成的排名数（按排名间距和节点的平均高度估算），且至少为 1。
+/// 然后沿边向下传播以满足最小长度。两步都只会增大排名，重复到不再变化为止。
+/// 固定坐标与边的方向矛盾时（例如固定在下方的节点有一条边指向固定在上方的节点），
+/// 无法同时满足，此时保留最后一次的结果。
+pub fn pin_ranks(graph: &mut Graph, ctx: &mut LayoutContext) {
+    let mut pins: Vec<(f64, NodeIndex)> = layout_pins(&ctx.pins, graph.config().rankdir)
+        .into_iter()
+        .filter(|&(node, _)| graph.node_label(node).is_some_and(|l| l.rank.is_some()))
+        .map(|(node, point)| (point.y, node))
+        .collect();
+    if pins.is_empty() {
+        return;
+    }
+    pins.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut groups: Vec<(f64, Vec<NodeIndex>)> = Vec::new();
+    for (along, node) in pins {
+        match groups.last_mut() {
+            Some((first, group)) if along - *first < SAME_RANK_EPSILON => group.push(node),
+            _ => groups.push((along, vec![node])),
+        }
+    }
+
+    // 一个排名在排名方向上占据的距离。此时排名间距已被 `make_space_for_edge_labels` 减半，
+    // 最小长度加倍，所以每个排名单位是半个排名间距加半个节点高度
+    let vertical = matches!(
+        graph.config().rankdir,
+        RankDirection::TopBottom | RankDirection::BottomTop
+    );
+    let sizes: Vec<f64> = graph
+        .node_indices()
+        .filter_map(|node| graph.node_label(node))
+        .filter(|label| label.dummy.is_none())
+        .map(|label| if vertical { label.height } else { label.width })
+        .collect();
+    let mean_size = sizes.iter().sum::<f64>() / sizes.len().max(1) as f64;
+    let rank_unit = (graph.config().rank_sep + mean_size / 2.0).max(1.0);
+
+    let Ok(sorted) = toposort(graph.petgraph(), None) else {
+        log::warn!("pin_ranks: graph has a cycle, pinned ranks are not adjusted");
+        return;
+    };
+    let sorted: Vec<NodeIndex> = sorted
+        .into_iter()
+        .map(|index| graph.wrap_index(index))
+        .collect();
+    let rank = |graph: &Graph, node: NodeIndex| graph.node_label(node).and_then(|l| l.rank);
+
+    for _ in 0..=groups.len() {
+        let mut changed = false;
+
+        let mut previous: Option<(f64, i32)> = None;
+        for (along, group) in &groups {
+            let floor = previous.map_or(i32::MIN, |(previous_along, previous_rank)| {
+                let gap = ((along - previous_along) / rank_unit).round() as i32;
+                previous_rank.saturating_add(gap.max(1))
+            });
+            let target = group
+                .iter()
+                .filter_map(|&node| rank(graph, node))
+                .max()
+                .unwrap_or(floor)
+                .max(floor);
+            for &node in group {
+                if rank(graph, node) != Some(target) {
+                    graph.node_label_mut(node).unwrap().rank = Some(target);
+                    changed = true;
+                }
+            }
+            previous = Some((*along, target));
+        }
+
+        for &v in &sorted {
+            let Some(v_rank) = rank(graph, v) else {
+                continue;
+            };
+            for edge in graph.out_edges(v) {
+                let minlen = graph.edge_label(&edge).map_or(1, |l| l.minlen);
+                if let Some(w_rank) = rank(graph, edge.target)
+                    && w_rank < v_rank + minlen
+                {
+                    graph.node_label_mut(edge.target).unwrap().rank = Some(v_rank + minlen);
+                    changed = true;
+                }
+            }
+        }
+
+        if !changed {
+            return;
+        }
+    }
+    log::warn!("pin_ranks: pinned coordinates contradict edge directions");
+}
+
+/// 把含固定节点的排名移到固定节点的坐标上
+///
+/// 在 `position_y` 之后调用。第一个含固定节点的排名保持原位，其他固定排名按与它的相对距离放置；
+/// 两个固定排名之间的排名按原来的间距比例分布，最后一个固定排名之后的排名整体平移。
+pub fn pin_rank_coordinates(graph: &mut Graph, pins: &IndexMap<NodeIndex, Point>) {
+    let pins = layout_pins(pins, graph.config().rankdir);
+    if pins.is_empty() {
+        return;
+    }
+
+    let layers: Vec<Vec<NodeIndex>> = build_layer_matrix(graph)
+        .into_iter()
+        .filter(|layer| !layer.is_empty())
+        .collect();
+    let ys: Vec<f64> = layers
+        .iter()
+        .map(|layer| graph.node_label(layer[0]).and_then(|l| l.y).unwrap_or(0.0))
+        .collect();
+    let pinned: Vec<(usize, f64)> = layers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, layer)| {
+            let pin = layer.iter().find_map(|node| pins.get(node))?;
+            Some((i, pin.y))
+        })
+        .collect();
+    let Some(&(first, first_target)) = pinned.first() else {
+        return;
+    };
+    let offset = ys[first] - first_target;
+    let (last, last_target) = pinned[pinned.len() - 1];
+
+    let mut new_ys = ys.clone();
+    for pair in pinned.windows(2) {
+        let ((a, a_target), (b, b_target)) = (pair[0], pair[1]);
+        let (a_target, b_target) = (a_target + offset, b_target + offset);
+        let scale = (b_target - a_target) / (ys[b] - ys[a]);
+        for i in a..=b {
+            new_ys[i] = a_target + (ys[i] - ys[a]) * scale;
+        }
+    }
+    let shift = last_target + offset - ys[last];
+    for i in last + 1..layers.len() {
+        new_ys[i] = ys[i] + shift;
+    }
+    new_ys[last] = last_target + offset;
+
+    for (layer, y) in layers.iter().zip(new_ys) {
+        for &node in layer {
+            if let Some(label) = graph.node_label_mut(node) {
+                label.y = Some(y);
+            }
+        }
+    }
+}
+
+/// 整体平移图，使固定节点落在目标坐标上
+///
+/// 在 `translate_graph` 之后调用，此时坐标已经变换到输出坐标系。平移量取与最多固定节点
+/// 一致的偏移，平移后与目标的差在 [`PIN_EPSILON`] 之内的节点对齐到目标上以消除浮点误差，
+/// 其余节点的固定坐标无法满足，记录在 `LayoutStats::pin_conflicts` 中。
+pub fn translate_to_pins(graph: &mut Graph, ctx: &mut LayoutContext) {
+    let offsets: Vec<(NodeIndex, Point, Point)> = ctx
+        .pins
+        .iter()
+        .filter_map(|(&node, &target)| {
+            let label = graph.node_label(node)?;
+            let offset = Point::new(target.x - label.x?, target.y - label.y?);
+            Some((node, target, offset))
+        })
+        .collect();
+    let agrees =
+        |a: Point, b: Point| (a.x - b.x).abs() <= PIN_EPSILON && (a.y - b.y).abs() <= PIN_EPSILON;
+    let votes = |offset: Point| {
+        offsets
+            .iter()
+            .filter(|&&(_, _, other)| agrees(offset, other))
+            .count()
+    };
+
+    // 得票相同时取第一个固定节点的偏移
+    let mut best: Option<(Point, usize)> = None;
+    for &(_, _, offset) in &offsets {
+        let count = votes(offset);
+        if best.is_none_or(|(_, best_count)| count > best_count) {
+            best = Some((offset, count));
+        }
+    }
+    let Some((shift, _)) = best else {
+        return;
+    };
+
+    crate::position::shift_graph(graph, shift.x, shift.y);
+
+    for (node, target, offset) in offsets {
+        if !agrees(offset, shift) {
+            log::warn!("translate_to_pins: {:?} cannot be placed at its pin", node);
+            ctx.stats.pin_conflicts.push(node);
+            continue;
+        }
+        if let Some(label) = graph.node_label_mut(node) {
+            label.x = Some(target.x);
+            label.y = Some(target.y);
+        }
+    }
+}
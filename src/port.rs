@@ -0,0 +1,89 @@
+//! 节点端口
+//!
+//! `NodeLabel::ports` 声明节点上的端口，`EdgeLabel::source_port`、`target_port` 引用端口 ID。
+//! 端口的边和位置按输出坐标系理解，布局中两处使用端口：
+//!
+//! - 排序：初始排序按源端口的位置访问后继节点，`sort_subgraph` 计算重心时用 [`across_offset`]
+//!   修正两端的位置，连到左侧端口的邻居倾向于排在左侧
+//! - 路由：`assign_node_intersects` 和 `route_edges` 让边的端点落在 [`port_point`] 上，
+//!   不再与节点边界求交
+//!
+//! 无环化反转边时两端的端口随之交换，边拆分为虚拟节点链时第一段和最后一段分别保留源端口和目标端口。
+
+use crate::pin::to_layout_coordinates;
+use crate::shape::intersect_node;
+use crate::types::{NodeLabel, NodeShape, Point, Port, PortSide, RankDirection};
+
+/// 节点上 ID 为 `id` 的端口
+pub fn find_port<'a>(node: &'a NodeLabel, id: &str) -> Option<&'a Port> {
+    node.ports.iter().find(|port| port.id == id)
+}
+
+/// 端口在所在边上的相对位置，0 到 1
+fn relative_position(node: &NodeLabel, port: &Port) -> f64 {
+    if let Some(offset) = port.offset.filter(|offset| !offset.is_nan()) {
+        return offset.clamp(0.0, 1.0);
+    }
+    // 同一边上未设置偏移的端口按顺序均匀分布，声明顺序打破平局
+    let mut siblings: Vec<(usize, usize)> = node
+        .ports
+        .iter()
+        .enumerate()
+        .filter(|(_, other)| {
+            other.side == port.side && other.offset.is_none_or(|offset| offset.is_nan())
+        })
+        .map(|(i, other)| (other.order, i))
+        .collect();
+    siblings.sort();
+    let index = siblings
+        .iter()
+        .position(|&(_, i)| std::ptr::eq(&node.ports[i], port))
+        .unwrap_or(0);
+    (index + 1) as f64 / (siblings.len() + 1) as f64
+}
+
+/// 端口相对节点中心的偏移，输出坐标系，宽高为 `width`、`height`
+fn offset_in(node: &NodeLabel, port: &Port, width: f64, height: f64) -> Point {
+    let t = relative_position(node, port);
+    let (w, h) = (width / 2.0, height / 2.0);
+    match port.side {
+        PortSide::Top => Point::new(-w + t * width, -h),
+        PortSide::Bottom => Point::new(-w + t * width, h),
+        PortSide::Left => Point::new(-w, -h + t * height),
+        PortSide::Right => Point::new(w, -h + t * height),
+    }
+}
+
+/// 端口在输出坐标系中的位置，节点没有坐标或没有该端口时返回 `None`
+///
+/// 非矩形节点的端口先按外接矩形定位，再沿中心方向投影到形状边界上。
+pub fn port_point(node: &NodeLabel, id: Option<&str>) -> Option<Point> {
+    let port = find_port(node, id?)?;
+    let offset = offset_in(node, port, node.width, node.height);
+    let point = Point::new(node.x? + offset.x, node.y? + offset.y);
+    Some(match node.shape {
+        NodeShape::Rect => point,
+        _ => intersect_node(node, &point),
+    })
+}
+
+/// 端口所在边的单位外法线，输出坐标系
+pub fn side_normal(side: PortSide) -> Point {
+    match side {
+        PortSide::Top => Point::new(0.0, -1.0),
+        PortSide::Bottom => Point::new(0.0, 1.0),
+        PortSide::Left => Point::new(-1.0, 0.0),
+        PortSide::Right => Point::new(1.0, 0.0),
+    }
+}
+
+/// 端口在排名内方向上相对节点中心的位置，以节点在该方向上的尺寸为单位，范围 -0.5 到 0.5
+///
+/// 排序阶段只有节点的顺序没有坐标，用这个值细分同一节点上不同端口的位置。
+/// 节点没有该端口时返回 0。
+pub fn across_offset(node: &NodeLabel, id: Option<&str>, rankdir: RankDirection) -> f64 {
+    let Some(port) = id.and_then(|id| find_port(node, id)) else {
+        return 0.0;
+    };
+    to_layout_coordinates(offset_in(node, port, 1.0, 1.0), rankdir).x
+}
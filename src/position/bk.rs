@@ -8,8 +8,10 @@
 //! - 四种对齐方向 (u+l, u+r, d+l, d+r)
 //! - 平衡算法
 
+use crate::budget::Budget;
 use crate::graph::Graph;
 use crate::graph::NodeIndex;
+use crate::incremental::LayoutHints;
 use crate::types::*;
 use crate::util::is_placeholder;
 use indexmap::IndexMap;
@@ -132,6 +134,12 @@ pub struct BrandesKoepf {
     layers: Vec<Vec<NodeIndex>>,
     /// 冲突信息
     conflicts: IndexMap<(NodeIndex, NodeIndex), ConflictType>,
+    /// 布局预算
+    budget: Budget,
+    /// 固定节点在输出坐标系中的目标位置
+    pins: IndexMap<NodeIndex, Point>,
+    /// 增量布局提示
+    hints: LayoutHints,
 }
 
 impl BrandesKoepf {
@@ -176,10 +184,7 @@ impl BrandesKoepf {
 
         // 如果有节点没有 rank，打印警告但不 panic
         if !nodes_without_rank.is_empty() {
-            println!(
-                "警告: 以下节点没有分配 rank，将被跳过: {:?}",
-                nodes_without_rank
-            );
+            log::warn!("以下节点没有分配 rank，将被跳过: {:?}", nodes_without_rank);
         }
 
         Self {
@@ -187,14 +192,37 @@ impl BrandesKoepf {
             ranks,
             layers: Vec::new(),
             conflicts: IndexMap::new(),
+            budget: Budget::default(),
+            pins: IndexMap::new(),
+            hints: LayoutHints::default(),
         }
     }
 
+    /// 使用布局预算
+    ///
+    /// 布局取消时不再计算坐标；超过截止时间时只使用已经算出的对齐方向。
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// 使用固定节点的目标位置，坐标与布局结果使用同一坐标系
+    pub fn with_pins(mut self, pins: IndexMap<NodeIndex, Point>) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// 使用增量布局提示
+    pub fn with_hints(mut self, hints: LayoutHints) -> Self {
+        self.hints = hints;
+        self
+    }
+
     /// 获取 ranks 信息（用于调试）
     pub fn get_ranks(&self) -> &IndexMap<NodeIndex, i32> {
         &self.ranks
     }
-    
+
     /// 获取 layers 信息（用于调试）
     pub fn get_layers(&self) -> &Vec<Vec<NodeIndex>> {
         &self.layers
@@ -231,31 +259,34 @@ impl BrandesKoepf {
     pub fn run(&mut self) -> BKResult {
         let mut result = BKResult::new();
 
-        println!("=== Brandes-Köpf 算法开始 ===");
-        println!("输入图信息:");
-        println!("  节点数: {}", self.graph.node_count());
-        println!("  边数: {}", self.graph.edge_count());
+        log::debug!(
+            "Brandes-Köpf 开始: nodes={} edges={}",
+            self.graph.node_count(),
+            self.graph.edge_count()
+        );
 
         // 构建层级结构（层级已在构造函数中从图的节点中提取）
-        println!("\n1. 构建层级结构...");
         self.build_layers();
-        println!("  层级结构:");
-        for (i, layer) in self.layers.iter().enumerate() {
-            println!("    层 {}: {:?}", i, layer);
-        }
+        log::trace!("层级结构: {:?}", self.layers);
 
         // 检测冲突
-        println!("\n2. 检测冲突...");
         self.find_conflicts();
-        println!("  冲突检测完成");
 
         // 计算四种对齐方向的位置
-        println!("\n3. 计算四种对齐方向的位置...");
         let mut xss = IndexMap::new();
-        for vert in ["u", "d"] {
+        'alignments: for vert in ["u", "d"] {
             for horiz in ["l", "r"] {
+                // 布局已取消，结果不会被使用
+                if self.budget.cancelled() {
+                    return result;
+                }
+                // 超过截止时间时用已经算出的对齐方向（至少是 ul）平衡坐标
+                if self.budget.expired() && !xss.is_empty() {
+                    log::debug!("超过截止时间，只使用 {} 个对齐方向", xss.len());
+                    break 'alignments;
+                }
+
                 let direction = format!("{}{}", vert, horiz);
-                println!("\n  处理方向: {}", direction);
 
                 let _direction = match (vert, horiz) {
                     ("u", "l") => AlignmentDirection::UpLeft,
@@ -280,10 +311,7 @@ impl BrandesKoepf {
                     adjusted_layering
                 };
 
-                println!("    调整后的层级:");
-                for (i, layer) in adjusted_layering.iter().enumerate() {
-                    println!("      层 {}: {:?}", i, layer);
-                }
+                log::trace!("方向 {}: 调整后的层级 {:?}", direction, adjusted_layering);
 
                 let neighbor_fn = if vert == "u" {
                     |g: &Graph, v: NodeIndex| g.predecessors(v).collect::<Vec<_>>()
@@ -291,48 +319,46 @@ impl BrandesKoepf {
                     |g: &Graph, v: NodeIndex| g.successors(v).collect::<Vec<_>>()
                 };
 
-                println!("    执行垂直对齐...");
                 let align = self.vertical_alignment(&adjusted_layering, &neighbor_fn);
-                println!("      对齐结果: {:?}", align);
+                log::trace!("方向 {}: 对齐结果 {:?}", direction, align);
 
-                println!("    执行水平压缩...");
-                let xs = self.horizontal_compaction(&adjusted_layering, &align, horiz == "r");
-                println!("      压缩结果: {:?}", xs);
+                let mut xs = self.horizontal_compaction(&adjusted_layering, &align, horiz == "r");
+                // 右对齐时层内顺序被反转，坐标需要取反
+                if horiz == "r" {
+                    for x in xs.values_mut() {
+                        *x = -*x;
+                    }
+                }
+                log::trace!("方向 {}: 压缩结果 {:?}", direction, xs);
 
                 xss.insert(direction, xs);
             }
         }
 
         // 找到最小宽度对齐
-        println!("\n5. 找到最小宽度对齐...");
         let smallest_width = self.find_smallest_width_alignment(&xss);
-        println!("  最小宽度对齐: {:?}", smallest_width);
+        log::trace!("最小宽度对齐: {:?}", smallest_width);
 
         // 如果所有对齐都包含无效值，则只返回ul对齐的结果
-        let final_xs = if let Some(alignment) = smallest_width {
+        let mut final_xs = if let Some(alignment) = smallest_width {
             // 对齐坐标
-            println!("\n6. 对齐坐标...");
             self.align_coordinates(&mut xss, &alignment);
-            println!("  坐标对齐完成");
 
             // 平衡坐标
-            println!("\n7. 平衡坐标...");
-            let balanced = self.balance(&xss, None);
-            println!("  最终坐标: {:?}", balanced);
-            balanced
+            self.balance(&xss, None)
         } else {
             // 如果所有对齐都包含无效值，只返回ul对齐的结果
-            println!("\n6. 所有对齐都包含无效值，返回ul对齐结果...");
+            log::debug!("所有对齐都包含无效值，返回 ul 对齐结果");
             xss.get("ul").cloned().unwrap_or_default()
         };
 
+        self.hint_coordinates(&mut final_xs);
+        self.pin_coordinates(&mut final_xs);
+
         // 设置最终位置
-        println!("\n8. 设置最终位置...");
         for (node, &x) in &final_xs {
             if let Some(&rank) = self.ranks.get(node) {
-                let node_label = self.graph.node_label(*node).unwrap();
-                let label = node_label.label.as_deref().unwrap_or("Unknown");
-                println!("  {}: x = {:.6}, rank = {}", label, x, rank);
+                log::trace!("{:?}: x = {:.6}, rank = {}", node, x, rank);
 
                 result.positions.insert(
                     *node,
@@ -345,7 +371,7 @@ impl BrandesKoepf {
             }
         }
 
-        println!("\n=== Brandes-Köpf 算法完成 ===");
+        log::debug!("Brandes-Köpf 完成: positions={}", result.positions.len());
         result
     }
 
@@ -762,8 +788,13 @@ impl BrandesKoepf {
                 }
 
                 let mut ws = neighbor_fn(&self.graph, v);
-                // 过滤掉不在层级矩阵中的邻居节点和占位符节点
-                ws.retain(|&w| pos.contains_key(&w) && !is_placeholder(w));
+                // 过滤掉不在层级矩阵中的邻居节点和占位符节点；同一排名内的边
+                // （例如排名约束分组内的边）两端不能对齐到同一个块
+                ws.retain(|&w| {
+                    pos.contains_key(&w)
+                        && !is_placeholder(w)
+                        && self.ranks.get(&w) != self.ranks.get(&v)
+                });
                 if !ws.is_empty() {
                     ws.sort_by_key(|&w| pos.get(&w).copied().unwrap_or(0));
                     let mp = (ws.len() - 1) as f64 / 2.0;
@@ -844,21 +875,11 @@ impl BrandesKoepf {
         align: &Alignment,
         reverse_sep: bool,
     ) -> IndexMap<NodeIndex, f64> {
-        println!("      开始水平压缩...");
-        println!("        输入层级: {:?}", layering);
-        println!("        对齐根: {:?}", align.root);
-        println!("        反向分离: {}", reverse_sep);
-
         let mut xs = IndexMap::new();
         let (block_graph, node_map) =
             self.build_block_graph_with_mapping(layering, &align.root, reverse_sep);
 
-        println!("        块图节点数: {}", block_graph.node_count());
-        println!("        块图边数: {}", block_graph.edge_count());
-        println!("        节点映射: {:?}", node_map);
-
         // 第一遍：分配最小坐标
-        println!("        第一遍：分配最小坐标...");
         self.iterate(
             &block_graph,
             |elem| {
@@ -868,16 +889,7 @@ impl BrandesKoepf {
                     if let Some(&source_x) = xs.get(&source) {
                         let edge_weight = edge.weight();
                         let current_x: f64 = xs.get(&elem).copied().unwrap_or(0.0);
-                        let new_x = current_x.max(source_x + edge_weight);
-                        xs.insert(elem, new_x);
-                        println!(
-                            "          节点 {}: 从 {} 更新到 {} (源: {}, 边权重: {})",
-                            elem.index(),
-                            current_x,
-                            new_x,
-                            source_x,
-                            edge_weight
-                        );
+                        xs.insert(elem, current_x.max(source_x + edge_weight));
                     }
                 }
             },
@@ -888,10 +900,9 @@ impl BrandesKoepf {
             },
         );
 
-        println!("        第一遍完成，坐标: {:?}", xs);
+        log::trace!("水平压缩第一遍: {:?}", xs);
 
         // 第二遍：分配最大坐标
-        println!("        第二遍：分配最大坐标...");
         self.iterate(
             &block_graph,
             |elem| {
@@ -906,15 +917,7 @@ impl BrandesKoepf {
 
                 if min != f64::INFINITY {
                     let current_x = xs.get(&elem).copied().unwrap_or(0.0);
-                    let new_x = current_x.max(min);
-                    xs.insert(elem, new_x);
-                    println!(
-                        "          节点 {}: 从 {} 更新到 {} (最小: {})",
-                        elem.index(),
-                        current_x,
-                        new_x,
-                        min
-                    );
+                    xs.insert(elem, current_x.max(min));
                 }
             },
             |elem| {
@@ -924,23 +927,18 @@ impl BrandesKoepf {
             },
         );
 
-        println!("        第二遍完成，坐标: {:?}", xs);
+        log::trace!("水平压缩第二遍: {:?}", xs);
 
         // 为所有节点分配 x 坐标
-        println!("        分配最终坐标...");
         let mut final_xs = IndexMap::new();
         for (v, &root_v) in &align.root {
             if let Some(&block_node_id) = node_map.get(&root_v) {
                 if let Some(&x) = xs.get(&block_node_id) {
                     final_xs.insert(*v, x);
-                    let node_label = self.graph.node_label(*v).unwrap();
-                    let label = node_label.label.as_deref().unwrap_or("Unknown");
-                    println!("          节点 {}: x = {:.6}", label, x);
                 }
             }
         }
 
-        println!("        水平压缩完成，最终坐标: {:?}", final_xs);
         final_xs
     }
 
@@ -954,17 +952,11 @@ impl BrandesKoepf {
         PetGraph<f64, f64, Directed>,
         IndexMap<NodeIndex, petgraph::graph::NodeIndex>,
     ) {
-        println!("        构建块图...");
-        println!("          输入层级: {:?}", layering);
-        println!("          对齐根: {:?}", root);
-        println!("          反向分离: {}", reverse_sep);
-
         let mut block_graph = PetGraph::<f64, f64, Directed>::new();
-        let node_sep = 50.0; // 与JavaScript demo匹配的节点间距
-        let edge_sep = 1.0; // 默认边间距
+        let node_sep = self.graph.config().node_sep;
+        let edge_sep = self.graph.config().edge_sep;
 
         // 首先添加所有节点
-        println!("          添加节点...");
         let mut node_map = IndexMap::new();
         for layer in layering {
             for &v in layer {
@@ -977,15 +969,11 @@ impl BrandesKoepf {
                 if !node_map.contains_key(&v_root) {
                     let node_id = block_graph.add_node(v_root.index() as f64);
                     node_map.insert(v_root, node_id);
-                    let node_label = self.graph.node_label(v).unwrap();
-                    let label = node_label.label.as_deref().unwrap_or("Unknown");
-                    println!("            添加节点 {} (根: {})", label, v_root.index());
                 }
             }
         }
 
         // 然后添加边
-        println!("          添加边...");
         for (_layer_idx, layer) in layering.iter().enumerate() {
             let mut u = None;
             for &v in layer {
@@ -1010,55 +998,110 @@ impl BrandesKoepf {
                             .fold(0.0, f64::max);
                         let final_weight = sep_value.max(prev_max);
                         block_graph.add_edge(u_node_id, v_node_id, final_weight);
-
-                        let u_label = self
-                            .graph
-                            .node_label(u_node)
-                            .unwrap()
-                            .label
-                            .as_deref()
-                            .unwrap_or("Unknown");
-                        let v_label = self
-                            .graph
-                            .node_label(v)
-                            .unwrap()
-                            .label
-                            .as_deref()
-                            .unwrap_or("Unknown");
-                        println!(
-                            "            添加边 {} -> {} (权重: {:.6})",
-                            u_label, v_label, final_weight
-                        );
-                    } else {
-                        let u_label = self
-                            .graph
-                            .node_label(u_node)
-                            .unwrap()
-                            .label
-                            .as_deref()
-                            .unwrap_or("Unknown");
-                        let v_label = self
-                            .graph
-                            .node_label(v)
-                            .unwrap()
-                            .label
-                            .as_deref()
-                            .unwrap_or("Unknown");
-                        println!("            跳过边 {} -> {} (相同根节点)", u_label, v_label);
                     }
                 }
                 u = Some(v);
             }
         }
 
-        println!(
-            "          块图构建完成: {} 节点, {} 边",
+        log::trace!(
+            "块图: {} 节点, {} 边",
             block_graph.node_count(),
             block_graph.edge_count()
         );
         (block_graph, node_map)
     }
 
+    /// 把固定节点放在目标 x 上，同层其他节点向两侧推开以保持间距
+    ///
+    /// 先整体平移使固定节点与目标的平均偏差为零，再逐层处理：从左到右把固定节点右侧的节点
+    /// 推到间距之外，从右到左把固定节点左侧的节点推到间距之外。两个固定节点之间放不下
+    /// 其他节点时无法保持间距。
+    pub fn pin_coordinates(&self, xs: &mut IndexMap<NodeIndex, f64>) {
+        let pins: IndexMap<NodeIndex, f64> =
+            crate::pin::layout_pins(&self.pins, self.graph.config().rankdir)
+                .into_iter()
+                .filter(|(node, _)| xs.contains_key(node))
+                .map(|(node, point)| (node, point.x))
+                .collect();
+        self.hold_coordinates(xs, &pins, true);
+    }
+
+    /// 把有增量布局提示的节点放回上一次布局的 x 上
+    ///
+    /// 与 `pin_coordinates` 相同，但提示不是硬约束：间距不足时有提示的节点也会被推开。
+    /// 没有提示的节点先随同层有提示节点的平均位移一起移动，使虚拟节点链跟随两端的节点。
+    pub fn hint_coordinates(&self, xs: &mut IndexMap<NodeIndex, f64>) {
+        let rankdir = self.graph.config().rankdir;
+        let hints: IndexMap<NodeIndex, f64> = self
+            .hints
+            .nodes
+            .iter()
+            .filter(|(node, _)| xs.contains_key(*node))
+            .map(|(&node, hint)| {
+                let point = crate::pin::to_layout_coordinates(
+                    crate::types::Point::new(hint.x, hint.y),
+                    rankdir,
+                );
+                (node, point.x)
+            })
+            .collect();
+        self.hold_coordinates(xs, &hints, false);
+    }
+
+    /// 把目标节点放在目标 x 上并恢复同层节点的间距
+    ///
+    /// `exact` 为 `true` 时目标节点总是位于目标上，否则从左到右处理时间距优先，
+    /// 没有目标的节点随同层目标节点的平均位移一起移动。
+    fn hold_coordinates(
+        &self,
+        xs: &mut IndexMap<NodeIndex, f64>,
+        targets: &IndexMap<NodeIndex, f64>,
+        exact: bool,
+    ) {
+        if targets.is_empty() {
+            return;
+        }
+
+        let offset =
+            targets.iter().map(|(node, x)| xs[node] - x).sum::<f64>() / targets.len() as f64;
+        let node_sep = self.graph.config().node_sep;
+        let edge_sep = self.graph.config().edge_sep;
+
+        for layer in &self.layers {
+            let shifts: Vec<f64> = layer
+                .iter()
+                .filter_map(|v| Some(targets.get(v)? + offset - xs[v]))
+                .collect();
+            let layer_shift = if exact || shifts.is_empty() {
+                0.0
+            } else {
+                shifts.iter().sum::<f64>() / shifts.len() as f64
+            };
+
+            for i in 0..layer.len() {
+                let v = layer[i];
+                let target = targets.get(&v).map(|x| x + offset);
+                let mut x = target.unwrap_or(xs[&v] + layer_shift);
+                if i > 0 && !(exact && target.is_some()) {
+                    let u = layer[i - 1];
+                    x = x.max(xs[&u] + self.sep(node_sep, edge_sep, false, u, v));
+                }
+                xs[&v] = x;
+            }
+            for i in (0..layer.len().saturating_sub(1)).rev() {
+                let (v, w) = (layer[i], layer[i + 1]);
+                if targets.contains_key(&v) {
+                    continue;
+                }
+                let max = xs[&w] - self.sep(node_sep, edge_sep, false, v, w);
+                if xs[&v] > max {
+                    xs[&v] = max;
+                }
+            }
+        }
+    }
+
     /// 计算分离值
     pub fn sep(
         &self,
@@ -1084,17 +1127,6 @@ impl BrandesKoepf {
         let v_width = v_label.width;
         let w_width = w_label.width;
 
-        println!(
-            "            计算分离值: {} -> {}",
-            v_label.label.as_deref().unwrap_or("Unknown"),
-            w_label.label.as_deref().unwrap_or("Unknown")
-        );
-        println!("              v_width: {}, w_width: {}", v_width, w_width);
-        println!(
-            "              node_sep: {}, edge_sep: {}, reverse_sep: {}",
-            node_sep, edge_sep, reverse_sep
-        );
-
         sum += v_width / 2.0;
 
         // 处理 v 节点的 labelpos (NodeLabel 没有 labelpos 字段，跳过)
@@ -1140,7 +1172,6 @@ impl BrandesKoepf {
             sum += if reverse_sep { delta } else { -delta };
         }
 
-        println!("              分离值: {:.6}", sum);
         sum
     }
 
@@ -2,24 +2,32 @@
 
 pub mod bk;
 
-use crate::counters::*;
-use crate::graph::Graph;
+use crate::context::LayoutContext;
+use crate::graph::{Graph, NodeIndex};
+use crate::types::{Point, RankDirection};
 use crate::util::build_layer_matrix;
+use indexmap::IndexMap;
 
 /// 计算节点位置
 ///
 /// 对应 JS 函数: position() in lib/position/index.js
-pub fn position(graph: &mut Graph) {
+pub fn position(graph: &mut Graph, ctx: &mut LayoutContext) {
     // 转换为非复合图，只处理叶子节点
     let (mut simplified_graph, old_to_new) = crate::util::as_non_compound_graph(graph);
+    let pins: IndexMap<NodeIndex, Point> = ctx
+        .pins
+        .iter()
+        .filter_map(|(node, &point)| Some((*old_to_new.get(node)?, point)))
+        .collect();
 
     position_y(&mut simplified_graph);
+    crate::pin::pin_rank_coordinates(&mut simplified_graph, &pins);
 
-    let bk_result = {
-        increment_bk();
-        let mut bk = bk::BrandesKoepf::new(simplified_graph.clone());
-        bk.run()
-    };
+    let bk_result = bk::BrandesKoepf::new(simplified_graph.clone())
+        .with_budget(ctx.budget.clone())
+        .with_pins(pins)
+        .with_hints(ctx.hints.remap(&old_to_new))
+        .run();
 
     // 将坐标分配回原图
     for (new_node_id, position) in bk_result.positions {
@@ -140,154 +148,176 @@ fn calculate_edge_points(
 }
 
 /// 调整坐标系统
+///
+/// 布局算法总是按从上到下的方向计算坐标。对于 LR/RL，先交换节点和边标签的宽高，
+/// 使排名方向上的间距使用节点的真实宽度。
+///
+/// 对应 JS 函数: coordinateSystem.adjust() in lib/coordinate-system.js
 pub fn adjust_coordinate_system(graph: &mut Graph) {
-    let config = graph.config();
+    match graph.config().rankdir {
+        RankDirection::LeftRight | RankDirection::RightLeft => swap_width_height(graph),
+        RankDirection::TopBottom | RankDirection::BottomTop => {}
+    }
+}
 
-    match config.rankdir {
-        crate::types::RankDirection::TopBottom => {
-            // 默认方向，不需要调整
-        }
-        crate::types::RankDirection::BottomTop => {
-            // 翻转Y坐标
-            flip_y_coordinates(graph);
-        }
-        crate::types::RankDirection::LeftRight => {
-            // 交换X和Y坐标
-            swap_xy_coordinates(graph);
-        }
-        crate::types::RankDirection::RightLeft => {
-            // 交换X和Y坐标，然后翻转X坐标
-            swap_xy_coordinates(graph);
-            flip_x_coordinates(graph);
-        }
+/// 撤销坐标系统调整
+///
+/// 将从上到下方向计算出的节点坐标、边路径点和边标签坐标变换到 `rankdir` 指定的方向，
+/// 并恢复 [`adjust_coordinate_system`] 交换过的宽高。
+///
+/// 对应 JS 函数: coordinateSystem.undo() in lib/coordinate-system.js
+pub fn undo_coordinate_system(graph: &mut Graph) {
+    let rankdir = graph.config().rankdir;
+
+    if matches!(rankdir, RankDirection::BottomTop | RankDirection::RightLeft) {
+        reverse_y(graph);
+    }
+
+    if matches!(rankdir, RankDirection::LeftRight | RankDirection::RightLeft) {
+        swap_xy(graph);
+        swap_width_height(graph);
     }
 }
 
-/// 翻转Y坐标
-fn flip_y_coordinates(graph: &mut Graph) {
-    // 先收集所有Y坐标，避免借用冲突
-    let max_y = graph
-        .node_indices()
-        .filter_map(|id| graph.node_label(id)?.y)
-        .fold(0.0, f64::max);
-
-    // 收集需要更新的节点
-    let mut nodes_to_update = Vec::new();
-    for node_id in graph.node_indices() {
-        if let Some(label) = graph.node_label(node_id) {
-            if let Some(y) = label.y {
-                nodes_to_update.push((node_id, max_y - y));
-            }
+/// 交换节点和边标签的宽高
+fn swap_width_height(graph: &mut Graph) {
+    for node_id in graph.node_indices().collect::<Vec<_>>() {
+        if let Some(label) = graph.node_label_mut(node_id) {
+            std::mem::swap(&mut label.width, &mut label.height);
         }
     }
 
-    // 更新节点坐标
-    for (node_id, new_y) in nodes_to_update {
-        if let Some(label) = graph.node_label_mut(node_id) {
-            label.y = Some(new_y);
+    for edge in graph.edges() {
+        if let Some(edge_label) = graph.edge_label_mut(&edge) {
+            std::mem::swap(&mut edge_label.width, &mut edge_label.height);
         }
     }
 }
 
-/// 翻转X坐标
-fn flip_x_coordinates(graph: &mut Graph) {
-    // 先收集所有X坐标，避免借用冲突
-    let max_x = graph
-        .node_indices()
-        .filter_map(|id| graph.node_label(id)?.x)
-        .fold(0.0, f64::max);
-
-    // 收集需要更新的节点
-    let mut nodes_to_update = Vec::new();
-    for node_id in graph.node_indices() {
-        if let Some(label) = graph.node_label(node_id) {
-            if let Some(x) = label.x {
-                nodes_to_update.push((node_id, max_x - x));
-            }
+/// 翻转Y坐标
+fn reverse_y(graph: &mut Graph) {
+    for node_id in graph.node_indices().collect::<Vec<_>>() {
+        if let Some(label) = graph.node_label_mut(node_id) {
+            label.y = label.y.map(|y| -y);
         }
     }
 
-    // 更新节点坐标
-    for (node_id, new_x) in nodes_to_update {
-        if let Some(label) = graph.node_label_mut(node_id) {
-            label.x = Some(new_x);
+    for edge in graph.edges() {
+        if let Some(edge_label) = graph.edge_label_mut(&edge) {
+            for point in &mut edge_label.points {
+                point.y = -point.y;
+            }
+            edge_label.y = edge_label.y.map(|y| -y);
         }
     }
 }
 
 /// 交换X和Y坐标
-fn swap_xy_coordinates(graph: &mut Graph) {
-    // 收集需要更新的节点，避免借用冲突
-    let mut nodes_to_update = Vec::new();
-
-    for node_id in graph.node_indices() {
-        if let Some(label) = graph.node_label(node_id) {
-            if let (Some(x), Some(y)) = (label.x, label.y) {
-                nodes_to_update.push((node_id, y, x));
-            }
+fn swap_xy(graph: &mut Graph) {
+    for node_id in graph.node_indices().collect::<Vec<_>>() {
+        if let Some(label) = graph.node_label_mut(node_id) {
+            std::mem::swap(&mut label.x, &mut label.y);
         }
     }
 
-    // 应用更新
-    for (node_id, new_x, new_y) in nodes_to_update {
-        if let Some(label) = graph.node_label_mut(node_id) {
-            label.x = Some(new_x);
-            label.y = Some(new_y);
+    for edge in graph.edges() {
+        if let Some(edge_label) = graph.edge_label_mut(&edge) {
+            for point in &mut edge_label.points {
+                std::mem::swap(&mut point.x, &mut point.y);
+            }
+            std::mem::swap(&mut edge_label.x, &mut edge_label.y);
         }
     }
 }
 
 /// 平移图到原点
+///
+/// 节点和边标签的包围盒左上角平移到 (`marginx`, `marginy`)，
+/// 图的宽高包含两侧边距。
+///
+/// 对应 JS 函数: translateGraph() in lib/layout.js
 pub fn translate_graph(graph: &mut Graph) {
     let mut min_x = f64::INFINITY;
+    let mut max_x = f64::NEG_INFINITY;
     let mut min_y = f64::INFINITY;
+    let mut max_y = f64::NEG_INFINITY;
 
-    // 找到最小坐标
+    let mut extend = |x: f64, y: f64, width: f64, height: f64| {
+        min_x = min_x.min(x - width / 2.0);
+        max_x = max_x.max(x + width / 2.0);
+        min_y = min_y.min(y - height / 2.0);
+        max_y = max_y.max(y + height / 2.0);
+    };
+
+    // 找到包围盒
     for node_id in graph.node_indices() {
-        if let Some(label) = graph.node_label(node_id) {
-            if let (Some(x), Some(y)) = (label.x, label.y) {
-                min_x = min_x.min(x - label.width / 2.0);
-                min_y = min_y.min(y - label.height / 2.0);
-            }
+        if let Some(label) = graph.node_label(node_id)
+            && let (Some(x), Some(y)) = (label.x, label.y)
+        {
+            extend(x, y, label.width, label.height);
+        }
+    }
+    for edge in graph.edges() {
+        if let Some(edge_label) = graph.edge_label(&edge)
+            && let (Some(x), Some(y)) = (edge_label.x, edge_label.y)
+        {
+            extend(x, y, edge_label.width, edge_label.height);
         }
     }
 
+    // 空图没有包围盒
+    if min_x > max_x {
+        (min_x, max_x, min_y, max_y) = (0.0, 0.0, 0.0, 0.0);
+    }
+
+    let (marginx, marginy) = (graph.config().marginx, graph.config().marginy);
+    min_x -= marginx;
+    min_y -= marginy;
+
     // 平移所有节点
     for node_id in graph.node_indices().collect::<Vec<_>>() {
         if let Some(label) = graph.node_label_mut(node_id) {
-            if let (Some(x), Some(y)) = (label.x, label.y) {
-                label.x = Some(x - min_x);
-                label.y = Some(y - min_y);
-            }
+            label.x = label.x.map(|x| x - min_x);
+            label.y = label.y.map(|y| y - min_y);
         }
     }
 
-    // 平移所有边的控制点
+    // 平移所有边的控制点和标签
     for edge in graph.edges() {
         if let Some(edge_label) = graph.edge_label_mut(&edge) {
             for point in &mut edge_label.points {
                 point.x -= min_x;
                 point.y -= min_y;
             }
+            edge_label.x = edge_label.x.map(|x| x - min_x);
+            edge_label.y = edge_label.y.map(|y| y - min_y);
         }
     }
 
     // 更新图尺寸
-    let mut max_x: f64 = 0.0;
-    let mut max_y: f64 = 0.0;
+    let config = graph.config_mut();
+    config.width = Some(max_x - min_x + marginx);
+    config.height = Some(max_y - min_y + marginy);
+}
 
-    for node_id in graph.node_indices() {
-        if let Some(label) = graph.node_label(node_id) {
-            if let (Some(x), Some(y)) = (label.x, label.y) {
-                max_x = max_x.max(x + label.width / 2.0);
-                max_y = max_y.max(y + label.height / 2.0);
+/// 平移图中的所有节点、边的路径点、控制点和标签，不改变图的尺寸
+pub fn shift_graph(graph: &mut Graph, dx: f64, dy: f64) {
+    for node in graph.node_indices().collect::<Vec<_>>() {
+        if let Some(label) = graph.node_label_mut(node) {
+            label.x = label.x.map(|x| x + dx);
+            label.y = label.y.map(|y| y + dy);
+        }
+    }
+    for edge in graph.edges() {
+        if let Some(label) = graph.edge_label_mut(&edge) {
+            for point in label.points.iter_mut().chain(&mut label.control_points) {
+                point.x += dx;
+                point.y += dy;
             }
+            label.x = label.x.map(|x| x + dx);
+            label.y = label.y.map(|y| y + dy);
         }
     }
-
-    let config = graph.config_mut();
-    config.width = Some(max_x + config.marginx);
-    config.height = Some(max_y + config.marginy);
 }
 
 #[cfg(test)]
@@ -336,4 +366,39 @@ mod tests {
         assert!(final_label.x.unwrap() >= 0.0);
         assert!(final_label.y.unwrap() >= 0.0);
     }
+    #[test]
+    fn test_translate_graph_covers_edge_labels_and_both_margins() {
+        let mut graph = Graph::new();
+        graph.config_mut().marginx = 5.0;
+        graph.config_mut().marginy = 5.0;
+        let node = |x, y| NodeLabel {
+            width: 10.0,
+            height: 10.0,
+            x: Some(x),
+            y: Some(y),
+            ..Default::default()
+        };
+        let a = graph.add_node(node(0.0, 0.0));
+        let b = graph.add_node(node(0.0, 50.0));
+        // 边标签比两个节点都更靠右
+        graph.add_edge(
+            Edge::new(a, b),
+            EdgeLabel {
+                width: 20.0,
+                height: 10.0,
+                x: Some(30.0),
+                y: Some(25.0),
+                ..Default::default()
+            },
+        );
+
+        translate_graph(&mut graph);
+
+        let label = graph.node_label(a).unwrap();
+        assert_eq!((label.x, label.y), (Some(10.0), Some(10.0)));
+        let edge_label = graph.edge_label(&Edge::new(a, b)).unwrap();
+        assert_eq!((edge_label.x, edge_label.y), (Some(40.0), Some(35.0)));
+        assert_eq!(graph.config().width, Some(55.0));
+        assert_eq!(graph.config().height, Some(70.0));
+    }
 }
@@ -0,0 +1,344 @@
+//! 排名约束
+//!
+//! 每个 [`RankConstraint`] 的节点合并为一个分组；所有 `Min`、`Source` 约束的节点合并为
+//! 第一个排名的分组，所有 `Max`、`Sink` 约束的节点合并为最后一个排名的分组。
+//! 排名时每个分组收缩成一个节点，第一个排名的分组再通过权重为 0 的边指向其他所有节点，
+//! 其他所有节点指向最后一个排名的分组，然后在收缩后的图上运行排序器，
+//! 最后把分组的排名赋给组内的所有节点。任何排序器都因此满足约束。
+
+use crate::error::LayoutError;
+use crate::graph::{Graph, NodeIndex};
+use crate::types::{Edge, EdgeLabel, RankKind};
+use indexmap::IndexMap;
+use petgraph::algo::tarjan_scc;
+
+/// 独占第一个或最后一个排名时与相邻排名的最小间隔
+///
+/// 与 `make_space_for_edge_labels` 加倍后的默认最小长度一致。
+const EXCLUSIVE_MINLEN: i32 = 2;
+
+/// 第一个或最后一个排名的分组
+#[derive(Debug, Clone, Copy)]
+struct Extreme {
+    /// 分组中的任意一个节点
+    node: NodeIndex,
+    /// 是否独占该排名（`Source`、`Sink`）
+    exclusive: bool,
+}
+
+/// 排名约束合并后的节点分组
+#[derive(Debug, Clone, Default)]
+pub struct RankGroups {
+    /// 分组，每组第一个节点是代表节点
+    groups: Vec<Vec<NodeIndex>>,
+    /// 受约束的节点 -> 所在分组
+    group_of: IndexMap<NodeIndex, usize>,
+    first: Option<Extreme>,
+    last: Option<Extreme>,
+}
+
+/// 收缩后的图
+pub struct ContractedGraph {
+    /// 每个分组收缩为一个节点，并添加了第一个和最后一个排名的约束边
+    pub graph: Graph,
+    /// 原图节点 -> 收缩图节点
+    pub nodes: IndexMap<NodeIndex, NodeIndex>,
+}
+
+impl RankGroups {
+    /// 合并图中的排名约束
+    ///
+    /// 约束引用不存在的节点，或同一个节点既要在第一个排名又要在最后一个排名时返回错误。
+    pub fn new(graph: &Graph) -> Result<Self, LayoutError> {
+        let mut groups = Self::default();
+        for constraint in graph.rank_constraints() {
+            let Some((&head, rest)) = constraint.nodes.split_first() else {
+                continue;
+            };
+            for &node in &constraint.nodes {
+                graph.check_node(node, None)?;
+            }
+            for &node in rest {
+                groups.union(head, node);
+            }
+            groups.union(head, head);
+
+            let (extreme, exclusive) = match constraint.kind {
+                RankKind::Same => continue,
+                RankKind::Min => (&mut groups.first, false),
+                RankKind::Source => (&mut groups.first, true),
+                RankKind::Max => (&mut groups.last, false),
+                RankKind::Sink => (&mut groups.last, true),
+            };
+            let exclusive = exclusive || extreme.is_some_and(|e| e.exclusive);
+            let previous = extreme.replace(Extreme {
+                node: head,
+                exclusive,
+            });
+            if let Some(previous) = previous {
+                groups.union(previous.node, head);
+            }
+        }
+
+        if let (Some(first), Some(last)) = (groups.first, groups.last)
+            && groups.leader(first.node) == groups.leader(last.node)
+        {
+            return Err(LayoutError::RankConstraintConflict {
+                nodes: groups.members(first.node),
+                edge: None,
+            });
+        }
+        Ok(groups)
+    }
+
+    /// 合并两个节点所在的分组
+    fn union(&mut self, a: NodeIndex, b: NodeIndex) {
+        let group_a = match self.group_of.get(&a) {
+            Some(&group) => group,
+            None => {
+                self.groups.push(vec![a]);
+                self.group_of.insert(a, self.groups.len() - 1);
+                self.groups.len() - 1
+            }
+        };
+        match self.group_of.get(&b) {
+            Some(&group_b) if group_b == group_a => {}
+            Some(&group_b) => {
+                let moved = std::mem::take(&mut self.groups[group_b]);
+                for &node in &moved {
+                    self.group_of.insert(node, group_a);
+                }
+                self.groups[group_a].extend(moved);
+            }
+            None => {
+                self.groups[group_a].push(b);
+                self.group_of.insert(b, group_a);
+            }
+        }
+    }
+
+    /// 节点所在分组的代表节点，不受约束的节点是它自己的代表
+    pub fn leader(&self, node: NodeIndex) -> NodeIndex {
+        self.group_of
+            .get(&node)
+            .map_or(node, |&group| self.groups[group][0])
+    }
+
+    /// 节点所在分组的所有节点
+    pub fn members(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        self.group_of
+            .get(&node)
+            .map_or_else(|| vec![node], |&group| self.groups[group].clone())
+    }
+
+    /// 检查图中已有的排名是否满足约束，用于 `Ranker::None`
+    pub fn check_ranks(&self, graph: &Graph) -> Result<(), LayoutError> {
+        let rank = |node: NodeIndex| graph.node_label(node).and_then(|l| l.rank);
+        let conflict = |node: NodeIndex| LayoutError::RankConstraintConflict {
+            nodes: self.members(node),
+            edge: None,
+        };
+
+        for group in self.groups.iter().filter(|group| !group.is_empty()) {
+            let mut ranks = group.iter().filter_map(|&node| rank(node));
+            if let Some(first) = ranks.next()
+                && ranks.any(|r| r != first)
+            {
+                return Err(conflict(group[0]));
+            }
+        }
+
+        let others: Vec<(NodeIndex, i32)> = graph
+            .node_indices()
+            .filter(|&node| graph.node_label(node).is_some_and(|l| l.dummy.is_none()))
+            .filter_map(|node| Some((self.leader(node), rank(node)?)))
+            .collect();
+        for (extreme, sign) in [(self.first, 1), (self.last, -1)] {
+            let Some(extreme) = extreme else {
+                continue;
+            };
+            let leader = self.leader(extreme.node);
+            let Some(extreme_rank) = self.members(leader).into_iter().find_map(rank) else {
+                continue;
+            };
+            let violated = others.iter().any(|&(other, other_rank)| {
+                let gap = sign * (other_rank - extreme_rank);
+                other != leader && (gap < 0 || (extreme.exclusive && gap == 0))
+            });
+            if violated {
+                return Err(conflict(leader));
+            }
+        }
+        Ok(())
+    }
+
+    /// 把每个分组收缩为一个节点
+    ///
+    /// 组内节点之间的边被忽略；组间的平行边合并，最小长度取最大值，权重相加。
+    /// 有边指向第一个排名的分组、从最后一个排名的分组出发，或收缩后出现含正长度边的环时
+    /// 返回错误。环上的边长度都为 0 时环上的节点只能在同一排名，合并为一个分组后重新收缩。
+    pub fn contract(&self, graph: &Graph) -> Result<ContractedGraph, LayoutError> {
+        let contracted = self.contract_groups(graph)?;
+
+        // 同一排名的分组与边的方向矛盾时收缩图中出现环
+        let positive = |g: &Graph, edge: &Edge| g.edge_label(edge).is_none_or(|l| l.minlen > 0);
+        let mut merged: Option<RankGroups> = None;
+        for component in tarjan_scc(contracted.graph.petgraph()) {
+            if component.len() < 2 {
+                continue;
+            }
+            let cycle: Vec<NodeIndex> = component
+                .into_iter()
+                .map(|index| contracted.graph.wrap_index(index))
+                .collect();
+            let in_cycle = |node: NodeIndex| cycle.contains(&contracted.nodes[&node]);
+            let infeasible = contracted.graph.edges().into_iter().any(|edge| {
+                cycle.contains(&edge.source)
+                    && cycle.contains(&edge.target)
+                    && positive(&contracted.graph, &edge)
+            });
+
+            let members: Vec<NodeIndex> = graph
+                .node_indices()
+                .filter(|&node| in_cycle(node))
+                .collect();
+            if infeasible {
+                let cycle_edges: Vec<Edge> = graph
+                    .edges()
+                    .into_iter()
+                    .filter(|edge| in_cycle(edge.source) && in_cycle(edge.target))
+                    .collect();
+                let edge = cycle_edges
+                    .iter()
+                    .find(|edge| positive(graph, edge))
+                    .or(cycle_edges.first())
+                    .cloned();
+                let nodes = members
+                    .into_iter()
+                    .filter(|node| self.group_of.contains_key(node))
+                    .collect();
+                return Err(LayoutError::RankConstraintConflict { nodes, edge });
+            }
+
+            let groups = merged.get_or_insert_with(|| self.clone());
+            for &node in &members {
+                groups.union(members[0], node);
+            }
+        }
+
+        match merged {
+            Some(groups) => groups.contract(graph),
+            None => Ok(contracted),
+        }
+    }
+
+    /// 收缩分组并添加第一个和最后一个排名的约束边，不检查环
+    fn contract_groups(&self, graph: &Graph) -> Result<ContractedGraph, LayoutError> {
+        let mut config = graph.config().clone();
+        config.rank_constraints.clear();
+        let mut contracted = Graph::with_config(config);
+
+        let mut nodes = IndexMap::new();
+        for node in graph.node_indices() {
+            let leader = self.leader(node);
+            if leader == node
+                && let Some(label) = graph.node_label(node)
+            {
+                nodes.insert(node, contracted.add_node(label.clone()));
+            }
+        }
+        for node in graph.node_indices() {
+            if let Some(&leader) = nodes.get(&self.leader(node)) {
+                nodes.insert(node, leader);
+            }
+        }
+
+        let is_real = |node: NodeIndex| graph.node_label(node).is_some_and(|l| l.dummy.is_none());
+        let first = self.first.map(|e| (self.leader(e.node), e.exclusive));
+        let last = self.last.map(|e| (self.leader(e.node), e.exclusive));
+
+        let mut edges: IndexMap<(NodeIndex, NodeIndex), EdgeLabel> = IndexMap::new();
+        let mut merge = |source: NodeIndex, target: NodeIndex, minlen: i32, weight: f64| {
+            let label = edges.entry((source, target)).or_insert(EdgeLabel {
+                minlen,
+                weight: 0.0,
+                ..Default::default()
+            });
+            label.minlen = label.minlen.max(minlen);
+            label.weight += weight;
+        };
+
+        for edge in graph.edges() {
+            let (source, target) = (self.leader(edge.source), self.leader(edge.target));
+            if source == target {
+                continue;
+            }
+            let label = graph.edge_label(&edge);
+            let minlen = label.map_or(1, |l| l.minlen);
+            let weight = label.map_or(1.0, |l| l.weight);
+
+            let into_first = first.filter(|&(leader, exclusive)| {
+                target == leader && is_real(edge.source) && (minlen > 0 || exclusive)
+            });
+            let out_of_last = last.filter(|&(leader, exclusive)| {
+                source == leader && is_real(edge.target) && (minlen > 0 || exclusive)
+            });
+            if let Some((leader, _)) = into_first.or(out_of_last) {
+                return Err(LayoutError::RankConstraintConflict {
+                    nodes: self.members(leader),
+                    edge: Some(edge),
+                });
+            }
+            merge(nodes[&source], nodes[&target], minlen, weight);
+        }
+
+        for node in graph.node_indices() {
+            if self.leader(node) != node || !is_real(node) {
+                continue;
+            }
+            if let Some((leader, exclusive)) = first
+                && leader != node
+            {
+                let minlen = if exclusive { EXCLUSIVE_MINLEN } else { 0 };
+                merge(nodes[&leader], nodes[&node], minlen, 0.0);
+            }
+            if let Some((leader, exclusive)) = last
+                && leader != node
+            {
+                let minlen = if exclusive { EXCLUSIVE_MINLEN } else { 0 };
+                merge(nodes[&node], nodes[&leader], minlen, 0.0);
+            }
+        }
+
+        for ((source, target), label) in edges {
+            contracted.add_edge(Edge::new(source, target), label);
+        }
+
+        Ok(ContractedGraph {
+            graph: contracted,
+            nodes,
+        })
+    }
+}
+
+impl ContractedGraph {
+    /// 把收缩图的排名写回原图
+    pub fn expand(self, graph: &mut Graph) {
+        for (&node, &contracted) in &self.nodes {
+            let rank = self.graph.node_label(contracted).and_then(|l| l.rank);
+            if let Some(label) = graph.node_label_mut(node) {
+                label.rank = rank;
+            }
+        }
+    }
+}
+
+/// 检查图的排名约束能否满足
+pub fn check(graph: &Graph) -> Result<(), LayoutError> {
+    let groups = RankGroups::new(graph)?;
+    if graph.config().ranker == crate::types::Ranker::None {
+        groups.check_ranks(graph)
+    } else {
+        groups.contract(graph).map(|_| ())
+    }
+}
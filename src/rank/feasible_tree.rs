@@ -78,28 +78,27 @@ fn optimize_ranks_with_network_flow(graph: &mut Graph, _aux_graph: &mut Graph) {
         improved = false;
         iterations += 1;
 
-        // 尝试优化每条边
+        // 把节点上移到所有入边中最紧的位置，只看单条边会违反其他入边的最小长度
         let mut edge_updates = Vec::new();
-        for edge in graph.edges() {
-            if let Some(edge_label) = graph.edge_label(&edge) {
-                let current_slack = super::util::slack(graph, &edge);
-
-                if current_slack > 0 {
-                    if let (Some(source_label), Some(target_label)) =
-                        (graph.node_label(edge.source), graph.node_label(edge.target))
-                    {
-                        let source_rank = source_label.rank.unwrap_or(0);
-                        let target_rank = target_label.rank.unwrap_or(0);
-                        let min_len = edge_label.minlen as i32;
-
-                        // 调整目标节点的排名
-                        let new_target_rank = source_rank + min_len;
-                        if new_target_rank < target_rank {
-                            edge_updates.push((edge.target, new_target_rank));
-                            improved = true;
-                        }
-                    }
-                }
+        for node_id in graph.node_indices() {
+            let lowest = graph
+                .in_edges(node_id)
+                .iter()
+                .filter_map(|edge| {
+                    let source_rank = graph.node_label(edge.source)?.rank.unwrap_or(0);
+                    let min_len = graph.edge_label(edge)?.minlen;
+                    Some(source_rank + min_len)
+                })
+                .max();
+            let rank = graph
+                .node_label(node_id)
+                .and_then(|label| label.rank)
+                .unwrap_or(0);
+            if let Some(new_rank) = lowest
+                && new_rank < rank
+            {
+                edge_updates.push((node_id, new_rank));
+                improved = true;
             }
         }
 
@@ -1,39 +1,67 @@
 //! 排名算法模块
 
+pub mod constraints;
 pub mod feasible_tree;
 pub mod network_simplex;
 pub mod util;
 
+use crate::context::LayoutContext;
 use crate::graph::Graph;
 use crate::types::Ranker;
-use crate::util::time;
+use crate::util::{stage, stage_with_context};
+use constraints::RankGroups;
 
 /// 为图中的每个节点分配排名
 ///
+/// 有排名约束时在收缩后的图上排名，见 [`constraints`]。约束无法满足时忽略约束，
+/// 冲突记录在 `LayoutStats::rank_constraint_conflict` 中；`try_layout` 会在布局之前返回错误。
+///
 /// 对应 JS 函数: rank() in lib/rank/index.js
-pub fn rank(graph: &mut Graph) {
+pub fn rank(graph: &mut Graph, ctx: &mut LayoutContext) {
+    if graph.rank_constraints().is_empty() || graph.config().ranker == Ranker::None {
+        run_ranker(graph, ctx);
+        return;
+    }
+
+    let contracted = RankGroups::new(graph).and_then(|groups| groups.contract(graph));
+    match contracted {
+        Ok(mut contracted) => {
+            // 收缩图的节点不同，排名时换用转换到收缩图上的提示
+            let hints = ctx.hints.remap(&contracted.nodes);
+            let hints = std::mem::replace(&mut ctx.hints, hints);
+            run_ranker(&mut contracted.graph, ctx);
+            ctx.hints = hints;
+            contracted.expand(graph);
+        }
+        Err(err) => {
+            log::warn!("rank constraints are ignored: {}", err);
+            ctx.stats.rank_constraint_conflict = Some(err);
+            run_ranker(graph, ctx);
+        }
+    }
+}
+
+/// 运行配置的排序器
+fn run_ranker(graph: &mut Graph, ctx: &mut LayoutContext) {
     let ranker = graph.config().ranker;
 
     match ranker {
         Ranker::NetworkSimplex => {
-            time("network-simplex", || {
-                network_simplex::network_simplex(graph);
-            });
+            stage_with_context(
+                "network-simplex",
+                graph,
+                ctx,
+                network_simplex::network_simplex,
+            );
         }
         Ranker::FeasibleTree => {
-            time("feasible-tree", || {
-                feasible_tree_ranker(graph);
-            });
+            stage("feasible-tree", graph, ctx, feasible_tree_ranker);
         }
         Ranker::TightTree => {
-            time("tight-tree", || {
-                tight_tree_ranker(graph);
-            });
+            stage("tight-tree", graph, ctx, tight_tree_ranker);
         }
         Ranker::LongestPath => {
-            time("longest-path", || {
-                longest_path_ranker(graph);
-            });
+            stage("longest-path", graph, ctx, longest_path_ranker);
         }
         Ranker::None => {
             // 不进行排名
@@ -1,615 +1,459 @@
 //! 网络单纯形算法 - 精确复现JavaScript版本
+//!
+//! 生成树单独保存在 [`Tree`] 中，不写入节点标签的 `parent` 字段，
+//! 因此不会破坏复合图的父子关系。
 
+use crate::budget::{Budget, BudgetKind};
+use crate::context::LayoutContext;
 use crate::graph::{Graph, NodeIndex};
 use crate::types::*;
-use std::collections::HashSet;
+use indexmap::{IndexMap, IndexSet};
 
-/// 网络单纯形算法 - 精确复现JavaScript版本
-pub fn network_simplex(graph: &mut Graph) {
-    // 1. 简化图
-    // simplify(g) - 在Rust中我们假设图已经是简化的
-
-    // 2. 初始化rank
-    super::util::longest_path(graph);
-
-    // 3. 构建feasible tree
-    feasible_tree(graph);
-
-    // 4. 初始化low/lim值
-    init_low_lim_values(graph);
+/// 生成树中节点的属性
+#[derive(Debug, Clone, Default)]
+struct TreeNode {
+    low: i32,
+    lim: i32,
+    parent: Option<NodeIndex>,
+}
 
-    // 5. 初始化cut值
-    init_cut_values(graph);
+/// 无向生成树
+///
+/// 对应 JS 中 feasibleTree() 返回的无向 graphlib 图。边以无序节点对为键，
+/// 值为割值；遍历顺序与插入顺序一致，保证结果确定。
+#[derive(Debug, Default)]
+struct Tree {
+    nodes: IndexMap<NodeIndex, TreeNode>,
+    neighbors: IndexMap<NodeIndex, IndexSet<NodeIndex>>,
+    edges: IndexMap<(NodeIndex, NodeIndex), i32>,
+}
 
-    // 6. 迭代优化
-    loop {
-        if let Some(leave_edge) = leave_edge(graph) {
-            let enter_edge = enter_edge(graph, &leave_edge);
-            exchange_edges(graph, &leave_edge, &enter_edge);
+impl Tree {
+    fn key(v: NodeIndex, w: NodeIndex) -> (NodeIndex, NodeIndex) {
+        if v.index() <= w.index() {
+            (v, w)
         } else {
-            break;
+            (w, v)
         }
     }
-}
 
-/// 构建feasible tree - 精确复现JavaScript版本
-fn feasible_tree(graph: &mut Graph) {
-    // 选择任意节点作为起始点
-    let start = graph.node_indices().next().unwrap();
-    let size = graph.node_count();
-
-    // 使用HashSet来跟踪树中的节点
-    let mut tree_nodes = std::collections::HashSet::new();
-    tree_nodes.insert(start);
-
-    // 迭代构建tight tree
-    while tight_tree(graph, &mut tree_nodes) < size {
-        let edge = find_min_slack_edge_for_tree(graph, &tree_nodes);
-        let delta = if tree_nodes.contains(&edge.source) {
-            super::util::slack(graph, &edge) as i32
-        } else {
-            -(super::util::slack(graph, &edge) as i32)
-        };
-        shift_ranks_for_tree(graph, &tree_nodes, delta);
+    fn add_node(&mut self, v: NodeIndex) {
+        self.nodes.entry(v).or_default();
+        self.neighbors.entry(v).or_default();
     }
 
-    // 建立树关系 - 将tight edges标记为树边
-    establish_tree_relationships(graph, &tree_nodes);
-}
+    fn has_node(&self, v: NodeIndex) -> bool {
+        self.nodes.contains_key(&v)
+    }
 
-/// 建立树关系 - 将tight edges标记为树边
-fn establish_tree_relationships(
-    graph: &mut Graph,
-    tree_nodes: &std::collections::HashSet<NodeIndex>,
-) {
-    // 首先清除所有现有的树边标记
-    for edge in graph.edges() {
-        if let Some(edge_label) = graph.edge_label_mut(&edge) {
-            edge_label.cutvalue = None; // 清除cut值
+    fn add_edge(&mut self, v: NodeIndex, w: NodeIndex) {
+        self.add_node(v);
+        self.add_node(w);
+        self.edges.insert(Self::key(v, w), 0);
+        self.neighbors[&v].insert(w);
+        self.neighbors[&w].insert(v);
+    }
+
+    fn remove_edge(&mut self, v: NodeIndex, w: NodeIndex) {
+        self.edges.shift_remove(&Self::key(v, w));
+        if let Some(ns) = self.neighbors.get_mut(&v) {
+            ns.shift_remove(&w);
+        }
+        if let Some(ns) = self.neighbors.get_mut(&w) {
+            ns.shift_remove(&v);
         }
     }
 
-    // 找到所有tight edges并标记为树边
-    let mut visited = std::collections::HashSet::new();
-    let mut parent_map = std::collections::HashMap::new();
+    fn has_edge(&self, v: NodeIndex, w: NodeIndex) -> bool {
+        self.edges.contains_key(&Self::key(v, w))
+    }
 
-    fn dfs_establish_tree(
-        graph: &mut Graph,
-        tree_nodes: &std::collections::HashSet<NodeIndex>,
-        visited: &mut std::collections::HashSet<NodeIndex>,
-        parent_map: &mut std::collections::HashMap<NodeIndex, NodeIndex>,
-        v: NodeIndex,
-    ) {
-        visited.insert(v);
+    fn cut_value(&self, v: NodeIndex, w: NodeIndex) -> i32 {
+        self.edges.get(&Self::key(v, w)).copied().unwrap_or(0)
+    }
 
-        for edge in graph.out_edges(v) {
-            let w = edge.target;
-            if tree_nodes.contains(&w)
-                && !visited.contains(&w)
-                && super::util::slack(graph, &edge) == 0
-            {
-                parent_map.insert(w, v);
-                if let Some(edge_label) = graph.edge_label_mut(&edge) {
-                    edge_label.cutvalue = Some(0); // 标记为树边
-                }
-                dfs_establish_tree(graph, tree_nodes, visited, parent_map, w);
-            }
+    fn set_cut_value(&mut self, v: NodeIndex, w: NodeIndex, cut_value: i32) {
+        if let Some(value) = self.edges.get_mut(&Self::key(v, w)) {
+            *value = cut_value;
         }
+    }
+}
 
-        for edge in graph.in_edges(v) {
-            let w = edge.source;
-            if tree_nodes.contains(&w)
-                && !visited.contains(&w)
-                && super::util::slack(graph, &edge) == 0
-            {
-                parent_map.insert(w, v);
-                if let Some(edge_label) = graph.edge_label_mut(&edge) {
-                    edge_label.cutvalue = Some(0); // 标记为树边
-                }
-                dfs_establish_tree(graph, tree_nodes, visited, parent_map, w);
-            }
-        }
+/// 网络单纯形算法 - 精确复现JavaScript版本
+///
+/// 对应 JS 函数: networkSimplex() in lib/rank/network-simplex.js
+pub fn network_simplex(graph: &mut Graph, ctx: &mut LayoutContext) {
+    if graph.node_count() == 0 {
+        return;
     }
 
-    // 从起始节点开始建立树关系
-    let start = tree_nodes.iter().next().unwrap();
-    dfs_establish_tree(graph, tree_nodes, &mut visited, &mut parent_map, *start);
+    // 生成树按节点对查找边，先合并多重边
+    let (mut simplified, old_to_new) = crate::util::simplify(graph);
+    let hints = ctx.hints.remap(&old_to_new);
+    crate::incremental::anchor_ranks(&mut simplified, &hints);
+    let (pivots, exceeded) = run(&mut simplified, &ctx.budget);
+    ctx.stats.network_simplex_pivots += pivots;
+    if let Some(kind) = exceeded {
+        ctx.stats.exceed_budget(kind);
+    }
 
-    // 设置父节点关系
-    for (child, parent) in parent_map {
-        if let Some(node_label) = graph.node_label_mut(child) {
-            node_label.parent = Some(parent);
+    for (old_node_id, new_node_id) in old_to_new {
+        let rank = simplified.node_label(new_node_id).and_then(|l| l.rank);
+        if let Some(label) = graph.node_label_mut(old_node_id) {
+            label.rank = rank;
         }
     }
 }
 
-/// 找到tight edges的最大树并返回节点数
-fn tight_tree(graph: &Graph, tree_nodes: &mut std::collections::HashSet<NodeIndex>) -> usize {
-    let mut visited = HashSet::new();
+/// 在简单图上运行网络单纯形，返回换边次数和用完的预算
+///
+/// 每次换边后排名都是可行的，预算用完时直接停止，保留当前排名。
+fn run(graph: &mut Graph, budget: &Budget) -> (usize, Option<BudgetKind>) {
+    // 1. 初始化rank
+    super::util::longest_path(graph);
 
-    fn dfs(
-        graph: &Graph,
-        tree_nodes: &mut std::collections::HashSet<NodeIndex>,
-        visited: &mut HashSet<NodeIndex>,
-        v: NodeIndex,
-    ) {
-        visited.insert(v);
+    // 2. 构建feasible tree
+    let mut tree = feasible_tree(graph);
 
-        for edge in graph.out_edges(v) {
-            let w = edge.target;
-            if !visited.contains(&w)
-                && !tree_nodes.contains(&w)
-                && super::util::slack(graph, &edge) == 0
-            {
-                tree_nodes.insert(w);
-                dfs(graph, tree_nodes, visited, w);
-            }
-        }
+    // 3. 初始化low/lim值和cut值
+    init_low_lim_values(&mut tree);
+    init_cut_values(&mut tree, graph);
 
-        for edge in graph.in_edges(v) {
-            let w = edge.source;
-            if !visited.contains(&w)
-                && !tree_nodes.contains(&w)
-                && super::util::slack(graph, &edge) == 0
-            {
-                tree_nodes.insert(w);
-                dfs(graph, tree_nodes, visited, w);
-            }
+    // 4. 迭代优化
+    let mut pivots = 0;
+    while let Some(leave) = leave_edge(&tree) {
+        if budget.cancelled() {
+            break;
+        }
+        if let Some(kind) = budget.simplex_pivots_exhausted(pivots) {
+            return (pivots, Some(kind));
         }
+        let Some(enter) = enter_edge(&tree, graph, leave) else {
+            break;
+        };
+        exchange_edges(&mut tree, graph, leave, &enter);
+        pivots += 1;
     }
+    (pivots, None)
+}
 
-    let nodes: Vec<NodeIndex> = tree_nodes.iter().cloned().collect();
-    for node in nodes {
-        if !visited.contains(&node) {
-            dfs(graph, tree_nodes, &mut visited, node);
+/// 构建feasible tree
+///
+/// 对应 JS 函数: feasibleTree() in lib/rank/feasible-tree.js
+fn feasible_tree(graph: &mut Graph) -> Tree {
+    let mut tree = Tree::default();
+    let nodes: Vec<NodeIndex> = graph.node_indices().collect();
+    let size = nodes.len();
+    tree.add_node(nodes[0]);
+
+    while tight_tree(&mut tree, graph) < size {
+        match find_min_slack_edge(&tree, graph) {
+            Some(edge) => {
+                let slack = super::util::slack(graph, &edge);
+                let delta = if tree.has_node(edge.source) {
+                    slack
+                } else {
+                    -slack
+                };
+                shift_ranks(&tree, graph, delta);
+            }
+            None => {
+                // 图不连通时从下一个连通分量继续，得到生成森林
+                if let Some(&v) = nodes.iter().find(|&&v| !tree.has_node(v)) {
+                    tree.add_node(v);
+                }
+            }
         }
     }
 
-    tree_nodes.len()
+    tree
 }
 
-/// 找到最小slack的边
-fn find_min_slack_edge_for_tree(
-    graph: &Graph,
-    tree_nodes: &std::collections::HashSet<NodeIndex>,
-) -> Edge {
-    let mut min_slack = f64::INFINITY;
-    let mut min_edge = None;
-
-    for edge in graph.edges() {
-        let source_in_tree = tree_nodes.contains(&edge.source);
-        let target_in_tree = tree_nodes.contains(&edge.target);
-
-        let edge_slack = if source_in_tree != target_in_tree {
-            super::util::slack(graph, &edge) as f64
-        } else {
-            f64::INFINITY
-        };
-
-        if edge_slack < min_slack {
-            min_slack = edge_slack;
-            min_edge = Some(edge);
+/// 沿紧边扩展生成树，返回树中节点数
+fn tight_tree(tree: &mut Tree, graph: &Graph) -> usize {
+    fn dfs(tree: &mut Tree, graph: &Graph, v: NodeIndex) {
+        let node_edges = graph.out_edges(v).into_iter().chain(graph.in_edges(v));
+        for edge in node_edges.collect::<Vec<_>>() {
+            let w = if edge.source == v {
+                edge.target
+            } else {
+                edge.source
+            };
+            if !tree.has_node(w) && super::util::slack(graph, &edge) == 0 {
+                tree.add_edge(v, w);
+                dfs(tree, graph, w);
+            }
         }
     }
 
-    min_edge.unwrap()
+    let nodes: Vec<NodeIndex> = tree.nodes.keys().copied().collect();
+    for v in nodes {
+        dfs(tree, graph, v);
+    }
+
+    tree.nodes.len()
 }
 
-/// 调整rank值
-fn shift_ranks_for_tree(
-    graph: &mut Graph,
-    tree_nodes: &std::collections::HashSet<NodeIndex>,
-    delta: i32,
-) {
-    // 直接遍历tree_nodes中的所有节点
-    for node in tree_nodes {
-        if let Some(label) = graph.node_label_mut(*node) {
-            if let Some(rank) = label.rank {
-                label.rank = Some(rank + delta);
-            }
+/// 找到恰好有一个端点在树中、松弛度最小的边
+fn find_min_slack_edge(tree: &Tree, graph: &Graph) -> Option<Edge> {
+    graph
+        .edges()
+        .into_iter()
+        .filter(|edge| tree.has_node(edge.source) != tree.has_node(edge.target))
+        .min_by_key(|edge| super::util::slack(graph, edge))
+}
+
+/// 平移树中所有节点的rank
+fn shift_ranks(tree: &Tree, graph: &mut Graph, delta: i32) {
+    for &v in tree.nodes.keys() {
+        if let Some(label) = graph.node_label_mut(v)
+            && let Some(rank) = label.rank
+        {
+            label.rank = Some(rank + delta);
         }
     }
 }
 
-/// 初始化low/lim值 - 精确复现JavaScript版本
-fn init_low_lim_values(graph: &mut Graph) {
-    // 找到根节点（没有父节点的节点）
-    let root = graph
-        .node_indices()
-        .find(|&node| {
-            if let Some(label) = graph.node_label(node) {
-                label.parent.is_none()
-            } else {
-                false
-            }
-        })
-        .unwrap();
-
-    dfs_assign_low_lim(graph, &mut HashSet::new(), 1, root, None);
+/// 初始化low/lim值
+///
+/// 对应 JS 函数: initLowLimValues() in lib/rank/network-simplex.js
+fn init_low_lim_values(tree: &mut Tree) {
+    let mut visited = IndexSet::new();
+    let mut next_lim = 1;
+    let roots: Vec<NodeIndex> = tree.nodes.keys().copied().collect();
+    for root in roots {
+        if !visited.contains(&root) {
+            next_lim = dfs_assign_low_lim(tree, &mut visited, next_lim, root, None);
+        }
+    }
 }
 
 /// DFS分配low/lim值
 fn dfs_assign_low_lim(
-    graph: &mut Graph,
-    visited: &mut HashSet<NodeIndex>,
+    tree: &mut Tree,
+    visited: &mut IndexSet<NodeIndex>,
     mut next_lim: i32,
     v: NodeIndex,
     parent: Option<NodeIndex>,
 ) -> i32 {
     let low = next_lim;
-    let mut label = graph.node_label_mut(v).unwrap().clone();
-
     visited.insert(v);
 
-    // 遍历邻居节点，只考虑树边
-    for edge in graph.out_edges(v) {
-        let w = edge.target;
-        if !visited.contains(&w) && is_tree_edge(graph, v, w) {
-            next_lim = dfs_assign_low_lim(graph, visited, next_lim, w, Some(v));
+    let neighbors: Vec<NodeIndex> = tree.neighbors[&v].iter().copied().collect();
+    for w in neighbors {
+        if !visited.contains(&w) {
+            next_lim = dfs_assign_low_lim(tree, visited, next_lim, w, Some(v));
         }
     }
 
-    for edge in graph.in_edges(v) {
-        let w = edge.source;
-        if !visited.contains(&w) && is_tree_edge(graph, w, v) {
-            next_lim = dfs_assign_low_lim(graph, visited, next_lim, w, Some(v));
-        }
-    }
-
-    label.low = Some(low);
-    label.lim = Some(next_lim);
-    if let Some(p) = parent {
-        label.parent = Some(p);
-    } else {
-        label.parent = None;
-    }
-
-    *graph.node_label_mut(v).unwrap() = label;
+    let node = &mut tree.nodes[&v];
+    node.low = low;
+    node.lim = next_lim;
+    node.parent = parent;
     next_lim + 1
 }
 
 /// 初始化cut值
-fn init_cut_values(graph: &mut Graph) {
-    // 获取后序遍历的节点（除了根节点）
-    let mut vs = postorder(graph);
-    if !vs.is_empty() {
-        vs.pop(); // 移除根节点
-    }
-
-    for v in vs {
-        assign_cut_value(graph, v);
-    }
-}
-
-/// 分配cut值
-fn assign_cut_value(graph: &mut Graph, child: NodeIndex) {
-    let child_label = graph.node_label(child).unwrap();
-    if let Some(parent) = child_label.parent {
-        let cut_value = calc_cut_value(graph, child);
-        if let Some(edge_label) = graph.edge_label_mut(&Edge::new(child, parent)) {
-            edge_label.cutvalue = Some(cut_value);
-        } else if let Some(edge_label) = graph.edge_label_mut(&Edge::new(parent, child)) {
-            edge_label.cutvalue = Some(cut_value);
+///
+/// 对应 JS 函数: initCutValues() in lib/rank/network-simplex.js
+fn init_cut_values(tree: &mut Tree, graph: &Graph) {
+    for v in postorder(tree) {
+        if let Some(parent) = tree.nodes[&v].parent {
+            let cut_value = calc_cut_value(tree, graph, v, parent);
+            tree.set_cut_value(v, parent, cut_value);
         }
     }
 }
 
-/// 计算cut值
-fn calc_cut_value(graph: &Graph, child: NodeIndex) -> i32 {
-    let child_label = graph.node_label(child).unwrap();
-    let parent = child_label.parent.unwrap();
-
+/// 计算child与其父节点之间树边的cut值
+///
+/// 对应 JS 函数: calcCutValue() in lib/rank/network-simplex.js
+fn calc_cut_value(tree: &Tree, graph: &Graph, child: NodeIndex, parent: NodeIndex) -> i32 {
     // 确定child是否是边的尾部
-    let mut child_is_tail = true;
-    let mut graph_edge = graph.edge_label(&Edge::new(child, parent));
-
-    if graph_edge.is_none() {
-        child_is_tail = false;
-        graph_edge = graph.edge_label(&Edge::new(parent, child));
-    }
-
-    let mut cut_value = 0;
-    if let Some(edge_label) = graph_edge {
-        cut_value = edge_label.weight as i32;
-    }
-
-    // 遍历child的所有边
-    for edge in graph.out_edges(child) {
-        let other = edge.target;
-        if other != parent {
-            let is_out_edge = true;
-            let points_to_head = is_out_edge == child_is_tail;
-            let other_weight = graph
-                .edge_label(&edge)
-                .map(|e| e.weight as i32)
-                .unwrap_or(0);
+    let (child_is_tail, graph_edge) = match graph.edge_label(&Edge::new(child, parent)) {
+        Some(label) => (true, label),
+        None => (
+            false,
+            graph
+                .edge_label(&Edge::new(parent, child))
+                .expect("tree edge must exist in graph"),
+        ),
+    };
 
-            cut_value += if points_to_head {
-                other_weight
-            } else {
-                -other_weight
-            };
+    let mut cut_value = graph_edge.weight as i32;
 
-            if is_tree_edge(graph, child, other) {
-                if let Some(edge_label) = graph.edge_label(&Edge::new(child, other)) {
-                    if let Some(other_cut_value) = edge_label.cutvalue {
-                        cut_value += if points_to_head {
-                            -other_cut_value
-                        } else {
-                            other_cut_value
-                        };
-                    }
-                } else if let Some(edge_label) = graph.edge_label(&Edge::new(other, child)) {
-                    if let Some(other_cut_value) = edge_label.cutvalue {
-                        cut_value += if points_to_head {
-                            -other_cut_value
-                        } else {
-                            other_cut_value
-                        };
-                    }
-                }
-            }
+    let node_edges = graph
+        .out_edges(child)
+        .into_iter()
+        .chain(graph.in_edges(child));
+    for edge in node_edges {
+        let is_out_edge = edge.source == child;
+        let other = if is_out_edge {
+            edge.target
+        } else {
+            edge.source
+        };
+        if other == parent {
+            continue;
         }
-    }
 
-    for edge in graph.in_edges(child) {
-        let other = edge.source;
-        if other != parent {
-            let is_out_edge = false;
-            let points_to_head = is_out_edge == child_is_tail;
-            let other_weight = graph
-                .edge_label(&edge)
-                .map(|e| e.weight as i32)
-                .unwrap_or(0);
+        let points_to_head = is_out_edge == child_is_tail;
+        let other_weight = graph.edge_label(&edge).map_or(0, |l| l.weight as i32);
+        cut_value += if points_to_head {
+            other_weight
+        } else {
+            -other_weight
+        };
 
+        if tree.has_edge(child, other) {
+            let other_cut_value = tree.cut_value(child, other);
             cut_value += if points_to_head {
-                other_weight
+                -other_cut_value
             } else {
-                -other_weight
+                other_cut_value
             };
-
-            if is_tree_edge(graph, child, other) {
-                if let Some(edge_label) = graph.edge_label(&Edge::new(other, child)) {
-                    if let Some(other_cut_value) = edge_label.cutvalue {
-                        cut_value += if points_to_head {
-                            -other_cut_value
-                        } else {
-                            other_cut_value
-                        };
-                    }
-                } else if let Some(edge_label) = graph.edge_label(&Edge::new(child, other)) {
-                    if let Some(other_cut_value) = edge_label.cutvalue {
-                        cut_value += if points_to_head {
-                            -other_cut_value
-                        } else {
-                            other_cut_value
-                        };
-                    }
-                }
-            }
         }
     }
 
     cut_value
 }
 
-/// 检查是否是树边
-fn is_tree_edge(graph: &Graph, u: NodeIndex, v: NodeIndex) -> bool {
-    if let Some(edge_label) = graph.edge_label(&Edge::new(u, v)) {
-        edge_label.cutvalue.is_some()
-    } else if let Some(edge_label) = graph.edge_label(&Edge::new(v, u)) {
-        edge_label.cutvalue.is_some()
-    } else {
-        false
-    }
-}
-
-/// 后序遍历
-fn postorder(graph: &Graph) -> Vec<NodeIndex> {
-    let mut result = Vec::new();
-    let mut visited = HashSet::new();
-
+/// 后序遍历生成树
+fn postorder(tree: &Tree) -> Vec<NodeIndex> {
     fn dfs(
-        graph: &Graph,
-        visited: &mut HashSet<NodeIndex>,
+        tree: &Tree,
+        visited: &mut IndexSet<NodeIndex>,
         result: &mut Vec<NodeIndex>,
         v: NodeIndex,
     ) {
         visited.insert(v);
-
-        // 遍历所有邻居，只考虑树边
-        for edge in graph.out_edges(v) {
-            let w = edge.target;
-            if !visited.contains(&w) && is_tree_edge(graph, v, w) {
-                dfs(graph, visited, result, w);
+        for &w in &tree.neighbors[&v] {
+            if !visited.contains(&w) {
+                dfs(tree, visited, result, w);
             }
         }
-
-        for edge in graph.in_edges(v) {
-            let w = edge.source;
-            if !visited.contains(&w) && is_tree_edge(graph, w, v) {
-                dfs(graph, visited, result, w);
-            }
-        }
-
         result.push(v);
     }
 
-    let nodes: Vec<NodeIndex> = graph.node_indices().collect();
-    for node in nodes {
-        if !visited.contains(&node) {
-            dfs(graph, &mut visited, &mut result, node);
+    let mut result = Vec::new();
+    let mut visited = IndexSet::new();
+    for &v in tree.nodes.keys() {
+        if !visited.contains(&v) {
+            dfs(tree, &mut visited, &mut result, v);
         }
     }
-
     result
 }
 
 /// 找到离开边
-fn leave_edge(graph: &Graph) -> Option<Edge> {
-    for edge in graph.edges() {
-        if let Some(edge_label) = graph.edge_label(&edge) {
-            if let Some(cutvalue) = edge_label.cutvalue {
-                if cutvalue < 0 {
-                    return Some(edge);
-                }
-            }
-        }
-    }
-    None
+///
+/// 对应 JS 函数: leaveEdge() in lib/rank/network-simplex.js
+fn leave_edge(tree: &Tree) -> Option<(NodeIndex, NodeIndex)> {
+    tree.edges
+        .iter()
+        .find(|&(_, &cut_value)| cut_value < 0)
+        .map(|(&edge, _)| edge)
 }
 
 /// 找到进入边
-fn enter_edge(graph: &Graph, edge: &Edge) -> Edge {
-    let v = edge.source;
-    let w = edge.target;
-
+///
+/// 对应 JS 函数: enterEdge() in lib/rank/network-simplex.js
+fn enter_edge(tree: &Tree, graph: &Graph, edge: (NodeIndex, NodeIndex)) -> Option<Edge> {
     // 确保v是尾部，w是头部
-    let (v, w) = if !graph.has_edge(&Edge::new(v, w)) {
-        (w, v)
+    let (v, w) = if graph.has_edge(&Edge::new(edge.0, edge.1)) {
+        edge
     } else {
-        (v, w)
+        (edge.1, edge.0)
     };
 
-    let v_label = graph.node_label(v).unwrap();
-    let w_label = graph.node_label(w).unwrap();
-    let mut tail_label = v_label;
-    let mut flip = false;
-
-    // 如果根在边的尾部，需要翻转逻辑
-    if v_label.lim.unwrap_or(0) > w_label.lim.unwrap_or(0) {
-        tail_label = w_label;
-        flip = true;
-    }
-
-    // 找到候选边
-    let mut candidates = Vec::new();
-    for candidate_edge in graph.edges() {
-        let candidate_v_label = graph.node_label(candidate_edge.source).unwrap();
-        let candidate_w_label = graph.node_label(candidate_edge.target).unwrap();
-
-        let v_is_descendant = is_descendant(graph, candidate_v_label, &tail_label);
-        let w_is_descendant = is_descendant(graph, candidate_w_label, &tail_label);
-
-        if flip == v_is_descendant && flip != w_is_descendant {
-            candidates.push(candidate_edge);
-        }
-    }
+    let v_label = &tree.nodes[&v];
+    let w_label = &tree.nodes[&w];
+    let (tail_label, flip) = if v_label.lim > w_label.lim {
+        (w_label, true)
+    } else {
+        (v_label, false)
+    };
 
-    // 找到最小slack的边
-    candidates
+    graph
+        .edges()
         .into_iter()
-        .min_by(|a, b| {
-            let slack_a = super::util::slack(graph, a);
-            let slack_b = super::util::slack(graph, b);
-            slack_a.partial_cmp(&slack_b).unwrap()
+        .filter(|candidate| {
+            flip == is_descendant(&tree.nodes[&candidate.source], tail_label)
+                && flip != is_descendant(&tree.nodes[&candidate.target], tail_label)
         })
-        .unwrap()
+        .min_by_key(|candidate| super::util::slack(graph, candidate))
 }
 
 /// 检查是否是后代
-fn is_descendant(_graph: &Graph, v_label: &NodeLabel, root_label: &NodeLabel) -> bool {
-    let v_lim = v_label.lim.unwrap_or(0);
-    let root_low = root_label.low.unwrap_or(0);
-    let root_lim = root_label.lim.unwrap_or(0);
-
-    root_low <= v_lim && v_lim <= root_lim
+fn is_descendant(v_label: &TreeNode, root_label: &TreeNode) -> bool {
+    root_label.low <= v_label.lim && v_label.lim <= root_label.lim
 }
 
 /// 交换边
-fn exchange_edges(graph: &mut Graph, e: &Edge, f: &Edge) {
-    // 移除旧边的树标记
-    if let Some(edge_label) = graph.edge_label_mut(e) {
-        edge_label.cutvalue = None;
-    }
-
-    // 添加新边的树标记
-    if let Some(edge_label) = graph.edge_label_mut(f) {
-        edge_label.cutvalue = Some(0);
-    }
-
-    // 重新初始化low/lim值
-    init_low_lim_values(graph);
-
-    // 重新初始化cut值
-    init_cut_values(graph);
-
-    // 更新rank
-    update_ranks(graph);
+///
+/// 对应 JS 函数: exchangeEdges() in lib/rank/network-simplex.js
+fn exchange_edges(tree: &mut Tree, graph: &mut Graph, e: (NodeIndex, NodeIndex), f: &Edge) {
+    tree.remove_edge(e.0, e.1);
+    tree.add_edge(f.source, f.target);
+    init_low_lim_values(tree);
+    init_cut_values(tree, graph);
+    update_ranks(tree, graph);
 }
 
 /// 更新rank
-fn update_ranks(graph: &mut Graph) {
-    // 找到根节点
-    let root = graph
-        .node_indices()
-        .find(|&v| graph.node_label(v).unwrap().parent.is_none())
-        .unwrap();
-
-    // 前序遍历
-    let mut vs = preorder(graph, root);
-    vs.remove(0); // 移除根节点
-
-    for v in vs {
-        let v_label = graph.node_label(v).unwrap();
-        if let Some(parent) = v_label.parent {
-            let mut edge = graph.edge_label(&Edge::new(v, parent));
-            let mut flipped = false;
-
-            if edge.is_none() {
-                edge = graph.edge_label(&Edge::new(parent, v));
-                flipped = true;
-            }
+///
+/// 对应 JS 函数: updateRanks() in lib/rank/network-simplex.js
+fn update_ranks(tree: &Tree, graph: &mut Graph) {
+    // 按前序遍历，子节点的rank由父节点和树边的minlen决定
+    for v in preorder(tree) {
+        let Some(parent) = tree.nodes[&v].parent else {
+            continue;
+        };
 
-            if let (Some(v_label), Some(parent_label), Some(edge_label)) =
-                (graph.node_label(v), graph.node_label(parent), edge)
-            {
-                if let (Some(_v_rank), Some(parent_rank)) = (v_label.rank, parent_label.rank) {
-                    let new_rank = if flipped {
-                        parent_rank + edge_label.minlen
-                    } else {
-                        parent_rank - edge_label.minlen
-                    };
-
-                    if let Some(v_label_mut) = graph.node_label_mut(v) {
-                        v_label_mut.rank = Some(new_rank);
-                    }
-                }
-            }
+        let (minlen, flipped) = match graph.edge_label(&Edge::new(v, parent)) {
+            Some(label) => (label.minlen, false),
+            None => (
+                graph
+                    .edge_label(&Edge::new(parent, v))
+                    .map_or(1, |label| label.minlen),
+                true,
+            ),
+        };
+
+        let parent_rank = graph.node_label(parent).and_then(|l| l.rank).unwrap_or(0);
+        if let Some(label) = graph.node_label_mut(v) {
+            label.rank = Some(if flipped {
+                parent_rank + minlen
+            } else {
+                parent_rank - minlen
+            });
         }
     }
 }
 
-/// 前序遍历
-fn preorder(graph: &Graph, root: NodeIndex) -> Vec<NodeIndex> {
-    let mut result = Vec::new();
-    let mut visited = HashSet::new();
-
+/// 前序遍历生成树
+fn preorder(tree: &Tree) -> Vec<NodeIndex> {
     fn dfs(
-        graph: &Graph,
-        visited: &mut HashSet<NodeIndex>,
+        tree: &Tree,
+        visited: &mut IndexSet<NodeIndex>,
         result: &mut Vec<NodeIndex>,
         v: NodeIndex,
     ) {
         visited.insert(v);
         result.push(v);
-
-        // 遍历所有邻居，只考虑树边
-        for edge in graph.out_edges(v) {
-            let w = edge.target;
-            if !visited.contains(&w) && is_tree_edge(graph, v, w) {
-                dfs(graph, visited, result, w);
+        for &w in &tree.neighbors[&v] {
+            if !visited.contains(&w) {
+                dfs(tree, visited, result, w);
             }
         }
+    }
 
-        for edge in graph.in_edges(v) {
-            let w = edge.source;
-            if !visited.contains(&w) && is_tree_edge(graph, w, v) {
-                dfs(graph, visited, result, w);
-            }
+    let mut result = Vec::new();
+    let mut visited = IndexSet::new();
+    for (&v, node) in &tree.nodes {
+        if node.parent.is_none() && !visited.contains(&v) {
+            dfs(tree, &mut visited, &mut result, v);
         }
     }
-
-    dfs(graph, &mut visited, &mut result, root);
     result
 }
@@ -113,8 +113,8 @@ fn dfs_rank(
         label.rank = Some(rank);
     }
 
-    println!(
-        "  dfs_rank: NodeIndex({:?}) -> rank={}, has_out_edges={}, min_rank={}",
+    log::trace!(
+        "dfs_rank: {:?} -> rank={}, has_out_edges={}, min_rank={}",
         node_id, rank, has_out_edges, min_rank
     );
 
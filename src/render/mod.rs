@@ -0,0 +1,7 @@
+//! 布局结果的渲染
+//!
+//! [`svg`] 把 `layout` 之后的图输出为 SVG。
+
+pub mod svg;
+
+pub use svg::{SvgStyle, render_svg, render_svg_with_style};
@@ -0,0 +1,436 @@
+//! SVG 渲染
+//!
+//! 输出顺序为：集群（复合节点，外层在下）、边、叶子节点、边标签，保证文字不被遮挡。
+//!
+//! - 节点按 [`NodeShape`] 绘制为以 (`x`, `y`) 为中心、外接矩形为 `width` x `height` 的形状，标签居中
+//! - 复合节点绘制为集群矩形，标签在顶部
+//! - 边按 [`PathType`] 绘制路由阶段生成的路径，终点带箭头
+//! - 边标签放在布局计算出的 `x`/`y` 处
+//!
+//! 没有坐标的节点、少于两个点的边以及 `hidden` 的节点和边不会输出。
+
+use crate::graph::{Graph, NodeIndex};
+use crate::route::bezier_control_points;
+use crate::shape::ShapeBoundary;
+use crate::types::{EdgeLabel, NodeLabel, NodeShape, PathType, Point};
+use std::fmt::Write;
+
+/// SVG 样式配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgStyle {
+    /// 节点填充色
+    pub node_fill: String,
+    /// 节点边框色
+    pub node_stroke: String,
+    /// 集群填充色
+    pub cluster_fill: String,
+    /// 集群边框色
+    pub cluster_stroke: String,
+    /// 边的颜色
+    pub edge_stroke: String,
+    /// 线宽
+    pub stroke_width: f64,
+    /// 节点圆角半径
+    pub corner_radius: f64,
+    /// 字体
+    pub font_family: String,
+    /// 字号
+    pub font_size: f64,
+    /// 文字颜色
+    pub text_color: String,
+    /// 箭头长度
+    pub arrow_size: f64,
+    /// 画布四周的留白
+    pub padding: f64,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        Self {
+            node_fill: "#ffffff".to_string(),
+            node_stroke: "#333333".to_string(),
+            cluster_fill: "#f5f5f5".to_string(),
+            cluster_stroke: "#999999".to_string(),
+            edge_stroke: "#333333".to_string(),
+            stroke_width: 1.5,
+            corner_radius: 0.0,
+            font_family: "sans-serif".to_string(),
+            font_size: 14.0,
+            text_color: "#000000".to_string(),
+            arrow_size: 8.0,
+            padding: 10.0,
+        }
+    }
+}
+
+/// 使用默认样式渲染
+pub fn render_svg(graph: &Graph) -> String {
+    render_svg_with_style(graph, &SvgStyle::default())
+}
+
+/// 使用指定样式渲染
+pub fn render_svg_with_style(graph: &Graph, style: &SvgStyle) -> String {
+    let (min, max) = bounds(graph);
+    let width = max.x - min.x + 2.0 * style.padding;
+    let height = max.y - min.y + 2.0 * style.padding;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+        num(width),
+        num(height),
+        num(min.x - style.padding),
+        num(min.y - style.padding),
+        num(width),
+        num(height)
+    );
+    let _ = writeln!(
+        svg,
+        r#"<defs><marker id="dagviz-arrowhead" viewBox="0 0 10 10" refX="10" refY="5" markerUnits="userSpaceOnUse" markerWidth="{}" markerHeight="{}" orient="auto"><path d="M 0 0 L 10 5 L 0 10 z" fill="{}"/></marker></defs>"#,
+        num(style.arrow_size),
+        num(style.arrow_size),
+        escape(&style.edge_stroke)
+    );
+    let _ = writeln!(
+        svg,
+        r#"<g font-family="{}" font-size="{}" fill="{}">"#,
+        escape(&style.font_family),
+        num(style.font_size),
+        escape(&style.text_color)
+    );
+
+    write_clusters(&mut svg, graph, style);
+    write_edges(&mut svg, graph, style);
+    write_nodes(&mut svg, graph, style);
+    write_edge_labels(&mut svg, graph, style);
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+/// 节点的外接矩形，没有坐标或被隐藏时返回 None
+fn node_rect(graph: &Graph, node: NodeIndex) -> Option<(f64, f64, f64, f64)> {
+    let label = graph.node_label(node)?;
+    if label.hidden {
+        return None;
+    }
+    let (x, y) = (label.x?, label.y?);
+    Some((
+        x - label.width / 2.0,
+        y - label.height / 2.0,
+        label.width,
+        label.height,
+    ))
+}
+
+/// 所有可见元素的包围盒
+fn bounds(graph: &Graph) -> (Point, Point) {
+    let mut min = Point::new(f64::INFINITY, f64::INFINITY);
+    let mut max = Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY);
+    let mut extend = |x: f64, y: f64| {
+        min.x = min.x.min(x);
+        min.y = min.y.min(y);
+        max.x = max.x.max(x);
+        max.y = max.y.max(y);
+    };
+
+    for node in graph.node_indices() {
+        if let Some((x, y, width, height)) = node_rect(graph, node) {
+            extend(x, y);
+            extend(x + width, y + height);
+        }
+    }
+    for edge in graph.edges() {
+        let Some(label) = graph.edge_label(&edge) else {
+            continue;
+        };
+        for point in &label.points {
+            extend(point.x, point.y);
+        }
+        if let (Some(x), Some(y)) = (label.x, label.y) {
+            extend(x - label.width / 2.0, y - label.height / 2.0);
+            extend(x + label.width / 2.0, y + label.height / 2.0);
+        }
+    }
+
+    // 图的尺寸由布局给出时，画布至少覆盖 (0, 0) - (width, height)
+    if let (Some(width), Some(height)) = (graph.config().width, graph.config().height) {
+        extend(0.0, 0.0);
+        extend(width, height);
+    }
+
+    if min.x > max.x {
+        (Point::new(0.0, 0.0), Point::new(0.0, 0.0))
+    } else {
+        (min, max)
+    }
+}
+
+fn depth(graph: &Graph, mut node: NodeIndex) -> usize {
+    let mut depth = 0;
+    while let Some(parent) = graph.parent(node) {
+        depth += 1;
+        node = parent;
+    }
+    depth
+}
+
+fn write_clusters(svg: &mut String, graph: &Graph, style: &SvgStyle) {
+    let mut clusters: Vec<NodeIndex> = graph
+        .node_indices()
+        .filter(|&node| !graph.children(node).is_empty())
+        .collect();
+    // 外层集群先画，内层覆盖在上面
+    clusters.sort_by_key(|&node| depth(graph, node));
+
+    for node in clusters {
+        let Some((x, y, width, height)) = node_rect(graph, node) else {
+            continue;
+        };
+        let _ = writeln!(
+            svg,
+            r#"<g class="cluster"><rect x="{}" y="{}" width="{}" height="{}" rx="{}" fill="{}" stroke="{}" stroke-width="{}"/>"#,
+            num(x),
+            num(y),
+            num(width),
+            num(height),
+            num(style.corner_radius),
+            escape(&style.cluster_fill),
+            escape(&style.cluster_stroke),
+            num(style.stroke_width)
+        );
+        if let Some(text) = graph.node_label(node).and_then(|l| l.label.as_deref()) {
+            write_text(svg, x + width / 2.0, y + style.font_size, text, style);
+        }
+        svg.push_str("</g>\n");
+    }
+}
+
+fn write_nodes(svg: &mut String, graph: &Graph, style: &SvgStyle) {
+    for node in graph.node_indices() {
+        if !graph.children(node).is_empty() {
+            continue;
+        }
+        let (Some((x, y, width, height)), Some(label)) =
+            (node_rect(graph, node), graph.node_label(node))
+        else {
+            continue;
+        };
+        let _ = writeln!(
+            svg,
+            r#"<g class="node">{} fill="{}" stroke="{}" stroke-width="{}"/>"#,
+            shape_element(label, (x, y, width, height), style),
+            escape(&style.node_fill),
+            escape(&style.node_stroke),
+            num(style.stroke_width)
+        );
+        if let Some(text) = graph.node_label(node).and_then(|l| l.label.as_deref()) {
+            write_text(svg, x + width / 2.0, y + height / 2.0, text, style);
+        }
+        svg.push_str("</g>\n");
+    }
+}
+
+/// 节点形状对应的 SVG 元素，不含样式属性和结尾
+fn shape_element(
+    label: &NodeLabel,
+    (x, y, width, height): (f64, f64, f64, f64),
+    style: &SvgStyle,
+) -> String {
+    let (cx, cy) = (x + width / 2.0, y + height / 2.0);
+    let rect = |radius: f64| {
+        format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}""#,
+            num(x),
+            num(y),
+            num(width),
+            num(height),
+            num(radius)
+        )
+    };
+    match &label.shape {
+        NodeShape::Rect => rect(style.corner_radius),
+        NodeShape::RoundedRect { radius } => rect(radius.clamp(0.0, width.min(height) / 2.0)),
+        NodeShape::Ellipse => format!(
+            r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}""#,
+            num(cx),
+            num(cy),
+            num(width / 2.0),
+            num(height / 2.0)
+        ),
+        NodeShape::Circle => format!(
+            r#"<circle cx="{}" cy="{}" r="{}""#,
+            num(cx),
+            num(cy),
+            num(width.min(height) / 2.0)
+        ),
+        shape => {
+            let points: Vec<String> = shape
+                .outline(label)
+                .iter()
+                .map(|p| format!("{},{}", num(p.x), num(p.y)))
+                .collect();
+            format!(r#"<polygon points="{}""#, points.join(" "))
+        }
+    }
+}
+
+fn write_edges(svg: &mut String, graph: &Graph, style: &SvgStyle) {
+    for edge in graph.edges() {
+        let Some(label) = graph.edge_label(&edge) else {
+            continue;
+        };
+        if label.hidden || label.points.len() < 2 {
+            continue;
+        }
+        let _ = writeln!(
+            svg,
+            r#"<path class="edge" d="{}" fill="none" stroke="{}" stroke-width="{}" marker-end="url(#dagviz-arrowhead)"/>"#,
+            edge_path(label),
+            escape(&style.edge_stroke),
+            num(style.stroke_width)
+        );
+    }
+}
+
+fn write_edge_labels(svg: &mut String, graph: &Graph, style: &SvgStyle) {
+    for edge in graph.edges() {
+        let Some(label) = graph.edge_label(&edge) else {
+            continue;
+        };
+        if label.hidden {
+            continue;
+        }
+        if let (Some(text), Some(x), Some(y)) = (label.label.as_deref(), label.x, label.y) {
+            svg.push_str(r#"<g class="edge-label">"#);
+            write_text(svg, x, y, text, style);
+            svg.push_str("</g>\n");
+        }
+    }
+}
+
+/// 以 (x, y) 为中心写出文字，`\n` 分隔多行
+fn write_text(svg: &mut String, x: f64, y: f64, text: &str, style: &SvgStyle) {
+    let lines: Vec<&str> = text.split('\n').collect();
+    // 多行文字整体垂直居中
+    let first_y = y - (lines.len() as f64 - 1.0) * style.font_size / 2.0;
+    let _ = write!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="central">"#,
+        num(x),
+        num(first_y)
+    );
+    if lines.len() == 1 {
+        svg.push_str(&escape(text));
+    } else {
+        for (i, line) in lines.iter().enumerate() {
+            let _ = write!(
+                svg,
+                r#"<tspan x="{}" dy="{}">{}</tspan>"#,
+                num(x),
+                num(if i == 0 { 0.0 } else { style.font_size }),
+                escape(line)
+            );
+        }
+    }
+    svg.push_str("</text>");
+}
+
+/// 把边的路由结果转换为 SVG 路径数据
+///
+/// - `Line`: 首尾两点之间的直线
+/// - `Polyline`、`Orthogonal`: 依次连接所有点，正交路径的转折点已由路由阶段生成
+/// - `Bezier`: 使用 `control_points` 中的三次贝塞尔曲线，没有控制点时按 `points` 现场生成
+pub fn edge_path(label: &EdgeLabel) -> String {
+    let points = &label.points;
+    let mut d = String::new();
+    let Some(first) = points.first() else {
+        return d;
+    };
+    let _ = write!(d, "M {} {}", num(first.x), num(first.y));
+
+    match label.path_type {
+        PathType::Line => {
+            if let Some(last) = points.last() {
+                line_to(&mut d, *last);
+            }
+        }
+        PathType::Polyline | PathType::Orthogonal => {
+            for &point in &points[1..] {
+                line_to(&mut d, point);
+            }
+        }
+        PathType::Bezier => {
+            let generated;
+            let control_points = if label.control_points.len() % 3 == 1 {
+                &label.control_points
+            } else {
+                generated = bezier_control_points(points);
+                &generated
+            };
+            for segment in control_points[1..].chunks(3) {
+                let _ = write!(
+                    d,
+                    " C {} {} {} {} {} {}",
+                    num(segment[0].x),
+                    num(segment[0].y),
+                    num(segment[1].x),
+                    num(segment[1].y),
+                    num(segment[2].x),
+                    num(segment[2].y)
+                );
+            }
+        }
+    }
+    d
+}
+
+fn line_to(d: &mut String, point: Point) {
+    let _ = write!(d, " L {} {}", num(point.x), num(point.y));
+}
+
+/// 格式化数值，最多保留两位小数
+fn num(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// 转义 XML 特殊字符
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_num_trims_zeros() {
+        assert_eq!(num(10.0), "10");
+        assert_eq!(num(2.5), "2.5");
+        assert_eq!(num(1.234), "1.23");
+        assert_eq!(num(-0.001), "0");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"<a & "b">"#), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
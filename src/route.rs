@@ -0,0 +1,297 @@
+//! 边路由模块
+//!
+//! 在 `assign_node_intersects` 之后运行，此时 `points` 以源节点和目标节点边界上的交点开头和结尾，
+//! 中间是虚拟节点留下的折点。按每条边的 [`PathType`] 生成最终几何：
+//!
+//! - `Line`: 只保留两端，端点为两个节点中心连线与形状边界的交点
+//! - `Polyline`: 保留所有折点
+//! - `Bezier`: 保留所有折点，并在 `control_points` 中生成经过这些点的三次贝塞尔曲线
+//! - `Orthogonal`: 从节点在排名方向上朝向对方的边界出发，只在排名之间的空隙里横向转折，
+//!   因此不会穿过其他节点
+//!
+//! 自环保留 `position_self_edges` 生成的折点，`Orthogonal` 的自环画成矩形。
+//!
+//! 连接端口的一端总是落在端口上。正交路径从位于节点侧面的端口出发时，先沿端口的外法线
+//! 走出 `edge_sep` 的距离再转折，不会贴着节点的边界走。
+
+use crate::graph::Graph;
+use crate::port::{find_port, port_point, side_normal};
+use crate::shape::intersect_node;
+use crate::types::{EdgeLabel, NodeLabel, PathType, Point, RankDirection};
+
+/// 按路径类型生成每条边的几何
+pub fn route_edges(graph: &mut Graph) {
+    let vertical = matches!(
+        graph.config().rankdir,
+        RankDirection::TopBottom | RankDirection::BottomTop
+    );
+    let bands = rank_bands(graph, vertical);
+    let stub = graph.config().edge_sep;
+
+    for edge in graph.edges() {
+        let (Some(source), Some(target), Some(label)) = (
+            graph.node_label(edge.source),
+            graph.node_label(edge.target),
+            graph.edge_label(&edge),
+        ) else {
+            continue;
+        };
+        if label.points.len() < 2 {
+            continue;
+        }
+
+        let path_type = label.path_type;
+        let (points, control_points) = match path_type {
+            _ if edge.source == edge.target => self_loop_points(&label.points, path_type, vertical),
+            PathType::Line => (straight_points(source, target, label), Vec::new()),
+            PathType::Polyline => (label.points.clone(), Vec::new()),
+            PathType::Bezier => (label.points.clone(), bezier_control_points(&label.points)),
+            PathType::Orthogonal => {
+                let ends = OrthogonalEnds {
+                    source: port_anchor(source, label.source_port.as_deref()),
+                    target: port_anchor(target, label.target_port.as_deref()),
+                    stub,
+                };
+                let points =
+                    orthogonal_points(source, target, &label.points, &bands, vertical, &ends);
+                (points, Vec::new())
+            }
+        };
+
+        if let Some(label) = graph.edge_label_mut(&edge) {
+            label.points = points;
+            label.control_points = control_points;
+        }
+    }
+}
+
+/// 两个节点中心连线与各自边界的交点，连接端口的一端使用端口的位置
+fn straight_points(source: &NodeLabel, target: &NodeLabel, label: &EdgeLabel) -> Vec<Point> {
+    let center = |node: &NodeLabel| Point::new(node.x.unwrap_or(0.0), node.y.unwrap_or(0.0));
+    let source_port = port_point(source, label.source_port.as_deref());
+    let target_port = port_point(target, label.target_port.as_deref());
+    vec![
+        source_port
+            .unwrap_or_else(|| intersect_node(source, &target_port.unwrap_or(center(target)))),
+        target_port
+            .unwrap_or_else(|| intersect_node(target, &source_port.unwrap_or(center(source)))),
+    ]
+}
+
+/// 自环的几何
+///
+/// 环不能画成直线，`Line` 按 `Polyline` 处理；`Orthogonal` 从两端沿原来的方向走到环的最远点，
+/// 形成一个矩形的环。
+fn self_loop_points(
+    points: &[Point],
+    path_type: PathType,
+    vertical: bool,
+) -> (Vec<Point>, Vec<Point>) {
+    match path_type {
+        PathType::Line | PathType::Polyline => (points.to_vec(), Vec::new()),
+        PathType::Bezier => (points.to_vec(), bezier_control_points(points)),
+        PathType::Orthogonal => {
+            let (start, end) = (points[0], points[points.len() - 1]);
+            // 最远点在排名内方向上离节点最远
+            let tip = points[points.len() / 2];
+            let route = vec![
+                start,
+                point_at(along(start, vertical), across(tip, vertical), vertical),
+                point_at(along(end, vertical), across(tip, vertical), vertical),
+                end,
+            ];
+            (route, Vec::new())
+        }
+    }
+}
+
+/// 边在端口一端的端点和端口所在边的外法线
+fn port_anchor(node: &NodeLabel, id: Option<&str>) -> Option<(Point, Point)> {
+    let port = find_port(node, id?)?;
+    Some((port_point(node, id)?, side_normal(port.side)))
+}
+
+/// 正交路径两端的端口
+struct OrthogonalEnds {
+    source: Option<(Point, Point)>,
+    target: Option<(Point, Point)>,
+    /// 从侧面端口出发时沿外法线走出的距离
+    stub: f64,
+}
+
+/// 生成依次经过所有点的三次贝塞尔曲线
+///
+/// 返回 `[p0, c1, c2, p1, c1, c2, p2, ...]`，共 `3 * (n - 1) + 1` 个点。
+/// 控制点按 Catmull-Rom 样条计算，曲线在每个折点处的切线方向与前后两点的连线平行。
+pub fn bezier_control_points(points: &[Point]) -> Vec<Point> {
+    if points.len() < 2 {
+        return points.to_vec();
+    }
+
+    let last = points.len() - 1;
+    let mut result = Vec::with_capacity(3 * last + 1);
+    result.push(points[0]);
+    for i in 0..last {
+        let prev = points[i.saturating_sub(1)];
+        let from = points[i];
+        let to = points[i + 1];
+        let next = points[(i + 2).min(last)];
+
+        result.push(Point::new(
+            from.x + (to.x - prev.x) / 6.0,
+            from.y + (to.y - prev.y) / 6.0,
+        ));
+        result.push(Point::new(
+            to.x - (next.x - from.x) / 6.0,
+            to.y - (next.y - from.y) / 6.0,
+        ));
+        result.push(to);
+    }
+    result
+}
+
+/// 点在排名方向上的坐标
+fn along(point: Point, vertical: bool) -> f64 {
+    if vertical { point.y } else { point.x }
+}
+
+/// 点在排名内方向上的坐标
+fn across(point: Point, vertical: bool) -> f64 {
+    if vertical { point.x } else { point.y }
+}
+
+fn point_at(along: f64, across: f64, vertical: bool) -> Point {
+    if vertical {
+        Point::new(across, along)
+    } else {
+        Point::new(along, across)
+    }
+}
+
+/// 节点在排名方向上占据的区间，合并重叠部分后按起点排序
+///
+/// 同一排名的节点中心对齐，所以每个区间就是一个排名所在的带，区间之间是排名间距留出的空隙。
+fn rank_bands(graph: &Graph, vertical: bool) -> Vec<(f64, f64)> {
+    let mut intervals: Vec<(f64, f64)> = graph
+        .node_indices()
+        .filter(|&node| graph.children(node).is_empty())
+        .filter_map(|node| {
+            let label = graph.node_label(node)?;
+            if label.dummy.is_some() {
+                return None;
+            }
+            let center = Point::new(label.x?, label.y?);
+            let half = if vertical { label.height } else { label.width } / 2.0;
+            let c = along(center, vertical);
+            Some((c - half, c + half))
+        })
+        .collect();
+    intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut bands: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in intervals {
+        match bands.last_mut() {
+            Some(band) if start <= band.1 => band.1 = band.1.max(end),
+            _ => bands.push((start, end)),
+        }
+    }
+    bands
+}
+
+/// 在 `from` 和 `to` 之间找一个不被任何排名带覆盖的位置，取最宽空隙的中点
+fn free_along(from: f64, to: f64, bands: &[(f64, f64)]) -> f64 {
+    let (lo, hi) = if from < to { (from, to) } else { (to, from) };
+    let mut best: Option<(f64, f64)> = None;
+    let mut cursor = lo;
+    for &(start, end) in bands {
+        if end <= cursor {
+            continue;
+        }
+        if start >= hi {
+            break;
+        }
+        if start > cursor && best.is_none_or(|(a, b)| start - cursor > b - a) {
+            best = Some((cursor, start));
+        }
+        cursor = cursor.max(end);
+    }
+    if cursor < hi && best.is_none_or(|(a, b)| hi - cursor > b - a) {
+        best = Some((cursor, hi));
+    }
+    let (a, b) = best.unwrap_or((lo, hi));
+    (a + b) / 2.0
+}
+
+/// 生成正交路径
+///
+/// `points` 的首尾是边界交点，中间是折点。首尾替换为从节点中心沿排名方向朝向对方
+/// 与形状边界的交点（矩形即边中点，连接端口时为端口位置），相邻两点之间先沿排名方向
+/// 走到空隙中，横向移动后再沿排名方向到达下一个点。
+fn orthogonal_points(
+    source: &NodeLabel,
+    target: &NodeLabel,
+    points: &[Point],
+    bands: &[(f64, f64)],
+    vertical: bool,
+    ends: &OrthogonalEnds,
+) -> Vec<Point> {
+    let center = |node: &NodeLabel| Point::new(node.x.unwrap_or(0.0), node.y.unwrap_or(0.0));
+    // 从节点中心沿排名方向出发，与形状边界的交点
+    let facing = |node: &NodeLabel, sign: f64| {
+        let c = center(node);
+        let reach = node.width + node.height + 1.0;
+        let far = point_at(
+            along(c, vertical) + sign * reach,
+            across(c, vertical),
+            vertical,
+        );
+        intersect_node(node, &far)
+    };
+    let (source_center, target_center) = (center(source), center(target));
+    let direction = (along(target_center, vertical) - along(source_center, vertical)).signum();
+
+    let bends = &points[1..points.len() - 1];
+    let (start, end) = if direction == 0.0 {
+        // 同一排名的两个节点，保留边界交点
+        (points[0], points[points.len() - 1])
+    } else {
+        (facing(source, direction), facing(target, -direction))
+    };
+
+    let start = ends.source.map_or(start, |(point, _)| point);
+    let end = ends.target.map_or(end, |(point, _)| point);
+
+    // 侧面端口的法线沿排名内方向，先沿法线离开节点
+    let stub = |(point, normal): (Point, Point)| {
+        (across(normal, vertical) != 0.0).then(|| {
+            Point::new(
+                point.x + normal.x * ends.stub,
+                point.y + normal.y * ends.stub,
+            )
+        })
+    };
+    let source_stub = ends.source.and_then(stub);
+    let target_stub = ends.target.and_then(stub);
+
+    let mut route = vec![start];
+    route.extend(source_stub);
+    let targets = bends
+        .iter()
+        .copied()
+        .chain(target_stub)
+        .chain(std::iter::once(end));
+    for next in targets {
+        let current = *route.last().unwrap();
+        let (a0, a1) = (along(current, vertical), along(next, vertical));
+        let (c0, c1) = (across(current, vertical), across(next, vertical));
+        if a0 != a1 && c0 != c1 {
+            let turn = free_along(a0, a1, bands);
+            route.push(point_at(turn, c0, vertical));
+            route.push(point_at(turn, c1, vertical));
+        }
+        if route.last() != Some(&next) {
+            route.push(next);
+        }
+    }
+    route
+}
@@ -0,0 +1,248 @@
+//! 节点形状
+//!
+//! `NodeLabel::shape` 决定边的端点在节点边界上的位置。`width`、`height` 始终是形状的外接矩形，
+//! 布局按外接矩形排列节点，只有裁剪边的端点（`assign_node_intersects`、`route_edges`）、
+//! 端口的位置和 SVG 渲染使用实际形状。
+//!
+//! 自定义形状实现 [`ShapeBoundary`]，通过 [`NodeShape::custom`] 设置到节点上：
+//!
+//! ```
+//! use dagviz::shape::ShapeBoundary;
+//! use dagviz::types::{NodeLabel, NodeShape, Point};
+//!
+//! /// 只有下半部分的半圆
+//! struct HalfDisc;
+//!
+//! impl ShapeBoundary for HalfDisc {
+//!     fn intersect(&self, node: &NodeLabel, point: &Point) -> Point {
+//!         let center = Point::new(node.x.unwrap_or(0.0), node.y.unwrap_or(0.0));
+//!         if point.y <= center.y {
+//!             return dagviz::util::intersect_rect(node, point);
+//!         }
+//!         NodeShape::Ellipse.intersect(node, point)
+//!     }
+//! }
+//!
+//! let label = NodeLabel {
+//!     shape: NodeShape::custom(HalfDisc),
+//!     ..Default::default()
+//! };
+//! ```
+
+use crate::types::{NodeLabel, NodeShape, Point};
+use crate::util::intersect_rect;
+use std::fmt;
+use std::sync::Arc;
+
+/// `ShapeBoundary::outline` 默认实现的采样数
+const OUTLINE_SAMPLES: usize = 32;
+
+/// 形状的边界
+pub trait ShapeBoundary: Send + Sync {
+    /// 从节点中心指向 `point` 的射线与边界的交点
+    ///
+    /// 节点中心为 (`x`, `y`)，外接矩形为 `width` x `height`，坐标与布局结果使用同一坐标系。
+    /// `point` 与中心重合时返回中心。
+    fn intersect(&self, node: &NodeLabel, point: &Point) -> Point;
+
+    /// 边界的多边形近似，按顺时针排列，用于渲染
+    ///
+    /// 默认沿均匀分布的方向对 [`intersect`](Self::intersect) 采样。
+    fn outline(&self, node: &NodeLabel) -> Vec<Point> {
+        let (x, y) = (node.x.unwrap_or(0.0), node.y.unwrap_or(0.0));
+        let reach = node.width + node.height + 1.0;
+        (0..OUTLINE_SAMPLES)
+            .map(|i| {
+                let angle = i as f64 / OUTLINE_SAMPLES as f64 * std::f64::consts::TAU;
+                let far = Point::new(x + reach * angle.cos(), y + reach * angle.sin());
+                self.intersect(node, &far)
+            })
+            .collect()
+    }
+}
+
+/// 用户定义的形状
+///
+/// 按引用比较，克隆时共享同一个实现。
+#[derive(Clone)]
+pub struct CustomShape(pub Arc<dyn ShapeBoundary>);
+
+impl fmt::Debug for CustomShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomShape")
+    }
+}
+
+impl PartialEq for CustomShape {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl NodeShape {
+    /// 使用自定义形状
+    pub fn custom(shape: impl ShapeBoundary + 'static) -> Self {
+        NodeShape::Custom(CustomShape(Arc::new(shape)))
+    }
+
+    /// 多边形形状的顶点，相对节点中心，按顺时针排列；其他形状返回 `None`
+    pub fn vertices(&self, node: &NodeLabel) -> Option<Vec<Point>> {
+        let (x, y) = (node.x.unwrap_or(0.0), node.y.unwrap_or(0.0));
+        let (w, h) = (node.width, node.height);
+        let unit: Vec<(f64, f64)> = match self {
+            NodeShape::Diamond => vec![(0.0, -0.5), (0.5, 0.0), (0.0, 0.5), (-0.5, 0.0)],
+            NodeShape::Hexagon => vec![
+                (-0.25, -0.5),
+                (0.25, -0.5),
+                (0.5, 0.0),
+                (0.25, 0.5),
+                (-0.25, 0.5),
+                (-0.5, 0.0),
+            ],
+            NodeShape::Polygon { points } => points.iter().map(|p| (p.x, p.y)).collect(),
+            _ => return None,
+        };
+        Some(
+            unit.into_iter()
+                .map(|(ux, uy)| Point::new(x + ux * w, y + uy * h))
+                .collect(),
+        )
+    }
+}
+
+impl ShapeBoundary for NodeShape {
+    fn intersect(&self, node: &NodeLabel, point: &Point) -> Point {
+        let center = Point::new(node.x.unwrap_or(0.0), node.y.unwrap_or(0.0));
+        let (dx, dy) = (point.x - center.x, point.y - center.y);
+        if dx == 0.0 && dy == 0.0 {
+            return center;
+        }
+        let (a, b) = (node.width / 2.0, node.height / 2.0);
+
+        let scale = match self {
+            NodeShape::Rect => return intersect_rect(node, point),
+            NodeShape::RoundedRect { radius } => {
+                return intersect_rounded_rect(node, point, *radius);
+            }
+            NodeShape::Ellipse => ellipse_scale(dx, dy, a, b),
+            NodeShape::Circle => {
+                let r = a.min(b);
+                ellipse_scale(dx, dy, r, r)
+            }
+            NodeShape::Diamond => {
+                let extent = dx.abs() / a + dy.abs() / b;
+                if extent.is_finite() {
+                    1.0 / extent
+                } else {
+                    0.0
+                }
+            }
+            NodeShape::Hexagon | NodeShape::Polygon { .. } => {
+                let vertices = self.vertices(node).unwrap_or_default();
+                match polygon_scale(center, dx, dy, &vertices) {
+                    Some(scale) => scale,
+                    None => return intersect_rect(node, point),
+                }
+            }
+            NodeShape::Custom(shape) => return shape.0.intersect(node, point),
+        };
+        Point::new(center.x + dx * scale, center.y + dy * scale)
+    }
+
+    fn outline(&self, node: &NodeLabel) -> Vec<Point> {
+        match self {
+            NodeShape::Custom(shape) => shape.0.outline(node),
+            NodeShape::Rect => {
+                let (x, y) = (node.x.unwrap_or(0.0), node.y.unwrap_or(0.0));
+                let (a, b) = (node.width / 2.0, node.height / 2.0);
+                vec![
+                    Point::new(x - a, y - b),
+                    Point::new(x + a, y - b),
+                    Point::new(x + a, y + b),
+                    Point::new(x - a, y + b),
+                ]
+            }
+            _ => match self.vertices(node) {
+                Some(vertices) => vertices,
+                None => {
+                    // 曲线形状按默认方式采样
+                    struct Sampled<'a>(&'a NodeShape);
+                    impl ShapeBoundary for Sampled<'_> {
+                        fn intersect(&self, node: &NodeLabel, point: &Point) -> Point {
+                            self.0.intersect(node, point)
+                        }
+                    }
+                    Sampled(self).outline(node)
+                }
+            },
+        }
+    }
+}
+
+/// 从节点中心指向 `point` 的射线与节点形状边界的交点
+pub fn intersect_node(node: &NodeLabel, point: &Point) -> Point {
+    node.shape.intersect(node, point)
+}
+
+/// 射线方向 (`dx`, `dy`) 到达半轴为 `a`、`b` 的椭圆所需的倍数
+fn ellipse_scale(dx: f64, dy: f64, a: f64, b: f64) -> f64 {
+    let extent = ((dx / a).powi(2) + (dy / b).powi(2)).sqrt();
+    if extent.is_finite() {
+        1.0 / extent
+    } else {
+        0.0
+    }
+}
+
+/// 射线方向 (`dx`, `dy`) 到达多边形边界所需的倍数
+///
+/// 取射线与各边交点中最远的一个，即从外部沿射线进入多边形时遇到的第一条边。
+/// 射线与所有边都不相交（中心在多边形外）时返回 `None`。
+fn polygon_scale(center: Point, dx: f64, dy: f64, vertices: &[Point]) -> Option<f64> {
+    let mut best: Option<f64> = None;
+    for i in 0..vertices.len() {
+        let p = vertices[i];
+        let q = vertices[(i + 1) % vertices.len()];
+        let (ex, ey) = (q.x - p.x, q.y - p.y);
+        let denom = dx * ey - dy * ex;
+        if denom == 0.0 {
+            continue;
+        }
+        let (px, py) = (p.x - center.x, p.y - center.y);
+        // center + t * d = p + s * e
+        let t = (px * ey - py * ex) / denom;
+        let s = (px * dy - py * dx) / denom;
+        if t > 0.0 && (0.0..=1.0).contains(&s) {
+            best = Some(best.map_or(t, |best| best.max(t)));
+        }
+    }
+    best
+}
+
+/// 圆角矩形：交点落在圆角区域时改为与圆角所在的圆求交
+fn intersect_rounded_rect(node: &NodeLabel, point: &Point, radius: f64) -> Point {
+    let hit = intersect_rect(node, point);
+    let (x, y) = (node.x.unwrap_or(0.0), node.y.unwrap_or(0.0));
+    let (a, b) = (node.width / 2.0, node.height / 2.0);
+    let r = radius.clamp(0.0, a.min(b));
+    let (inner_a, inner_b) = (a - r, b - r);
+    if r == 0.0 || (hit.x - x).abs() <= inner_a || (hit.y - y).abs() <= inner_b {
+        return hit;
+    }
+
+    // 圆角的圆心，与交点在同一象限
+    let cx = x + inner_a.copysign(hit.x - x);
+    let cy = y + inner_b.copysign(hit.y - y);
+    let (dx, dy) = (point.x - x, point.y - y);
+    // |(x, y) + t * d - c| = r，取较大的根
+    let (ox, oy) = (x - cx, y - cy);
+    let qa = dx * dx + dy * dy;
+    let qb = 2.0 * (ox * dx + oy * dy);
+    let qc = ox * ox + oy * oy - r * r;
+    let disc = qb * qb - 4.0 * qa * qc;
+    if disc < 0.0 {
+        return hit;
+    }
+    let t = (-qb + disc.sqrt()) / (2.0 * qa);
+    Point::new(x + dx * t, y + dy * t)
+}
@@ -0,0 +1,109 @@
+//! 布局统计
+//!
+//! 每次布局的统计信息保存在布局上下文 [`LayoutContext`](crate::context::LayoutContext) 中，
+//! `layout` 结束时返回给调用方。
+//! 统计属于单次布局，多个线程同时布局不同的图时互不影响。
+
+use crate::budget::BudgetKind;
+use crate::error::LayoutError;
+use crate::graph::NodeIndex;
+use std::time::Duration;
+
+/// 单个布局阶段的耗时
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageTiming {
+    /// 阶段名，与日志目标 `dagviz::stage::<name>` 中的名称一致
+    pub name: String,
+    /// 墙钟耗时
+    pub elapsed: Duration,
+}
+
+/// 一次布局的统计信息
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayoutStats {
+    /// 各阶段耗时，按结束顺序排列
+    ///
+    /// 阶段可以嵌套，例如 `run_layout` 包含其余的排名、排序和定位阶段，
+    /// 它在所有子阶段之后结束。
+    pub stages: Vec<StageTiming>,
+    /// 排序阶段的扫描次数
+    pub order_sweeps: usize,
+    /// 每次扫描之后的交叉数
+    pub crossings: Vec<usize>,
+    /// 网络单纯形的换边次数
+    pub network_simplex_pivots: usize,
+    /// 插入的虚拟节点数，包括长边、边标签、代理、边框和嵌套根节点
+    pub dummy_nodes: usize,
+    /// 为消除环而反转的边数
+    pub reversed_edges: usize,
+    /// 用完的预算，按首次用完的顺序排列
+    pub budgets_exceeded: Vec<BudgetKind>,
+    /// 布局是否被取消，取消时输入图没有被修改
+    pub cancelled: bool,
+    /// 增量布局时节点相对上一次布局的位移，没有提示时为 `None`
+    pub displacement: Option<Displacement>,
+    /// 没有落在目标坐标上的固定节点，即输入图中的节点
+    ///
+    /// 固定坐标之间或与边的方向矛盾时无法同时满足，这些节点保留布局算出的坐标。
+    pub pin_conflicts: Vec<NodeIndex>,
+    /// 排名约束无法满足时被忽略的约束的冲突，节点和边属于输入图
+    ///
+    /// `try_layout` 在这种情况下返回同样的错误；`layout` 只按边排名并在这里记录冲突。
+    pub rank_constraint_conflict: Option<LayoutError>,
+}
+
+/// 增量布局中节点相对上一次布局的位移
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Displacement {
+    /// 有提示且仍在图中的节点数
+    pub nodes: usize,
+    /// 坐标发生变化的节点数
+    pub moved: usize,
+    /// 位移之和
+    pub total: f64,
+    /// 最大位移
+    pub max: f64,
+}
+
+impl Displacement {
+    /// 平均位移，没有节点时为 0
+    pub fn mean(&self) -> f64 {
+        if self.nodes == 0 {
+            0.0
+        } else {
+            self.total / self.nodes as f64
+        }
+    }
+}
+
+impl LayoutStats {
+    /// 记录一个阶段的耗时
+    pub fn record_stage(&mut self, name: &str, elapsed: Duration) {
+        self.stages.push(StageTiming {
+            name: name.to_string(),
+            elapsed,
+        });
+    }
+
+    /// 指定阶段的耗时，阶段运行多次时取总和
+    pub fn stage_time(&self, name: &str) -> Option<Duration> {
+        self.stages
+            .iter()
+            .filter(|stage| stage.name == name)
+            .map(|stage| stage.elapsed)
+            .reduce(|a, b| a + b)
+    }
+
+    /// 记录用完的预算，同一种预算只记录一次
+    pub fn exceed_budget(&mut self, kind: BudgetKind) {
+        if !self.budgets_exceeded.contains(&kind) {
+            log::warn!("layout budget exceeded: {:?}", kind);
+            self.budgets_exceeded.push(kind);
+        }
+    }
+
+    /// 最后一次扫描之后的交叉数
+    pub fn final_crossings(&self) -> Option<usize> {
+        self.crossings.last().copied()
+    }
+}
@@ -2,19 +2,40 @@
 //!
 //! 这个模块定义了 dagviz 中使用的核心类型，包括节点、边、配置等。
 
+use crate::budget::{CancellationToken, LayoutBudget};
 use crate::graph::NodeIndex;
+use crate::incremental::LayoutHints;
+use crate::stats::LayoutStats;
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 
 /// 边类型
+///
+/// 边由源节点、目标节点和可选的名称共同标识，同一对节点之间可以存在多条名称不同的边。
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Edge {
     pub source: NodeIndex,
     pub target: NodeIndex,
+    /// 边的名称，未命名的边为 `None`
+    pub name: Option<String>,
 }
 
 impl Edge {
     pub fn new(source: NodeIndex, target: NodeIndex) -> Self {
-        Edge { source, target }
+        Edge {
+            source,
+            target,
+            name: None,
+        }
+    }
+
+    /// 创建命名边
+    pub fn with_name(source: NodeIndex, target: NodeIndex, name: impl Into<String>) -> Self {
+        Edge {
+            source,
+            target,
+            name: Some(name.into()),
+        }
     }
 }
 
@@ -23,12 +44,16 @@ impl Edge {
 pub enum Dummy {
     /// 边虚拟节点
     Edge,
+    /// 承载边标签的边虚拟节点
+    EdgeLabel,
     /// 边标签代理节点
     EdgeProxy,
     /// 边界节点
     Border,
     /// 自环边节点
     SelfEdge,
+    /// 为同层边的标签留出空间的节点
+    FlatEdgeLabel,
     /// 根节点
     Root,
 }
@@ -38,9 +63,11 @@ impl std::str::FromStr for Dummy {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "edge" => Ok(Dummy::Edge),
+            "edge-label" => Ok(Dummy::EdgeLabel),
             "edge-proxy" => Ok(Dummy::EdgeProxy),
             "border" => Ok(Dummy::Border),
             "selfedge" => Ok(Dummy::SelfEdge),
+            "flat-edge-label" => Ok(Dummy::FlatEdgeLabel),
             "root" | "_root" => Ok(Dummy::Root),
             _ => Err(format!("Unknown dummy type: {}", s)),
         }
@@ -48,7 +75,8 @@ impl std::str::FromStr for Dummy {
 }
 
 /// 排序器类型
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Ranker {
     /// 网络单纯形算法
     NetworkSimplex,
@@ -68,16 +96,130 @@ impl Default for Ranker {
     }
 }
 
+/// 排名约束的类型，与 Graphviz 子图的 `rank` 属性对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RankKind {
+    /// 组内节点排名相同
+    Same,
+    /// 组内节点排名相同且位于第一个排名，其他节点也可以在该排名
+    Min,
+    /// 组内节点排名相同且位于最后一个排名，其他节点也可以在该排名
+    Max,
+    /// 组内节点排名相同且独占第一个排名
+    Source,
+    /// 组内节点排名相同且独占最后一个排名
+    Sink,
+}
+
+impl std::str::FromStr for RankKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "same" => Ok(RankKind::Same),
+            "min" => Ok(RankKind::Min),
+            "max" => Ok(RankKind::Max),
+            "source" => Ok(RankKind::Source),
+            "sink" => Ok(RankKind::Sink),
+            _ => Err(format!("Unknown rank constraint: {}", s)),
+        }
+    }
+}
+
+/// 排名约束：一组节点共享同一个排名，并可以要求位于第一个或最后一个排名
+///
+/// 多个 `Min`、`Source` 约束的节点都在第一个排名上，多个 `Max`、`Sink` 约束同理。
+/// 同一组内节点之间的边不约束排名，成为同一排名内的边。
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankConstraint {
+    /// 约束类型
+    pub kind: RankKind,
+    /// 受约束的节点
+    pub nodes: Vec<NodeIndex>,
+}
+
+impl RankConstraint {
+    /// 创建排名约束
+    pub fn new(kind: RankKind, nodes: impl IntoIterator<Item = NodeIndex>) -> Self {
+        Self {
+            kind,
+            nodes: nodes.into_iter().collect(),
+        }
+    }
+
+    /// 组内节点排名相同
+    pub fn same(nodes: impl IntoIterator<Item = NodeIndex>) -> Self {
+        Self::new(RankKind::Same, nodes)
+    }
+}
+
+/// 顺序约束的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OrderKind {
+    /// 同一排名内的节点按给定顺序排列
+    Sequence,
+    /// 节点位于所在排名的最前面
+    Leftmost,
+    /// 节点位于所在排名的最后面
+    Rightmost,
+}
+
+/// 顺序约束：规定同一排名内节点的先后顺序
+///
+/// 排名内的顺序在 `TB`、`BT` 方向上是从左到右，在 `LR`、`RL` 方向上是从上到下。
+/// `Sequence` 只约束位于同一排名的节点，不同排名的节点之间没有约束。
+/// 交叉最小化不会违反顺序约束；约束相互矛盾时按原有顺序打破环并记录警告。
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderConstraint {
+    /// 约束类型
+    pub kind: OrderKind,
+    /// 受约束的节点
+    pub nodes: Vec<NodeIndex>,
+}
+
+impl OrderConstraint {
+    /// 创建顺序约束
+    pub fn new(kind: OrderKind, nodes: impl IntoIterator<Item = NodeIndex>) -> Self {
+        Self {
+            kind,
+            nodes: nodes.into_iter().collect(),
+        }
+    }
+
+    /// 同一排名内的节点按给定顺序排列
+    pub fn sequence(nodes: impl IntoIterator<Item = NodeIndex>) -> Self {
+        Self::new(OrderKind::Sequence, nodes)
+    }
+
+    /// 节点位于所在排名的最前面
+    pub fn leftmost(nodes: impl IntoIterator<Item = NodeIndex>) -> Self {
+        Self::new(OrderKind::Leftmost, nodes)
+    }
+
+    /// 节点位于所在排名的最后面
+    pub fn rightmost(nodes: impl IntoIterator<Item = NodeIndex>) -> Self {
+        Self::new(OrderKind::Rightmost, nodes)
+    }
+}
+
 /// 排序方向
-#[derive(Debug, Clone, Copy, PartialEq)]
+///
+/// 序列化为 dagre 的写法：`"TB"`、`"BT"`、`"LR"`、`"RL"`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RankDirection {
     /// 从上到下
+    #[serde(rename = "TB")]
     TopBottom,
     /// 从下到上
+    #[serde(rename = "BT")]
     BottomTop,
     /// 从左到右
+    #[serde(rename = "LR")]
     LeftRight,
     /// 从右到左
+    #[serde(rename = "RL")]
     RightLeft,
 }
 
@@ -88,7 +230,8 @@ impl Default for RankDirection {
 }
 
 /// 标签位置
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LabelPosition {
     /// 居中
     Center,
@@ -108,100 +251,271 @@ impl Default for LabelPosition {
     }
 }
 
+/// 端口所在的节点边，按输出坐标系（已按 `rankdir` 变换）理解
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortSide {
+    /// 上边
+    Top,
+    /// 右边
+    Right,
+    /// 下边
+    Bottom,
+    /// 左边
+    Left,
+}
+
+/// 节点上的端口，边通过 `EdgeLabel::source_port`、`target_port` 连接到端口
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Port {
+    /// 端口 ID，在同一个节点内唯一
+    pub id: String,
+    /// 端口所在的边
+    pub side: PortSide,
+    /// 在所在边上的相对位置，0 是上边、下边的左端或左边、右边的上端，1 是另一端
+    ///
+    /// 未设置时，同一边上所有未设置偏移的端口按 `order`（相同时按声明顺序）均匀分布。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<f64>,
+    /// 未设置 `offset` 时在所在边上的排列顺序
+    #[serde(default)]
+    pub order: usize,
+}
+
+impl Port {
+    /// 创建端口，位置按声明顺序在所在边上均匀分布
+    pub fn new(id: impl Into<String>, side: PortSide) -> Self {
+        Self {
+            id: id.into(),
+            side,
+            offset: None,
+            order: 0,
+        }
+    }
+
+    /// 设置在所在边上的相对位置
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// 设置在所在边上的排列顺序
+    pub fn with_order(mut self, order: usize) -> Self {
+        self.order = order;
+        self
+    }
+}
+
+/// 节点形状，决定边的端点在节点边界上的位置
+///
+/// `width`、`height` 是形状的外接矩形，布局仍按外接矩形排列节点。
+/// 自定义形状见 [`crate::shape::ShapeBoundary`]。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum NodeShape {
+    /// 矩形
+    #[default]
+    Rect,
+    /// 圆角矩形，`radius` 是圆角半径，不超过宽高较小值的一半
+    RoundedRect {
+        /// 圆角半径
+        radius: f64,
+    },
+    /// 内切于外接矩形的椭圆
+    Ellipse,
+    /// 圆，直径为宽高的较小值
+    Circle,
+    /// 顶点位于四条边中点的菱形
+    Diamond,
+    /// 左右两端为顶点的六边形，上下两边各占宽度的一半
+    Hexagon,
+    /// 多边形
+    Polygon {
+        /// 顶点相对节点中心的位置，以宽高为单位，范围 -0.5 到 0.5，按顺序相连
+        points: Vec<Point>,
+    },
+    /// 用户定义的形状，不参与序列化
+    #[serde(skip)]
+    Custom(crate::shape::CustomShape),
+}
+
+impl NodeShape {
+    /// 序列化时省略默认的矩形和无法序列化的自定义形状
+    pub(crate) fn skip_serializing(&self) -> bool {
+        matches!(self, NodeShape::Rect | NodeShape::Custom(_))
+    }
+}
+
 /// 节点标签，包含节点的所有属性
-#[derive(Debug, Clone, Default)]
+///
+/// 序列化时只包含用户输入和布局结果；父节点由 [`crate::io`] 在图一级记录，
+/// 布局过程中使用的内部字段不参与序列化。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NodeLabel {
     /// 节点标签文本
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
     /// 节点宽度
     pub width: f64,
     /// 节点高度
     pub height: f64,
     /// 节点 X 坐标
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub x: Option<f64>,
     /// 节点 Y 坐标
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub y: Option<f64>,
     /// 节点层级（用于分层布局）
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rank: Option<i32>,
     /// 节点在层级中的顺序
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub order: Option<usize>,
     /// 节点是否被隐藏
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
     pub hidden: bool,
+    /// 节点是否固定在 `x`、`y` 给定的位置上，坐标与布局结果使用同一坐标系
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// 节点上的端口
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<Port>,
+    /// 节点形状
+    #[serde(skip_serializing_if = "NodeShape::skip_serializing")]
+    pub shape: NodeShape,
     /// 父节点索引（用于子图）
+    #[serde(skip)]
     pub parent: Option<NodeIndex>,
     /// 虚拟节点类型
+    #[serde(skip)]
     pub dummy: Option<Dummy>,
     /// 最小层级（用于复合图）
+    #[serde(skip)]
     pub min_rank: Option<i32>,
     /// 最大层级（用于复合图）
+    #[serde(skip)]
     pub max_rank: Option<i32>,
     /// 边界节点数组（用于复合图）
+    #[serde(skip)]
     pub border_left: Vec<NodeIndex>,
     /// 边界节点数组（用于复合图）
+    #[serde(skip)]
     pub border_right: Vec<NodeIndex>,
     /// 顶部边界节点
+    #[serde(skip)]
     pub border_top: Option<NodeIndex>,
     /// 底部边界节点
+    #[serde(skip)]
     pub border_bottom: Option<NodeIndex>,
     /// 边界类型
+    #[serde(skip)]
     pub border_type: Option<String>,
-    /// 自环边列表
-    pub self_edges: Option<Vec<EdgeLabel>>,
+    /// 排序前暂时移除的自环边及其标签
+    #[serde(skip)]
+    pub self_edges: Option<Vec<(Edge, EdgeLabel)>>,
     /// 边对象（用于虚拟节点）
+    #[serde(skip)]
     pub edge_obj: Option<Edge>,
+    /// 被拆分的原始边标签（只保存在虚拟节点链的第一个节点上）
+    #[serde(skip)]
+    pub edge_label: Option<Box<EdgeLabel>>,
     /// low值（用于NetworkSimplex算法）
+    #[serde(skip)]
     pub low: Option<i32>,
     /// lim值（用于NetworkSimplex算法）
+    #[serde(skip)]
     pub lim: Option<i32>,
     /// 自定义属性
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
     pub custom: IndexMap<String, serde_json::Value>,
 }
 
 /// 边标签，包含边的所有属性
-#[derive(Debug, Clone)]
+///
+/// 无环化和网络单纯形使用的内部字段不参与序列化。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EdgeLabel {
     /// 边标签文本
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
     /// 边的最小长度
     pub minlen: i32,
     /// 边的权重
     pub weight: f64,
+    /// 边是否参与排名，为 `false` 时不影响去环和排名，两端的排名只由其他边决定
+    ///
+    /// 这样的边仍然拆分成虚拟节点链并布线，可以从下层指向上层。
+    /// 对应 Graphviz 的 `constraint=false`。
+    #[serde(skip_serializing_if = "is_true")]
+    pub constraint: bool,
     /// 边是否被隐藏
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
     pub hidden: bool,
     /// 边的控制点列表（用于多控制点边）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub points: Vec<Point>,
+    /// 贝塞尔曲线的控制点，依次为起点和每段的两个控制点及终点
+    ///
+    /// 仅 `PathType::Bezier` 的边在布局后填充，`points` 中的每个点都是曲线经过的点。
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub control_points: Vec<Point>,
+    /// 边从源节点的哪个端口出发，未设置时从节点边界与中心连线的交点出发
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_port: Option<String>,
+    /// 边到达目标节点的哪个端口，未设置时到达节点边界与中心连线的交点
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_port: Option<String>,
     /// 边的路径类型（直线、曲线、折线等）
     pub path_type: PathType,
     /// 边标签的 X 坐标
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub x: Option<f64>,
     /// 边标签的 Y 坐标
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub y: Option<f64>,
     /// 边标签的宽度
     pub width: f64,
     /// 边标签的高度
     pub height: f64,
     /// 边是否被反转（用于无环化）
+    #[serde(skip)]
     pub reversed: Option<bool>,
     /// 反转边的原始名称
+    #[serde(skip)]
     pub forward_name: Option<String>,
     /// 边标签的偏移量
     pub labeloffset: f64,
     /// 边标签的位置
     pub labelpos: LabelPosition,
     /// cut值（用于NetworkSimplex算法）
+    #[serde(skip)]
     pub cutvalue: Option<i32>,
+    /// 边标签所在的层级（由边标签代理节点确定）
+    #[serde(skip)]
+    pub label_rank: Option<i32>,
     /// 自定义属性
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
     pub custom: IndexMap<String, serde_json::Value>,
 }
 
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
 impl Default for EdgeLabel {
     fn default() -> Self {
         Self {
             label: None,
             minlen: 1, // 默认最小长度为 1，与 JavaScript 版本一致
             weight: 1.0,
+            constraint: true,
             hidden: false,
             points: Vec::new(),
+            control_points: Vec::new(),
+            source_port: None,
+            target_port: None,
             path_type: PathType::default(),
             x: None,
             y: None,
@@ -212,13 +526,14 @@ impl Default for EdgeLabel {
             labeloffset: 10.0,
             labelpos: LabelPosition::default(),
             cutvalue: None,
+            label_rank: None,
             custom: IndexMap::new(),
         }
     }
 }
 
 /// 点坐标
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -231,7 +546,8 @@ impl Point {
 }
 
 /// 路径类型
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PathType {
     /// 直线
     Line,
@@ -250,7 +566,8 @@ impl Default for PathType {
 }
 
 /// 图配置
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GraphConfig {
     /// 节点间距
     pub node_sep: f64,
@@ -275,18 +592,32 @@ pub struct GraphConfig {
     /// 无环化算法
     pub acyclicer: String,
     /// 图的最大层级
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_rank: Option<i32>,
     /// 图的宽度
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub width: Option<f64>,
     /// 图的高度
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub height: Option<f64>,
-    /// 左边距
+    /// 水平边距，左右两侧各留出该宽度
     pub marginx: f64,
-    /// 上边距
+    /// 垂直边距，上下两侧各留出该高度
     pub marginy: f64,
     /// 虚拟节点链
+    #[serde(skip)]
     pub dummy_chains: Option<Vec<NodeIndex>>,
+    /// 嵌套图中相邻节点排名之间的间隔，只有不是它的整数倍的空排名才会被移除
+    #[serde(skip)]
+    pub node_rank_factor: Option<i32>,
+    /// 排名约束，通过 `Graph::add_rank_constraint` 添加
+    #[serde(skip)]
+    pub rank_constraints: Vec<RankConstraint>,
+    /// 顺序约束，通过 `Graph::add_order_constraint` 添加
+    #[serde(skip)]
+    pub order_constraints: Vec<OrderConstraint>,
     /// 自定义属性
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
     pub custom: IndexMap<String, serde_json::Value>,
 }
 
@@ -307,16 +638,20 @@ impl Default for GraphConfig {
             max_rank: None,
             width: None,
             height: None,
-            marginx: 20.0,
-            marginy: 20.0,
+            marginx: 0.0,
+            marginy: 0.0,
             dummy_chains: None,
+            node_rank_factor: None,
+            rank_constraints: Vec::new(),
+            order_constraints: Vec::new(),
             custom: IndexMap::new(),
         }
     }
 }
 
 /// 布局方向
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum Direction {
     /// 从上到下
     TopToBottom,
@@ -329,7 +664,8 @@ pub enum Direction {
 }
 
 /// 对齐方式
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Align {
     /// 居中对齐
     Center,
@@ -358,6 +694,12 @@ pub struct LayoutOptions {
     pub show_node_labels: bool,
     /// 是否禁用最优排序启发式
     pub disable_optimal_order_heuristic: bool,
+    /// 排序扫描、网络单纯形换边和总耗时的预算
+    pub budget: LayoutBudget,
+    /// 取消令牌，取消后布局不修改输入图
+    pub cancel: Option<CancellationToken>,
+    /// 上一次布局的结果，设置后进行增量布局
+    pub hints: Option<LayoutHints>,
     /// 自定义属性
     pub custom: IndexMap<String, serde_json::Value>,
 }
@@ -371,11 +713,25 @@ impl Default for LayoutOptions {
             show_edge_labels: true,
             show_node_labels: true,
             disable_optimal_order_heuristic: false,
+            budget: LayoutBudget::default(),
+            cancel: None,
+            hints: None,
             custom: IndexMap::new(),
         }
     }
 }
 
+/// 布局结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayoutResult {
+    /// 布局后图的宽度
+    pub width: f64,
+    /// 布局后图的高度
+    pub height: f64,
+    /// 本次布局的统计信息
+    pub stats: LayoutStats,
+}
+
 /// 子图信息
 #[derive(Debug, Clone)]
 pub struct Subgraph {
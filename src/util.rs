@@ -1,5 +1,6 @@
 //! 工具函数
 
+use crate::context::LayoutContext;
 use crate::graph::Graph;
 use crate::graph::NodeIndex;
 use crate::types::*;
@@ -85,7 +86,10 @@ pub fn range_with_step(start: i32, end: i32, step: i32) -> Vec<i32> {
     if step > 0 {
         (start..end).step_by(step as usize).collect()
     } else {
-        (end..start).step_by((-step) as usize).rev().collect()
+        ((end + 1)..=start)
+            .rev()
+            .step_by((-step) as usize)
+            .collect()
     }
 }
 
@@ -155,7 +159,6 @@ pub fn normalize_ranks(graph: &mut Graph) {
     for node_id in graph.node_indices() {
         if let Some(label) = graph.node_label(node_id) {
             if let Some(rank) = label.rank {
-                println!("get rank {}", rank);
                 nodes_to_update.push((node_id, rank - min_rank));
             }
         }
@@ -169,44 +172,43 @@ pub fn normalize_ranks(graph: &mut Graph) {
 }
 
 /// 移除空排名
+///
+/// 只移除不是 `node_rank_factor` 整数倍的空排名，这些排名由嵌套图拉开的边产生；
+/// 其余空排名为边标签和 `minlen` 留出空间，需要保留。没有设置 `node_rank_factor` 时移除所有空排名。
+///
+/// 对应 JS 函数: removeEmptyRanks() in lib/util.js
 pub fn remove_empty_ranks(graph: &mut Graph) {
-    let layers = build_layer_matrix(graph);
-    let max_rank_val = max_rank(graph);
-    let min_rank_val = min_rank(graph);
-
-    let mut rank_shift = IndexMap::new();
-    let mut current_rank = 0;
-
-    // 构建 rank 映射，跳过空层级
-    for rank in min_rank_val..=max_rank_val {
-        let adjusted_rank = (rank - min_rank_val) as usize;
-        if adjusted_rank < layers.len() && !layers[adjusted_rank].is_empty() {
-            rank_shift.insert(rank, current_rank);
-            current_rank += 1;
-        }
-    }
-
-    // 如果没有空层级需要移除，直接返回
-    if rank_shift.len() == (max_rank_val - min_rank_val + 1) as usize {
+    let ranks: Vec<(NodeIndex, i32)> = graph
+        .node_indices()
+        .filter_map(|node_id| Some((node_id, graph.node_label(node_id)?.rank?)))
+        .collect();
+    let Some(offset) = ranks.iter().map(|&(_, rank)| rank).min() else {
         return;
+    };
+    let max_rank_val = ranks.iter().map(|&(_, rank)| rank).max().unwrap_or(offset);
+    let node_rank_factor = graph.config().node_rank_factor;
+
+    // 每个排名需要减去的偏移量
+    let mut occupied = vec![false; (max_rank_val - offset + 1) as usize];
+    for &(_, rank) in &ranks {
+        occupied[(rank - offset) as usize] = true;
     }
-
-    // 收集需要更新的节点
-    let mut nodes_to_update = Vec::new();
-    for node_id in graph.node_indices() {
-        if let Some(label) = graph.node_label(node_id) {
-            if let Some(rank) = label.rank {
-                if let Some(&new_rank) = rank_shift.get(&rank) {
-                    nodes_to_update.push((node_id, new_rank));
-                }
-            }
+    let mut delta = 0;
+    let mut deltas = Vec::with_capacity(occupied.len());
+    for (i, &occupied) in occupied.iter().enumerate() {
+        if !occupied && node_rank_factor.is_none_or(|factor| i as i32 % factor != 0) {
+            delta -= 1;
         }
+        deltas.push(delta);
     }
 
     // 更新节点排名
-    for (node_id, new_rank) in nodes_to_update {
-        if let Some(label) = graph.node_label_mut(node_id) {
-            label.rank = Some(new_rank);
+    for (node_id, rank) in ranks {
+        let delta = deltas[(rank - offset) as usize];
+        if delta != 0
+            && let Some(label) = graph.node_label_mut(node_id)
+        {
+            label.rank = Some(rank + delta);
         }
     }
 }
@@ -293,6 +295,103 @@ where
     result
 }
 
+/// 布局阶段跟踪
+///
+/// 每个阶段使用独立的日志目标 `dagviz::stage::<name>`，可以按阶段单独开启，
+/// 例如 `RUST_LOG=dagviz::stage::order=debug`。debug 级别记录节点数、边数和耗时，
+/// trace 级别在阶段结束时额外输出完整的图状态。
+pub struct StageSpan {
+    target: String,
+    timer: Timer,
+}
+
+impl StageSpan {
+    /// 进入阶段
+    pub fn enter(name: &str, graph: &Graph) -> Self {
+        let target = format!("dagviz::stage::{}", name);
+        log::debug!(
+            target: &target,
+            "enter nodes={} edges={}",
+            graph.node_count(),
+            graph.edge_count()
+        );
+        Self {
+            target,
+            timer: Timer::new(),
+        }
+    }
+
+    /// 离开阶段，返回阶段耗时
+    pub fn exit(self, graph: &Graph) -> std::time::Duration {
+        let elapsed = self.timer.elapsed();
+        log::debug!(
+            target: &self.target,
+            "exit nodes={} edges={} elapsed={:?}",
+            graph.node_count(),
+            graph.edge_count(),
+            elapsed
+        );
+        if log::log_enabled!(target: &self.target, log::Level::Trace) {
+            log_graph_state(&self.target, graph);
+        }
+        elapsed
+    }
+}
+
+/// 在阶段跟踪中执行一个布局步骤，耗时记录到上下文的布局统计中
+pub fn stage<F, R>(name: &str, graph: &mut Graph, ctx: &mut LayoutContext, f: F) -> R
+where
+    F: FnOnce(&mut Graph) -> R,
+{
+    stage_with_context(name, graph, ctx, |graph, _| f(graph))
+}
+
+/// 与 [`stage`] 相同，布局步骤同时需要读写布局上下文
+pub fn stage_with_context<F, R>(name: &str, graph: &mut Graph, ctx: &mut LayoutContext, f: F) -> R
+where
+    F: FnOnce(&mut Graph, &mut LayoutContext) -> R,
+{
+    let span = StageSpan::enter(name, graph);
+    let result = f(graph, ctx);
+    let elapsed = span.exit(graph);
+    ctx.stats.record_stage(name, elapsed);
+    ctx.check_deadline();
+    result
+}
+
+/// 在 trace 级别输出图中所有节点和边的布局属性
+fn log_graph_state(target: &str, graph: &Graph) {
+    for node_id in graph.node_indices() {
+        if let Some(node) = graph.node_label(node_id) {
+            log::trace!(
+                target: target,
+                "node {}: rank={:?} order={:?} x={:?} y={:?} width={} height={} dummy={:?}",
+                node_id.index(),
+                node.rank,
+                node.order,
+                node.x,
+                node.y,
+                node.width,
+                node.height,
+                node.dummy
+            );
+        }
+    }
+    for edge in graph.edges() {
+        if let Some(label) = graph.edge_label(&edge) {
+            log::trace!(
+                target: target,
+                "edge {} -> {}: minlen={} weight={} points={}",
+                edge.source.index(),
+                edge.target.index(),
+                label.minlen,
+                label.weight,
+                label.points.len()
+            );
+        }
+    }
+}
+
 /// 空操作（用于禁用时间测量）
 pub fn no_time<F, R>(_name: &str, f: F) -> R
 where
@@ -301,6 +400,46 @@ where
     f()
 }
 
+/// 合并多重边
+///
+/// 同一对节点之间的多条边（例如名称不同的平行边）合并为一条未命名边：
+/// 权重相加，最小长度取最大值。返回 (simplified_graph, old_to_new_mapping)
+///
+/// 对应 JS 函数: simplify() in lib/util.js
+pub fn simplify(graph: &Graph) -> (Graph, IndexMap<NodeIndex, NodeIndex>) {
+    let mut simplified = Graph::with_config(graph.config().clone());
+    let mut old_to_new = IndexMap::new();
+
+    for old_node_id in graph.node_indices() {
+        if let Some(label) = graph.node_label(old_node_id) {
+            let new_node_id = simplified.add_node(label.clone());
+            old_to_new.insert(old_node_id, new_node_id);
+        }
+    }
+
+    for edge in graph.edges() {
+        let Some(edge_label) = graph.edge_label(&edge) else {
+            continue;
+        };
+        let simple_edge = Edge::new(old_to_new[&edge.source], old_to_new[&edge.target]);
+        if let Some(merged) = simplified.edge_label_mut(&simple_edge) {
+            merged.weight += edge_label.weight;
+            merged.minlen = merged.minlen.max(edge_label.minlen);
+        } else {
+            simplified.add_edge(
+                simple_edge,
+                EdgeLabel {
+                    weight: edge_label.weight,
+                    minlen: edge_label.minlen,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    (simplified, old_to_new)
+}
+
 /// 作为非复合图处理
 ///
 /// 对应 JS 函数: asNonCompoundGraph() in lib/util.js
@@ -313,11 +452,11 @@ pub fn as_non_compound_graph(graph: &Graph) -> (Graph, IndexMap<NodeIndex, NodeI
 
     // 只添加没有子节点的节点（叶子节点）
     for old_node_id in graph.node_indices() {
-        if graph.children(old_node_id).is_empty() {
-            if let Some(label) = graph.node_label(old_node_id) {
-                let new_node_id = simplified.add_node(label.clone());
-                old_to_new.insert(old_node_id, new_node_id);
-            }
+        if graph.children(old_node_id).is_empty()
+            && let Some(label) = graph.node_label(old_node_id)
+        {
+            let new_node_id = simplified.add_node(label.clone());
+            old_to_new.insert(old_node_id, new_node_id);
         }
     }
 
@@ -325,10 +464,14 @@ pub fn as_non_compound_graph(graph: &Graph) -> (Graph, IndexMap<NodeIndex, NodeI
     for edge in graph.edges() {
         if let (Some(&new_source), Some(&new_target)) =
             (old_to_new.get(&edge.source), old_to_new.get(&edge.target))
+            && let Some(edge_label) = graph.edge_label(&edge)
         {
-            if let Some(edge_label) = graph.edge_label(&edge) {
-                simplified.add_edge(Edge::new(new_source, new_target), edge_label.clone());
-            }
+            let new_edge = Edge {
+                source: new_source,
+                target: new_target,
+                name: edge.name.clone(),
+            };
+            simplified.add_edge(new_edge, edge_label.clone());
         }
     }
 
@@ -375,6 +518,8 @@ mod tests {
     fn test_range() {
         assert_eq!(range(0, 5), vec![0, 1, 2, 3, 4]);
         assert_eq!(range_with_step(0, 10, 2), vec![0, 2, 4, 6, 8]);
+        assert_eq!(range_with_step(3, -1, -1), vec![3, 2, 1, 0]);
+        assert_eq!(range_with_step(5, 0, -2), vec![5, 3, 1]);
     }
 
     #[test]
@@ -392,4 +537,19 @@ mod tests {
         assert!(intersection.x >= 0.0 && intersection.x <= 100.0);
         assert!(intersection.y >= 0.0 && intersection.y <= 50.0);
     }
+
+    #[test]
+    fn test_stage_returns_closure_result() {
+        let mut graph = Graph::new();
+        let mut ctx = LayoutContext::new();
+        let count = stage("test", &mut graph, &mut ctx, |g| {
+            g.add_node(NodeLabel::default());
+            g.node_count()
+        });
+
+        assert_eq!(count, 1);
+        assert_eq!(graph.node_count(), 1);
+        assert_eq!(ctx.stats.stages.len(), 1);
+        assert_eq!(ctx.stats.stages[0].name, "test");
+    }
 }
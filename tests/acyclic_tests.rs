@@ -217,6 +217,37 @@ fn test_greedy_prefers_to_break_cycles_at_low_weight_edges() {
     assert!(!g.has_edge(&g.create_query_edge(c, d)));
 }
 
+#[test]
+fn test_greedy_removes_the_node_with_the_largest_weight_delta_first() {
+    let mut g = setup_test_graph_with_acyclicer("greedy");
+
+    // Create cycle: a -> b -> c -> a, where only a -> b is cheap
+    let a = g.add_node(NodeLabel::default());
+    let b = g.add_node(NodeLabel::default());
+    let c = g.add_node(NodeLabel::default());
+
+    let heavy = EdgeLabel {
+        weight: 5.0,
+        ..Default::default()
+    };
+    let light = EdgeLabel {
+        weight: 1.0,
+        ..Default::default()
+    };
+
+    g.add_edge(g.create_edge(a, b), light);
+    g.add_edge(g.create_edge(b, c), heavy.clone());
+    g.add_edge(g.create_edge(c, a), heavy);
+
+    Acyclic::run(&mut g);
+
+    assert!(find_cycles(&g).is_empty());
+    // b has the largest out - in weight, so its cheap in-edge is reversed
+    // rather than the in-edges of the first node
+    assert!(!g.has_edge(&g.create_query_edge(a, b)));
+    assert!(g.has_edge(&g.create_query_edge(c, a)));
+}
+
 #[test]
 fn test_unknown_acyclicer_still_works() {
     let mut g = setup_test_graph_with_acyclicer("unknown-should-still-work");
@@ -0,0 +1,225 @@
+use dagviz::error::LayoutError;
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::types::{Edge, EdgeLabel, NodeLabel};
+
+/// 用宽度区分节点，用权重区分边
+fn add_nodes(g: &mut Graph, count: usize) -> Vec<NodeIndex> {
+    (0..count)
+        .map(|i| {
+            g.add_node(NodeLabel {
+                width: i as f64,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+fn add_edge(g: &mut Graph, source: NodeIndex, target: NodeIndex, weight: f64) -> Edge {
+    let edge = Edge::new(source, target);
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            weight,
+            ..Default::default()
+        },
+    );
+    edge
+}
+
+/// 边映射与 petgraph 中的边一致
+fn assert_consistent(g: &Graph) {
+    assert_eq!(g.edges().len(), g.edge_count());
+    for edge in g.edges() {
+        let index = g.find_edge(edge.source, edge.target).unwrap();
+        assert_eq!(
+            g.petgraph().edge_endpoints(index),
+            Some((edge.source.node_index, edge.target.node_index)),
+            "edge {:?} maps to the wrong petgraph edge",
+            edge
+        );
+        assert!(g.out_edges(edge.source).contains(&edge));
+        assert!(g.in_edges(edge.target).contains(&edge));
+    }
+}
+
+#[test]
+fn test_removing_a_node_keeps_other_handles_valid() {
+    let mut g = Graph::new();
+    let nodes = add_nodes(&mut g, 5);
+
+    assert!(g.remove_node(nodes[1]).is_some());
+    assert!(!g.has_node(nodes[1]));
+    assert_eq!(g.node_count(), 4);
+    for (i, &node) in nodes.iter().enumerate().filter(|&(i, _)| i != 1) {
+        assert_eq!(g.node_label(node).unwrap().width, i as f64);
+    }
+    assert!(g.node_label(nodes[1]).is_none());
+    assert!(g.remove_node(nodes[1]).is_none());
+}
+
+#[test]
+fn test_removing_a_node_keeps_edges_of_other_nodes() {
+    let mut g = Graph::new();
+    let nodes = add_nodes(&mut g, 5);
+    add_edge(&mut g, nodes[0], nodes[2], 1.0);
+    let de = add_edge(&mut g, nodes[3], nodes[4], 2.0);
+    let ce = add_edge(&mut g, nodes[2], nodes[4], 3.0);
+
+    g.remove_node(nodes[0]);
+
+    assert_eq!(g.edge_count(), 2);
+    assert_eq!(g.edge_label(&de).unwrap().weight, 2.0);
+    assert_eq!(g.edge_label(&ce).unwrap().weight, 3.0);
+    assert!(g.in_edges(nodes[2]).is_empty());
+    assert_eq!(
+        g.predecessors(nodes[4]).collect::<Vec<_>>().len(),
+        2,
+        "both remaining in-edges of e survive"
+    );
+    assert_consistent(&g);
+}
+
+#[test]
+fn test_removed_parent_is_not_inherited_by_a_reused_index() {
+    let mut g = Graph::new();
+    let nodes = add_nodes(&mut g, 2);
+    let (child, parent) = (nodes[0], nodes[1]);
+    g.set_parent(child, parent);
+
+    g.remove_node(parent);
+    assert_eq!(g.parent(child), None);
+
+    // StableGraph 复用刚释放的索引，新节点不能继承旧节点的子节点
+    let reused = g.add_node(NodeLabel::default());
+    assert_eq!(reused.index(), parent.index());
+    assert_eq!(g.parent(child), None);
+    assert!(g.children(reused).is_empty());
+}
+
+#[test]
+fn test_stale_index_does_not_reach_the_node_reusing_its_slot() {
+    let mut g = Graph::new();
+    let nodes = add_nodes(&mut g, 3);
+    add_edge(&mut g, nodes[0], nodes[1], 1.0);
+    let stale = nodes[1];
+    g.remove_node(stale);
+
+    let reused = g.add_node(NodeLabel {
+        width: 9.0,
+        ..Default::default()
+    });
+    add_edge(&mut g, nodes[2], reused, 2.0);
+    assert_eq!(reused.index(), stale.index());
+    assert_ne!(reused, stale);
+
+    // 旧索引与新节点共用槽位，但不能读到或删除新节点
+    assert!(!g.has_node(stale));
+    assert!(g.node_label(stale).is_none());
+    assert!(g.node_label_mut(stale).is_none());
+    assert!(g.in_edges(stale).is_empty());
+    assert_eq!(g.predecessors(stale).count(), 0);
+    assert!(g.check_node(stale, None).is_err());
+    assert!(g.remove_node(stale).is_none());
+
+    assert_eq!(g.node_label(reused).unwrap().width, 9.0);
+    assert_eq!(g.predecessors(reused).collect::<Vec<_>>(), [nodes[2]]);
+    assert_consistent(&g);
+}
+
+#[test]
+fn test_interleaved_removals_and_lookups() {
+    let mut g = Graph::new();
+    let nodes = add_nodes(&mut g, 8);
+    let mut edges = Vec::new();
+    for i in 0..nodes.len() {
+        for j in i + 1..nodes.len() {
+            if (i + j) % 3 != 0 {
+                edges.push(add_edge(&mut g, nodes[i], nodes[j], (i * 10 + j) as f64));
+            }
+        }
+    }
+
+    let mut removed = vec![false; nodes.len()];
+    for &victim in &[0, 5, 3] {
+        // 先删一条边，再删一个节点，每一步都检查所有剩余的句柄
+        if let Some(edge) = edges
+            .iter()
+            .find(|e| e.source.index() != victim && e.target.index() != victim && g.has_edge(e))
+            .cloned()
+        {
+            assert!(g.remove_edge(&edge).is_some());
+            assert!(!g.has_edge(&edge));
+            assert_consistent(&g);
+        }
+
+        g.remove_node(nodes[victim]);
+        removed[victim] = true;
+        assert_consistent(&g);
+
+        for (i, &node) in nodes.iter().enumerate() {
+            assert_eq!(g.has_node(node), !removed[i]);
+            if !removed[i] {
+                assert_eq!(g.node_label(node).unwrap().width, i as f64);
+            }
+        }
+        for edge in g.edges() {
+            let (i, j) = (edge.source.index(), edge.target.index());
+            assert_eq!(g.edge_label(&edge).unwrap().weight, (i * 10 + j) as f64);
+        }
+    }
+
+    // 删除之后新增的节点和边同样可以正常使用
+    let extra = g.add_node(NodeLabel {
+        width: 100.0,
+        ..Default::default()
+    });
+    let edge = add_edge(&mut g, nodes[7], extra, 7.5);
+    assert_eq!(g.edge_label(&edge).unwrap().weight, 7.5);
+    assert_eq!(g.node_label(nodes[7]).unwrap().width, 7.0);
+    assert_consistent(&g);
+}
+
+#[test]
+fn test_try_accessors_report_foreign_and_removed_nodes() {
+    let mut g = Graph::new();
+    let nodes = add_nodes(&mut g, 2);
+    let mut other = Graph::new();
+    let foreign = other.add_node(NodeLabel::default());
+
+    assert_eq!(g.try_node_label(nodes[1]).unwrap().width, 1.0);
+    assert_eq!(g.try_parent(nodes[0]), Ok(None));
+    g.try_set_parent(nodes[0], nodes[1]).unwrap();
+    assert_eq!(g.try_parent(nodes[0]), Ok(Some(nodes[1])));
+    assert_eq!(
+        g.try_create_edge(nodes[0], nodes[1]),
+        Ok(Edge::new(nodes[0], nodes[1]))
+    );
+
+    assert!(matches!(
+        g.try_node_label(foreign),
+        Err(LayoutError::ForeignNode { .. })
+    ));
+    assert!(matches!(
+        g.try_set_parent(nodes[0], foreign),
+        Err(LayoutError::ForeignNode { .. })
+    ));
+    assert_eq!(
+        g.try_create_edge(nodes[0], foreign),
+        Err(LayoutError::ForeignNode {
+            node: foreign,
+            graph_id: g.graph_id(),
+            edge: Some(Edge::new(nodes[0], foreign)),
+        })
+    );
+
+    g.remove_node(nodes[1]);
+    assert_eq!(
+        g.try_parent(nodes[1]),
+        Err(LayoutError::UnknownNode {
+            node: nodes[1],
+            edge: None,
+        })
+    );
+    assert!(g.try_set_parent(nodes[0], nodes[1]).is_err());
+    assert_eq!(g.try_parent(nodes[0]), Ok(None));
+}
@@ -0,0 +1,112 @@
+use dagviz::io::dot;
+use dagviz::{EdgeLabel, KeyedEdge, KeyedGraph, NodeLabel};
+
+fn node(width: f64, height: f64) -> NodeLabel {
+    NodeLabel {
+        width,
+        height,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_set_edge_and_set_parent_create_missing_nodes() {
+    let mut g = KeyedGraph::new();
+    g.set_node("a", node(50.0, 20.0));
+    g.set_edge("a", "b", EdgeLabel::default());
+    g.set_parent("b", "cluster");
+
+    assert_eq!(g.node_ids().collect::<Vec<_>>(), vec!["a", "b", "cluster"]);
+    assert_eq!(g.node("a").unwrap().width, 50.0);
+    assert_eq!(g.node("b").unwrap().width, 0.0);
+    assert!(g.edge("a", "b").is_some());
+    assert!(g.edge("b", "a").is_none());
+    assert_eq!(g.parent("b"), Some("cluster"));
+    assert_eq!(g.parent("a"), None);
+    assert_eq!(g.children("cluster"), vec!["b"]);
+
+    // 替换标签时保留父节点
+    g.set_node("b", node(10.0, 10.0));
+    assert_eq!(g.node("b").unwrap().width, 10.0);
+    assert_eq!(g.parent("b"), Some("cluster"));
+}
+
+#[test]
+fn test_named_edges_are_addressed_by_name() {
+    let mut g = KeyedGraph::new();
+    g.set_edge("a", "b", EdgeLabel::default());
+    g.set_named_edge(
+        "a",
+        "b",
+        "x",
+        EdgeLabel {
+            minlen: 2,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(g.edges().len(), 2);
+    assert_eq!(g.named_edge("a", "b", "x").unwrap().minlen, 2);
+    assert_eq!(g.edge("a", "b").unwrap().minlen, 1);
+
+    assert!(g.remove_named_edge("a", "b", "x").is_some());
+    assert!(g.named_edge("a", "b", "x").is_none());
+    assert!(g.edge("a", "b").is_some());
+}
+
+#[test]
+fn test_remove_node_drops_its_id_edges_and_parent_links() {
+    let mut g = KeyedGraph::new();
+    g.set_edge("a", "b", EdgeLabel::default());
+    g.set_parent("a", "cluster");
+
+    assert!(g.remove_node("cluster").is_some());
+    assert!(!g.has_node("cluster"));
+    assert_eq!(g.parent("a"), None);
+
+    assert!(g.remove_node("b").is_some());
+    assert!(g.edges().is_empty());
+    assert_eq!(g.graph().edge_count(), 0);
+
+    // 同一个 ID 可以重新添加
+    g.set_edge("a", "b", EdgeLabel::default());
+    assert_eq!(g.node_count(), 2);
+    assert_eq!(g.graph().node_count(), 2);
+}
+
+#[test]
+fn test_layout_results_are_keyed_by_id() {
+    let mut g = KeyedGraph::new();
+    g.set_node("a", node(50.0, 100.0));
+    g.set_node("b", node(75.0, 200.0));
+    g.set_node("c", node(20.0, 20.0));
+    g.set_edge("a", "b", EdgeLabel::default());
+    g.set_parent("c", "cluster");
+
+    let result = g.layout(None);
+    assert_eq!(
+        result.nodes.keys().collect::<Vec<_>>(),
+        vec!["a", "b", "c", "cluster"]
+    );
+    let (a, b) = (&result.nodes["a"], &result.nodes["b"]);
+    assert!(a.y.unwrap() < b.y.unwrap());
+    assert_eq!(a.x, g.node("a").unwrap().x);
+
+    let edge = KeyedEdge {
+        source: "a".into(),
+        target: "b".into(),
+        name: None,
+    };
+    assert!(!result.edges[&edge].points.is_empty());
+
+    assert!(result.width > 0.0 && result.height > 0.0);
+}
+
+#[test]
+fn test_from_dot_keys_nodes_and_clusters() {
+    let parsed = dot::parse("digraph { subgraph cluster_x { a } a -> b }").unwrap();
+    let g = KeyedGraph::from(parsed);
+
+    assert!(g.edge("a", "b").is_some());
+    assert_eq!(g.parent("a"), Some("cluster_x"));
+}
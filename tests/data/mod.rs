@@ -1 +1,4 @@
-// Data tests module - tests will be added here
+// Data tests module
+
+mod graph_tests;
+mod keyed_graph_tests;
@@ -0,0 +1,311 @@
+use dagviz::io::FormatError;
+use dagviz::io::dot::{self, DotGraph};
+use dagviz::layout::layout;
+use dagviz::types::{Edge, NodeShape, Point, Port, PortSide, RankDirection};
+use serde_json::json;
+
+fn parse(source: &str) -> DotGraph {
+    match dot::parse(source) {
+        Ok(parsed) => parsed,
+        Err(err) => panic!("failed to parse DOT: {}", err),
+    }
+}
+
+fn parse_err(source: &str) -> FormatError {
+    match dot::parse(source) {
+        Ok(_) => panic!("expected {:?} to be rejected", source),
+        Err(err) => err,
+    }
+}
+
+#[test]
+fn test_parses_nodes_edges_and_attributes() {
+    let parsed = parse(
+        r#"
+        // 流水线
+        digraph pipeline {
+            rankdir = LR;
+            graph [nodesep=0.5, ranksep="1.0 equally"];
+            node [shape=box];
+            a [label="Start", width=1, height=0.5];
+            b;
+            a -> b -> c [minlen=2, weight=3, color=red];
+        }
+        "#,
+    );
+    let g = &parsed.graph;
+    let ids = &parsed.ids;
+
+    assert_eq!(g.node_count(), 3);
+    assert_eq!(g.config().rankdir, RankDirection::LeftRight);
+    assert_eq!(g.config().node_sep, 36.0);
+    assert_eq!(g.config().rank_sep, 72.0);
+
+    let a = g.node_label(ids["a"]).unwrap();
+    assert_eq!(a.label.as_deref(), Some("Start"));
+    assert_eq!((a.width, a.height), (72.0, 36.0));
+    assert_eq!(a.custom["shape"], json!("box"));
+    assert_eq!(a.shape, NodeShape::Rect);
+
+    // 未指定尺寸时使用 Graphviz 默认值，标签默认为节点 ID
+    let c = g.node_label(ids["c"]).unwrap();
+    assert_eq!(c.label.as_deref(), Some("c"));
+    assert_eq!((c.width, c.height), (54.0, 36.0));
+
+    for (source, target) in [("a", "b"), ("b", "c")] {
+        let label = g.edge_label(&Edge::new(ids[source], ids[target])).unwrap();
+        assert_eq!(label.minlen, 2);
+        assert_eq!(label.weight, 3.0);
+        assert_eq!(label.custom["color"], json!("red"));
+    }
+}
+
+#[test]
+fn test_clusters_become_compound_nodes() {
+    let parsed = parse(
+        r#"digraph {
+            subgraph cluster_outer {
+                label = "Outer";
+                a;
+                subgraph cluster_inner {
+                    graph [label=Inner, style=filled];
+                    b; c;
+                }
+            }
+            d -> a;
+            b -> d;
+        }"#,
+    );
+    let g = &parsed.graph;
+    let outer = parsed.clusters["cluster_outer"];
+    let inner = parsed.clusters["cluster_inner"];
+
+    assert_eq!(g.node_label(outer).unwrap().label.as_deref(), Some("Outer"));
+    assert_eq!(g.node_label(inner).unwrap().label.as_deref(), Some("Inner"));
+    assert_eq!(
+        g.node_label(inner).unwrap().custom["style"],
+        json!("filled")
+    );
+    assert_eq!(g.parent(inner), Some(outer));
+    assert_eq!(g.parent(parsed.ids["a"]), Some(outer));
+    assert_eq!(g.parent(parsed.ids["b"]), Some(inner));
+    assert_eq!(g.parent(parsed.ids["c"]), Some(inner));
+    assert_eq!(g.parent(parsed.ids["d"]), None);
+    // 集群名不是节点 ID
+    assert!(!parsed.ids.contains_key("cluster_outer"));
+}
+
+#[test]
+fn test_nodes_declared_first_move_into_clusters() {
+    let parsed = parse(
+        r#"digraph {
+            a -> b;
+            subgraph cluster_x { a }
+        }"#,
+    );
+
+    assert_eq!(
+        parsed.graph.parent(parsed.ids["a"]),
+        Some(parsed.clusters["cluster_x"])
+    );
+    assert_eq!(parsed.graph.parent(parsed.ids["b"]), None);
+}
+
+#[test]
+fn test_records_rank_same_groups() {
+    let parsed = parse(
+        r#"digraph {
+            a -> b; a -> c; c -> d;
+            { rank = same; b; d }
+            subgraph s { rank=same c e }
+        }"#,
+    );
+    let ids = &parsed.ids;
+
+    assert_eq!(
+        parsed.same_rank,
+        vec![vec![ids["b"], ids["d"]], vec![ids["c"], ids["e"]]]
+    );
+}
+
+#[test]
+fn test_subgraph_operands_fan_out() {
+    let parsed = parse("digraph { a -> { b c } -> d }");
+    let g = &parsed.graph;
+    let ids = &parsed.ids;
+
+    assert_eq!(g.edge_count(), 4);
+    for (source, target) in [("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")] {
+        assert!(g.has_edge(&Edge::new(ids[source], ids[target])));
+    }
+}
+
+#[test]
+fn test_scoped_defaults_do_not_leak() {
+    let parsed = parse(
+        r#"digraph {
+            { node [width=2]; edge [weight=5]; a -> b }
+            c -> d;
+        }"#,
+    );
+    let g = &parsed.graph;
+    let ids = &parsed.ids;
+
+    assert_eq!(g.node_label(ids["a"]).unwrap().width, 144.0);
+    assert_eq!(g.node_label(ids["c"]).unwrap().width, 54.0);
+    assert_eq!(
+        g.edge_label(&Edge::new(ids["a"], ids["b"])).unwrap().weight,
+        5.0
+    );
+    assert_eq!(
+        g.edge_label(&Edge::new(ids["c"], ids["d"])).unwrap().weight,
+        1.0
+    );
+}
+
+#[test]
+fn test_repeated_edges_are_kept_unless_strict() {
+    let parsed = parse("digraph { a -> b; a -> b [label=again] }");
+    let (a, b) = (parsed.ids["a"], parsed.ids["b"]);
+    assert_eq!(parsed.graph.edge_count(), 2);
+    assert_eq!(
+        parsed
+            .graph
+            .edge_label(&Edge::with_name(a, b, "1"))
+            .unwrap()
+            .label
+            .as_deref(),
+        Some("again")
+    );
+
+    let parsed = parse("strict digraph { a -> b; a -> b [label=again] }");
+    assert_eq!(parsed.graph.edge_count(), 1);
+}
+
+#[test]
+fn test_lexes_quoting_comments_and_ports() {
+    let parsed = parse(
+        "/* header */\n# 1 \"generated.dot\"\nDiGraph {\n  \"node one\" [label=\"say \\\"hi\\\"\" + \" there\"];\n  h [label=<<b>bold</b>>];\n  \"node one\":out:s -> h:in;\n  -1.5 -> h\n}",
+    );
+    let g = &parsed.graph;
+    let ids = &parsed.ids;
+
+    assert_eq!(
+        g.node_label(ids["node one"]).unwrap().label.as_deref(),
+        Some("say \"hi\" there")
+    );
+    assert_eq!(
+        g.node_label(ids["h"]).unwrap().label.as_deref(),
+        Some("<b>bold</b>")
+    );
+    assert!(g.has_edge(&Edge::new(ids["node one"], ids["h"])));
+    assert!(g.has_edge(&Edge::new(ids["-1.5"], ids["h"])));
+}
+
+#[test]
+fn test_parsed_graph_can_be_laid_out() {
+    let mut parsed = parse(
+        r#"digraph {
+            rankdir=LR;
+            subgraph cluster_0 { a -> b }
+            b -> c;
+        }"#,
+    );
+
+    layout(&mut parsed.graph, None);
+
+    let x = |id: &str| parsed.graph.node_label(parsed.ids[id]).unwrap().x.unwrap();
+    assert!(x("a") < x("b"));
+    assert!(x("b") < x("c"));
+}
+
+#[test]
+fn test_reports_errors() {
+    assert!(matches!(
+        parse_err("graph { a -- b }"),
+        FormatError::Unsupported(_)
+    ));
+    assert!(matches!(
+        parse_err("digraph { a -- b }"),
+        FormatError::Syntax { line: 1, .. }
+    ));
+    assert!(matches!(
+        parse_err("digraph {\n a -> b\n c [label=\"open\n}"),
+        FormatError::Syntax { line: 3, .. }
+    ));
+    assert!(matches!(
+        parse_err("digraph { a -> b [minlen=two] }"),
+        FormatError::InvalidAttribute { ref key, .. } if key == "minlen"
+    ));
+    assert!(matches!(
+        parse_err("digraph { node [width=wide]; a }"),
+        FormatError::InvalidAttribute { ref key, .. } if key == "width"
+    ));
+    assert!(matches!(
+        parse_err("digraph { a } extra"),
+        FormatError::Syntax { .. }
+    ));
+}
+
+#[test]
+fn test_compass_points_become_ports() {
+    let mut parsed = parse("digraph { a:se -> b:n; a -> c [tailport=w, headport=c]; b:in -> c }");
+    let ids = parsed.ids.clone();
+    let g = &mut parsed.graph;
+
+    let a_ports = &g.node_label(ids["a"]).unwrap().ports;
+    assert_eq!(
+        a_ports,
+        &vec![
+            Port::new("se", PortSide::Bottom).with_offset(1.0),
+            Port::new("w", PortSide::Left).with_offset(0.5),
+        ]
+    );
+    assert!(g.node_label(ids["c"]).unwrap().ports.is_empty());
+    let ab = g.edge_label(&Edge::new(ids["a"], ids["b"])).unwrap();
+    assert_eq!(ab.source_port.as_deref(), Some("se"));
+    assert_eq!(ab.target_port.as_deref(), Some("n"));
+    let bc = g.edge_label(&Edge::new(ids["b"], ids["c"])).unwrap();
+    assert_eq!((bc.source_port.as_ref(), bc.target_port.as_ref()), (None, None));
+
+    layout(g, None);
+
+    let b = g.node_label(ids["b"]).unwrap();
+    let points = &g.edge_label(&Edge::new(ids["a"], ids["b"])).unwrap().points;
+    assert_eq!(
+        points[points.len() - 1],
+        Point::new(b.x.unwrap(), b.y.unwrap() - b.height / 2.0)
+    );
+}
+
+#[test]
+fn test_shape_attribute_maps_to_node_shape() {
+    let parsed = parse(
+        "digraph { node [shape=ellipse]; a; b [shape=diamond]; c [shape=box]; d [shape=star] }",
+    );
+    let g = &parsed.graph;
+    let shape = |id: &str| g.node_label(parsed.ids[id]).unwrap().shape.clone();
+    assert_eq!(shape("a"), NodeShape::Ellipse);
+    assert_eq!(shape("b"), NodeShape::Diamond);
+    assert_eq!(shape("c"), NodeShape::Rect);
+    assert_eq!(shape("d"), NodeShape::Rect);
+    let d = g.node_label(parsed.ids["d"]).unwrap();
+    assert_eq!(d.custom["shape"], json!("star"));
+}
+
+#[test]
+fn test_constraint_attribute_maps_to_edge_label() {
+    let parsed = parse(
+        "digraph { a -> b [constraint=false]; b -> c [constraint=0]; c -> d [constraint=yes] }",
+    );
+    let (g, ids) = (&parsed.graph, &parsed.ids);
+    let constraint =
+        |s: &str, t: &str| g.edge_label(&Edge::new(ids[s], ids[t])).unwrap().constraint;
+    assert!(!constraint("a", "b"));
+    assert!(!constraint("b", "c"));
+    assert!(constraint("c", "d"));
+    assert!(matches!(
+        parse_err("digraph { a -> b [constraint=maybe] }"),
+        FormatError::InvalidAttribute { ref key, .. } if key == "constraint"
+    ));
+}
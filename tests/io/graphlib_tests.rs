@@ -0,0 +1,185 @@
+use dagviz::graph::Graph;
+use dagviz::io::FormatError;
+use dagviz::io::graphlib::{self, NodeIds};
+use dagviz::layout::layout;
+use dagviz::types::{Edge, LabelPosition, Point, RankDirection, Ranker};
+use serde_json::json;
+
+/// dagre 测试中常见的复合多重图
+fn sample() -> serde_json::Value {
+    json!({
+        "options": { "directed": true, "multigraph": true, "compound": true },
+        "nodes": [
+            { "v": "a", "value": { "label": "A", "width": 50, "height": 20, "class": "start" }, "parent": "sg" },
+            { "v": "b", "value": { "width": "30", "height": 40 } },
+            { "v": "sg", "value": {} }
+        ],
+        "edges": [
+            { "v": "a", "w": "b", "value": { "minlen": 2, "weight": 3 } },
+            { "v": "a", "w": "b", "name": "alt", "value": { "labelpos": "l", "width": 10, "height": 5, "style": "dashed" } }
+        ],
+        "value": { "rankdir": "lr", "nodesep": 30, "ranker": "longest-path", "align": "UL" }
+    })
+}
+
+fn read(value: serde_json::Value) -> (Graph, NodeIds) {
+    match graphlib::from_value(value) {
+        Ok(result) => result,
+        Err(err) => panic!("failed to read graphlib document: {}", err),
+    }
+}
+
+fn read_err(value: serde_json::Value) -> FormatError {
+    match graphlib::from_value(value) {
+        Ok(_) => panic!("expected document to be rejected"),
+        Err(err) => err,
+    }
+}
+
+#[test]
+fn test_reads_nodes_edges_and_graph_attributes() {
+    let (g, ids) = read(sample());
+
+    assert_eq!(g.node_count(), 3);
+    assert_eq!(g.parent(ids["a"]), Some(ids["sg"]));
+
+    let a = g.node_label(ids["a"]).unwrap();
+    assert_eq!(a.label.as_deref(), Some("A"));
+    assert_eq!(a.width, 50.0);
+    assert_eq!(a.custom["class"], json!("start"));
+    // dagre 会把数字字符串转换成数字
+    assert_eq!(g.node_label(ids["b"]).unwrap().width, 30.0);
+
+    let plain = g.edge_label(&Edge::new(ids["a"], ids["b"])).unwrap();
+    assert_eq!(plain.minlen, 2);
+    assert_eq!(plain.weight, 3.0);
+    assert_eq!(plain.labelpos, LabelPosition::Right);
+
+    let alt = g
+        .edge_label(&Edge::with_name(ids["a"], ids["b"], "alt"))
+        .unwrap();
+    assert_eq!(alt.labelpos, LabelPosition::Left);
+    assert_eq!(alt.custom["style"], json!("dashed"));
+
+    let config = g.config();
+    assert_eq!(config.rankdir, RankDirection::LeftRight);
+    assert_eq!(config.node_sep, 30.0);
+    assert_eq!(config.edge_sep, 20.0);
+    assert_eq!(config.ranker, Ranker::LongestPath);
+    assert_eq!(config.acyclicer, "dfs");
+    assert_eq!(config.custom["align"], json!("UL"));
+}
+
+#[test]
+fn test_write_then_read_is_lossless() {
+    let (g, ids) = read(sample());
+
+    let written = graphlib::to_value(&g, Some(&ids));
+    let (restored, restored_ids) = read(written.clone());
+
+    assert_eq!(graphlib::to_value(&restored, Some(&restored_ids)), written);
+    assert_eq!(
+        written["options"],
+        json!({ "directed": true, "multigraph": true, "compound": true })
+    );
+    assert_eq!(written["nodes"][0]["v"], json!("a"));
+    assert_eq!(written["nodes"][0]["parent"], json!("sg"));
+    assert_eq!(written["nodes"][0]["value"]["class"], json!("start"));
+    assert_eq!(written["edges"][1]["name"], json!("alt"));
+    assert_eq!(written["edges"][1]["value"]["labelpos"], json!("l"));
+    assert_eq!(written["value"]["rankdir"], json!("LR"));
+}
+
+#[test]
+fn test_writes_layout_results() {
+    let (mut g, ids) = read(json!({
+        "nodes": [
+            { "v": "a", "value": { "width": 50, "height": 20 } },
+            { "v": "b", "value": { "width": 30, "height": 40 } }
+        ],
+        "edges": [{ "v": "a", "w": "b", "value": {} }],
+        "value": {}
+    }));
+    layout(&mut g, None);
+
+    let written = graphlib::to_value(&g, Some(&ids));
+
+    let a = g.node_label(ids["a"]).unwrap();
+    assert_eq!(written["nodes"][0]["value"]["x"], json!(a.x.unwrap()));
+    assert_eq!(written["nodes"][0]["value"]["y"], json!(a.y.unwrap()));
+    let points = &g.edge_label(&Edge::new(ids["a"], ids["b"])).unwrap().points;
+    assert!(!points.is_empty());
+    assert_eq!(written["edges"][0]["value"]["points"], json!(points));
+    assert_eq!(written["value"]["width"], json!(g.config().width.unwrap()));
+
+    let (restored, restored_ids) = read(written);
+    assert_eq!(
+        restored
+            .edge_label(&Edge::new(restored_ids["a"], restored_ids["b"]))
+            .unwrap()
+            .points,
+        *points
+    );
+}
+
+#[test]
+fn test_creates_missing_nodes_like_graphlib() {
+    let (g, ids) = read(json!({
+        "options": { "compound": true },
+        "nodes": [{ "v": "a", "parent": "p" }],
+        "edges": [{ "v": "a", "w": "b" }]
+    }));
+
+    assert_eq!(g.node_count(), 3);
+    assert_eq!(g.parent(ids["a"]), Some(ids["p"]));
+    assert!(g.has_edge(&Edge::new(ids["a"], ids["b"])));
+}
+
+#[test]
+fn test_ignores_edge_names_outside_multigraphs() {
+    let (g, ids) = read(json!({
+        "nodes": [{ "v": "a" }, { "v": "b" }],
+        "edges": [
+            { "v": "a", "w": "b", "name": "x", "value": { "weight": 1 } },
+            { "v": "a", "w": "b", "name": "y", "value": { "weight": 2 } }
+        ]
+    }));
+
+    assert_eq!(g.edge_count(), 1);
+    assert_eq!(
+        g.edge_label(&Edge::new(ids["a"], ids["b"])).unwrap().weight,
+        2.0
+    );
+}
+
+#[test]
+fn test_uses_node_indices_without_ids() {
+    let (g, _) = read(json!({
+        "nodes": [{ "v": "a" }, { "v": "b" }],
+        "edges": [{ "v": "a", "w": "b", "value": { "points": [{ "x": 1, "y": 2 }] } }]
+    }));
+
+    let written = graphlib::to_value(&g, None);
+
+    assert_eq!(written["nodes"][1]["v"], json!("1"));
+    assert_eq!(written["edges"][0]["v"], json!("0"));
+    assert_eq!(
+        written["edges"][0]["value"]["points"],
+        json!([Point::new(1.0, 2.0)])
+    );
+}
+
+#[test]
+fn test_rejects_invalid_attributes() {
+    let err = read_err(json!({ "nodes": [{ "v": "a", "value": { "width": "wide" } }] }));
+    assert!(matches!(err, FormatError::InvalidAttribute { ref key, .. } if key == "width"));
+
+    let err = read_err(json!({ "value": { "rankdir": "diagonal" } }));
+    assert!(matches!(err, FormatError::InvalidAttribute { ref key, .. } if key == "rankdir"));
+
+    let err = read_err(json!({ "edges": [{ "v": "a", "w": "b", "value": { "minlen": 1.5 } }] }));
+    assert!(matches!(err, FormatError::InvalidAttribute { ref key, .. } if key == "minlen"));
+
+    let err = read_err(json!({ "options": { "directed": false } }));
+    assert!(matches!(err, FormatError::Unsupported(_)));
+}
@@ -0,0 +1,298 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::io::{FormatError, GraphDocument, SCHEMA_VERSION, json};
+use dagviz::layout::layout;
+use dagviz::types::{
+    Edge, EdgeLabel, LabelPosition, NodeLabel, OrderConstraint, Point, RankConstraint,
+    RankDirection, RankKind, Ranker,
+};
+use serde_json::json;
+
+/// 构建一个覆盖父节点、命名边和自定义属性的图
+fn sample_graph() -> Graph {
+    let mut g = Graph::new();
+    g.config_mut().rankdir = RankDirection::LeftRight;
+    g.config_mut().ranker = Ranker::LongestPath;
+    g.config_mut()
+        .custom
+        .insert("theme".to_string(), json!("dark"));
+
+    let a = g.add_node(NodeLabel {
+        label: Some("A".to_string()),
+        width: 50.0,
+        height: 20.0,
+        ..Default::default()
+    });
+    let b = g.add_node(NodeLabel {
+        width: 30.0,
+        height: 40.0,
+        ..Default::default()
+    });
+    let cluster = g.add_node(NodeLabel {
+        label: Some("cluster".to_string()),
+        ..Default::default()
+    });
+    g.set_parent(a, cluster);
+    g.set_parent(b, cluster);
+    g.node_label_mut(b)
+        .unwrap()
+        .custom
+        .insert("data".to_string(), json!({ "id": 42, "tags": ["x", "y"] }));
+
+    g.add_edge(Edge::new(a, b), EdgeLabel::default());
+    g.add_edge(
+        Edge::with_name(a, b, "second"),
+        EdgeLabel {
+            weight: 2.0,
+            minlen: 3,
+            labelpos: LabelPosition::Left,
+            ..Default::default()
+        },
+    );
+    g.edge_label_mut(&Edge::with_name(a, b, "second"))
+        .unwrap()
+        .custom
+        .insert("color".to_string(), json!("red"));
+
+    g
+}
+
+/// 读取应当失败的文档
+fn read_err(text: &str) -> FormatError {
+    match json::from_str(text) {
+        Ok(_) => panic!("expected {} to be rejected", text),
+        Err(err) => err,
+    }
+}
+
+/// 读取后的图有新的图 ID，按位置取节点
+fn nth_node(g: &Graph, n: usize) -> NodeIndex {
+    g.node_indices().nth(n).unwrap()
+}
+
+/// 比较两个图的序列化结果，覆盖所有参与序列化的字段
+fn assert_same(left: &Graph, right: &Graph) {
+    assert_eq!(
+        serde_json::to_value(left).unwrap(),
+        serde_json::to_value(right).unwrap()
+    );
+}
+
+#[test]
+fn test_round_trips_structure_and_custom_data() {
+    let g = sample_graph();
+
+    let text = json::to_string(&g).unwrap();
+    let restored = json::from_str(&text).unwrap();
+
+    assert_same(&g, &restored);
+    assert_eq!(restored.node_count(), 3);
+    assert_eq!(restored.edge_count(), 2);
+    assert_eq!(
+        restored.parent(nth_node(&restored, 0)),
+        Some(nth_node(&restored, 2))
+    );
+    assert_eq!(restored.config().rankdir, RankDirection::LeftRight);
+    assert_eq!(restored.config().custom["theme"], json!("dark"));
+
+    let second = restored
+        .edges()
+        .into_iter()
+        .find(|e| e.name.as_deref() == Some("second"))
+        .unwrap();
+    let label = restored.edge_label(&second).unwrap();
+    assert_eq!(label.minlen, 3);
+    assert_eq!(label.labelpos, LabelPosition::Left);
+    assert_eq!(label.custom["color"], json!("red"));
+}
+
+#[test]
+fn test_round_trips_layout_results() {
+    let mut g = Graph::new();
+    let a = g.add_node(NodeLabel {
+        width: 50.0,
+        height: 20.0,
+        ..Default::default()
+    });
+    let b = g.add_node(NodeLabel {
+        width: 30.0,
+        height: 40.0,
+        ..Default::default()
+    });
+    g.add_edge(Edge::new(a, b), EdgeLabel::default());
+    layout(&mut g, None);
+
+    let restored: Graph = serde_json::from_str(&serde_json::to_string(&g).unwrap()).unwrap();
+
+    assert_same(&g, &restored);
+    let (ra, rb) = (nth_node(&restored, 0), nth_node(&restored, 1));
+    let label = restored.node_label(rb).unwrap();
+    assert_eq!(label.x, g.node_label(b).unwrap().x);
+    assert_eq!(label.y, g.node_label(b).unwrap().y);
+    assert!(label.x.is_some());
+    assert_eq!(
+        restored.edge_label(&Edge::new(ra, rb)).unwrap().points,
+        g.edge_label(&Edge::new(a, b)).unwrap().points
+    );
+    assert_eq!(restored.config().width, g.config().width);
+}
+
+#[test]
+fn test_round_trips_rank_constraints() {
+    let mut g = sample_graph();
+    let (a, b) = (nth_node(&g, 0), nth_node(&g, 1));
+    g.add_rank_constraint(RankConstraint::new(RankKind::Min, [b, a]));
+
+    let value = serde_json::to_value(&g).unwrap();
+    assert_eq!(
+        value["rank_constraints"],
+        json!([{ "kind": "min", "nodes": [1, 0] }])
+    );
+
+    let restored = json::from_str(&value.to_string()).unwrap();
+    assert_same(&g, &restored);
+    let (ra, rb) = (nth_node(&restored, 0), nth_node(&restored, 1));
+    assert_eq!(
+        restored.rank_constraints(),
+        &[RankConstraint::new(RankKind::Min, [rb, ra])]
+    );
+}
+
+#[test]
+fn test_round_trips_order_constraints() {
+    let mut g = sample_graph();
+    let (a, b) = (nth_node(&g, 0), nth_node(&g, 1));
+    g.add_order_constraint(OrderConstraint::sequence([b, a]));
+    g.add_order_constraint(OrderConstraint::rightmost([a]));
+
+    let value = serde_json::to_value(&g).unwrap();
+    assert_eq!(
+        value["order_constraints"],
+        json!([
+            { "kind": "sequence", "nodes": [1, 0] },
+            { "kind": "rightmost", "nodes": [0] }
+        ])
+    );
+
+    let restored = json::from_str(&value.to_string()).unwrap();
+    assert_same(&g, &restored);
+    let (ra, rb) = (nth_node(&restored, 0), nth_node(&restored, 1));
+    assert_eq!(
+        restored.order_constraints(),
+        &[
+            OrderConstraint::sequence([rb, ra]),
+            OrderConstraint::rightmost([ra])
+        ]
+    );
+}
+
+#[test]
+fn test_writes_documented_schema() {
+    let g = sample_graph();
+    let value = serde_json::to_value(&g).unwrap();
+
+    assert_eq!(value["version"], json!(SCHEMA_VERSION));
+    assert_eq!(value["config"]["rankdir"], json!("LR"));
+    assert_eq!(value["config"]["ranker"], json!("longest-path"));
+    assert_eq!(
+        value["nodes"][0],
+        json!({ "id": 0, "parent": 2, "label": "A", "width": 50.0, "height": 20.0 })
+    );
+    assert_eq!(value["edges"][1]["name"], json!("second"));
+    assert_eq!(value["edges"][1]["labelpos"], json!("left"));
+    // 内部字段不出现在输出中
+    assert!(value["edges"][0].get("reversed").is_none());
+    assert!(value["config"].get("dummy_chains").is_none());
+    assert!(value.get("rank_constraints").is_none());
+    assert!(value.get("order_constraints").is_none());
+}
+
+#[test]
+fn test_missing_fields_take_defaults() {
+    let g = json::from_str(
+        r#"{
+            "version": 1,
+            "nodes": [{ "id": 7, "parent": 9 }, { "id": 9 }],
+            "edges": [{ "source": 7, "target": 9, "points": [{ "x": 1.0, "y": 2.0 }] }]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(g.config().node_sep, 50.0);
+    let (child, parent) = {
+        let nodes: Vec<_> = g.node_indices().collect();
+        (nodes[0], nodes[1])
+    };
+    assert_eq!(g.parent(child), Some(parent));
+
+    let label = g.edge_label(&Edge::new(child, parent)).unwrap();
+    assert_eq!(label.minlen, 1);
+    assert_eq!(label.weight, 1.0);
+    assert_eq!(label.labeloffset, 10.0);
+    assert_eq!(label.points, vec![Point::new(1.0, 2.0)]);
+}
+
+#[test]
+fn test_document_maps_ids_to_nodes() {
+    let document: GraphDocument = serde_json::from_value(json!({
+        "version": 1,
+        "nodes": [{ "id": 10 }, { "id": 3 }],
+        "edges": [{ "source": 10, "target": 3 }]
+    }))
+    .unwrap();
+
+    let (g, ids) = document.into_graph_with_ids().unwrap();
+
+    assert!(g.has_edge(&Edge::new(ids[&10], ids[&3])));
+}
+
+#[test]
+fn test_rejects_newer_versions() {
+    let err = read_err(r#"{ "version": 99, "graph": "something else" }"#);
+    assert!(matches!(
+        err,
+        FormatError::UnsupportedVersion { found: 99, .. }
+    ));
+
+    let err = read_err(r#"{ "nodes": [] }"#);
+    assert!(matches!(err, FormatError::MissingVersion));
+}
+
+#[test]
+fn test_rejects_dangling_references() {
+    let err = read_err(r#"{ "version": 1, "nodes": [{ "id": 0, "parent": 5 }] }"#);
+    assert!(matches!(err, FormatError::UnknownNode { id: 5 }));
+
+    let err = read_err(
+        r#"{ "version": 1, "nodes": [{ "id": 0 }], "edges": [{ "source": 0, "target": 1 }] }"#,
+    );
+    assert!(matches!(err, FormatError::UnknownNode { id: 1 }));
+
+    let err = read_err(r#"{ "version": 1, "nodes": [{ "id": 0 }, { "id": 0 }] }"#);
+    assert!(matches!(err, FormatError::DuplicateNode { id: 0 }));
+
+    let err = read_err(
+        r#"{ "version": 1, "nodes": [{ "id": 0 }, { "id": 1 }],
+             "edges": [{ "source": 0, "target": 1 }, { "source": 0, "target": 1 }] }"#,
+    );
+    assert!(matches!(err, FormatError::DuplicateEdge { .. }));
+
+    let err = read_err(
+        r#"{ "version": 2, "nodes": [{ "id": 0 }],
+             "rank_constraints": [{ "kind": "same", "nodes": [0, 4] }] }"#,
+    );
+    assert!(matches!(err, FormatError::UnknownNode { id: 4 }));
+
+    let err = read_err(
+        r#"{ "version": 2, "nodes": [{ "id": 0 }],
+             "order_constraints": [{ "kind": "leftmost", "nodes": [2] }] }"#,
+    );
+    assert!(matches!(err, FormatError::UnknownNode { id: 2 }));
+}
+
+#[test]
+fn test_deserialize_reports_format_errors() {
+    let result: Result<Graph, _> =
+        serde_json::from_str(r#"{ "version": 1, "nodes": [{ "id": 0, "parent": 1 }] }"#);
+    let err = result.err().unwrap().to_string();
+    assert!(err.contains("node 1 does not exist"), "{}", err);
+}
@@ -0,0 +1,5 @@
+// IO tests module
+
+mod dot_tests;
+mod graphlib_tests;
+mod json_tests;
@@ -0,0 +1,192 @@
+use dagviz::budget::{Budget, BudgetKind, LayoutBudget};
+use dagviz::context::LayoutContext;
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::{layout, try_layout};
+use dagviz::types::{Edge, EdgeLabel, LayoutOptions, NodeLabel};
+use dagviz::util::stage_with_context;
+use std::time::Duration;
+
+/// Gansner 等人论文中的例子，网络单纯形需要换边才能得到最优排名
+fn gansner() -> (Graph, Vec<NodeIndex>) {
+    let mut g = Graph::new();
+    let nodes: Vec<NodeIndex> = (0..8)
+        .map(|_| {
+            g.add_node(NodeLabel {
+                width: 20.0,
+                height: 20.0,
+                ..Default::default()
+            })
+        })
+        .collect();
+    for (v, w) in [
+        (0, 1),
+        (1, 2),
+        (2, 3),
+        (3, 7),
+        (0, 4),
+        (4, 6),
+        (0, 5),
+        (5, 6),
+        (6, 7),
+    ] {
+        g.add_edge(Edge::new(nodes[v], nodes[w]), EdgeLabel::default());
+    }
+    (g, nodes)
+}
+
+fn with_budget(budget: LayoutBudget) -> LayoutOptions {
+    LayoutOptions {
+        budget,
+        ..Default::default()
+    }
+}
+
+/// 每条边的目标节点都在源节点下方
+fn assert_edges_point_down(g: &Graph) {
+    for edge in g.edges() {
+        let source = g.node_label(edge.source).unwrap().y.unwrap();
+        let target = g.node_label(edge.target).unwrap().y.unwrap();
+        assert!(source < target, "edge {:?} does not point down", edge);
+    }
+}
+
+#[test]
+fn test_unlimited_budget_is_never_exceeded() {
+    let (mut g, _) = gansner();
+    let stats = layout(&mut g, None);
+    assert!(stats.budgets_exceeded.is_empty());
+    assert!(stats.network_simplex_pivots > 0);
+}
+
+#[test]
+fn test_order_sweeps_stop_at_the_limit() {
+    let (mut g, _) = gansner();
+    let opts = with_budget(LayoutBudget {
+        max_order_sweeps: Some(1),
+        ..Default::default()
+    });
+    let stats = layout(&mut g, Some(&opts));
+
+    assert_eq!(stats.order_sweeps, 1);
+    assert_eq!(stats.budgets_exceeded, vec![BudgetKind::OrderSweeps]);
+    assert_edges_point_down(&g);
+}
+
+#[test]
+fn test_order_sweeps_keep_the_best_order_seen_before_the_limit() {
+    // 第二次扫描的交叉比第一次多，预算在这之后用完
+    let tangled = || {
+        let mut g = Graph::new();
+        let nodes: Vec<NodeIndex> = (0..11)
+            .map(|_| {
+                g.add_node(NodeLabel {
+                    width: 20.0,
+                    height: 20.0,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        for (v, w) in [
+            (3, 5),
+            (2, 4),
+            (3, 4),
+            (0, 6),
+            (1, 6),
+            (7, 10),
+            (6, 9),
+            (7, 9),
+            (4, 8),
+        ] {
+            g.add_edge(Edge::new(nodes[v], nodes[w]), EdgeLabel::default());
+        }
+        (g, nodes)
+    };
+    let x = |g: &Graph, nodes: &[NodeIndex]| -> Vec<f64> {
+        nodes
+            .iter()
+            .map(|&node| g.node_label(node).unwrap().x.unwrap())
+            .collect()
+    };
+    let sweeps = |limit| {
+        with_budget(LayoutBudget {
+            max_order_sweeps: Some(limit),
+            ..Default::default()
+        })
+    };
+
+    let (mut first, first_nodes) = tangled();
+    let first_stats = layout(&mut first, Some(&sweeps(1)));
+    let (mut g, nodes) = tangled();
+    let stats = layout(&mut g, Some(&sweeps(2)));
+
+    assert_eq!(stats.crossings.len(), 2);
+    assert_eq!(stats.crossings[0], first_stats.crossings[0]);
+    assert!(stats.crossings[1] > stats.crossings[0]);
+    assert_eq!(x(&g, &nodes), x(&first, &first_nodes));
+}
+
+#[test]
+fn test_simplex_pivot_limit_keeps_a_feasible_ranking() {
+    let (mut g, nodes) = gansner();
+    let opts = with_budget(LayoutBudget {
+        max_simplex_pivots: Some(0),
+        ..Default::default()
+    });
+    let stats = layout(&mut g, Some(&opts));
+
+    assert_eq!(stats.network_simplex_pivots, 0);
+    assert_eq!(stats.budgets_exceeded, vec![BudgetKind::SimplexPivots]);
+    assert_edges_point_down(&g);
+
+    // 未优化的排名与最优排名不同
+    let (mut optimal, optimal_nodes) = gansner();
+    layout(&mut optimal, None);
+    let y = |g: &Graph, nodes: &[NodeIndex]| -> Vec<f64> {
+        nodes
+            .iter()
+            .map(|&node| g.node_label(node).unwrap().y.unwrap())
+            .collect()
+    };
+    assert_ne!(y(&g, &nodes), y(&optimal, &optimal_nodes));
+}
+
+#[test]
+fn test_expired_time_limit_still_produces_a_layout() {
+    let (mut g, nodes) = gansner();
+    let opts = with_budget(LayoutBudget {
+        time_limit: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    let result = try_layout(&mut g, Some(&opts)).unwrap();
+
+    assert!(
+        result
+            .stats
+            .budgets_exceeded
+            .contains(&BudgetKind::TimeLimit)
+    );
+    assert_eq!(result.stats.order_sweeps, 0);
+    assert_eq!(result.stats.network_simplex_pivots, 0);
+    for node in nodes {
+        let label = g.node_label(node).unwrap();
+        assert!(label.x.is_some() && label.y.is_some());
+    }
+    assert_edges_point_down(&g);
+}
+
+#[test]
+fn test_deadline_is_checked_after_each_stage() {
+    let (mut g, _) = gansner();
+    let mut ctx = LayoutContext::with_budget(Budget::start(LayoutBudget {
+        time_limit: Some(Duration::ZERO),
+        ..Default::default()
+    }));
+
+    // 阶段本身不检查预算，截止时间仍然被记录
+    stage_with_context("noop", &mut g, &mut ctx, |_, _| {});
+    assert_eq!(ctx.stats.budgets_exceeded, [BudgetKind::TimeLimit]);
+
+    let mut unlimited = LayoutContext::new();
+    stage_with_context("noop", &mut g, &mut unlimited, |_, _| {});
+    assert!(unlimited.stats.budgets_exceeded.is_empty());
+}
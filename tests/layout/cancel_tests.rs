@@ -0,0 +1,123 @@
+use dagviz::budget::{Budget, CancellationToken};
+use dagviz::error::LayoutError;
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::{layout, try_layout};
+use dagviz::position::bk::BrandesKoepf;
+use dagviz::types::{Edge, EdgeLabel, LayoutOptions, NodeLabel};
+use std::thread;
+use std::time::Duration;
+
+/// 每层 `width` 个节点，相邻两层之间全连接
+fn layered(layers: usize, width: usize) -> (Graph, Vec<NodeIndex>) {
+    let mut g = Graph::new();
+    let nodes: Vec<NodeIndex> = (0..layers * width)
+        .map(|_| {
+            g.add_node(NodeLabel {
+                width: 20.0,
+                height: 20.0,
+                ..Default::default()
+            })
+        })
+        .collect();
+    for layer in 1..layers {
+        for v in 0..width {
+            for w in 0..width {
+                let source = nodes[(layer - 1) * width + v];
+                let target = nodes[layer * width + (v * 7 + w) % width];
+                g.add_edge(Edge::new(source, target), EdgeLabel::default());
+            }
+        }
+    }
+    (g, nodes)
+}
+
+fn cancellable(cancel: &CancellationToken) -> LayoutOptions {
+    LayoutOptions {
+        cancel: Some(cancel.clone()),
+        ..Default::default()
+    }
+}
+
+fn assert_untouched(g: &Graph, nodes: &[NodeIndex]) {
+    assert!(g.config().width.is_none());
+    for &node in nodes {
+        let label = g.node_label(node).unwrap();
+        assert!(label.x.is_none() && label.y.is_none() && label.rank.is_none());
+    }
+    for edge in g.edges() {
+        assert!(g.edge_label(&edge).unwrap().points.is_empty());
+    }
+}
+
+#[test]
+fn test_cancelled_layout_leaves_input_untouched() {
+    let (mut g, nodes) = layered(3, 3);
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+
+    let err = try_layout(&mut g, Some(&cancellable(&cancel))).unwrap_err();
+    assert_eq!(err, LayoutError::Cancelled);
+    assert_untouched(&g, &nodes);
+
+    let stats = layout(&mut g, Some(&cancellable(&cancel)));
+    assert!(stats.cancelled);
+    assert_eq!(stats.order_sweeps, 0);
+    assert_eq!(stats.network_simplex_pivots, 0);
+    assert_untouched(&g, &nodes);
+}
+
+#[test]
+fn test_uncancelled_token_does_not_affect_layout() {
+    let (mut g, nodes) = layered(3, 3);
+    let cancel = CancellationToken::new();
+
+    let result = try_layout(&mut g, Some(&cancellable(&cancel))).unwrap();
+    assert!(!result.stats.cancelled);
+    for node in nodes {
+        assert!(g.node_label(node).unwrap().x.is_some());
+    }
+}
+
+#[test]
+fn test_brandes_koepf_stops_when_cancelled() {
+    let (mut g, nodes) = layered(2, 2);
+    for (i, &node) in nodes.iter().enumerate() {
+        let label = g.node_label_mut(node).unwrap();
+        label.rank = Some((i / 2) as i32);
+        label.order = Some(i % 2);
+    }
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let budget = Budget::default().with_cancellation(Some(cancel));
+
+    assert!(
+        BrandesKoepf::new(g)
+            .with_budget(budget)
+            .run()
+            .positions
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_layout_can_be_cancelled_from_another_thread() {
+    let (mut g, nodes) = layered(12, 12);
+    let cancel = CancellationToken::new();
+    let canceller = {
+        let cancel = cancel.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(1));
+            cancel.cancel();
+        })
+    };
+
+    // 布局可能在取消之前完成，两种结果都要正确
+    match try_layout(&mut g, Some(&cancellable(&cancel))) {
+        Ok(result) => assert!(!result.stats.cancelled),
+        Err(err) => {
+            assert_eq!(err, LayoutError::Cancelled);
+            assert_untouched(&g, &nodes);
+        }
+    }
+    canceller.join().unwrap();
+}
@@ -0,0 +1,138 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::layout;
+use dagviz::types::{Edge, EdgeLabel, RankDirection};
+
+use super::node;
+
+fn chain(g: &mut Graph, len: usize) -> Vec<NodeIndex> {
+    let nodes: Vec<NodeIndex> = (0..len).map(|_| node(g)).collect();
+    for pair in nodes.windows(2) {
+        g.add_edge(Edge::new(pair[0], pair[1]), EdgeLabel::default());
+    }
+    nodes
+}
+
+fn loose(g: &mut Graph, source: NodeIndex, target: NodeIndex) -> Edge {
+    let edge = Edge::new(source, target);
+    let label = EdgeLabel {
+        constraint: false,
+        ..Default::default()
+    };
+    g.add_edge(edge.clone(), label);
+    edge
+}
+
+fn rank(g: &Graph, node: NodeIndex) -> i32 {
+    g.node_label(node).unwrap().rank.unwrap()
+}
+
+#[test]
+fn test_non_constraint_edges_do_not_change_ranks() {
+    let mut g = Graph::new();
+    let nodes = chain(&mut g, 3);
+    let extra = node(&mut g);
+    g.add_edge(Edge::new(nodes[0], extra), EdgeLabel::default());
+    // 普通的边会把 extra 推到 nodes[2] 之下
+    loose(&mut g, nodes[2], extra);
+
+    layout(&mut g, None);
+
+    assert_eq!(rank(&g, extra), rank(&g, nodes[1]));
+}
+
+#[test]
+fn test_upward_edges_keep_their_direction() {
+    let mut g = Graph::new();
+    let nodes = chain(&mut g, 4);
+    let edge = loose(&mut g, nodes[3], nodes[0]);
+
+    layout(&mut g, None);
+
+    // 不参与去环，排名仍由链决定
+    for pair in nodes.windows(2) {
+        assert!(rank(&g, pair[0]) < rank(&g, pair[1]));
+    }
+    let bottom = g.node_label(nodes[3]).unwrap();
+    let top = g.node_label(nodes[0]).unwrap();
+    let points = &g.edge_label(&edge).unwrap().points;
+    // 跨越多个排名的边拆分成虚拟节点链，起点在源节点上边、终点在目标节点下边
+    assert!(points.len() > 2, "{points:?}");
+    assert_eq!(points[0].y, bottom.y.unwrap() - bottom.height / 2.0);
+    assert_eq!(points.last().unwrap().y, top.y.unwrap() + top.height / 2.0);
+    for pair in points.windows(2) {
+        assert!(pair[0].y >= pair[1].y, "{points:?}");
+    }
+    assert!(g.edge_label(&edge).unwrap().reversed.is_none());
+}
+
+#[test]
+fn test_labeled_upward_edges_get_a_label_position() {
+    let mut g = Graph::new();
+    let nodes = chain(&mut g, 3);
+    let edge = Edge::new(nodes[2], nodes[0]);
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            constraint: false,
+            width: 30.0,
+            height: 10.0,
+            ..Default::default()
+        },
+    );
+
+    layout(&mut g, None);
+
+    let label = g.edge_label(&edge).unwrap();
+    let y = label.y.unwrap();
+    assert!(y > g.node_label(nodes[0]).unwrap().y.unwrap());
+    assert!(y < g.node_label(nodes[2]).unwrap().y.unwrap());
+}
+
+#[test]
+fn test_edges_between_nodes_on_the_same_rank_are_flat() {
+    let mut g = Graph::new();
+    let root = node(&mut g);
+    let a = node(&mut g);
+    let b = node(&mut g);
+    g.add_edge(Edge::new(root, a), EdgeLabel::default());
+    g.add_edge(Edge::new(root, b), EdgeLabel::default());
+    let edge = loose(&mut g, b, a);
+
+    layout(&mut g, None);
+
+    let (a, b) = (g.node_label(a).unwrap(), g.node_label(b).unwrap());
+    assert_eq!(a.rank, b.rank);
+    let points = &g.edge_label(&edge).unwrap().points;
+    assert!(points.iter().all(|p| Some(p.y) == a.y), "{points:?}");
+}
+
+#[test]
+fn test_isolated_endpoints_are_ranked_on_their_own() {
+    for rankdir in [RankDirection::TopBottom, RankDirection::LeftRight] {
+        let mut g = Graph::new();
+        g.config_mut().rankdir = rankdir;
+        let nodes = chain(&mut g, 3);
+        let note = node(&mut g);
+        let edge = loose(&mut g, note, nodes[2]);
+
+        layout(&mut g, None);
+
+        assert_eq!(rank(&g, note), rank(&g, nodes[0]), "{rankdir:?}");
+        assert!(g.edge_label(&edge).unwrap().points.len() >= 2);
+    }
+}
+
+#[test]
+fn test_constraint_defaults_to_true_and_round_trips_through_json() {
+    assert!(EdgeLabel::default().constraint);
+    let json = serde_json::to_value(EdgeLabel::default()).unwrap();
+    assert!(json.get("constraint").is_none());
+    let loose = EdgeLabel {
+        constraint: false,
+        ..Default::default()
+    };
+    let json = serde_json::to_value(&loose).unwrap();
+    assert_eq!(json["constraint"], serde_json::json!(false));
+    let back: EdgeLabel = serde_json::from_value(json).unwrap();
+    assert!(!back.constraint);
+}
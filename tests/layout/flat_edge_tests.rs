@@ -0,0 +1,147 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::layout;
+use dagviz::types::{
+    Edge, EdgeLabel, GraphConfig, NodeLabel, OrderConstraint, Point, RankDirection,
+};
+
+use super::node;
+
+fn flat(g: &mut Graph, source: NodeIndex, target: NodeIndex, width: f64, height: f64) -> Edge {
+    let edge = Edge::new(source, target);
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            minlen: 0,
+            width,
+            height,
+            ..Default::default()
+        },
+    );
+    edge
+}
+
+fn label(g: &Graph, node: NodeIndex) -> &NodeLabel {
+    g.node_label(node).unwrap()
+}
+
+/// 根节点下挂三个子节点，第一个和第三个之间有一条同层边
+fn siblings(rankdir: RankDirection) -> (Graph, [NodeIndex; 4]) {
+    let mut g = Graph::with_config(GraphConfig {
+        rankdir,
+        ..Default::default()
+    });
+    let nodes = [node(&mut g), node(&mut g), node(&mut g), node(&mut g)];
+    let [root, a, x, b] = nodes;
+    for child in [a, x, b] {
+        g.add_edge(Edge::new(root, child), EdgeLabel::default());
+    }
+    (g, [root, a, x, b])
+}
+
+#[test]
+fn test_minlen_zero_keeps_endpoints_on_the_same_rank() {
+    let mut g = Graph::new();
+    let a = node(&mut g);
+    let b = node(&mut g);
+    let c = node(&mut g);
+    flat(&mut g, a, b, 0.0, 0.0);
+    g.add_edge(Edge::new(b, c), EdgeLabel::default());
+
+    layout(&mut g, None);
+
+    assert_eq!(label(&g, a).y, label(&g, b).y);
+    assert!(label(&g, c).y > label(&g, b).y);
+    assert!(label(&g, a).x < label(&g, b).x);
+}
+
+#[test]
+fn test_flat_endpoints_are_placed_next_to_each_other() {
+    let (mut g, [_, a, x, b]) = siblings(RankDirection::TopBottom);
+    flat(&mut g, a, b, 0.0, 0.0);
+
+    layout(&mut g, None);
+
+    let (a, x, b) = (label(&g, a), label(&g, x), label(&g, b));
+    assert_eq!(b.order.unwrap(), a.order.unwrap() + 1);
+    assert!(a.x < b.x);
+    assert!(x.order != Some(1), "x should not separate the flat edge");
+}
+
+#[test]
+fn test_flat_edges_are_horizontal() {
+    let (mut g, [_, a, _, b]) = siblings(RankDirection::TopBottom);
+    let edge = flat(&mut g, a, b, 0.0, 0.0);
+
+    layout(&mut g, None);
+
+    let (a, b) = (label(&g, a), label(&g, b));
+    let points = &g.edge_label(&edge).unwrap().points;
+    let y = a.y.unwrap();
+    assert_eq!(
+        points,
+        &vec![
+            Point::new(a.x.unwrap() + a.width / 2.0, y),
+            Point::new(b.x.unwrap() - b.width / 2.0, y),
+        ]
+    );
+}
+
+#[test]
+fn test_flat_edge_labels_sit_above_the_edge_between_the_nodes() {
+    let (mut g, [_, a, _, b]) = siblings(RankDirection::TopBottom);
+    let edge = flat(&mut g, a, b, 80.0, 14.0);
+
+    layout(&mut g, None);
+
+    let (a, b) = (label(&g, a), label(&g, b));
+    let edge = g.edge_label(&edge).unwrap();
+    let (x, y) = (edge.x.unwrap(), edge.y.unwrap());
+    assert_eq!(y + edge.height / 2.0, a.y.unwrap());
+    // 两端之间留出了标签的宽度
+    assert!(a.x.unwrap() + a.width / 2.0 <= x - edge.width / 2.0);
+    assert!(x + edge.width / 2.0 <= b.x.unwrap() - b.width / 2.0);
+    assert_eq!(edge.points.len(), 2);
+}
+
+#[test]
+fn test_blocked_flat_edges_arc_over_the_nodes_between() {
+    let (mut g, [root, a, x, b]) = siblings(RankDirection::TopBottom);
+    let edge = flat(&mut g, a, b, 30.0, 10.0);
+    g.add_order_constraint(OrderConstraint::sequence([a, x, b]));
+
+    layout(&mut g, None);
+
+    let (a, x, b) = (label(&g, a), label(&g, x), label(&g, b));
+    assert!(a.x < x.x && x.x < b.x);
+    let edge = g.edge_label(&edge).unwrap();
+    let points = &edge.points;
+    let top = a.y.unwrap() - a.height / 2.0;
+    assert_eq!(points.len(), 4);
+    assert_eq!(points[0], Point::new(a.x.unwrap(), top));
+    assert_eq!(points[3], Point::new(b.x.unwrap(), top));
+    // 绕行的部分在本排名与上一个排名之间
+    let level = points[1].y;
+    assert_eq!(points[2].y, level);
+    assert!(level < top);
+    let root = label(&g, root);
+    assert!(level > root.y.unwrap() + root.height / 2.0);
+    // 标签在绕行部分的上方
+    assert_eq!(edge.y.unwrap() + edge.height / 2.0, level);
+    assert!(edge.y.unwrap() - edge.height / 2.0 >= 0.0);
+}
+
+#[test]
+fn test_flat_edges_follow_rankdir() {
+    let (mut g, [_, a, _, b]) = siblings(RankDirection::LeftRight);
+    let edge = flat(&mut g, a, b, 30.0, 10.0);
+
+    layout(&mut g, None);
+
+    let (a, b) = (label(&g, a), label(&g, b));
+    assert_eq!(a.x, b.x);
+    let edge = g.edge_label(&edge).unwrap();
+    assert!(edge.points.iter().all(|p| Some(p.x) == a.x));
+    // 标签位于朝向上一个排名的一侧
+    assert_eq!(edge.x.unwrap() + edge.width / 2.0, a.x.unwrap());
+    assert_eq!((edge.width, edge.height), (30.0, 10.0));
+}
@@ -0,0 +1,226 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::layout;
+use dagviz::types::{Edge, EdgeLabel, NodeLabel, Point};
+
+fn add_node(g: &mut Graph, width: f64, height: f64) -> NodeIndex {
+    g.add_node(NodeLabel {
+        width,
+        height,
+        ..Default::default()
+    })
+}
+
+/// 给每条边一个不同的权重，布局后用来确认标签没有被交换
+fn add_edge(g: &mut Graph, edge: Edge, weight: f64) {
+    g.add_edge(
+        edge,
+        EdgeLabel {
+            weight,
+            ..Default::default()
+        },
+    );
+}
+
+/// 点是否落在节点的矩形内（含边界）
+fn inside(g: &Graph, node: NodeIndex, point: &Point) -> bool {
+    let label = g.node_label(node).unwrap();
+    let (x, y) = (label.x.unwrap(), label.y.unwrap());
+    (point.x - x).abs() <= label.width / 2.0 + 1e-9
+        && (point.y - y).abs() <= label.height / 2.0 + 1e-9
+}
+
+/// 边的路径必须从自己的源节点出发，到自己的目标节点结束
+fn assert_routed(g: &Graph, edge: &Edge) {
+    let points = &g.edge_label(edge).unwrap().points;
+    assert!(points.len() >= 2, "edge {:?} has no route", edge);
+    assert!(
+        inside(g, edge.source, points.first().unwrap()),
+        "edge {:?} does not start at its source: {:?}",
+        edge,
+        points
+    );
+    assert!(
+        inside(g, edge.target, points.last().unwrap()),
+        "edge {:?} does not end at its target: {:?}",
+        edge,
+        points
+    );
+}
+
+fn assert_weight(g: &Graph, edge: &Edge, weight: f64) {
+    let label = g.edge_label(edge).unwrap();
+    assert_eq!(label.weight, weight, "label of {:?} was swapped", edge);
+    assert_eq!(label.reversed, None, "edge {:?} is still reversed", edge);
+    assert_eq!(label.forward_name, None);
+}
+
+#[test]
+fn test_nodes_receive_their_own_positions() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 30.0, 60.0);
+    let c = add_node(&mut g, 80.0, 40.0);
+    // c 在 b 之前排名，两个图的节点遍历顺序与排名顺序不同
+    add_edge(&mut g, Edge::new(a, c), 1.0);
+    add_edge(&mut g, Edge::new(c, b), 1.0);
+
+    layout(&mut g, None);
+
+    let rank = |n: NodeIndex| g.node_label(n).unwrap().rank.unwrap();
+    assert!(rank(a) < rank(c));
+    assert!(rank(c) < rank(b));
+
+    let y = |n: NodeIndex| g.node_label(n).unwrap().y.unwrap();
+    assert!(y(a) < y(c));
+    assert!(y(c) < y(b));
+
+    // 尺寸保持不变
+    assert_eq!(g.node_label(b).unwrap().width, 30.0);
+    assert_eq!(g.node_label(c).unwrap().height, 40.0);
+}
+
+#[test]
+fn test_reversed_edges_of_a_two_cycle_keep_their_direction() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 30.0, 40.0);
+    let forward = Edge::new(a, b);
+    let backward = Edge::new(b, a);
+    add_edge(&mut g, forward.clone(), 2.0);
+    add_edge(&mut g, backward.clone(), 3.0);
+
+    layout(&mut g, None);
+
+    assert_eq!(g.edge_count(), 2);
+    assert_routed(&g, &forward);
+    assert_routed(&g, &backward);
+    assert_weight(&g, &forward, 2.0);
+    assert_weight(&g, &backward, 3.0);
+}
+
+#[test]
+fn test_named_parallel_edges_are_routed_separately() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 30.0, 40.0);
+    let unnamed = Edge::new(a, b);
+    let x = Edge::with_name(a, b, "x");
+    let y = Edge::with_name(a, b, "y");
+    add_edge(&mut g, unnamed.clone(), 1.0);
+    add_edge(&mut g, x.clone(), 2.0);
+    add_edge(&mut g, y.clone(), 3.0);
+
+    layout(&mut g, None);
+
+    assert_eq!(g.edge_count(), 3);
+    for edge in [&unnamed, &x, &y] {
+        assert_routed(&g, edge);
+    }
+    assert_weight(&g, &unnamed, 1.0);
+    assert_weight(&g, &x, 2.0);
+    assert_weight(&g, &y, 3.0);
+}
+
+#[test]
+fn test_reversed_parallel_edges_keep_their_names() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 30.0, 40.0);
+    let forward = Edge::new(a, b);
+    let back_x = Edge::with_name(b, a, "x");
+    let back_y = Edge::with_name(b, a, "y");
+    add_edge(&mut g, forward.clone(), 5.0);
+    add_edge(&mut g, back_x.clone(), 1.0);
+    add_edge(&mut g, back_y.clone(), 2.0);
+
+    layout(&mut g, None);
+
+    assert_eq!(g.edge_count(), 3);
+    for edge in [&forward, &back_x, &back_y] {
+        assert_routed(&g, edge);
+    }
+    assert_weight(&g, &forward, 5.0);
+    assert_weight(&g, &back_x, 1.0);
+    assert_weight(&g, &back_y, 2.0);
+}
+
+#[test]
+fn test_adding_the_same_edge_twice_updates_its_label() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 30.0, 40.0);
+    let first = g.add_edge(Edge::new(a, b), EdgeLabel::default());
+    let second = g.add_edge(
+        Edge::new(a, b),
+        EdgeLabel {
+            weight: 4.0,
+            ..Default::default()
+        },
+    );
+
+    // 返回原有的边索引，不会留下第二条 petgraph 边
+    assert_eq!(first, second);
+    assert_eq!(g.edge_count(), 1);
+    assert_eq!(g.edge_label(&Edge::new(a, b)).unwrap().weight, 4.0);
+    // 名称不同的边是另一条边
+    add_edge(&mut g, Edge::with_name(a, b, "other"), 2.0);
+    assert_eq!(g.edge_count(), 2);
+    assert_eq!(g.edge_label(&Edge::new(a, b)).unwrap().weight, 4.0);
+    assert!(g.remove_edge(&Edge::with_name(a, b, "other")).is_some());
+
+    layout(&mut g, None);
+
+    assert_routed(&g, &Edge::new(a, b));
+    assert_weight(&g, &Edge::new(a, b), 4.0);
+}
+
+#[test]
+fn test_removed_edges_do_not_shift_other_edges() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 30.0, 40.0);
+    let c = add_node(&mut g, 60.0, 30.0);
+    let ab = Edge::new(a, b);
+    let bc = Edge::new(b, c);
+    let ba = Edge::with_name(b, a, "back");
+    add_edge(&mut g, ab.clone(), 1.0);
+    add_edge(&mut g, Edge::new(a, c), 9.0);
+    add_edge(&mut g, bc.clone(), 2.0);
+    add_edge(&mut g, ba.clone(), 3.0);
+
+    // 删除中间的边后，最后一条边会被 petgraph 移动到它的位置
+    assert!(g.remove_edge(&Edge::new(a, c)).is_some());
+    assert!(!g.has_edge(&Edge::new(a, c)));
+    assert_eq!(g.edge_count(), 3);
+    assert_eq!(g.edge_label(&ba).unwrap().weight, 3.0);
+
+    layout(&mut g, None);
+
+    for edge in [&ab, &bc, &ba] {
+        assert_routed(&g, edge);
+    }
+    assert_weight(&g, &ab, 1.0);
+    assert_weight(&g, &bc, 2.0);
+    assert_weight(&g, &ba, 3.0);
+}
+
+#[test]
+fn test_removing_a_node_removes_its_edges() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 30.0, 40.0);
+    let c = add_node(&mut g, 60.0, 30.0);
+    add_edge(&mut g, Edge::new(a, b), 1.0);
+    add_edge(&mut g, Edge::new(b, c), 2.0);
+    add_edge(&mut g, Edge::new(c, b), 3.0);
+
+    g.remove_node(c);
+
+    assert_eq!(g.edge_count(), 1);
+    assert_eq!(g.edges(), vec![Edge::new(a, b)]);
+
+    layout(&mut g, None);
+
+    assert_routed(&g, &Edge::new(a, b));
+    assert_weight(&g, &Edge::new(a, b), 1.0);
+}
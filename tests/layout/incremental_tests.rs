@@ -0,0 +1,168 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::incremental::LayoutHints;
+use dagviz::layout::layout;
+use dagviz::types::{Edge, EdgeLabel, LayoutOptions};
+
+use super::node;
+
+fn edge(g: &mut Graph, source: NodeIndex, target: NodeIndex) {
+    g.add_edge(Edge::new(source, target), EdgeLabel::default());
+}
+
+fn incremental(g: &Graph) -> LayoutOptions {
+    LayoutOptions {
+        hints: Some(LayoutHints::from_graph(g)),
+        ..Default::default()
+    }
+}
+
+fn xy(g: &Graph, node: NodeIndex) -> (f64, f64) {
+    let label = g.node_label(node).unwrap();
+    (label.x.unwrap(), label.y.unwrap())
+}
+
+/// 三级流水线：一个源节点分出三条支路，每条支路两个节点后汇合
+fn pipeline() -> (Graph, Vec<NodeIndex>) {
+    let mut g = Graph::new();
+    let source = node(&mut g);
+    let sink = node(&mut g);
+    let mut nodes = vec![source, sink];
+    for _ in 0..3 {
+        let first = node(&mut g);
+        let second = node(&mut g);
+        edge(&mut g, source, first);
+        edge(&mut g, first, second);
+        edge(&mut g, second, sink);
+        nodes.extend([first, second]);
+    }
+    (g, nodes)
+}
+
+#[test]
+fn test_hints_are_read_from_a_laid_out_graph() {
+    let (mut g, nodes) = pipeline();
+    layout(&mut g, None);
+
+    let hints = LayoutHints::from_graph(&g);
+    assert_eq!(hints.nodes.len(), nodes.len());
+    for node in nodes {
+        let label = g.node_label(node).unwrap();
+        let hint = hints.nodes[&node];
+        assert_eq!(hint.rank, label.rank);
+        assert!(hint.order.is_some());
+        assert_eq!((hint.x, hint.y), xy(&g, node));
+    }
+}
+
+#[test]
+fn test_relayout_with_own_hints_does_not_move_anything() {
+    let (mut g, nodes) = pipeline();
+    assert!(layout(&mut g, None).displacement.is_none());
+    let before: Vec<(f64, f64)> = nodes.iter().map(|&node| xy(&g, node)).collect();
+
+    let opts = incremental(&g);
+    let stats = layout(&mut g, Some(&opts));
+
+    let after: Vec<(f64, f64)> = nodes.iter().map(|&node| xy(&g, node)).collect();
+    assert_eq!(before, after);
+    let displacement = stats.displacement.unwrap();
+    assert_eq!(displacement.nodes, nodes.len());
+    assert_eq!(displacement.moved, 0);
+    assert_eq!(displacement.mean(), 0.0);
+}
+
+#[test]
+fn test_added_node_only_changes_the_layout_locally() {
+    let (mut g, nodes) = pipeline();
+    layout(&mut g, None);
+    let opts = incremental(&g);
+    let before: Vec<(f64, f64)> = nodes.iter().map(|&node| xy(&g, node)).collect();
+
+    // 在源节点下方新增一条支路
+    let added = node(&mut g);
+    edge(&mut g, nodes[0], added);
+    let stats = layout(&mut g, Some(&opts));
+
+    // 原有节点都没有移动
+    let after: Vec<(f64, f64)> = nodes.iter().map(|&node| xy(&g, node)).collect();
+    assert_eq!(before, after);
+    let displacement = stats.displacement.unwrap();
+    assert_eq!(displacement.nodes, nodes.len());
+    assert_eq!(displacement.moved, 0);
+
+    // 新节点与同层节点保持间距
+    let (added_x, added_y) = xy(&g, added);
+    for &node in &nodes {
+        let (x, y) = xy(&g, node);
+        if y == added_y {
+            assert!((x - added_x).abs() >= 40.0 + g.config().node_sep - 1e-9);
+        }
+    }
+}
+
+#[test]
+fn test_rank_weight_keeps_nodes_on_their_previous_rank() {
+    let mut g = Graph::new();
+    let a = node(&mut g);
+    let b = node(&mut g);
+    let c = node(&mut g);
+    let d = node(&mut g);
+    edge(&mut g, a, b);
+    edge(&mut g, b, c);
+    layout(&mut g, None);
+    let opts = incremental(&g);
+    let rank = |g: &Graph, node| g.node_label(node).unwrap().rank.unwrap();
+    assert_eq!(rank(&g, d), rank(&g, a));
+
+    edge(&mut g, d, c);
+
+    // 没有提示时 d 被拉到 c 的上一层
+    let mut fresh = g.clone();
+    layout(&mut fresh, None);
+    assert_eq!(rank(&fresh, d), rank(&fresh, b));
+
+    layout(&mut g, Some(&opts));
+    assert_eq!(rank(&g, d), rank(&g, a));
+    assert_eq!(xy(&g, d).1, xy(&g, a).1);
+
+    // 代价为 0 时提示不影响排名
+    let opts = LayoutOptions {
+        hints: opts.hints.map(|hints| hints.with_rank_weight(0.0)),
+        ..Default::default()
+    };
+    layout(&mut g, Some(&opts));
+    assert_eq!(rank(&g, d), rank(&g, b));
+}
+
+#[test]
+fn test_sweeps_with_fewer_crossings_replace_the_hinted_order() {
+    let mut g = Graph::new();
+    let a = node(&mut g);
+    let b = node(&mut g);
+    let c = node(&mut g);
+    let d = node(&mut g);
+    edge(&mut g, a, c);
+    edge(&mut g, b, d);
+    layout(&mut g, None);
+    assert!(xy(&g, a).0 < xy(&g, b).0 && xy(&g, c).0 < xy(&g, d).0);
+
+    // 提示中交换 c 和 d，初始排序有一个交叉
+    let mut opts = incremental(&g);
+    let hints = opts.hints.as_mut().unwrap();
+    let (c_order, d_order) = (hints.nodes[&c].order, hints.nodes[&d].order);
+    hints.nodes[&c].order = d_order;
+    hints.nodes[&d].order = c_order;
+
+    let mut seeded = g.clone();
+    let seed_only = LayoutOptions {
+        disable_optimal_order_heuristic: true,
+        ..opts.clone()
+    };
+    layout(&mut seeded, Some(&seed_only));
+    assert!(xy(&seeded, c).0 > xy(&seeded, d).0);
+
+    let stats = layout(&mut g, Some(&opts));
+    assert_eq!(stats.crossings.iter().min(), Some(&0));
+    let (a_x, b_x, c_x, d_x) = (xy(&g, a).0, xy(&g, b).0, xy(&g, c).0, xy(&g, d).0);
+    assert_eq!(a_x < b_x, c_x < d_x);
+}
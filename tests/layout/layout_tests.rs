@@ -46,7 +46,7 @@ fn test_can_layout_two_nodes_same_rank() {
     if let (Some(a_label), Some(b_label)) = (g.node_label(a), g.node_label(b)) {
         assert_eq!(a_label.x, Some(25.0)); // 50 / 2
         assert_eq!(a_label.y, Some(100.0)); // 200 / 2
-        assert_eq!(b_label.x, Some(162.5)); // 50 + 200 + 75 / 2
+        assert_eq!(b_label.x, Some(287.5)); // 50 + 200 + 75 / 2
         assert_eq!(b_label.y, Some(100.0)); // 200 / 2
     }
 }
@@ -77,7 +77,7 @@ fn test_can_layout_two_nodes_connected_by_edge() {
         assert_eq!(a_label.x, Some(37.5)); // 75 / 2
         assert_eq!(a_label.y, Some(50.0)); // 100 / 2
         assert_eq!(b_label.x, Some(37.5)); // 75 / 2
-        assert_eq!(b_label.y, Some(400.0)); // 100 + 300 + 200 / 2
+        assert_eq!(b_label.y, Some(500.0)); // 100 + 300 + 200 / 2
     }
 }
 
@@ -115,7 +115,7 @@ fn test_can_layout_edge_with_label() {
         assert_eq!(a_label.x, Some(37.5)); // 75 / 2
         assert_eq!(a_label.y, Some(50.0)); // 100 / 2
         assert_eq!(b_label.x, Some(37.5)); // 75 / 2
-        assert_eq!(b_label.y, Some(485.0)); // 100 + 150 + 70 + 150 + 200 / 2
+        assert_eq!(b_label.y, Some(570.0)); // 100 + 150 + 70 + 150 + 200 / 2
     }
 }
 
@@ -152,7 +152,7 @@ fn test_can_layout_short_cycle() {
         assert_eq!(a_label.x, Some(50.0)); // 100 / 2
         assert_eq!(a_label.y, Some(50.0)); // 100 / 2
         assert_eq!(b_label.x, Some(50.0)); // 100 / 2
-        assert_eq!(b_label.y, Some(250.0)); // 100 + 200 + 100 / 2
+        assert_eq!(b_label.y, Some(350.0)); // 100 + 200 + 100 / 2
     }
 }
 
@@ -237,3 +237,33 @@ fn test_can_layout_graph_with_subgraphs() {
         assert!(label.y.is_some());
     }
 }
+
+#[test]
+fn test_margins_default_to_zero_and_apply_on_both_sides() {
+    let mut g = setup_test_graph();
+    let a = g.add_node(NodeLabel {
+        width: 50.0,
+        height: 100.0,
+        ..Default::default()
+    });
+
+    layout(&mut g, None);
+
+    let label = g.node_label(a).unwrap();
+    assert_eq!((label.x, label.y), (Some(25.0), Some(50.0)));
+    assert_eq!(
+        (g.config().width, g.config().height),
+        (Some(50.0), Some(100.0))
+    );
+
+    g.config_mut().marginx = 20.0;
+    g.config_mut().marginy = 10.0;
+    layout(&mut g, None);
+
+    let label = g.node_label(a).unwrap();
+    assert_eq!((label.x, label.y), (Some(45.0), Some(60.0)));
+    assert_eq!(
+        (g.config().width, g.config().height),
+        (Some(90.0), Some(120.0))
+    );
+}
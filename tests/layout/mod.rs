@@ -1,3 +1,31 @@
 // Layout tests module
 
+mod budget_tests;
+mod cancel_tests;
+mod constraint_edge_tests;
+mod flat_edge_tests;
+mod identity_tests;
+mod incremental_tests;
 mod layout_tests;
+mod multigraph_tests;
+mod normalize_tests;
+mod pin_tests;
+mod port_tests;
+mod rankdir_tests;
+mod route_tests;
+mod self_loop_tests;
+mod shape_tests;
+mod stats_tests;
+mod try_layout_tests;
+
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::types::NodeLabel;
+
+/// 添加一个 40×20 的节点
+pub fn node(g: &mut Graph) -> NodeIndex {
+    g.add_node(NodeLabel {
+        width: 40.0,
+        height: 20.0,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,108 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::layout;
+use dagviz::types::{Edge, EdgeLabel, LabelPosition, NodeLabel};
+
+fn add_node(g: &mut Graph) -> NodeIndex {
+    g.add_node(NodeLabel {
+        width: 80.0,
+        height: 30.0,
+        ..Default::default()
+    })
+}
+
+fn labelled(text: &str) -> EdgeLabel {
+    EdgeLabel {
+        label: Some(text.to_string()),
+        width: 40.0,
+        height: 16.0,
+        labelpos: LabelPosition::Center,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_labelled_parallel_edges_get_their_own_label_and_route() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g);
+    let b = add_node(&mut g);
+    let edges = [
+        Edge::new(a, b),
+        Edge::with_name(a, b, "x"),
+        Edge::with_name(a, b, "y"),
+    ];
+    for (i, edge) in edges.iter().enumerate() {
+        g.add_edge(edge.clone(), labelled(&format!("label {}", i)));
+    }
+
+    layout(&mut g, None);
+
+    let labels: Vec<&EdgeLabel> = edges.iter().map(|e| g.edge_label(e).unwrap()).collect();
+    for (i, label) in labels.iter().enumerate() {
+        assert_eq!(
+            label.label.as_deref(),
+            Some(format!("label {}", i).as_str())
+        );
+        assert_eq!(label.points.len(), 3, "{:?}", label.points);
+        assert_eq!(label.points[1].x, label.x.unwrap());
+    }
+    // 标签互不重叠
+    for i in 0..labels.len() {
+        for j in i + 1..labels.len() {
+            let (p, q) = (labels[i], labels[j]);
+            assert!(
+                (p.x.unwrap() - q.x.unwrap()).abs() >= 40.0,
+                "labels {} and {} overlap: {:?} {:?}",
+                i,
+                j,
+                p.x,
+                q.x
+            );
+            assert_eq!(p.y, q.y);
+        }
+    }
+}
+
+#[test]
+fn test_parallel_edges_are_addressed_by_name() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g);
+    let b = add_node(&mut g);
+    let unnamed = Edge::new(a, b);
+    let x = Edge::with_name(a, b, "x");
+    let y = Edge::with_name(a, b, "y");
+    g.add_edge(unnamed.clone(), labelled("unnamed"));
+    g.add_edge(x.clone(), labelled("x"));
+    g.add_edge(y.clone(), labelled("y"));
+
+    assert_eq!(
+        names(&g, a, b),
+        vec![None, Some("x".into()), Some("y".into())]
+    );
+    assert!(g.edges_between(b, a).is_empty());
+    assert_eq!(g.edge_label(&x).unwrap().label.as_deref(), Some("x"));
+
+    let removed = g.remove_edge(&x).unwrap();
+    assert_eq!(removed.label.as_deref(), Some("x"));
+    assert!(g.edge_label(&x).is_none());
+    assert_eq!(names(&g, a, b), vec![None, Some("y".into())]);
+    assert_eq!(g.edge_label(&y).unwrap().label.as_deref(), Some("y"));
+    assert_eq!(
+        g.edge_label(&unnamed).unwrap().label.as_deref(),
+        Some("unnamed")
+    );
+
+    layout(&mut g, None);
+    let (p, q) = (g.edge_label(&unnamed).unwrap(), g.edge_label(&y).unwrap());
+    assert_ne!(p.points[1], q.points[1]);
+    assert_ne!(p.x, q.x);
+}
+
+fn names(g: &Graph, source: NodeIndex, target: NodeIndex) -> Vec<Option<String>> {
+    let mut names: Vec<_> = g
+        .edges_between(source, target)
+        .into_iter()
+        .map(|edge| edge.name)
+        .collect();
+    names.sort();
+    names
+}
@@ -0,0 +1,121 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::layout;
+use dagviz::types::{Edge, EdgeLabel, LabelPosition, NodeLabel, Point};
+
+fn add_node(g: &mut Graph, width: f64, height: f64) -> NodeIndex {
+    g.add_node(NodeLabel {
+        width,
+        height,
+        ..Default::default()
+    })
+}
+
+fn center(g: &Graph, node: NodeIndex) -> Point {
+    let label = g.node_label(node).unwrap();
+    Point::new(label.x.unwrap(), label.y.unwrap())
+}
+
+#[test]
+fn test_long_edges_keep_their_bend_points() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 50.0, 20.0);
+    let c = add_node(&mut g, 50.0, 20.0);
+    g.add_edge(Edge::new(a, b), EdgeLabel::default());
+    g.add_edge(Edge::new(b, c), EdgeLabel::default());
+    let long = Edge::new(a, c);
+    g.add_edge(long.clone(), EdgeLabel::default());
+    layout(&mut g, None);
+
+    // a 和 c 相隔四个排名，中间有三个虚拟节点
+    let points = &g.edge_label(&long).unwrap().points;
+    assert_eq!(points.len(), 5, "{:?}", points);
+    let (top, bottom) = (center(&g, a).y + 10.0, center(&g, c).y - 10.0);
+    assert_eq!(points[0].y, top);
+    assert_eq!(points[4].y, bottom);
+    for pair in points.windows(2) {
+        assert!(pair[0].y < pair[1].y, "{:?}", points);
+    }
+    // 折点绕开中间的节点 b
+    let b_center = center(&g, b);
+    assert!(
+        points
+            .iter()
+            .all(|p| (p.x - b_center.x).abs() > 25.0 || p.y != b_center.y)
+    );
+}
+
+#[test]
+fn test_edge_labels_take_the_position_of_their_dummy() {
+    let mut g = Graph::new();
+    g.config_mut().rank_sep = 300.0;
+    let a = add_node(&mut g, 50.0, 100.0);
+    let b = add_node(&mut g, 75.0, 200.0);
+    let edge = Edge::new(a, b);
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            width: 60.0,
+            height: 70.0,
+            labelpos: LabelPosition::Center,
+            ..Default::default()
+        },
+    );
+    layout(&mut g, None);
+
+    let label = g.edge_label(&edge).unwrap();
+    assert_eq!(label.x, Some(37.5));
+    assert_eq!(label.y, Some(100.0 + 150.0 + 70.0 / 2.0));
+    assert_eq!((label.width, label.height), (60.0, 70.0));
+    assert_eq!(
+        label.points,
+        vec![
+            Point::new(37.5, 100.0),
+            Point::new(37.5, 285.0),
+            Point::new(37.5, 470.0),
+        ]
+    );
+}
+
+#[test]
+fn test_side_labels_are_offset_from_the_edge() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 50.0, 20.0);
+    let edge = Edge::new(a, b);
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            width: 40.0,
+            height: 10.0,
+            labelpos: LabelPosition::Right,
+            labeloffset: 5.0,
+            ..Default::default()
+        },
+    );
+    layout(&mut g, None);
+
+    let label = g.edge_label(&edge).unwrap();
+    let bend = label.points[1];
+    assert_eq!(label.y, Some(bend.y));
+    assert!(label.x.unwrap() > bend.x, "{:?} vs {:?}", label.x, bend);
+}
+
+#[test]
+fn test_reversed_long_edges_run_from_source_to_target() {
+    let mut g = Graph::new();
+    let a = add_node(&mut g, 50.0, 20.0);
+    let b = add_node(&mut g, 50.0, 20.0);
+    let c = add_node(&mut g, 50.0, 20.0);
+    g.add_edge(Edge::new(a, b), EdgeLabel::default());
+    g.add_edge(Edge::new(b, c), EdgeLabel::default());
+    let back = Edge::new(c, a);
+    g.add_edge(back.clone(), EdgeLabel::default());
+    layout(&mut g, None);
+
+    let points = &g.edge_label(&back).unwrap().points;
+    assert!(points.len() > 2, "{:?}", points);
+    assert!(points.first().unwrap().y > points.last().unwrap().y);
+    assert_eq!(points.first().unwrap().y, center(&g, c).y - 10.0);
+    assert_eq!(points.last().unwrap().y, center(&g, a).y + 10.0);
+}
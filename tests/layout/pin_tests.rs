@@ -0,0 +1,155 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::{layout, try_layout};
+use dagviz::types::{Edge, EdgeLabel, RankDirection};
+
+use super::node;
+
+fn pin(g: &mut Graph, node: NodeIndex, x: f64, y: f64) {
+    let label = g.node_label_mut(node).unwrap();
+    label.x = Some(x);
+    label.y = Some(y);
+    label.pinned = true;
+}
+
+fn edge(g: &mut Graph, source: NodeIndex, target: NodeIndex) {
+    g.add_edge(Edge::new(source, target), EdgeLabel::default());
+}
+
+fn xy(g: &Graph, node: NodeIndex) -> (f64, f64) {
+    let label = g.node_label(node).unwrap();
+    (label.x.unwrap(), label.y.unwrap())
+}
+
+#[test]
+fn test_pinned_node_keeps_its_coordinates() {
+    let mut g = Graph::new();
+    let a = node(&mut g);
+    let b = node(&mut g);
+    let c = node(&mut g);
+    edge(&mut g, a, b);
+    edge(&mut g, b, c);
+    pin(&mut g, a, 500.0, 300.0);
+
+    layout(&mut g, None);
+
+    assert_eq!(xy(&g, a), (500.0, 300.0));
+    // 其余节点保持正常的排名间距
+    let rank_step = 20.0 + g.config().rank_sep;
+    assert_eq!(xy(&g, b).1, 300.0 + rank_step);
+    assert_eq!(xy(&g, c).1, 300.0 + 2.0 * rank_step);
+    assert!(!g.node_label(b).unwrap().pinned);
+}
+
+#[test]
+fn test_pinned_y_decides_the_rank() {
+    let mut g = Graph::new();
+    let a = node(&mut g);
+    let b = node(&mut g);
+    let c = node(&mut g);
+    edge(&mut g, a, c);
+    // b 与 a 没有边，排名由固定的 y 决定
+    pin(&mut g, a, 0.0, 0.0);
+    pin(&mut g, b, 200.0, 400.0);
+
+    layout(&mut g, None);
+
+    assert_eq!(xy(&g, a), (0.0, 0.0));
+    assert_eq!(xy(&g, b), (200.0, 400.0));
+    let c_y = xy(&g, c).1;
+    assert!(c_y > 0.0 && c_y < 400.0);
+}
+
+#[test]
+fn test_unpinned_nodes_keep_separation_from_pinned_ones() {
+    let mut g = Graph::new();
+    let root = node(&mut g);
+    let children: Vec<NodeIndex> = (0..4).map(|_| node(&mut g)).collect();
+    for &child in &children {
+        edge(&mut g, root, child);
+    }
+    // 把第二个子节点固定在第一个子节点通常所在的位置附近
+    pin(&mut g, children[1], 0.0, 100.0);
+
+    layout(&mut g, None);
+
+    assert_eq!(xy(&g, children[1]), (0.0, 100.0));
+    let mut xs: Vec<f64> = children.iter().map(|&child| xy(&g, child).0).collect();
+    xs.sort_by(f64::total_cmp);
+    let min_gap = 40.0 + g.config().node_sep;
+    for pair in xs.windows(2) {
+        assert!(pair[1] - pair[0] >= min_gap - 1e-9, "{:?}", xs);
+    }
+    for &child in &children {
+        assert_eq!(xy(&g, child).1, 100.0);
+    }
+}
+
+#[test]
+fn test_two_pins_in_one_rank_keep_their_distance() {
+    let mut g = Graph::new();
+    let root = node(&mut g);
+    let left = node(&mut g);
+    let right = node(&mut g);
+    edge(&mut g, root, left);
+    edge(&mut g, root, right);
+    pin(&mut g, left, 100.0, 50.0);
+    pin(&mut g, right, 400.0, 50.0);
+
+    layout(&mut g, None);
+
+    assert_eq!(xy(&g, left), (100.0, 50.0));
+    assert_eq!(xy(&g, right), (400.0, 50.0));
+    assert!(xy(&g, root).1 < 50.0);
+}
+
+#[test]
+fn test_pins_use_output_coordinates_for_left_right() {
+    let mut g = Graph::new();
+    g.config_mut().rankdir = RankDirection::LeftRight;
+    let a = node(&mut g);
+    let b = node(&mut g);
+    edge(&mut g, a, b);
+    pin(&mut g, b, 300.0, -20.0);
+
+    layout(&mut g, None);
+
+    assert_eq!(xy(&g, b), (300.0, -20.0));
+    let (a_x, a_y) = xy(&g, a);
+    assert!(a_x < 300.0);
+    assert_eq!(a_y, -20.0);
+}
+
+#[test]
+fn test_contradicting_pins_are_reported() {
+    let mut g = Graph::new();
+    let a = node(&mut g);
+    let b = node(&mut g);
+    let c = node(&mut g);
+    // b 固定在 a 下方却指向 a，a 被推到 c 的排名上，同一排名只能满足其中一个固定的 y
+    edge(&mut g, b, a);
+    pin(&mut g, a, 0.0, 0.0);
+    pin(&mut g, b, 0.0, 100.0);
+    pin(&mut g, c, 300.0, 170.0);
+
+    let stats = try_layout(&mut g, None).unwrap().stats;
+
+    assert_eq!(stats.pin_conflicts, [c]);
+    assert_eq!(xy(&g, a), (0.0, 0.0));
+    assert_eq!(xy(&g, b), (0.0, 100.0));
+    assert_ne!(xy(&g, c), (300.0, 170.0));
+}
+
+#[test]
+fn test_satisfied_pins_report_no_conflicts() {
+    let mut g = Graph::new();
+    let a = node(&mut g);
+    let b = node(&mut g);
+    edge(&mut g, a, b);
+    pin(&mut g, a, 10.0, 20.0);
+    pin(&mut g, b, 10.0, 200.0);
+
+    let stats = try_layout(&mut g, None).unwrap().stats;
+
+    assert!(stats.pin_conflicts.is_empty());
+    assert_eq!(xy(&g, b), (10.0, 200.0));
+}
@@ -0,0 +1,233 @@
+use dagviz::error::LayoutError;
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::{layout, try_layout};
+use dagviz::types::{Edge, EdgeLabel, PathType, Point, Port, PortSide, RankDirection};
+
+use super::node;
+
+fn node_with_ports(g: &mut Graph, ports: Vec<Port>) -> NodeIndex {
+    let node = node(g);
+    g.node_label_mut(node).unwrap().ports = ports;
+    node
+}
+
+fn port_edge(
+    g: &mut Graph,
+    source: (NodeIndex, Option<&str>),
+    target: (NodeIndex, Option<&str>),
+) -> Edge {
+    let edge = Edge::new(source.0, target.0);
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            source_port: source.1.map(str::to_string),
+            target_port: target.1.map(str::to_string),
+            ..Default::default()
+        },
+    );
+    edge
+}
+
+fn center(g: &Graph, node: NodeIndex) -> Point {
+    let label = g.node_label(node).unwrap();
+    Point::new(label.x.unwrap(), label.y.unwrap())
+}
+
+fn points(g: &Graph, edge: &Edge) -> Vec<Point> {
+    g.edge_label(edge).unwrap().points.clone()
+}
+
+#[test]
+fn test_edges_end_exactly_at_ports() {
+    let mut g = Graph::new();
+    let a = node_with_ports(
+        &mut g,
+        vec![Port::new("out", PortSide::Bottom).with_offset(0.25)],
+    );
+    let b = node_with_ports(&mut g, vec![Port::new("in", PortSide::Top)]);
+    let edge = port_edge(&mut g, (a, Some("out")), (b, Some("in")));
+
+    layout(&mut g, None);
+
+    let (a_center, b_center) = (center(&g, a), center(&g, b));
+    let points = points(&g, &edge);
+    assert_eq!(points[0], Point::new(a_center.x - 10.0, a_center.y + 10.0));
+    assert_eq!(
+        points[points.len() - 1],
+        Point::new(b_center.x, b_center.y - 10.0)
+    );
+}
+
+#[test]
+fn test_ports_without_offset_are_spread_by_order() {
+    let mut g = Graph::new();
+    let a = node_with_ports(
+        &mut g,
+        vec![
+            Port::new("second", PortSide::Bottom).with_order(1),
+            Port::new("first", PortSide::Bottom).with_order(0),
+            Port::new("third", PortSide::Bottom).with_order(1),
+        ],
+    );
+    let children: Vec<NodeIndex> = (0..3).map(|_| node(&mut g)).collect();
+    let edges: Vec<Edge> = ["first", "second", "third"]
+        .iter()
+        .zip(&children)
+        .map(|(&port, &child)| port_edge(&mut g, (a, Some(port)), (child, None)))
+        .collect();
+
+    layout(&mut g, None);
+
+    let a_center = center(&g, a);
+    let starts: Vec<f64> = edges.iter().map(|edge| points(&g, edge)[0].x).collect();
+    assert_eq!(
+        starts,
+        vec![a_center.x - 10.0, a_center.x, a_center.x + 10.0]
+    );
+}
+
+#[test]
+fn test_port_order_decides_the_order_of_successors() {
+    // 两种添加顺序下，连到左侧端口的节点都排在左侧
+    for error_first in [true, false] {
+        let mut g = Graph::new();
+        let a = node_with_ports(
+            &mut g,
+            vec![
+                Port::new("ok", PortSide::Bottom).with_offset(0.2),
+                Port::new("error", PortSide::Bottom).with_offset(0.8),
+            ],
+        );
+        let first = node(&mut g);
+        let second = node(&mut g);
+        let (on_error, on_ok) = if error_first {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        if error_first {
+            port_edge(&mut g, (a, Some("error")), (on_error, None));
+            port_edge(&mut g, (a, Some("ok")), (on_ok, None));
+        } else {
+            port_edge(&mut g, (a, Some("ok")), (on_ok, None));
+            port_edge(&mut g, (a, Some("error")), (on_error, None));
+        }
+
+        layout(&mut g, None);
+
+        assert!(center(&g, on_ok).x < center(&g, on_error).x);
+    }
+}
+
+#[test]
+fn test_target_ports_reorder_predecessors_in_sweeps() {
+    // 初始排序只看源端口，a 在 b 之前；扫描时按 x 上目标端口的位置把 b 换到左侧
+    for with_ports in [true, false] {
+        let mut g = Graph::new();
+        let a = node(&mut g);
+        let b = node(&mut g);
+        let ports = if with_ports {
+            vec![
+                Port::new("left", PortSide::Top).with_offset(0.0),
+                Port::new("right", PortSide::Top).with_offset(1.0),
+            ]
+        } else {
+            Vec::new()
+        };
+        let x = node_with_ports(&mut g, ports);
+        port_edge(&mut g, (a, None), (x, with_ports.then_some("right")));
+        port_edge(&mut g, (b, None), (x, with_ports.then_some("left")));
+
+        layout(&mut g, None);
+
+        assert_eq!(center(&g, b).x < center(&g, a).x, with_ports);
+    }
+}
+
+#[test]
+fn test_ports_follow_reversed_edges() {
+    let mut g = Graph::new();
+    let a = node_with_ports(&mut g, vec![Port::new("back", PortSide::Left)]);
+    let b = node_with_ports(&mut g, vec![Port::new("loop", PortSide::Right)]);
+    port_edge(&mut g, (a, None), (b, None));
+    let back = port_edge(&mut g, (b, Some("loop")), (a, Some("back")));
+
+    layout(&mut g, None);
+
+    let (a_center, b_center) = (center(&g, a), center(&g, b));
+    let points = points(&g, &back);
+    assert_eq!(points[0], Point::new(b_center.x + 20.0, b_center.y));
+    assert_eq!(
+        points[points.len() - 1],
+        Point::new(a_center.x - 20.0, a_center.y)
+    );
+}
+
+#[test]
+fn test_port_sides_use_output_coordinates() {
+    let mut g = Graph::new();
+    g.config_mut().rankdir = RankDirection::LeftRight;
+    let a = node_with_ports(&mut g, vec![Port::new("out", PortSide::Right)]);
+    let b = node_with_ports(&mut g, vec![Port::new("in", PortSide::Top)]);
+    let edge = port_edge(&mut g, (a, Some("out")), (b, Some("in")));
+
+    layout(&mut g, None);
+
+    let (a_center, b_center) = (center(&g, a), center(&g, b));
+    let points = points(&g, &edge);
+    assert_eq!(points[0], Point::new(a_center.x + 20.0, a_center.y));
+    assert_eq!(
+        points[points.len() - 1],
+        Point::new(b_center.x, b_center.y - 10.0)
+    );
+}
+
+#[test]
+fn test_orthogonal_routes_leave_side_ports_outward() {
+    let mut g = Graph::new();
+    g.config_mut().edge_sep = 5.0;
+    let a = node_with_ports(&mut g, vec![Port::new("error", PortSide::Right)]);
+    let b = node(&mut g);
+    let edge = Edge::new(a, b);
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            source_port: Some("error".to_string()),
+            path_type: PathType::Orthogonal,
+            ..Default::default()
+        },
+    );
+
+    layout(&mut g, None);
+
+    let a_center = center(&g, a);
+    let points = points(&g, &edge);
+    assert_eq!(points[0], Point::new(a_center.x + 20.0, a_center.y));
+    assert_eq!(points[1], Point::new(a_center.x + 25.0, a_center.y));
+    for pair in points.windows(2) {
+        assert!(
+            pair[0].x == pair[1].x || pair[0].y == pair[1].y,
+            "{:?}",
+            points
+        );
+    }
+}
+
+#[test]
+fn test_unknown_ports_are_rejected() {
+    let mut g = Graph::new();
+    let a = node_with_ports(&mut g, vec![Port::new("out", PortSide::Bottom)]);
+    let b = node(&mut g);
+    let edge = port_edge(&mut g, (a, Some("out")), (b, Some("in")));
+
+    let err = try_layout(&mut g, None).unwrap_err();
+    assert_eq!(
+        err,
+        LayoutError::UnknownPort {
+            edge,
+            node: b,
+            port: "in".to_string(),
+        }
+    );
+    assert!(g.node_label(a).unwrap().x.is_none());
+}
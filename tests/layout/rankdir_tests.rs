@@ -0,0 +1,162 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::layout;
+use dagviz::types::{Edge, EdgeLabel, NodeLabel, RankDirection};
+
+const EPSILON: f64 = 1e-9;
+const EDGES: [(usize, usize); 5] = [(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)];
+
+/// 构建一个菱形加尾节点的图，节点尺寸各不相同；`transpose` 为 true 时交换每个节点的宽高
+fn diamond(rankdir: RankDirection, transpose: bool) -> (Graph, Vec<NodeIndex>) {
+    let mut g = Graph::new();
+    g.config_mut().rankdir = rankdir;
+
+    let sizes = [
+        (50.0, 20.0),
+        (80.0, 30.0),
+        (40.0, 60.0),
+        (70.0, 25.0),
+        (30.0, 45.0),
+    ];
+    let nodes: Vec<NodeIndex> = sizes
+        .iter()
+        .map(|&(width, height)| {
+            let (width, height) = if transpose {
+                (height, width)
+            } else {
+                (width, height)
+            };
+            g.add_node(NodeLabel {
+                width,
+                height,
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    for (source, target) in EDGES {
+        g.add_edge(
+            Edge::new(nodes[source], nodes[target]),
+            EdgeLabel::default(),
+        );
+    }
+
+    (g, nodes)
+}
+
+fn position(g: &Graph, node: NodeIndex) -> (f64, f64) {
+    let label = g.node_label(node).unwrap();
+    (label.x.unwrap(), label.y.unwrap())
+}
+
+fn size(g: &Graph) -> (f64, f64) {
+    (g.config().width.unwrap(), g.config().height.unwrap())
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < EPSILON,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn test_lr_is_transpose_of_tb() {
+    let (mut tb, tb_nodes) = diamond(RankDirection::TopBottom, true);
+    let (mut lr, lr_nodes) = diamond(RankDirection::LeftRight, false);
+    layout(&mut tb, None);
+    layout(&mut lr, None);
+
+    for (&t, &l) in tb_nodes.iter().zip(&lr_nodes) {
+        let (tx, ty) = position(&tb, t);
+        let (lx, ly) = position(&lr, l);
+        assert_close(lx, ty);
+        assert_close(ly, tx);
+    }
+
+    let (tb_width, tb_height) = size(&tb);
+    let (lr_width, lr_height) = size(&lr);
+    assert_close(lr_width, tb_height);
+    assert_close(lr_height, tb_width);
+}
+
+#[test]
+fn test_lr_edge_points_are_transposed() {
+    let (mut tb, tb_nodes) = diamond(RankDirection::TopBottom, true);
+    let (mut lr, lr_nodes) = diamond(RankDirection::LeftRight, false);
+    layout(&mut tb, None);
+    layout(&mut lr, None);
+
+    for (source, target) in EDGES {
+        let tb_points = &tb
+            .edge_label(&Edge::new(tb_nodes[source], tb_nodes[target]))
+            .unwrap()
+            .points;
+        let lr_points = &lr
+            .edge_label(&Edge::new(lr_nodes[source], lr_nodes[target]))
+            .unwrap()
+            .points;
+
+        assert_eq!(tb_points.len(), lr_points.len());
+        for (tp, lp) in tb_points.iter().zip(lr_points) {
+            assert_close(lp.x, tp.y);
+            assert_close(lp.y, tp.x);
+        }
+    }
+}
+
+#[test]
+fn test_lr_keeps_node_sizes() {
+    let (mut g, nodes) = diamond(RankDirection::LeftRight, false);
+    layout(&mut g, None);
+
+    let label = g.node_label(nodes[1]).unwrap();
+    assert_eq!(label.width, 80.0);
+    assert_eq!(label.height, 30.0);
+}
+
+#[test]
+fn test_lr_ranks_grow_along_x() {
+    let (mut g, nodes) = diamond(RankDirection::LeftRight, false);
+    layout(&mut g, None);
+
+    let (ax, _) = position(&g, nodes[0]);
+    let (bx, _) = position(&g, nodes[1]);
+    let (dx, _) = position(&g, nodes[3]);
+    assert!(ax < bx && bx < dx);
+}
+
+#[test]
+fn test_bt_mirrors_tb_vertically() {
+    let (mut tb, tb_nodes) = diamond(RankDirection::TopBottom, false);
+    let (mut bt, bt_nodes) = diamond(RankDirection::BottomTop, false);
+    layout(&mut tb, None);
+    layout(&mut bt, None);
+
+    let (width, height) = size(&tb);
+    assert_eq!(size(&bt), (width, height));
+    for (&t, &b) in tb_nodes.iter().zip(&bt_nodes) {
+        let (tx, ty) = position(&tb, t);
+        let (bx, by) = position(&bt, b);
+        assert_close(bx, tx);
+        assert_close(by, height - ty);
+    }
+}
+
+#[test]
+fn test_rl_mirrors_lr_horizontally() {
+    let (mut lr, lr_nodes) = diamond(RankDirection::LeftRight, false);
+    let (mut rl, rl_nodes) = diamond(RankDirection::RightLeft, false);
+    layout(&mut lr, None);
+    layout(&mut rl, None);
+
+    let (width, height) = size(&lr);
+    assert_eq!(size(&rl), (width, height));
+    for (&l, &r) in lr_nodes.iter().zip(&rl_nodes) {
+        let (lx, ly) = position(&lr, l);
+        let (rx, ry) = position(&rl, r);
+        assert_close(rx, width - lx);
+        assert_close(ry, ly);
+    }
+}
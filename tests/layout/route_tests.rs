@@ -0,0 +1,198 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::{assign_node_intersects, layout};
+use dagviz::route::route_edges;
+use dagviz::types::{Edge, EdgeLabel, NodeLabel, PathType, Point, RankDirection};
+
+/// 添加一个已经放置好的节点
+fn placed(g: &mut Graph, x: f64, y: f64, width: f64, height: f64) -> NodeIndex {
+    g.add_node(NodeLabel {
+        x: Some(x),
+        y: Some(y),
+        width,
+        height,
+        ..Default::default()
+    })
+}
+
+fn routed_edge(g: &mut Graph, edge: &Edge, path_type: PathType, points: Vec<Point>) {
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            path_type,
+            points,
+            ..Default::default()
+        },
+    );
+    assign_node_intersects(g);
+    route_edges(g);
+}
+
+/// 线段是否穿过节点内部
+fn crosses(g: &Graph, node: NodeIndex, a: Point, b: Point) -> bool {
+    let label = g.node_label(node).unwrap();
+    let (x, y) = (label.x.unwrap(), label.y.unwrap());
+    let (w, h) = (label.width / 2.0, label.height / 2.0);
+    if a.x == b.x {
+        (a.x - x).abs() < w && a.y.min(b.y) < y + h && a.y.max(b.y) > y - h
+    } else {
+        (a.y - y).abs() < h && a.x.min(b.x) < x + w && a.x.max(b.x) > x - w
+    }
+}
+
+fn assert_orthogonal(points: &[Point]) {
+    for pair in points.windows(2) {
+        assert!(
+            pair[0].x == pair[1].x || pair[0].y == pair[1].y,
+            "segment {:?} is not axis aligned in {:?}",
+            pair,
+            points
+        );
+    }
+}
+
+#[test]
+fn test_node_intersects_keep_bend_points() {
+    let mut g = Graph::new();
+    let a = placed(&mut g, 0.0, 0.0, 20.0, 20.0);
+    let b = placed(&mut g, 100.0, 100.0, 20.0, 20.0);
+    let edge = Edge::new(a, b);
+    routed_edge(
+        &mut g,
+        &edge,
+        PathType::Polyline,
+        vec![Point::new(0.0, 50.0), Point::new(100.0, 50.0)],
+    );
+
+    assert_eq!(
+        g.edge_label(&edge).unwrap().points,
+        vec![
+            Point::new(0.0, 10.0),
+            Point::new(0.0, 50.0),
+            Point::new(100.0, 50.0),
+            Point::new(100.0, 90.0),
+        ]
+    );
+}
+
+#[test]
+fn test_line_edges_join_node_boundaries() {
+    let mut g = Graph::new();
+    let a = placed(&mut g, 0.0, 0.0, 20.0, 20.0);
+    let b = placed(&mut g, 100.0, 100.0, 20.0, 20.0);
+    let edge = Edge::new(a, b);
+    routed_edge(
+        &mut g,
+        &edge,
+        PathType::Line,
+        vec![Point::new(0.0, 50.0), Point::new(100.0, 50.0)],
+    );
+
+    assert_eq!(
+        g.edge_label(&edge).unwrap().points,
+        vec![Point::new(10.0, 10.0), Point::new(90.0, 90.0)]
+    );
+}
+
+#[test]
+fn test_bezier_curves_pass_through_bend_points() {
+    let mut g = Graph::new();
+    let a = placed(&mut g, 0.0, 0.0, 20.0, 20.0);
+    let b = placed(&mut g, 100.0, 200.0, 20.0, 20.0);
+    let edge = Edge::new(a, b);
+    routed_edge(
+        &mut g,
+        &edge,
+        PathType::Bezier,
+        vec![Point::new(0.0, 100.0), Point::new(100.0, 150.0)],
+    );
+
+    let label = g.edge_label(&edge).unwrap();
+    assert_eq!(label.points.len(), 4);
+    assert_eq!(label.control_points.len(), 3 * 3 + 1);
+    for (i, point) in label.points.iter().enumerate() {
+        assert_eq!(label.control_points[3 * i], *point);
+    }
+}
+
+#[test]
+fn test_orthogonal_routes_turn_between_ranks() {
+    let mut g = Graph::new();
+    let a = placed(&mut g, 0.0, 0.0, 20.0, 20.0);
+    let blocker = placed(&mut g, 50.0, 100.0, 40.0, 40.0);
+    let b = placed(&mut g, 100.0, 200.0, 20.0, 20.0);
+    let edge = Edge::new(a, b);
+    routed_edge(
+        &mut g,
+        &edge,
+        PathType::Orthogonal,
+        vec![Point::new(100.0, 100.0)],
+    );
+
+    let points = g.edge_label(&edge).unwrap().points.clone();
+    assert_orthogonal(&points);
+    assert_eq!(points.first(), Some(&Point::new(0.0, 10.0)));
+    assert_eq!(points.last(), Some(&Point::new(100.0, 190.0)));
+    assert!(points.contains(&Point::new(100.0, 100.0)));
+    for pair in points.windows(2) {
+        assert!(!crosses(&g, blocker, pair[0], pair[1]), "{:?}", points);
+    }
+}
+
+#[test]
+fn test_layout_routes_each_path_type() {
+    for rankdir in [RankDirection::TopBottom, RankDirection::LeftRight] {
+        let mut g = Graph::new();
+        g.config_mut().rankdir = rankdir;
+        let a = g.add_node(NodeLabel {
+            width: 50.0,
+            height: 20.0,
+            ..Default::default()
+        });
+        let b = g.add_node(NodeLabel {
+            width: 80.0,
+            height: 30.0,
+            ..Default::default()
+        });
+        let c = g.add_node(NodeLabel {
+            width: 30.0,
+            height: 30.0,
+            ..Default::default()
+        });
+        let d = g.add_node(NodeLabel {
+            width: 40.0,
+            height: 40.0,
+            ..Default::default()
+        });
+        let types = [PathType::Orthogonal, PathType::Bezier, PathType::Line];
+        let edges = [Edge::new(a, b), Edge::new(b, c), Edge::new(c, d)];
+        for (edge, path_type) in edges.iter().zip(types) {
+            g.add_edge(
+                edge.clone(),
+                EdgeLabel {
+                    path_type,
+                    ..Default::default()
+                },
+            );
+        }
+        layout(&mut g, None);
+
+        let orthogonal = g.edge_label(&edges[0]).unwrap();
+        assert_orthogonal(&orthogonal.points);
+        let source = g.node_label(a).unwrap();
+        let start = orthogonal.points[0];
+        match rankdir {
+            RankDirection::LeftRight => {
+                assert_eq!(start.x, source.x.unwrap() + source.width / 2.0)
+            }
+            _ => assert_eq!(start.y, source.y.unwrap() + source.height / 2.0),
+        }
+
+        let bezier = g.edge_label(&edges[1]).unwrap();
+        assert_eq!(
+            bezier.control_points.len(),
+            3 * (bezier.points.len() - 1) + 1
+        );
+
+        assert_eq!(g.edge_label(&edges[2]).unwrap().points.len(), 2);
+    }
+}
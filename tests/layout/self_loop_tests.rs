@@ -0,0 +1,184 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::layout;
+use dagviz::types::{Edge, EdgeLabel, GraphConfig, PathType, Point, RankDirection};
+
+use super::node;
+
+const EPSILON: f64 = 1e-9;
+
+fn graph(rankdir: RankDirection) -> Graph {
+    Graph::with_config(GraphConfig {
+        rankdir,
+        ..Default::default()
+    })
+}
+
+fn self_loop(g: &mut Graph, node: NodeIndex, name: &str, label: EdgeLabel) -> Edge {
+    let edge = Edge::with_name(node, node, name);
+    g.add_edge(edge.clone(), label);
+    edge
+}
+
+fn points(g: &Graph, edge: &Edge) -> Vec<Point> {
+    g.edge_label(edge).unwrap().points.clone()
+}
+
+/// 节点中心和半宽、半高
+fn bounds(g: &Graph, node: NodeIndex) -> (Point, f64, f64) {
+    let label = g.node_label(node).unwrap();
+    (
+        Point::new(label.x.unwrap(), label.y.unwrap()),
+        label.width / 2.0,
+        label.height / 2.0,
+    )
+}
+
+fn on_boundary(g: &Graph, node: NodeIndex, point: Point) -> bool {
+    let (center, w, h) = bounds(g, node);
+    let (dx, dy) = ((point.x - center.x).abs(), (point.y - center.y).abs());
+    ((dx - w).abs() < EPSILON && dy <= h + EPSILON)
+        || ((dy - h).abs() < EPSILON && dx <= w + EPSILON)
+}
+
+fn outside(g: &Graph, node: NodeIndex, point: Point) -> bool {
+    let (center, w, h) = bounds(g, node);
+    (point.x - center.x).abs() > w + EPSILON || (point.y - center.y).abs() > h + EPSILON
+}
+
+#[test]
+fn test_loop_leaves_and_returns_on_the_right_side() {
+    let mut g = graph(RankDirection::TopBottom);
+    let a = node(&mut g);
+    let b = node(&mut g);
+    g.add_edge(Edge::new(a, b), EdgeLabel::default());
+    let edge = self_loop(&mut g, a, "loop", EdgeLabel::default());
+
+    layout(&mut g, None);
+
+    let (center, w, h) = bounds(&g, a);
+    let points = points(&g, &edge);
+    assert_eq!(points.len(), 7);
+    // 矩形节点的环从右边水平离开，起点在上、终点在下
+    assert_eq!(points[0], Point::new(center.x + w, center.y - h));
+    assert_eq!(points[6], Point::new(center.x + w, center.y + h));
+    assert_eq!(points[3].y, center.y);
+    assert!(points[3].x > center.x + w);
+    for point in &points[1..6] {
+        assert!(outside(&g, a, *point), "{point:?} is inside the node");
+    }
+}
+
+#[test]
+fn test_multiple_loops_nest_outward() {
+    let mut g = graph(RankDirection::TopBottom);
+    let a = node(&mut g);
+    let loops: Vec<Edge> = ["x", "y", "z"]
+        .iter()
+        .map(|name| self_loop(&mut g, a, name, EdgeLabel::default()))
+        .collect();
+
+    layout(&mut g, None);
+
+    let mut geometry: Vec<Vec<Point>> = loops.iter().map(|edge| points(&g, edge)).collect();
+    geometry.sort_by(|p, q| p[3].x.total_cmp(&q[3].x));
+    for pair in geometry.windows(2) {
+        let (inner, outer) = (&pair[0], &pair[1]);
+        assert!(outer[3].x > inner[3].x);
+        // 外层的环在节点上的起止点更远离中心，折点包住内层的环
+        assert!(outer[0].y < inner[0].y && outer[6].y > inner[6].y);
+        assert!(outer[1].y < inner[1].y && outer[5].y > inner[5].y);
+    }
+    let (center, _, h) = bounds(&g, a);
+    assert_eq!(geometry[2][0].y, center.y - h);
+}
+
+#[test]
+fn test_loop_labels_are_placed_at_the_far_end() {
+    let mut g = graph(RankDirection::TopBottom);
+    let a = node(&mut g);
+    let edge = self_loop(
+        &mut g,
+        a,
+        "loop",
+        EdgeLabel {
+            label: Some("retry".to_string()),
+            width: 40.0,
+            height: 12.0,
+            ..Default::default()
+        },
+    );
+
+    layout(&mut g, None);
+
+    let label = g.edge_label(&edge).unwrap();
+    let points = &label.points;
+    let (x, y) = (label.x.unwrap(), label.y.unwrap());
+    assert!((x - points[3].x).abs() < EPSILON && (y - points[3].y).abs() < EPSILON);
+    assert_eq!((label.width, label.height), (40.0, 12.0));
+    // 图的尺寸包含标签
+    assert!(g.config().width.unwrap() >= x + 20.0);
+}
+
+#[test]
+fn test_loops_work_under_every_rankdir() {
+    for rankdir in [
+        RankDirection::TopBottom,
+        RankDirection::BottomTop,
+        RankDirection::LeftRight,
+        RankDirection::RightLeft,
+    ] {
+        let mut g = graph(rankdir);
+        let a = node(&mut g);
+        let b = node(&mut g);
+        g.add_edge(Edge::new(a, b), EdgeLabel::default());
+        let label = EdgeLabel {
+            width: 20.0,
+            height: 10.0,
+            ..Default::default()
+        };
+        let edge = self_loop(&mut g, b, "loop", label);
+
+        layout(&mut g, None);
+
+        let points = points(&g, &edge);
+        assert_eq!(points.len(), 7, "{rankdir:?}");
+        assert!(on_boundary(&g, b, points[0]), "{rankdir:?}: {points:?}");
+        assert!(on_boundary(&g, b, points[6]), "{rankdir:?}: {points:?}");
+        for point in &points[1..6] {
+            assert!(outside(&g, b, *point), "{rankdir:?}: {points:?}");
+        }
+        // 环位于排名内方向的一侧
+        let (center, _, _) = bounds(&g, b);
+        let vertical = matches!(rankdir, RankDirection::TopBottom | RankDirection::BottomTop);
+        if vertical {
+            assert_eq!(points[3].y, center.y, "{rankdir:?}");
+        } else {
+            assert_eq!(points[3].x, center.x, "{rankdir:?}");
+        }
+        let label = g.edge_label(&edge).unwrap();
+        assert_eq!((label.width, label.height), (20.0, 10.0), "{rankdir:?}");
+    }
+}
+
+#[test]
+fn test_orthogonal_loops_are_rectangular() {
+    let mut g = graph(RankDirection::TopBottom);
+    let a = node(&mut g);
+    let label = EdgeLabel {
+        path_type: PathType::Orthogonal,
+        ..Default::default()
+    };
+    let edge = self_loop(&mut g, a, "loop", label);
+
+    layout(&mut g, None);
+
+    let points = points(&g, &edge);
+    assert_eq!(points.len(), 4);
+    for pair in points.windows(2) {
+        assert!(
+            pair[0].x == pair[1].x || pair[0].y == pair[1].y,
+            "{points:?}"
+        );
+    }
+    assert!(outside(&g, a, points[1]) && outside(&g, a, points[2]));
+}
@@ -0,0 +1,193 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::layout;
+use dagviz::shape::ShapeBoundary;
+use dagviz::types::{Edge, EdgeLabel, NodeLabel, NodeShape, PathType, Point};
+
+const EPSILON: f64 = 1e-9;
+
+fn shaped(shape: NodeShape) -> NodeLabel {
+    NodeLabel {
+        width: 60.0,
+        height: 40.0,
+        shape,
+        ..Default::default()
+    }
+}
+
+fn placed(shape: NodeShape) -> NodeLabel {
+    NodeLabel {
+        x: Some(100.0),
+        y: Some(50.0),
+        ..shaped(shape)
+    }
+}
+
+/// 一个节点连向左右两个节点，两条边都斜着离开源节点
+fn fan_out(shape: NodeShape, path_type: PathType) -> (Graph, NodeIndex, Vec<Edge>) {
+    let mut g = Graph::new();
+    let a = g.add_node(shaped(shape));
+    let edges = (0..2)
+        .map(|_| {
+            let child = g.add_node(shaped(NodeShape::Rect));
+            let edge = Edge::new(a, child);
+            let label = EdgeLabel {
+                path_type,
+                ..Default::default()
+            };
+            g.add_edge(edge.clone(), label);
+            edge
+        })
+        .collect();
+    layout(&mut g, None);
+    (g, a, edges)
+}
+
+/// 点相对节点中心的偏移，以半宽、半高为单位
+fn normalized(g: &Graph, node: NodeIndex, point: Point) -> (f64, f64) {
+    let label = g.node_label(node).unwrap();
+    (
+        (point.x - label.x.unwrap()) / (label.width / 2.0),
+        (point.y - label.y.unwrap()) / (label.height / 2.0),
+    )
+}
+
+fn source_point(g: &Graph, edge: &Edge) -> Point {
+    g.edge_label(edge).unwrap().points[0]
+}
+
+#[test]
+fn test_edges_are_clipped_to_ellipse() {
+    let (g, a, edges) = fan_out(NodeShape::Ellipse, PathType::Polyline);
+    for edge in &edges {
+        let (u, v) = normalized(&g, a, source_point(&g, edge));
+        assert!(u != 0.0, "edge should leave the ellipse diagonally");
+        assert!((u * u + v * v - 1.0).abs() < EPSILON, "({u}, {v})");
+    }
+}
+
+#[test]
+fn test_edges_are_clipped_to_diamond() {
+    let (g, a, edges) = fan_out(NodeShape::Diamond, PathType::Line);
+    for edge in &edges {
+        let (u, v) = normalized(&g, a, source_point(&g, edge));
+        assert!(u != 0.0, "edge should leave the diamond diagonally");
+        assert!((u.abs() + v.abs() - 1.0).abs() < EPSILON, "({u}, {v})");
+    }
+}
+
+#[test]
+fn test_circle_uses_smaller_dimension() {
+    let node = placed(NodeShape::Circle);
+    let hit = NodeShape::Circle.intersect(&node, &Point::new(200.0, 150.0));
+    let distance = (hit.x - 100.0).hypot(hit.y - 50.0);
+    assert!((distance - 20.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_hexagon_and_polygon_boundaries() {
+    let hexagon = placed(NodeShape::Hexagon);
+    // 水平方向到达左右顶点，竖直方向到达上下两边
+    assert_eq!(
+        NodeShape::Hexagon.intersect(&hexagon, &Point::new(300.0, 50.0)),
+        Point::new(130.0, 50.0)
+    );
+    assert_eq!(
+        NodeShape::Hexagon.intersect(&hexagon, &Point::new(100.0, -100.0)),
+        Point::new(100.0, 30.0)
+    );
+
+    // 顶点朝上的三角形，底边在下边框上
+    let triangle = NodeShape::Polygon {
+        points: vec![
+            Point::new(0.0, -0.5),
+            Point::new(0.5, 0.5),
+            Point::new(-0.5, 0.5),
+        ],
+    };
+    let node = placed(triangle.clone());
+    assert_eq!(
+        triangle.intersect(&node, &Point::new(100.0, 200.0)),
+        Point::new(100.0, 70.0)
+    );
+    let right = triangle.intersect(&node, &Point::new(300.0, 50.0));
+    assert!((right.x - 115.0).abs() < EPSILON && (right.y - 50.0).abs() < EPSILON);
+}
+
+#[test]
+fn test_rounded_rect_cuts_corners() {
+    let shape = NodeShape::RoundedRect { radius: 10.0 };
+    let node = placed(shape.clone());
+    // 沿边中点方向与矩形相同
+    assert_eq!(
+        shape.intersect(&node, &Point::new(100.0, 0.0)),
+        Point::new(100.0, 30.0)
+    );
+    // 朝向角落时落在圆角上，比矩形的角更靠近中心
+    let hit = shape.intersect(&node, &Point::new(130.0, 70.0));
+    let corner_center = Point::new(120.0, 60.0);
+    let distance = (hit.x - corner_center.x).hypot(hit.y - corner_center.y);
+    assert!((distance - 10.0).abs() < EPSILON);
+    assert!(hit.x < 130.0 && hit.y < 70.0);
+}
+
+#[test]
+fn test_orthogonal_edges_start_on_shape() {
+    let shape = NodeShape::Polygon {
+        points: vec![
+            Point::new(-0.5, -0.5),
+            Point::new(0.5, -0.5),
+            Point::new(0.0, 0.25),
+        ],
+    };
+    let (g, a, edges) = fan_out(shape, PathType::Orthogonal);
+    let label = g.node_label(a).unwrap();
+    for edge in &edges {
+        assert_eq!(
+            source_point(&g, edge),
+            Point::new(label.x.unwrap(), label.y.unwrap() + 10.0)
+        );
+    }
+}
+
+/// 只接受竖直方向的边，总是从上下两端的中点进出
+struct Pill;
+
+impl ShapeBoundary for Pill {
+    fn intersect(&self, node: &NodeLabel, point: &Point) -> Point {
+        let (x, y) = (node.x.unwrap(), node.y.unwrap());
+        let half = if point.y < y { -1.0 } else { 1.0 } * node.height / 2.0;
+        Point::new(x, y + half)
+    }
+}
+
+#[test]
+fn test_custom_shapes_clip_edges() {
+    let (g, a, edges) = fan_out(NodeShape::custom(Pill), PathType::Polyline);
+    let label = g.node_label(a).unwrap();
+    for edge in &edges {
+        assert_eq!(
+            source_point(&g, edge),
+            Point::new(label.x.unwrap(), label.y.unwrap() + 20.0)
+        );
+    }
+    // 默认轮廓沿各个方向采样边界
+    let outline = Pill.outline(label);
+    assert!(outline.iter().all(|p| p.x == label.x.unwrap()));
+}
+
+#[test]
+fn test_shapes_round_trip_through_json() {
+    let label = shaped(NodeShape::RoundedRect { radius: 4.0 });
+    let json = serde_json::to_value(&label).unwrap();
+    assert_eq!(
+        json["shape"],
+        serde_json::json!({"kind": "rounded-rect", "radius": 4.0})
+    );
+    let back: NodeLabel = serde_json::from_value(json).unwrap();
+    assert_eq!(back.shape, label.shape);
+
+    let rect = serde_json::to_value(shaped(NodeShape::Rect)).unwrap();
+    assert!(rect.get("shape").is_none());
+    let custom = serde_json::to_value(shaped(NodeShape::custom(Pill))).unwrap();
+    assert!(custom.get("shape").is_none());
+}
@@ -0,0 +1,140 @@
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::layout::layout;
+use dagviz::stats::LayoutStats;
+use dagviz::types::{Edge, EdgeLabel, NodeLabel, Ranker};
+use std::thread;
+
+fn build(edges: &[(usize, usize)], ranker: Ranker) -> Graph {
+    let mut g = Graph::new();
+    g.config_mut().ranker = ranker;
+    let count = edges.iter().map(|&(v, w)| v.max(w)).max().unwrap_or(0) + 1;
+    let nodes: Vec<NodeIndex> = (0..count)
+        .map(|_| {
+            g.add_node(NodeLabel {
+                width: 20.0,
+                height: 20.0,
+                ..Default::default()
+            })
+        })
+        .collect();
+    for &(v, w) in edges {
+        g.add_edge(Edge::new(nodes[v], nodes[w]), EdgeLabel::default());
+    }
+    g
+}
+
+/// 去掉耗时，只比较确定性的计数
+fn counts(stats: &LayoutStats) -> LayoutStats {
+    LayoutStats {
+        stages: Vec::new(),
+        ..stats.clone()
+    }
+}
+
+/// Gansner 等人论文中的例子，最长路径的初始排名不是最优的
+const GANSNER: &[(usize, usize)] = &[
+    (0, 1),
+    (1, 2),
+    (2, 3),
+    (3, 7),
+    (0, 4),
+    (4, 6),
+    (0, 5),
+    (5, 6),
+    (6, 7),
+];
+
+#[test]
+fn test_layout_reports_counts_for_each_phase() {
+    // 0 -> 1 -> 2 -> 0 是一个环，0 -> 2 跨越两个排名
+    let mut g = build(
+        &[(0, 1), (1, 2), (2, 0), (0, 3), (3, 4)],
+        Ranker::NetworkSimplex,
+    );
+    let stats = layout(&mut g, None);
+
+    assert_eq!(stats.reversed_edges, 1);
+    assert!(stats.dummy_nodes > 0);
+    assert!(stats.order_sweeps > 0);
+    assert_eq!(stats.crossings.len(), stats.order_sweeps);
+    assert_eq!(stats.final_crossings(), Some(0));
+
+    for name in [
+        "build_layout_graph",
+        "acyclic",
+        "rank",
+        "network-simplex",
+        "order",
+        "position",
+        "run_layout",
+        "update_input_graph",
+    ] {
+        assert!(stats.stage_time(name).is_some(), "missing stage {}", name);
+    }
+    assert_eq!(stats.stages.last().unwrap().name, "update_input_graph");
+}
+
+#[test]
+fn test_unavoidable_crossings_are_counted() {
+    // 完全二分图 K2,3：任意两层画法都有 C(2,2)·C(3,2) = 3 个交叉
+    let mut g = build(
+        &[(0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4)],
+        Ranker::NetworkSimplex,
+    );
+    let stats = layout(&mut g, None);
+
+    assert_eq!(stats.final_crossings(), Some(3));
+    assert!(stats.crossings.iter().all(|&cc| cc >= 3));
+}
+
+#[test]
+fn test_network_simplex_pivots_are_counted() {
+    let stats = layout(&mut build(GANSNER, Ranker::NetworkSimplex), None);
+    assert!(stats.network_simplex_pivots > 0);
+
+    let stats = layout(&mut build(GANSNER, Ranker::LongestPath), None);
+    assert_eq!(stats.network_simplex_pivots, 0);
+}
+
+#[test]
+fn test_dummy_nodes_count_each_inserted_dummy_once() {
+    // 排名间隔为边标签留空而加倍，每条边跨两个排名，拆出 1 个虚拟节点；另有 1 个嵌套根节点
+    let stats = layout(&mut build(&[(0, 1), (1, 2)], Ranker::NetworkSimplex), None);
+    assert_eq!(stats.dummy_nodes, 3);
+
+    // 0 -> 2 跨四个排名，再拆出 3 个虚拟节点
+    let stats = layout(
+        &mut build(&[(0, 1), (1, 2), (0, 2)], Ranker::NetworkSimplex),
+        None,
+    );
+    assert_eq!(stats.dummy_nodes, 6);
+}
+
+#[test]
+fn test_concurrent_layouts_keep_their_own_stats() {
+    let expected_a = counts(&layout(&mut build(GANSNER, Ranker::NetworkSimplex), None));
+    let expected_b = counts(&layout(
+        &mut build(&[(0, 1), (1, 0)], Ranker::LongestPath),
+        None,
+    ));
+    assert_ne!(expected_a, expected_b);
+
+    let a = thread::spawn(move || {
+        (0..20)
+            .map(|_| counts(&layout(&mut build(GANSNER, Ranker::NetworkSimplex), None)))
+            .collect::<Vec<_>>()
+    });
+    let b = thread::spawn(move || {
+        (0..20)
+            .map(|_| {
+                counts(&layout(
+                    &mut build(&[(0, 1), (1, 0)], Ranker::LongestPath),
+                    None,
+                ))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    assert!(a.join().unwrap().iter().all(|stats| *stats == expected_a));
+    assert!(b.join().unwrap().iter().all(|stats| *stats == expected_b));
+}
@@ -0,0 +1,152 @@
+use dagviz::error::LayoutError;
+use dagviz::graph::Graph;
+use dagviz::layout::try_layout;
+use dagviz::types::{Edge, EdgeLabel, NodeLabel, Ranker};
+
+fn node(width: f64, height: f64) -> NodeLabel {
+    NodeLabel {
+        width,
+        height,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_try_layout_returns_dimensions() {
+    let mut g = Graph::new();
+    g.add_node(node(100.0, 50.0));
+
+    let result = try_layout(&mut g, None).unwrap();
+
+    assert_eq!(Some(result.width), g.config().width);
+    assert_eq!(Some(result.height), g.config().height);
+}
+
+#[test]
+fn test_rejects_nan_node_size() {
+    let mut g = Graph::new();
+    g.add_node(node(10.0, 10.0));
+    let bad = g.add_node(node(f64::NAN, 10.0));
+
+    let err = try_layout(&mut g, None).unwrap_err();
+
+    match err {
+        LayoutError::InvalidNodeSize { node, .. } => assert_eq!(node, bad),
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[test]
+fn test_rejects_negative_node_size() {
+    let mut g = Graph::new();
+    let bad = g.add_node(node(10.0, -1.0));
+
+    let err = try_layout(&mut g, None).unwrap_err();
+
+    assert_eq!(
+        err,
+        LayoutError::InvalidNodeSize {
+            node: bad,
+            width: 10.0,
+            height: -1.0,
+        }
+    );
+}
+
+#[test]
+fn test_rejects_dangling_parent() {
+    let mut g = Graph::new();
+    let a = g.add_node(node(10.0, 10.0));
+    let parent = g.add_node(node(0.0, 0.0));
+    g.remove_node(parent);
+    // remove_node 会清除子节点的父节点，悬空的父节点只能直接写入标签
+    g.node_label_mut(a).unwrap().parent = Some(parent);
+
+    let err = try_layout(&mut g, None).unwrap_err();
+
+    assert_eq!(err, LayoutError::DanglingParent { node: a, parent });
+}
+
+#[test]
+fn test_rejects_parent_cycle() {
+    let mut g = Graph::new();
+    let a = g.add_node(node(10.0, 10.0));
+    let b = g.add_node(node(10.0, 10.0));
+    let c = g.add_node(node(10.0, 10.0));
+    g.set_parent(a, b);
+    g.set_parent(b, c);
+    g.set_parent(c, a);
+
+    let err = try_layout(&mut g, None).unwrap_err();
+
+    match err {
+        LayoutError::ParentCycle { nodes } => {
+            assert_eq!(nodes.len(), 3);
+            assert!(nodes.contains(&a) && nodes.contains(&b) && nodes.contains(&c));
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[test]
+fn test_rejects_contradictory_fixed_ranks() {
+    let mut g = Graph::new();
+    g.config_mut().ranker = Ranker::None;
+    let a = g.add_node(NodeLabel {
+        rank: Some(1),
+        ..node(10.0, 10.0)
+    });
+    let b = g.add_node(NodeLabel {
+        rank: Some(1),
+        ..node(10.0, 10.0)
+    });
+    g.add_edge(Edge::new(a, b), EdgeLabel::default());
+
+    let err = try_layout(&mut g, None).unwrap_err();
+
+    assert_eq!(
+        err,
+        LayoutError::RankContradiction {
+            edge: Edge::new(a, b),
+            source_rank: 1,
+            target_rank: 1,
+            minlen: 1,
+        }
+    );
+}
+
+#[test]
+fn test_error_leaves_input_untouched() {
+    let mut g = Graph::new();
+    let a = g.add_node(node(10.0, 10.0));
+    g.add_node(node(f64::INFINITY, 10.0));
+
+    assert!(try_layout(&mut g, None).is_err());
+
+    let label = g.node_label(a).unwrap();
+    assert!(label.x.is_none());
+    assert!(label.y.is_none());
+    assert!(g.config().width.is_none());
+}
+
+#[test]
+fn test_rejects_parent_from_another_graph() {
+    let mut g = Graph::new();
+    let a = g.add_node(node(10.0, 10.0));
+    let mut other = Graph::new();
+    let foreign = other.add_node(node(0.0, 0.0));
+    // set_parent 会拒绝其他图的节点，只能直接写入标签
+    g.node_label_mut(a).unwrap().parent = Some(foreign);
+
+    let err = try_layout(&mut g, None).unwrap_err();
+
+    assert_eq!(
+        err,
+        LayoutError::ForeignNode {
+            node: foreign,
+            graph_id: g.graph_id(),
+            edge: None,
+        }
+    );
+    assert!(g.node_label(a).unwrap().x.is_none());
+}
@@ -5,10 +5,12 @@
 mod acyclic_tests;
 mod constraint_graph_tests;
 mod data;
+mod io;
 mod layout;
 mod order;
 mod position;
 mod rank;
+mod render;
 mod sort_subgraph_tests;
 mod util;
 
@@ -0,0 +1,209 @@
+use dagviz::context::LayoutContext;
+use dagviz::error::LayoutError;
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::keyed::KeyedGraph;
+use dagviz::layout::layout;
+use dagviz::order::order;
+use dagviz::types::{Edge, EdgeLabel, NodeLabel, OrderConstraint, OrderKind};
+
+use crate::layout::node;
+
+fn ranked(g: &mut Graph, rank: i32) -> NodeIndex {
+    g.add_node(NodeLabel {
+        rank: Some(rank),
+        ..Default::default()
+    })
+}
+
+fn edge(g: &mut Graph, source: NodeIndex, target: NodeIndex) {
+    g.add_edge(Edge::new(source, target), EdgeLabel::default());
+}
+
+fn x(g: &Graph, node: NodeIndex) -> f64 {
+    g.node_label(node).unwrap().x.unwrap()
+}
+
+fn order_of(g: &Graph, node: NodeIndex) -> usize {
+    g.node_label(node).unwrap().order.unwrap()
+}
+
+/// 一个根节点连向若干子节点
+fn fan_out(count: usize) -> (Graph, NodeIndex, Vec<NodeIndex>) {
+    let mut g = Graph::new();
+    let root = node(&mut g);
+    let children: Vec<NodeIndex> = (0..count).map(|_| node(&mut g)).collect();
+    for &child in &children {
+        edge(&mut g, root, child);
+    }
+    (g, root, children)
+}
+
+#[test]
+fn test_sequence_orders_nodes_from_left_to_right() {
+    let (mut g, _, children) = fan_out(3);
+    let [a, b, c] = children[..] else {
+        unreachable!()
+    };
+    g.add_order_constraint(OrderConstraint::sequence([c, a, b]));
+
+    layout(&mut g, None);
+
+    assert!(x(&g, c) < x(&g, a));
+    assert!(x(&g, a) < x(&g, b));
+}
+
+#[test]
+fn test_leftmost_and_rightmost_pin_the_ends_of_a_rank() {
+    let (mut g, _, children) = fan_out(4);
+    g.add_order_constraint(OrderConstraint::leftmost([children[3]]));
+    g.add_order_constraint(OrderConstraint::rightmost([children[0]]));
+
+    layout(&mut g, None);
+
+    assert_eq!(order_of(&g, children[3]), 0);
+    assert_eq!(order_of(&g, children[0]), 3);
+}
+
+#[test]
+fn test_sequence_only_constrains_nodes_on_the_same_rank() {
+    let mut g = Graph::new();
+    let root = ranked(&mut g, 0);
+    let a = ranked(&mut g, 1);
+    let b = ranked(&mut g, 1);
+    let deeper = ranked(&mut g, 2);
+    edge(&mut g, root, a);
+    edge(&mut g, root, b);
+    edge(&mut g, a, deeper);
+    // deeper 不在第 1 层，b 仍要排在 a 之前
+    g.add_order_constraint(OrderConstraint::sequence([b, deeper, a]));
+
+    order(&mut g, &mut LayoutContext::new(), None);
+
+    assert!(order_of(&g, b) < order_of(&g, a));
+    assert_eq!(order_of(&g, deeper), 0);
+}
+
+#[test]
+fn test_constraints_hold_when_the_heuristic_is_disabled() {
+    let mut g = Graph::new();
+    let root = ranked(&mut g, 0);
+    let nodes: Vec<NodeIndex> = (0..3).map(|_| ranked(&mut g, 1)).collect();
+    for &node in &nodes {
+        edge(&mut g, root, node);
+    }
+    g.add_order_constraint(OrderConstraint::sequence([nodes[2], nodes[1], nodes[0]]));
+    let opts = dagviz::types::LayoutOptions {
+        disable_optimal_order_heuristic: true,
+        ..Default::default()
+    };
+
+    order(&mut g, &mut LayoutContext::new(), Some(&opts));
+
+    let orders: Vec<usize> = nodes.iter().map(|&node| order_of(&g, node)).collect();
+    assert_eq!(orders, vec![2, 1, 0]);
+}
+
+#[test]
+fn test_sweeps_place_constrained_nodes_at_their_joint_barycenter() {
+    let mut g = Graph::new();
+    let parents: Vec<NodeIndex> = (0..4).map(|_| ranked(&mut g, 0)).collect();
+    g.add_order_constraint(OrderConstraint::sequence(parents.clone()));
+    let [a, b, x1, x2] = [(); 4].map(|_| ranked(&mut g, 1));
+    edge(&mut g, parents[3], a);
+    g.add_edge(
+        Edge::new(parents[0], b),
+        EdgeLabel {
+            weight: 5.0,
+            ..Default::default()
+        },
+    );
+    edge(&mut g, parents[1], x1);
+    edge(&mut g, parents[2], x2);
+    // a 的重心在 b 右侧；扫描时两者合并，按加权重心整体排在 x1、x2 之前。
+    // 只在扫描之后调整的话，b 会被挪到最右端，与三条边交叉
+    g.add_order_constraint(OrderConstraint::sequence([a, b]));
+
+    order(&mut g, &mut LayoutContext::new(), None);
+
+    let orders: Vec<usize> = [a, b, x1, x2].iter().map(|&n| order_of(&g, n)).collect();
+    assert_eq!(orders, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_contradictory_constraints_do_not_fail_the_layout() {
+    let (mut g, _, children) = fan_out(2);
+    g.add_order_constraint(OrderConstraint::sequence([children[0], children[1]]));
+    g.add_order_constraint(OrderConstraint::sequence([children[1], children[0]]));
+
+    layout(&mut g, None);
+
+    assert_ne!(x(&g, children[0]), x(&g, children[1]));
+}
+
+#[test]
+fn test_keyed_graph_adds_constraints_by_id() {
+    let mut g = KeyedGraph::new();
+    for id in ["in", "x", "y", "z"] {
+        g.set_node(
+            id,
+            NodeLabel {
+                width: 10.0,
+                height: 10.0,
+                ..Default::default()
+            },
+        );
+    }
+    for id in ["x", "y", "z"] {
+        g.set_edge("in", id, EdgeLabel::default());
+    }
+    g.add_order_constraint(OrderKind::Sequence, ["z", "y", "x"]);
+    g.add_order_constraint(OrderKind::Leftmost, ["z"]);
+
+    let result = g.layout(None);
+
+    let x = |id: &str| result.nodes[id].x.unwrap();
+    assert!(x("z") < x("y") && x("y") < x("x"));
+}
+
+#[test]
+fn test_constraints_across_clusters_keep_clusters_together() {
+    let (mut g, _, children) = fan_out(3);
+    let [a, b, c] = children[..] else {
+        unreachable!()
+    };
+    let cluster = g.add_node(NodeLabel::default());
+    g.set_parent(a, cluster);
+    g.set_parent(b, cluster);
+    // c 要排在 b 之前，约束提升到整个集群上，c 不会插进集群中间
+    g.add_order_constraint(OrderConstraint::sequence([c, b]));
+
+    layout(&mut g, None);
+
+    assert!(x(&g, c) < x(&g, a).min(x(&g, b)));
+}
+
+#[test]
+fn test_try_add_order_constraint_rejects_foreign_and_removed_nodes() {
+    let (mut g, _, children) = fan_out(2);
+    let [a, b] = children[..] else { unreachable!() };
+    let mut other = Graph::new();
+    let foreign = node(&mut other);
+
+    assert!(matches!(
+        g.try_add_order_constraint(OrderConstraint::sequence([a, foreign])),
+        Err(LayoutError::ForeignNode { .. })
+    ));
+    g.remove_node(b);
+    assert_eq!(
+        g.try_add_order_constraint(OrderConstraint::sequence([b, a])),
+        Err(LayoutError::UnknownNode {
+            node: b,
+            edge: None,
+        })
+    );
+    assert!(g.order_constraints().is_empty());
+
+    g.try_add_order_constraint(OrderConstraint::leftmost([a]))
+        .unwrap();
+    assert_eq!(g.order_constraints(), &[OrderConstraint::leftmost([a])]);
+}
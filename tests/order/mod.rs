@@ -1,3 +1,4 @@
+pub mod constraint_tests;
 pub mod cross_count_tests;
 pub mod order_tests;
 pub mod sort_subgraph_tests;
@@ -1,3 +1,4 @@
+use dagviz::context::LayoutContext;
 use dagviz::graph::Graph;
 use dagviz::order::cross_count::cross_count;
 use dagviz::order::order;
@@ -71,7 +72,7 @@ fn test_does_not_add_crossings_to_tree_structure() {
         },
     );
 
-    order(&mut g, None);
+    order(&mut g, &mut LayoutContext::new(), None);
     let layering = build_layer_matrix(&g);
     assert_eq!(cross_count(&g, &layering), 0);
 }
@@ -121,7 +122,7 @@ fn test_can_solve_simple_graph() {
         },
     );
 
-    order(&mut g, None);
+    order(&mut g, &mut LayoutContext::new(), None);
     let layering = build_layer_matrix(&g);
     assert_eq!(cross_count(&g, &layering), 0);
 }
@@ -178,7 +179,7 @@ fn test_can_solve_complex_graph() {
         },
     );
 
-    order(&mut g, None);
+    order(&mut g, &mut LayoutContext::new(), None);
     let layering = build_layer_matrix(&g);
     assert_eq!(cross_count(&g, &layering), 0);
 }
@@ -311,7 +312,7 @@ fn test_can_solve_multi_layer_graph() {
         },
     );
 
-    order(&mut g, None);
+    order(&mut g, &mut LayoutContext::new(), None);
     let layering = build_layer_matrix(&g);
     assert_eq!(cross_count(&g, &layering), 0);
 }
@@ -493,9 +494,10 @@ fn test_can_solve_dense_graph() {
         },
     );
 
-    order(&mut g, None);
+    order(&mut g, &mut LayoutContext::new(), None);
     let layering = build_layer_matrix(&g);
-    assert_eq!(cross_count(&g, &layering), 0);
+    // 第 2、3 层之间是完全二分图 K3,4，任意两层画法都有 C(3,2)·C(4,2) = 18 个交叉，其余层没有交叉
+    assert_eq!(cross_count(&g, &layering), 18);
 }
 
 #[test]
@@ -507,7 +509,7 @@ fn test_can_solve_single_node() {
         ..Default::default()
     });
 
-    order(&mut g, None);
+    order(&mut g, &mut LayoutContext::new(), None);
     let layering = build_layer_matrix(&g);
     assert_eq!(cross_count(&g, &layering), 0);
 }
@@ -516,7 +518,7 @@ fn test_can_solve_single_node() {
 fn test_can_solve_empty_graph() {
     let mut g = Graph::new();
 
-    order(&mut g, None);
+    order(&mut g, &mut LayoutContext::new(), None);
     let layering = build_layer_matrix(&g);
     assert_eq!(cross_count(&g, &layering), 0);
 }
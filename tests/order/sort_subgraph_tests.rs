@@ -1,7 +1,7 @@
 //! 子图排序功能测试
 //! 对应JavaScript版本的 test/order/sort-subgraph-test.js
 
-use dagviz::graph::Graph;
+use dagviz::graph::{Graph, NodeIndex};
 use dagviz::order::constraint_graph::ConstraintGraph;
 use dagviz::order::sort_subgraph::sort_subgraph;
 use dagviz::types::{Edge, EdgeLabel, NodeLabel};
@@ -78,3 +78,74 @@ fn test_sort_with_bias() {
     assert!(!result_left.is_empty());
     assert!(!result_right.is_empty());
 }
+
+#[test]
+fn test_constraint_keeps_source_before_target() {
+    // a 的重心大于 b，约束 a -> b 使两者合并且 a 仍在 b 之前
+    let mut g = Graph::new();
+    let root = g.add_node(NodeLabel::default());
+    let a = g.add_node(NodeLabel::default());
+    let b = g.add_node(NodeLabel::default());
+    g.set_parent(a, root);
+    g.set_parent(b, root);
+    let p0 = g.add_node(NodeLabel {
+        order: Some(0),
+        ..Default::default()
+    });
+    let p1 = g.add_node(NodeLabel {
+        order: Some(1),
+        ..Default::default()
+    });
+    g.add_edge(Edge::new(p1, a), EdgeLabel::default());
+    g.add_edge(Edge::new(p0, b), EdgeLabel::default());
+
+    let unconstrained = sort_subgraph(&g, root, &ConstraintGraph::new(), false);
+    assert_eq!(unconstrained.vs, vec![b, a]);
+
+    let mut cg = ConstraintGraph::new();
+    cg.add_constraint(a, b);
+    let result = sort_subgraph(&g, root, &cg, false);
+    assert_eq!(result.vs, vec![a, b]);
+}
+
+/// 在 root 下依次添加子节点，`pred_orders` 给出每个子节点唯一前驱的顺序，`None` 表示没有前驱
+fn add_children(g: &mut Graph, root: NodeIndex, pred_orders: &[Option<usize>]) -> Vec<NodeIndex> {
+    pred_orders
+        .iter()
+        .map(|&pred_order| {
+            let v = g.add_node(NodeLabel::default());
+            g.set_parent(v, root);
+            if let Some(order) = pred_order {
+                let pred = g.add_node(NodeLabel {
+                    order: Some(order),
+                    ..Default::default()
+                });
+                g.add_edge(Edge::new(pred, v), EdgeLabel::default());
+            }
+            v
+        })
+        .collect()
+}
+
+#[test]
+fn test_equal_barycenters_keep_position_unless_bias_right() {
+    let mut g = Graph::new();
+    let root = g.add_node(NodeLabel::default());
+    let vs = add_children(&mut g, root, &[Some(0), Some(0)]);
+    let cg = ConstraintGraph::new();
+
+    assert_eq!(sort_subgraph(&g, root, &cg, false).vs, vec![vs[0], vs[1]]);
+    assert_eq!(sort_subgraph(&g, root, &cg, true).vs, vec![vs[1], vs[0]]);
+}
+
+#[test]
+fn test_nodes_without_barycenter_keep_their_position() {
+    let mut g = Graph::new();
+    let root = g.add_node(NodeLabel::default());
+    let vs = add_children(&mut g, root, &[Some(1), None, Some(0)]);
+
+    let result = sort_subgraph(&g, root, &ConstraintGraph::new(), false);
+
+    // 中间的节点没有前驱，排序后仍在第二个位置
+    assert_eq!(result.vs, vec![vs[2], vs[1], vs[0]]);
+}
@@ -1,9 +1,11 @@
+use dagviz::budget::{Budget, LayoutBudget};
 use dagviz::graph::Graph;
 use dagviz::graph::NodeIndex;
 use dagviz::position::bk::*;
 use dagviz::types::{Dummy, Edge, EdgeLabel, NodeLabel};
 use dagviz::util::build_layer_matrix;
 use indexmap::IndexMap;
+use std::time::Duration;
 
 #[test]
 fn test_find_type1_conflicts_does_not_mark_edges_with_no_conflict() {
@@ -442,3 +444,68 @@ fn test_position_x() {
         assert!(x.is_finite());
     }
 }
+
+#[test]
+fn test_position_x_keeps_parallel_chains_straight_and_node_sep_apart() {
+    let mut g = Graph::with_config(dagviz::types::GraphConfig {
+        node_sep: 30.0,
+        ..Default::default()
+    });
+    let node = |rank, order| NodeLabel {
+        width: 10.0,
+        rank: Some(rank),
+        order: Some(order),
+        ..Default::default()
+    };
+    let a = g.add_node(node(0, 0));
+    let b = g.add_node(node(0, 1));
+    let c = g.add_node(node(1, 0));
+    let d = g.add_node(node(1, 1));
+    g.add_edge(Edge::new(a, c), EdgeLabel::default());
+    g.add_edge(Edge::new(b, d), EdgeLabel::default());
+
+    let result = g.compute_bk_positions();
+    let x = |n: NodeIndex| result.positions[&n].position;
+
+    // 四个对齐方向的结果一致时，平衡后两条链保持竖直，间距为宽度加 node_sep
+    assert_eq!(x(a), x(c));
+    assert_eq!(x(b), x(d));
+    assert_eq!(x(b) - x(a), 40.0);
+}
+
+#[test]
+fn test_expired_deadline_keeps_the_alignments_computed_so_far() {
+    // a 和 b 都指向 c，四个对齐方向平衡后 c 在 a、b 中间
+    let fork = || {
+        let mut g = Graph::new();
+        let mut add = |rank, order| {
+            g.add_node(NodeLabel {
+                rank: Some(rank),
+                order: Some(order),
+                width: 10.0,
+                ..Default::default()
+            })
+        };
+        let (a, b, c) = (add(0, 0), add(0, 1), add(1, 0));
+        g.add_edge(Edge::new(a, c), EdgeLabel::default());
+        g.add_edge(Edge::new(b, c), EdgeLabel::default());
+        (g, [a, b, c])
+    };
+
+    let (g, [a, b, c]) = fork();
+    let positions = BrandesKoepf::new(g).run().positions;
+    let x = |node| positions[&node].position;
+    assert!(x(a) < x(c) && x(c) < x(b));
+
+    // 截止时间已过时只算出 ul 对齐，c 与 a 对齐
+    let (g, [a, b, c]) = fork();
+    let budget = Budget::start(LayoutBudget {
+        time_limit: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    let positions = BrandesKoepf::new(g).with_budget(budget).run().positions;
+    let x = |node| positions[&node].position;
+    assert_eq!(positions.len(), 3);
+    assert_eq!(x(c), x(a));
+    assert!(x(a) < x(b));
+}
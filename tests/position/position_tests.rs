@@ -1,3 +1,4 @@
+use dagviz::context::LayoutContext;
 use dagviz::graph::Graph;
 use dagviz::position::position;
 use dagviz::types::{Edge, EdgeLabel, NodeLabel};
@@ -16,7 +17,7 @@ fn test_positions_single_node_at_origin() {
         ..Default::default()
     });
     
-    position(&mut g);
+    position(&mut g, &mut LayoutContext::new());
     
     if let Some(label) = g.node_label(a) {
         assert_eq!(label.x, Some(0.0));
@@ -41,7 +42,7 @@ fn test_positions_single_node_block_at_origin() {
     
     g.add_edge(Edge::new(a, b), EdgeLabel::default());
     
-    position(&mut g);
+    position(&mut g, &mut LayoutContext::new());
     
     if let (Some(a_label), Some(b_label)) = (g.node_label(a), g.node_label(b)) {
         assert_eq!(a_label.x, Some(0.0));
@@ -74,7 +75,7 @@ fn test_positions_single_node_block_at_origin_with_different_sizes() {
     g.add_edge(Edge::new(a, b), EdgeLabel::default());
     g.add_edge(Edge::new(b, c), EdgeLabel::default());
     
-    position(&mut g);
+    position(&mut g, &mut LayoutContext::new());
     
     if let (Some(a_label), Some(b_label), Some(c_label)) = 
         (g.node_label(a), g.node_label(b), g.node_label(c)) {
@@ -113,7 +114,7 @@ fn test_centers_node_if_predecessor_of_two_same_sized_nodes() {
     g.add_edge(Edge::new(a, b), EdgeLabel::default());
     g.add_edge(Edge::new(a, c), EdgeLabel::default());
     
-    position(&mut g);
+    position(&mut g, &mut LayoutContext::new());
     
     if let (Some(a_label), Some(b_label), Some(c_label)) = 
         (g.node_label(a), g.node_label(b), g.node_label(c)) {
@@ -131,7 +132,7 @@ fn test_centers_node_if_predecessor_of_two_same_sized_nodes() {
 fn test_handles_empty_graph() {
     let mut g = setup_test_graph();
     
-    position(&mut g);
+    position(&mut g, &mut LayoutContext::new());
     
     // Should not panic
     assert!(g.is_empty());
@@ -145,7 +146,7 @@ fn test_handles_single_node_without_rank() {
         ..Default::default()
     });
     
-    position(&mut g);
+    position(&mut g, &mut LayoutContext::new());
     
     // Should not panic, even without rank
     assert!(g.has_node(a));
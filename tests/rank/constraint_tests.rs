@@ -0,0 +1,223 @@
+use dagviz::error::LayoutError;
+use dagviz::graph::{Graph, NodeIndex};
+use dagviz::io::dot;
+use dagviz::layout::{layout, try_layout};
+use dagviz::types::{Edge, EdgeLabel, GraphConfig, NodeLabel, RankConstraint, RankKind, Ranker};
+
+const RANKERS: [Ranker; 4] = [
+    Ranker::NetworkSimplex,
+    Ranker::LongestPath,
+    Ranker::TightTree,
+    Ranker::FeasibleTree,
+];
+
+/// a -> b -> c，d -> c，e 是孤立节点
+fn graph(ranker: Ranker) -> (Graph, [NodeIndex; 5]) {
+    let mut g = Graph::with_config(GraphConfig {
+        ranker,
+        ..Default::default()
+    });
+    let nodes = [(); 5].map(|_| {
+        g.add_node(NodeLabel {
+            width: 20.0,
+            height: 20.0,
+            ..Default::default()
+        })
+    });
+    let [a, b, c, d, _] = nodes;
+    for (source, target) in [(a, b), (b, c), (d, c)] {
+        g.add_edge(Edge::new(source, target), EdgeLabel::default());
+    }
+    (g, nodes)
+}
+
+fn rank(g: &Graph, node: NodeIndex) -> i32 {
+    g.node_label(node).unwrap().rank.unwrap()
+}
+
+fn constrained(ranker: Ranker, kind: RankKind, nodes: &[usize]) -> (Graph, [NodeIndex; 5]) {
+    let (mut g, all) = graph(ranker);
+    g.add_rank_constraint(RankConstraint::new(kind, nodes.iter().map(|&i| all[i])));
+    (g, all)
+}
+
+#[test]
+fn test_same_rank_is_honored_by_every_ranker() {
+    for ranker in RANKERS {
+        let (mut g, [a, b, c, d, e]) = constrained(ranker, RankKind::Same, &[3, 0]);
+        g.add_rank_constraint(RankConstraint::same([e, c]));
+        try_layout(&mut g, None).unwrap();
+
+        assert_eq!(rank(&g, d), rank(&g, a), "{:?}", ranker);
+        assert_eq!(rank(&g, e), rank(&g, c), "{:?}", ranker);
+        assert!(rank(&g, a) < rank(&g, b) && rank(&g, b) < rank(&g, c));
+    }
+}
+
+#[test]
+fn test_min_and_source_place_nodes_on_the_first_rank() {
+    for ranker in RANKERS {
+        let (mut g, [a, _, _, d, _]) = constrained(ranker, RankKind::Min, &[3]);
+        try_layout(&mut g, None).unwrap();
+        assert_eq!(rank(&g, d), rank(&g, a), "{:?}", ranker);
+
+        let (mut g, nodes) = constrained(ranker, RankKind::Source, &[3]);
+        try_layout(&mut g, None).unwrap();
+        for &node in &nodes[..3] {
+            assert!(rank(&g, nodes[3]) < rank(&g, node), "{:?}", ranker);
+        }
+        assert!(rank(&g, nodes[3]) < rank(&g, nodes[4]), "{:?}", ranker);
+    }
+}
+
+#[test]
+fn test_max_and_sink_place_nodes_on_the_last_rank() {
+    for ranker in RANKERS {
+        let (mut g, [_, _, c, _, e]) = constrained(ranker, RankKind::Max, &[4]);
+        try_layout(&mut g, None).unwrap();
+        assert_eq!(rank(&g, e), rank(&g, c), "{:?}", ranker);
+
+        let (mut g, nodes) = constrained(ranker, RankKind::Sink, &[4]);
+        try_layout(&mut g, None).unwrap();
+        for &node in &nodes[..4] {
+            assert!(rank(&g, node) < rank(&g, nodes[4]), "{:?}", ranker);
+        }
+    }
+}
+
+#[test]
+fn test_contradictory_constraints_are_rejected() {
+    // 同一个节点既在第一个排名又在最后一个排名
+    let (mut g, [_, _, _, _, e]) = constrained(Ranker::NetworkSimplex, RankKind::Min, &[4]);
+    g.add_rank_constraint(RankConstraint::new(RankKind::Sink, [e]));
+    assert_eq!(
+        try_layout(&mut g, None).unwrap_err(),
+        LayoutError::RankConstraintConflict {
+            nodes: vec![e],
+            edge: None,
+        }
+    );
+
+    // 有边指向第一个排名
+    let (mut g, [a, b, ..]) = constrained(Ranker::NetworkSimplex, RankKind::Source, &[1]);
+    assert_eq!(
+        try_layout(&mut g, None).unwrap_err(),
+        LayoutError::RankConstraintConflict {
+            nodes: vec![b],
+            edge: Some(Edge::new(a, b)),
+        }
+    );
+
+    // a -> b -> c 不可能让 a 与 c 同一排名
+    let (mut g, [a, _, c, ..]) = constrained(Ranker::LongestPath, RankKind::Same, &[0, 2]);
+    let err = try_layout(&mut g, None).unwrap_err();
+    let LayoutError::RankConstraintConflict { nodes, edge } = &err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert_eq!(nodes, &vec![a, c]);
+    assert!(edge.is_some());
+    assert!(err.to_string().starts_with("rank constraints on nodes"));
+    assert!(g.node_label(a).unwrap().rank.is_none());
+
+    // layout 忽略无法满足的约束，并在统计中记录冲突
+    let stats = layout(&mut g, None);
+    assert!(rank(&g, a) < rank(&g, c));
+    assert_eq!(stats.rank_constraint_conflict, Some(err));
+}
+
+#[test]
+fn test_cycles_of_zero_length_edges_are_feasible() {
+    for ranker in RANKERS {
+        // a 与 c 同一排名，a -> b -> c 的最小长度都为 0，三个节点只能在同一排名
+        let (mut g, [a, b, c, d, _]) = graph(ranker);
+        for (source, target) in [(a, b), (b, c)] {
+            g.edge_label_mut(&Edge::new(source, target)).unwrap().minlen = 0;
+        }
+        g.add_rank_constraint(RankConstraint::same([a, c]));
+
+        let stats = try_layout(&mut g, None).unwrap().stats;
+        assert_eq!(stats.rank_constraint_conflict, None, "{:?}", ranker);
+        assert_eq!(rank(&g, a), rank(&g, b), "{:?}", ranker);
+        assert_eq!(rank(&g, b), rank(&g, c), "{:?}", ranker);
+        assert!(rank(&g, d) < rank(&g, c), "{:?}", ranker);
+    }
+}
+
+#[test]
+fn test_try_add_rank_constraint_rejects_foreign_and_removed_nodes() {
+    let (mut g, [a, b, ..]) = graph(Ranker::NetworkSimplex);
+    let mut other = Graph::new();
+    let foreign = other.add_node(NodeLabel::default());
+
+    assert!(matches!(
+        g.try_add_rank_constraint(RankConstraint::same([a, foreign])),
+        Err(LayoutError::ForeignNode { .. })
+    ));
+    g.remove_node(b);
+    assert_eq!(
+        g.try_add_rank_constraint(RankConstraint::same([a, b])),
+        Err(LayoutError::UnknownNode {
+            node: b,
+            edge: None,
+        })
+    );
+    assert!(g.rank_constraints().is_empty());
+
+    g.try_add_rank_constraint(RankConstraint::new(RankKind::Min, [a]))
+        .unwrap();
+    assert_eq!(
+        g.rank_constraints(),
+        &[RankConstraint::new(RankKind::Min, [a])]
+    );
+}
+
+#[test]
+fn test_fixed_ranks_are_checked_against_constraints() {
+    let (mut g, nodes) = constrained(Ranker::None, RankKind::Same, &[0, 3]);
+    for (node, r) in nodes.into_iter().zip([0, 2, 4, 2, 0]) {
+        g.node_label_mut(node).unwrap().rank = Some(r);
+    }
+    assert!(matches!(
+        try_layout(&mut g, None),
+        Err(LayoutError::RankConstraintConflict { .. })
+    ));
+
+    g.node_label_mut(nodes[3]).unwrap().rank = Some(0);
+    try_layout(&mut g, None).unwrap();
+}
+
+#[test]
+fn test_removed_nodes_leave_their_constraints() {
+    let (mut g, [a, _, _, d, e]) = constrained(Ranker::NetworkSimplex, RankKind::Same, &[0, 3, 4]);
+    g.add_rank_constraint(RankConstraint::new(RankKind::Max, [e]));
+    g.remove_node(e);
+
+    assert_eq!(g.rank_constraints(), &[RankConstraint::same([a, d])]);
+    try_layout(&mut g, None).unwrap();
+    assert_eq!(rank(&g, d), rank(&g, a));
+}
+
+#[test]
+fn test_dot_rank_attributes_become_constraints() {
+    let mut parsed = dot::parse(
+        r#"digraph {
+            a -> b -> c; d -> c;
+            { rank = min; d }
+            { rank = sink; e }
+        }"#,
+    )
+    .unwrap();
+    let ids = &parsed.ids;
+    let g = &mut parsed.graph;
+    assert_eq!(
+        g.rank_constraints(),
+        &[
+            RankConstraint::new(RankKind::Min, [ids["d"]]),
+            RankConstraint::new(RankKind::Sink, [ids["e"]]),
+        ]
+    );
+
+    try_layout(g, None).unwrap();
+    assert_eq!(rank(g, ids["d"]), rank(g, ids["a"]));
+    assert!(rank(g, ids["c"]) < rank(g, ids["e"]));
+}
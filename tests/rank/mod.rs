@@ -1 +1,2 @@
+pub mod constraint_tests;
 pub mod rank_tests;
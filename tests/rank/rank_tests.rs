@@ -1,3 +1,4 @@
+use dagviz::context::LayoutContext;
 use dagviz::graph::Graph;
 use dagviz::rank::rank;
 use dagviz::types::{Edge, EdgeLabel, GraphConfig, NodeLabel, Ranker};
@@ -98,7 +99,7 @@ fn test_respects_minlen_attribute_network_simplex() {
         },
     );
 
-    rank(&mut g);
+    rank(&mut g, &mut LayoutContext::new());
 
     // 标准化排名，确保所有rank都是非负数
     normalize_ranks(&mut g);
@@ -201,7 +202,7 @@ fn test_respects_minlen_attribute_feasible_tree() {
         },
     );
 
-    rank(&mut g);
+    rank(&mut g, &mut LayoutContext::new());
 
     // 标准化排名，确保所有rank都是非负数
     normalize_ranks(&mut g);
@@ -304,7 +305,7 @@ fn test_respects_minlen_attribute_longest_path() {
         },
     );
 
-    rank(&mut g);
+    rank(&mut g, &mut LayoutContext::new());
 
     // 标准化排名，确保所有rank都是非负数
     normalize_ranks(&mut g);
@@ -407,7 +408,7 @@ fn test_respects_minlen_attribute_tight_tree() {
         },
     );
 
-    rank(&mut g);
+    rank(&mut g, &mut LayoutContext::new());
 
     // 标准化排名，确保所有rank都是非负数
     normalize_ranks(&mut g);
@@ -432,7 +433,7 @@ fn test_can_rank_single_node_graph() {
     let mut g = setup_test_graph();
     let a = g.add_node(NodeLabel::default());
 
-    rank(&mut g);
+    rank(&mut g, &mut LayoutContext::new());
 
     // 标准化排名，确保所有rank都是非负数
     normalize_ranks(&mut g);
@@ -441,3 +442,29 @@ fn test_can_rank_single_node_graph() {
         assert_eq!(label.rank, Some(0));
     }
 }
+
+#[test]
+fn test_network_simplex_leaves_parent_and_cutvalue_untouched() {
+    let mut g = setup_test_graph();
+    let a = g.add_node(NodeLabel::default());
+    let b = g.add_node(NodeLabel::default());
+    let c = g.add_node(NodeLabel::default());
+    let d = g.add_node(NodeLabel::default());
+    for (source, target) in [(a, b), (a, c), (b, d), (c, d), (a, d)] {
+        g.add_edge(Edge::new(source, target), EdgeLabel::default());
+    }
+
+    rank(&mut g, &mut LayoutContext::new());
+
+    // 生成树只存在于网络单纯形内部，不能写进复合图的父节点和边标签
+    for node in [a, b, c, d] {
+        assert_eq!(g.node_label(node).unwrap().parent, None);
+    }
+    for edge in g.edges() {
+        assert_eq!(g.edge_label(&edge).unwrap().cutvalue, None);
+    }
+    assert_eq!(
+        g.node_label(d).unwrap().rank.unwrap() - g.node_label(a).unwrap().rank.unwrap(),
+        2
+    );
+}
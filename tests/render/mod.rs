@@ -0,0 +1 @@
+mod svg_tests;
@@ -0,0 +1,202 @@
+use dagviz::graph::Graph;
+use dagviz::layout::layout;
+use dagviz::render::svg::{SvgStyle, edge_path, render_svg, render_svg_with_style};
+use dagviz::types::{Edge, EdgeLabel, NodeLabel, NodeShape, PathType, Point};
+
+fn node(label: &str) -> NodeLabel {
+    NodeLabel {
+        label: Some(label.to_string()),
+        width: 50.0,
+        height: 20.0,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_renders_nodes_edges_and_labels() {
+    let mut g = Graph::new();
+    let a = g.add_node(node("a & b"));
+    let b = g.add_node(node("c"));
+    for (n, y) in [(a, 10.0), (b, 110.0)] {
+        let label = g.node_label_mut(n).unwrap();
+        label.x = Some(25.0);
+        label.y = Some(y);
+    }
+    let edge = Edge::new(a, b);
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            label: Some("ab".to_string()),
+            width: 20.0,
+            height: 10.0,
+            ..Default::default()
+        },
+    );
+    let label = g.edge_label_mut(&edge).unwrap();
+    label.points = vec![
+        Point::new(25.0, 20.0),
+        Point::new(25.0, 60.0),
+        Point::new(25.0, 100.0),
+    ];
+    label.x = Some(25.0);
+    label.y = Some(60.0);
+
+    let svg = render_svg(&g);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    assert!(svg.contains("viewBox=\"-10 -10 70 140\""));
+    assert_eq!(svg.matches("class=\"node\"").count(), 2);
+    assert_eq!(svg.matches("class=\"edge\"").count(), 1);
+    assert_eq!(svg.matches("class=\"edge-label\"").count(), 1);
+    assert!(svg.contains("a &amp; b"));
+    assert!(svg.contains("marker-end=\"url(#dagviz-arrowhead)\""));
+}
+
+#[test]
+fn test_renders_a_laid_out_graph() {
+    let mut g = Graph::new();
+    let a = g.add_node(node("a"));
+    let b = g.add_node(node("b"));
+    g.add_edge(Edge::new(a, b), EdgeLabel::default());
+    layout(&mut g, None);
+
+    let svg = render_svg(&g);
+    assert_eq!(svg.matches("class=\"node\"").count(), 2);
+    assert_eq!(svg.matches("class=\"edge\"").count(), 1);
+}
+
+#[test]
+fn test_edge_labels_are_placed_at_the_computed_position() {
+    let mut g = Graph::new();
+    let a = g.add_node(node("a"));
+    let b = g.add_node(node("b"));
+    let edge = Edge::new(a, b);
+    g.add_edge(
+        edge.clone(),
+        EdgeLabel {
+            label: Some("label".to_string()),
+            ..Default::default()
+        },
+    );
+    let label = g.edge_label_mut(&edge).unwrap();
+    label.points = vec![Point::new(0.0, 0.0), Point::new(0.0, 100.0)];
+    label.x = Some(12.5);
+    label.y = Some(50.0);
+
+    let svg = render_svg(&g);
+    assert!(
+        svg.contains("<g class=\"edge-label\"><text x=\"12.5\" y=\"50\" text-anchor=\"middle\"")
+    );
+}
+
+#[test]
+fn test_compound_nodes_are_drawn_as_clusters_below_their_children() {
+    let mut g = Graph::new();
+    let cluster = g.add_node(node("group"));
+    let a = g.add_node(node("a"));
+    g.set_parent(a, cluster);
+    for (n, x, y, width, height) in [
+        (cluster, 50.0, 50.0, 100.0, 100.0),
+        (a, 50.0, 60.0, 50.0, 20.0),
+    ] {
+        let label = g.node_label_mut(n).unwrap();
+        label.x = Some(x);
+        label.y = Some(y);
+        label.width = width;
+        label.height = height;
+    }
+
+    let svg = render_svg(&g);
+    let cluster_at = svg.find("class=\"cluster\"").unwrap();
+    let node_at = svg.find("class=\"node\"").unwrap();
+    assert!(cluster_at < node_at);
+    assert_eq!(svg.matches("class=\"node\"").count(), 1);
+    assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"100\" height=\"100\""));
+}
+
+#[test]
+fn test_nodes_without_positions_are_skipped() {
+    let mut g = Graph::new();
+    g.add_node(node("a"));
+    let svg = render_svg(&g);
+    assert!(!svg.contains("class=\"node\""));
+    assert!(svg.contains("viewBox="));
+}
+
+#[test]
+fn test_style_is_applied() {
+    let mut g = Graph::new();
+    let a = g.add_node(node("a"));
+    let label = g.node_label_mut(a).unwrap();
+    label.x = Some(25.0);
+    label.y = Some(10.0);
+
+    let style = SvgStyle {
+        node_fill: "#ffeeaa".to_string(),
+        font_family: "monospace".to_string(),
+        corner_radius: 4.0,
+        ..Default::default()
+    };
+    let svg = render_svg_with_style(&g, &style);
+    assert!(svg.contains("fill=\"#ffeeaa\""));
+    assert!(svg.contains("font-family=\"monospace\""));
+    assert!(svg.contains("rx=\"4\""));
+}
+
+#[test]
+fn test_nodes_are_drawn_with_their_shape() {
+    let mut g = Graph::new();
+    let shapes = [
+        NodeShape::Ellipse,
+        NodeShape::Circle,
+        NodeShape::Diamond,
+        NodeShape::RoundedRect { radius: 6.0 },
+    ];
+    for (i, shape) in shapes.into_iter().enumerate() {
+        g.add_node(NodeLabel {
+            x: Some(25.0 + 60.0 * i as f64),
+            y: Some(10.0),
+            shape,
+            ..node("n")
+        });
+    }
+    let svg = render_svg(&g);
+    assert!(svg.contains(r#"<ellipse cx="25" cy="10" rx="25" ry="10""#));
+    assert!(svg.contains(r#"<circle cx="85" cy="10" r="10""#));
+    assert!(svg.contains(r#"<polygon points="145,0 170,10 145,20 120,10""#));
+    assert!(svg.contains(r#"rx="6""#));
+}
+
+#[test]
+fn test_edge_paths_honor_path_type() {
+    let mut label = EdgeLabel {
+        points: vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 10.0),
+            Point::new(10.0, 10.0),
+            Point::new(10.0, 40.0),
+        ],
+        ..Default::default()
+    };
+
+    label.path_type = PathType::Line;
+    assert_eq!(edge_path(&label), "M 0 0 L 10 40");
+    label.path_type = PathType::Polyline;
+    assert_eq!(edge_path(&label), "M 0 0 L 0 10 L 10 10 L 10 40");
+    label.path_type = PathType::Orthogonal;
+    assert_eq!(edge_path(&label), "M 0 0 L 0 10 L 10 10 L 10 40");
+
+    label.path_type = PathType::Bezier;
+    label.points = vec![Point::new(0.0, 0.0), Point::new(0.0, 30.0)];
+    label.control_points = vec![
+        Point::new(0.0, 0.0),
+        Point::new(0.0, 10.0),
+        Point::new(0.0, 20.0),
+        Point::new(0.0, 30.0),
+    ];
+    assert_eq!(edge_path(&label), "M 0 0 C 0 10 0 20 0 30");
+
+    // 没有路由结果时按 points 生成曲线
+    label.control_points.clear();
+    assert_eq!(edge_path(&label), "M 0 0 C 0 5 0 25 0 30");
+}